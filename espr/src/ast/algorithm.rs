@@ -3,6 +3,7 @@
 use crate::{ast::*, derive_ast_component, parser::*};
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Statement {
     Alias {
         name: String,
@@ -53,6 +54,7 @@ pub enum Statement {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct RepeatControl {
     pub increment: Option<RepeatIncrement>,
     pub while_: Option<Expression>,
@@ -60,6 +62,7 @@ pub struct RepeatControl {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct RepeatIncrement {
     pub variable: String,
     pub begin: Expression,
@@ -68,6 +71,7 @@ pub struct RepeatIncrement {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Procedure {
     pub name: String,
     pub parameters: Vec<FormalParameter>,
@@ -80,6 +84,7 @@ pub struct Procedure {
 derive_ast_component!(Procedure, procedure_decl);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Function {
     pub name: String,
     pub parameters: Vec<FormalParameter>,
@@ -93,6 +98,7 @@ pub struct Function {
 derive_ast_component!(Function, function_decl);
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum ProcedureCallName {
     Reference(String),
     /// Built-in procedure `INSERT`
@@ -102,6 +108,7 @@ pub enum ProcedureCallName {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct FormalParameter {
     pub name: String,
     pub ty: Type,
@@ -110,6 +117,7 @@ pub struct FormalParameter {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Constant {
     pub name: String,
     pub ty: Type,
@@ -117,6 +125,7 @@ pub struct Constant {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Rule {
     pub name: String,
     pub references: Vec<String>,
@@ -128,6 +137,7 @@ pub struct Rule {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct LocalVariable {
     pub name: String,
     pub ty: Type,
@@ -135,6 +145,7 @@ pub struct LocalVariable {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum InterfaceSpec {
     Reference {
         name: String,
@@ -147,17 +158,20 @@ pub enum InterfaceSpec {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct WhereClause {
     pub rules: Vec<DomainRule>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct DomainRule {
     pub label: Option<String>,
     pub expr: Expression,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Declaration {
     Entity(Entity),
     Type(TypeDecl),
@@ -166,3 +180,17 @@ pub enum Declaration {
     Rule(Rule),
     SubTypeConstraint(SubTypeConstraint),
 }
+
+impl Declaration {
+    /// Name of the declared item, unique in a schema
+    pub fn name(&self) -> &str {
+        match self {
+            Declaration::Entity(e) => &e.name,
+            Declaration::Type(ty) => &ty.type_id,
+            Declaration::Function(f) => &f.name,
+            Declaration::Procedure(p) => &p.name,
+            Declaration::Rule(r) => &r.name,
+            Declaration::SubTypeConstraint(sub) => &sub.name,
+        }
+    }
+}
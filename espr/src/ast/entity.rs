@@ -4,6 +4,7 @@ use crate::{ast::*, parser::*};
 
 /// Parsed result of EXPRESS's ENTITY
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Entity {
     /// Name of this entity type
     pub name: String,
@@ -39,6 +40,7 @@ impl Entity {
 
 /// Intermediate output of [entity_body]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct EntityBody {
     pub attributes: Vec<EntityAttribute>,
     pub derive_clause: Option<DeriveClause>,
@@ -48,6 +50,7 @@ pub struct EntityBody {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum AttributeDecl {
     Reference(String),
     Qualified {
@@ -71,6 +74,7 @@ impl<'a> PartialEq<&'a str> for AttributeDecl {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct EntityAttribute {
     pub name: AttributeDecl,
     pub ty: Type,
@@ -78,11 +82,13 @@ pub struct EntityAttribute {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct DeriveClause {
     pub attributes: Vec<DerivedAttribute>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct DerivedAttribute {
     pub attr: AttributeDecl,
     pub ty: Type,
@@ -90,6 +96,7 @@ pub struct DerivedAttribute {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct InverseClause {
     pub attributes: Vec<InverseAttribute>,
 }
@@ -120,6 +127,7 @@ pub struct InverseClause {
 /// > knobs can only exist if they are used in the role of handle in one instance of a door
 ///
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct InverseAttribute {
     /// Name of this inverse relationship
     ///
@@ -144,6 +152,7 @@ pub struct InverseAttribute {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum AggregationOption {
     Set { bound: Option<Bound> },
     Bag { bound: Option<Bound> },
@@ -151,6 +160,7 @@ pub enum AggregationOption {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Constraint {
     AbstractEntity,
     AbstractSuperType(Option<SuperTypeExpression>),
@@ -158,11 +168,13 @@ pub enum Constraint {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct SubTypeDecl {
     pub entity_references: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum SuperTypeExpression {
     Reference(String),
     AndOr { factors: Vec<SuperTypeExpression> },
@@ -171,6 +183,7 @@ pub enum SuperTypeExpression {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct SubTypeConstraint {
     pub name: String,
     pub entity: String,
@@ -180,11 +193,13 @@ pub struct SubTypeConstraint {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct UniqueClause {
     pub rules: Vec<UniqueRule>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct UniqueRule {
     pub name: Option<String>,
     pub attributes: Vec<AttributeDecl>,
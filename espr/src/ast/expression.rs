@@ -6,6 +6,7 @@ use derive_more::From;
 use crate::parser::*;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Expression {
     Unary {
         op: UnaryOperator,
@@ -136,6 +137,7 @@ impl_binary_op_expression!(sub, std::ops::Sub, BinaryOperator::Sub);
 impl_binary_op_expression!(mul, std::ops::Mul, BinaryOperator::Mul);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum QualifiableFactor {
     /// [attribute_ref], [general_ref], [population], or [constant_ref]
     Reference(String),
@@ -149,6 +151,7 @@ pub enum QualifiableFactor {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum FunctionCallName {
     BuiltInFunction(BuiltInFunction),
     Reference(String),
@@ -156,6 +159,7 @@ pub enum FunctionCallName {
 
 #[allow(non_camel_case_types, clippy::upper_case_acronyms)] // to use original identifiers
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum BuiltInFunction {
     ABS,
     ACOS,
@@ -190,6 +194,7 @@ pub enum BuiltInFunction {
 
 /// Output of [qualifier]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Qualifier {
     /// Like `.x`
     Attribute(String),
@@ -202,6 +207,7 @@ pub enum Qualifier {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum BuiltInConstant {
     /// `CONST_E`, Napier's constant `e = 2.71828 …`
     Napier,
@@ -216,6 +222,7 @@ pub enum BuiltInConstant {
 
 /// Relation operators parsed by [rel_op] and [rel_op_extended]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum RelationOperator {
     /// `=`
     Equal,
@@ -243,6 +250,7 @@ pub enum RelationOperator {
 
 /// Unary operators parsed by [unary_op]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum UnaryOperator {
     /// `+`
     Plus,
@@ -254,6 +262,7 @@ pub enum UnaryOperator {
 
 /// Binary operators parsed by [add_like_op], [multiplication_like_op], and [power_op]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum BinaryOperator {
     /* Mul-like */
     /// `*`
@@ -285,6 +294,7 @@ pub enum BinaryOperator {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum IntervalOperator {
     /// `<`
     LessThan,
@@ -293,12 +303,14 @@ pub enum IntervalOperator {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Element {
     pub expr: Expression,
     pub repetition: Option<Expression>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Logical {
     False,
     True,
@@ -306,6 +318,7 @@ pub enum Logical {
 }
 
 #[derive(Debug, Clone, PartialEq, From)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Literal {
     Real(f64),
     String(String),
@@ -25,7 +25,7 @@ pub trait Component: Sized {
     }
 }
 
-#[macro_export(local_inner_macro)]
+#[macro_export(local_inner_macros)]
 macro_rules! derive_ast_component {
     ($component:ty, $parser:path) => {
         impl crate::ast::Component for $component {
@@ -45,13 +45,36 @@ macro_rules! derive_ast_component {
 
 /// Remarks in EXPRESS input, `(* ... *)` or `-- ...`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Remark {
     pub tag: Option<Vec<String>>,
     pub remark: String,
+    pub position: RemarkPosition,
+}
+
+/// Where a [Remark] is written in [SyntaxTree]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub enum RemarkPosition {
+    /// Before the first schema, or in a component parsed alone
+    Head,
+    /// In a schema, but not in or just after its declarations
+    Schema(String),
+    /// In or just after a declaration, e.g. an entity, in a schema
+    Declaration { schema: String, name: String },
+    /// Just after `END_SCHEMA;` of a schema
+    EndSchema(String),
+}
+
+impl Default for RemarkPosition {
+    fn default() -> Self {
+        RemarkPosition::Head
+    }
 }
 
 /// Entire syntax tree parsed from EXPRESS Language string
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct SyntaxTree {
     pub schemas: Vec<Schema>,
     pub remarks: Vec<Remark>,
@@ -63,10 +86,25 @@ impl SyntaxTree {
             .map(|(_start_space, schemas, _end_space)| schemas)
             .parse(input)
             .finish()?;
-        assert!(residual.is_empty());
+        if !residual.is_empty() {
+            // Remaining input does not start with a schema declaration
+            return Err(nom::error::VerboseError {
+                errors: vec![(
+                    residual,
+                    nom::error::VerboseErrorKind::Nom(nom::error::ErrorKind::Eof),
+                )],
+            });
+        }
         Ok(SyntaxTree { schemas, remarks })
     }
 
+    /// Append schemas parsed from another input, e.g. another `.exp` file
+    /// which this one refers through `USE FROM` or `REFERENCE FROM`
+    pub fn append(&mut self, other: SyntaxTree) {
+        self.schemas.extend(other.schemas);
+        self.remarks.extend(other.remarks);
+    }
+
     // Example syntax tree for easy testing
     //
     // FIXME Replace by e.g. proptest
@@ -127,5 +165,16 @@ mod tests {
         .unwrap();
         dbg!(&st);
         assert_eq!(st.remarks.len(), 6);
+        assert_eq!(
+            st.remarks[0].position,
+            super::RemarkPosition::Declaration {
+                schema: "one".to_string(),
+                name: "first".to_string()
+            }
+        );
+        assert_eq!(
+            st.remarks[3].position,
+            super::RemarkPosition::EndSchema("one".to_string())
+        );
     }
 }
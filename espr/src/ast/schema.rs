@@ -4,6 +4,7 @@ use crate::ast::{algorithm::*, entity::*, types::*};
 
 /// Parsed result of EXPRESS's SCHEMA
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Schema {
     pub name: String,
     pub entities: Vec<Entity>,
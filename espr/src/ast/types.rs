@@ -8,6 +8,7 @@ use crate::{
 
 /// Type declaration by [type_decl].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct TypeDecl {
     pub type_id: String,
     pub underlying_type: Type,
@@ -17,6 +18,7 @@ pub struct TypeDecl {
 /// Parameter type appears when *using* the type
 /// e.g. in attribute definition, function parameter, and so on.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Type {
     Simple(SimpleType),
     Named(String),
@@ -61,6 +63,7 @@ pub enum Type {
 
 /// Primitive types parsed by [simple_types]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum SimpleType {
     /// 8.1.1 Number data type
     Number,
@@ -82,12 +85,14 @@ derive_ast_component!(SimpleType, simple_types);
 
 /// Output of [width_spec]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct WidthSpec {
     pub width: usize,
     pub fixed: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Bound {
     pub lower: Expression,
     pub upper: Expression,
@@ -95,6 +100,7 @@ pub struct Bound {
 
 /// `EXTENSIBLE` and `GENERIC_ENTITY` keywords for [select_type] and [enumeration_type]
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum Extensibility {
     /// No `EXTENSIBLE`
     None,
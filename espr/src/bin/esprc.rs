@@ -1,38 +1,147 @@
 //! Executable for espr EXPRESS language compiler
 
-use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
-use std::{fs, path::*};
+use espr::{
+    ast::SyntaxTree,
+    codegen::rust::*,
+    ir::IR,
+    locator::SchemaLocator,
+    longform,
+    printer::{self, Printer},
+};
+use std::{fs, path::*, process};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 struct Arguments {
-    #[structopt(long = "num-error-lines", default_value = "10")]
-    num_lines: usize,
     #[structopt(long = "check", help = "Check input EXPRESS definitions can be parsed")]
     check: bool,
+    #[structopt(flatten)]
+    input: Input,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print the long form of the root schema expanding the schemas it interfaces
+    Longform {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Print EXPRESS files in the canonical layout
+    Fmt {
+        #[structopt(
+            long = "check",
+            help = "Report files not in the canonical layout without printing them"
+        )]
+        check: bool,
+        #[structopt(
+            long = "write",
+            conflicts_with = "check",
+            help = "Rewrite files in place instead of printing them"
+        )]
+        write: bool,
+        #[structopt(long = "num-error-lines", default_value = "10")]
+        num_lines: usize,
+        #[structopt(parse(from_os_str))]
+        sources: Vec<PathBuf>,
+    },
+    /// Dump the syntax tree of the input schemas
+    Ast {
+        #[structopt(flatten)]
+        input: Input,
+        #[structopt(flatten)]
+        format: Format,
+    },
+    /// Dump the intermediate representation of the input schemas
+    Ir {
+        #[structopt(flatten)]
+        input: Input,
+        #[structopt(flatten)]
+        format: Format,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct Format {
+    #[structopt(
+        long = "format",
+        default_value = "json",
+        possible_values = &["json"],
+        help = "Output format of the dump"
+    )]
+    format: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct Input {
+    #[structopt(long = "num-error-lines", default_value = "10")]
+    num_lines: usize,
+    #[structopt(
+        short = "I",
+        long = "include",
+        parse(from_os_str),
+        number_of_values = 1,
+        help = "Directory or file searched for schemas interfaced by USE FROM or REFERENCE FROM"
+    )]
+    include: Vec<PathBuf>,
+    #[structopt(long = "root", help = "Schema compiled with the schemas it interfaces")]
+    root: Option<String>,
     #[structopt(parse(from_os_str))]
-    source: PathBuf,
+    sources: Vec<PathBuf>,
 }
 
 fn main() {
     let args = Arguments::from_args();
-    let src = fs::read_to_string(&args.source).expect("Failed to load EXPRESS source code");
-    let st = match SyntaxTree::parse(&src) {
-        Ok(st) => st,
-        Err(e) => {
-            for (code, kind) in e.errors {
-                eprintln!(
-                    "Syntax Error occurred while parsing following line [{:?}]:",
-                    kind
-                );
-                for line in code.lines().take(args.num_lines) {
-                    eprintln!("> {}", line);
+    match &args.command {
+        Some(Command::Fmt {
+            check,
+            write,
+            num_lines,
+            sources,
+        }) => {
+            fmt(sources, *check, *write, *num_lines);
+            return;
+        }
+        Some(Command::Ast { input, format }) => {
+            let st = input.load();
+            dump(&st, format);
+            return;
+        }
+        Some(Command::Ir { input, format }) => {
+            let st = input.load();
+            match IR::from_syntax_tree(&st) {
+                Ok(ir) => dump(&ir, format),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
                 }
-                eprintln!();
             }
-            panic!("Syntax Error");
+            return;
         }
-    };
+        _ => {}
+    }
+    if let Some(Command::Longform { input }) = &args.command {
+        let st = input.load();
+        let root = match &input.root {
+            Some(root) => root.clone(),
+            None => st.schemas[0].name.clone(),
+        };
+        match longform::long_form(&st, &root) {
+            Ok(schema) => {
+                let mut printer = Printer::default();
+                printer.schema(&schema);
+                print!("{}", printer.finish());
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let st = args.input.load();
     if args.check {
         eprintln!("Parse succeeded");
         return;
@@ -44,3 +153,135 @@ fn main() {
         ir.to_token_stream(CratePrefix::Internal)
     );
 }
+
+impl Input {
+    /// Parse source files, and the files of schemas interfaced from them
+    fn load(&self) -> SyntaxTree {
+        if self.sources.is_empty() && self.root.is_none() {
+            eprintln!("No input: give EXPRESS source files or --root schema");
+            process::exit(1);
+        }
+
+        let mut st = SyntaxTree::default();
+        for source in &self.sources {
+            st.append(parse(source, self.num_lines));
+        }
+
+        let mut locator = SchemaLocator::default();
+        for path in &self.include {
+            if let Err(e) = locator.add_search_path(path) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        if let Some(root) = &self.root {
+            if !st.schemas.iter().any(|s| s.name.eq_ignore_ascii_case(root)) {
+                match locator.locate(root) {
+                    Some(path) => st.append(parse(path, self.num_lines)),
+                    None => {
+                        eprintln!(
+                            "Schema '{}' is not declared in any file in search paths",
+                            root
+                        );
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+        match locator.resolve(st) {
+            Ok(st) => st,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn fmt(sources: &[PathBuf], check: bool, write: bool, num_lines: usize) {
+    if sources.is_empty() {
+        eprintln!("No input: give EXPRESS source files to format");
+        process::exit(1);
+    }
+    let mut failed = false;
+    for source in sources {
+        let src = fs::read_to_string(source).expect("Failed to load EXPRESS source code");
+        let st = parse(source, num_lines);
+        let formatted = printer::to_string(&st);
+        if check {
+            if src != formatted {
+                eprintln!("{} is not formatted", source.display());
+                failed = true;
+            }
+            continue;
+        }
+        if !write {
+            print!("{}", formatted);
+            continue;
+        }
+        if src == formatted {
+            continue;
+        }
+        // Do not overwrite the source by a broken output
+        match SyntaxTree::parse(&formatted) {
+            Ok(reparsed) if reparsed.schemas == st.schemas => {
+                fs::write(source, formatted)
+                    .expect("Failed to write formatted EXPRESS source code");
+            }
+            _ => {
+                eprintln!(
+                    "{} is not rewritten since the formatted output is not parsed into the same schemas",
+                    source.display()
+                );
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(feature = "serialize")]
+fn dump<T: serde::Serialize>(value: &T, format: &Format) {
+    let dumped = match format.format.as_str() {
+        "json" => serde_json::to_string_pretty(value),
+        _ => unreachable!("Unsupported format is rejected by argument parser"),
+    };
+    match dumped {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "serialize"))]
+fn dump<T>(_value: &T, format: &Format) {
+    eprintln!(
+        "esprc is built without `serialize` feature, which is required to dump AST or IR in {}",
+        format.format
+    );
+    process::exit(1);
+}
+
+fn parse(path: &Path, num_lines: usize) -> SyntaxTree {
+    let src = fs::read_to_string(path).expect("Failed to load EXPRESS source code");
+    match SyntaxTree::parse(&src) {
+        Ok(st) => st,
+        Err(e) => {
+            for (code, kind) in e.errors {
+                eprintln!(
+                    "Syntax Error occurred while parsing following line [{:?}]:",
+                    kind
+                );
+                for line in code.lines().take(num_lines) {
+                    eprintln!("> {}", line);
+                }
+                eprintln!();
+            }
+            panic!("Syntax Error in {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Generate Rust constants from `CONSTANT` block
+
+use super::expression::*;
+use crate::{ast, ir::*};
+
+use inflector::Inflector;
+use proc_macro2::TokenStream;
+use quote::*;
+
+impl Schema {
+    /// `const` items for constants of simple types initialized by literals,
+    /// and lazily built thread-local `runtime::Value` for others
+    pub(crate) fn constant_items(&self) -> TokenStream {
+        let mut consts = TokenStream::new();
+        let mut statics = TokenStream::new();
+        let mut initializers = TokenStream::new();
+        for constant in &self.constants {
+            let ident = constant.ident();
+            let ty = &constant.ty;
+            if let Some(literal) = constant.literal() {
+                let doc = format!(" `CONSTANT {}`", constant.name);
+                consts.append_all(quote! {
+                    #[doc = #doc]
+                    pub const #ident: #ty = #literal;
+                });
+                continue;
+            }
+            // Constants whose initializers cannot be translated are removed by `Schema::translatable`
+            let expr = match Translator::new(self).translate(&constant.expr) {
+                Ok(expr) => expr,
+                Err(_) => continue,
+            };
+            let doc = format!(" `CONSTANT {}`", constant.name);
+            // Initializer is a function to keep the body of `thread_local!` small
+            let init = format_ident!("constant_{}", constant.name.to_snake_case());
+            statics.append_all(quote! {
+                #[doc = #doc]
+                pub static #ident: runtime::Value = #init();
+            });
+            initializers.append_all(quote! {
+                fn #init() -> runtime::Value {
+                    #expr
+                }
+            });
+        }
+        if statics.is_empty() {
+            return consts;
+        }
+        quote! {
+            #consts
+            thread_local! {
+                #statics
+            }
+            #initializers
+        }
+    }
+}
+
+impl Constant {
+    /// Name of the constant in upper case, e.g. `DUMMY_GRI`
+    pub(crate) fn ident(&self) -> syn::Ident {
+        format_ident!("{}", self.name.to_screaming_snake_case())
+    }
+
+    /// Rust literal of the constant if it is a simple type initialized by a literal
+    pub(crate) fn literal(&self) -> Option<TokenStream> {
+        let (negative, literal) = match &self.expr {
+            ast::Expression::Literal(literal) => (false, literal),
+            ast::Expression::Unary {
+                op: ast::UnaryOperator::Minus,
+                arg,
+            } => match arg.as_ref() {
+                ast::Expression::Literal(literal) => (true, literal),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let ty = match &self.ty {
+            TypeRef::SimpleType(SimpleType(ty)) => ty,
+            _ => return None,
+        };
+        let value = match (ty, literal) {
+            // Integer literals are also parsed as real numbers
+            (ast::SimpleType::Integer, ast::Literal::Real(value))
+                if value.fract() == 0.0 && value.abs() < i64::MAX as f64 =>
+            {
+                let value = proc_macro2::Literal::i64_unsuffixed(*value as i64);
+                quote! { #value }
+            }
+            (ast::SimpleType::Real | ast::SimpleType::Number, ast::Literal::Real(value)) => {
+                let value = proc_macro2::Literal::f64_unsuffixed(*value);
+                quote! { #value }
+            }
+            (ast::SimpleType::Boolen, ast::Literal::Logial(ast::Logical::True)) if !negative => {
+                quote! { true }
+            }
+            (ast::SimpleType::Boolen, ast::Literal::Logial(ast::Logical::False)) if !negative => {
+                quote! { false }
+            }
+            (ast::SimpleType::Logical, ast::Literal::Logial(logical)) if !negative => match logical
+            {
+                ast::Logical::True => quote! { Logical::True },
+                ast::Logical::False => quote! { Logical::False },
+                ast::Logical::Unknown => quote! { Logical::Unknown },
+            },
+            _ => return None,
+        };
+        Some(if negative {
+            quote! { -#value }
+        } else {
+            value
+        })
+    }
+}
@@ -19,28 +19,45 @@ struct Field {
 ///
 /// - a simple type
 /// - an enumeration
-/// - a set or list whose base type use place holder
+/// - an aggregate whose base type use place holder
 ///
 fn use_place_holder(ty: &TypeRef) -> bool {
     match ty {
         TypeRef::SimpleType(..) => false,
         TypeRef::Named { is_enumerate, .. } => !*is_enumerate,
-        TypeRef::Set { base, .. } | TypeRef::List { base, .. } => use_place_holder(base),
-        _ => true,
+        TypeRef::Entity { .. } => true,
+        _ => use_place_holder(ty.base().expect("Aggregate")),
     }
 }
 
 impl From<EntityAttribute> for Field {
     fn from(attr: EntityAttribute) -> Self {
-        let EntityAttribute { name, ty, optional } = attr;
+        let EntityAttribute {
+            name,
+            ty,
+            optional,
+            derived_in,
+        } = attr;
 
         let name = format_ident!("{}", name.into_safe());
-        let attributes = if use_place_holder(&ty) {
+        let mut attributes = if use_place_holder(&ty) {
             vec![parse_quote! { #[holder(use_place_holder)] }]
         } else {
             Vec::new()
         };
-        let ty = if optional {
+        if let Some(bound) = ty.holder_bound() {
+            attributes.push(parse_quote! { #bound });
+        }
+        // The instances of the subtypes omit the value as `*`, which is checked in `TableInit`.
+        // An optional attribute is not checked since it may be `$` in the other instances.
+        if !optional && !derived_in.is_empty() {
+            let subtypes = derived_in.iter().map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.to_pascal_case()),
+                _ => unreachable!(),
+            });
+            attributes.push(parse_quote! { #[holder(derived_in(#(#subtypes),*))] });
+        }
+        let ty = if optional || !derived_in.is_empty() {
             parse_quote! { Option<#ty> }
         } else {
             parse_quote! { #ty }
@@ -90,9 +107,24 @@ impl Entity {
     fn generate_any_enum(&self, tokens: &mut TokenStream) {
         let any = self.any_ident();
 
-        let mut fields = vec![format_ident!("{}", self.name.as_str().into_safe())];
-        let mut variants = vec![format_ident!("{}", self.name.to_pascal_case())];
-        let mut constraints = vec![format_ident!("{}", self.name.to_pascal_case())];
+        let mut fields = Vec::new();
+        let mut variants = Vec::new();
+        let mut constraints = Vec::new();
+
+        // Abstract entity is instantiated only through its subtypes
+        if !self.is_abstract {
+            fields.push(format_ident!("{}", self.name.as_str().into_safe()));
+            variants.push(format_ident!("{}", self.name.to_pascal_case()));
+            constraints.push(format_ident!("{}", self.name.to_pascal_case()));
+        }
+
+        // Complex entities are placed before single leaf entities
+        // since an instance of the complex entity is also registered in the tables of its leaves
+        for complex in &self.complex_constraints {
+            fields.push(complex.field_ident());
+            variants.push(complex.name_ident());
+            constraints.push(complex.name_ident());
+        }
 
         for ty in &self.constraints {
             match ty {
@@ -130,13 +162,26 @@ impl Entity {
         let name = self.name_ident();
 
         // `Self` to `SelfAny`
-        tokens.append_all(quote! {
-            impl Into<#any> for #name {
-                fn into(self) -> #any {
-                    #any::#name(Box::new(self))
+        if !self.is_abstract {
+            tokens.append_all(quote! {
+                impl Into<#any> for #name {
+                    fn into(self) -> #any {
+                        #any::#name(Box::new(self))
+                    }
                 }
-            }
-        });
+            });
+        }
+
+        for complex in &self.complex_constraints {
+            let name = complex.name_ident();
+            tokens.append_all(quote! {
+                impl Into<#any> for #name {
+                    fn into(self) -> #any {
+                        #any::#name(Box::new(self))
+                    }
+                }
+            });
+        }
 
         for ty in &self.constraints {
             if let TypeRef::Entity { name, .. } = ty {
@@ -167,12 +212,33 @@ impl Entity {
                 _ => unreachable!(),
             })
             .collect::<Vec<_>>();
+        // Complex entity is converted through its first component
+        let complex = self
+            .complex_constraints
+            .iter()
+            .map(|c| c.name_ident())
+            .collect::<Vec<_>>();
+        let complex_first = self
+            .complex_constraints
+            .iter()
+            .map(|c| c.component_idents()[0].clone())
+            .collect::<Vec<_>>();
+
+        let (itself, itself_super) = if self.is_abstract {
+            (quote! {}, quote! {})
+        } else {
+            (
+                quote! { #any::#name (x) => x.as_ref(), },
+                quote! { #any::#name (x) => AsRef::<#name>::as_ref(x).as_ref(), },
+            )
+        };
 
         tokens.append_all(quote! {
             impl AsRef<#name> for #any {
                 fn as_ref(&self) -> &#name {
                     match self {
-                        #any::#name (x) => x.as_ref(),
+                        #itself
+                        #(#any::#complex (x) => x.#complex_first.as_ref(),)*
                         #(#any::#constraints (x) => (**x).as_ref(),)*
                     }
                 }
@@ -191,7 +257,8 @@ impl Entity {
                 impl AsRef<#supertype> for #any {
                     fn as_ref(&self) -> &#supertype {
                         match self {
-                            #any::#name (x) => AsRef::<#name>::as_ref(x).as_ref(),
+                            #itself_super
+                            #(#any::#complex (x) => AsRef::<#name>::as_ref(&x.#complex_first).as_ref(),)*
                             #(#any::#constraints (x) => AsRef::<#name>::as_ref(x.as_ref()).as_ref(),)*
                         }
                     }
@@ -213,6 +280,7 @@ impl Entity {
                     attributes.push(parse_quote! { #[deref_mut] });
                 }
                 attributes.push(parse_quote! { #[holder(use_place_holder)] });
+                attributes.push(parse_quote! { #[holder(supertype)] });
                 let (name, ty) = match ty {
                     TypeRef::Named { name, .. } | TypeRef::Entity { name, .. } => {
                         let ty = format_ident!("{}", name.to_pascal_case());
@@ -233,6 +301,35 @@ impl Entity {
             .collect()
     }
 
+    /// `#[holder(abstract_entity)]`, `#[holder(instantiable(...))]`, and `#[holder(andor(...))]` checked in `TableInit`
+    fn subtype_constraints(&self) -> Vec<syn::Attribute> {
+        let mut attributes = Vec::new();
+        if self.is_abstract {
+            attributes.push(parse_quote! { #[holder(abstract_entity)] });
+        }
+        for ty in &self.constraints {
+            if let TypeRef::Entity { name, .. } = ty {
+                let subtype = format_ident!("{}", name.to_pascal_case());
+                attributes.push(parse_quote! { #[holder(instantiable(#subtype))] });
+            }
+        }
+        for complex in &self.complex_constraints {
+            let subtypes = complex.components.iter().map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.to_pascal_case()),
+                _ => unreachable!(),
+            });
+            attributes.push(parse_quote! { #[holder(instantiable(#(#subtypes),*))] });
+        }
+        if !self.andor_subtypes.is_empty() {
+            let subtypes = self.andor_subtypes.iter().map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.to_pascal_case()),
+                _ => unreachable!(),
+            });
+            attributes.push(parse_quote! { #[holder(andor(#(#subtypes),*))] });
+        }
+        attributes
+    }
+
     fn derives(&self) -> Vec<syn::Path> {
         let mut derives = vec![
             syn::parse_str("Debug").unwrap(),
@@ -270,12 +367,14 @@ impl ToTokens for Entity {
         let supertype_fields = self.supertype_fields();
 
         let derive = self.derives();
+        let subtype_constraints = self.subtype_constraints();
 
         tokens.append_all(quote! {
             #( #[derive(#derive)] )*
             #[holder(table = Tables)]
             #[holder(field = #field_name)]
             #[holder(generate_deserialize)]
+            #(#subtype_constraints)*
             pub struct #name {
                 #(#supertype_fields,)*
                 #(#fields,)*
@@ -291,3 +390,58 @@ impl ToTokens for Entity {
         }
     }
 }
+
+// Additional functions to use in codegen/rust for ir::ComplexEntity.
+impl ComplexEntity {
+    pub(crate) fn name_ident(&self) -> syn::Ident {
+        format_ident!("{}", self.name.to_pascal_case())
+    }
+
+    /// Field identifier
+    pub(crate) fn field_ident(&self) -> syn::Ident {
+        format_ident!("{}", self.name.as_str().into_safe())
+    }
+
+    /// Field identifiers of components
+    fn component_idents(&self) -> Vec<syn::Ident> {
+        self.components
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.as_str().into_safe()),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+}
+
+impl ToTokens for ComplexEntity {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = self.name_ident();
+        let field_name = self.field_ident();
+        let component_fields = self.component_idents();
+        let component_types = self
+            .components
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.to_pascal_case()),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        tokens.append_all(quote! {
+            #[derive(Debug, Clone, PartialEq, ::derive_new::new, Holder, AsRef, AsMut)]
+            #[holder(table = Tables)]
+            #[holder(field = #field_name)]
+            #[holder(complex)]
+            #[holder(generate_deserialize)]
+            pub struct #name {
+                #(
+                #[as_ref]
+                #[as_mut]
+                #[holder(use_place_holder)]
+                pub #component_fields: #component_types,
+                )*
+            }
+        });
+    }
+}
@@ -0,0 +1,726 @@
+//! Translate EXPRESS expressions into Rust code evaluated on `ruststep::runtime::Value`
+
+use super::function::Enclosing;
+use crate::{ast, ir::*};
+
+use check_keyword::CheckKeyword;
+use proc_macro2::TokenStream;
+use quote::*;
+
+/// Expression which cannot be translated into Rust code yet
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Unsupported expression: {0}")]
+pub struct Unsupported(pub String);
+
+/// Names visible in an expression
+///
+/// A name in expression is resolved in following order:
+///
+/// - local variables, e.g. the variable of `QUERY`, the parameters of a function,
+///   or the entities in `FOR` of a global rule
+/// - attributes of `SELF` including inherited ones, if the expression is in an entity
+/// - enumeration items declared in the schema
+///
+pub struct Translator<'a> {
+    pub(super) schema: &'a Schema,
+    pub(super) entity: Option<&'a Entity>,
+    pub(super) self_ref: SelfRef,
+    pub(super) population: PopulationRef,
+    pub(super) variables: Vec<String>,
+    /// Parameters declared with `VAR`, which are `&mut runtime::Value`
+    pub(super) var_parameters: Vec<String>,
+    /// Names declared by `ALIAS`, which cannot be assigned
+    pub(super) aliases: Vec<String>,
+    pub(super) returns: Returns,
+    /// Depth of nested `REPEAT` statements
+    pub(super) loops: usize,
+    /// Functions and procedures declared in the enclosing algorithms
+    pub(super) enclosing: Enclosing<'a>,
+}
+
+/// How `SELF` is referred in the generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SelfRef {
+    /// `SELF` is not available
+    None,
+    /// `self` of the struct with `id: Option<u64>`, e.g. in the getters of derived attributes
+    Struct,
+    /// `this: &runtime::Value` with `id: Option<u64>`, e.g. in `WHERE` rules
+    Value,
+}
+
+/// How the tables implementing `runtime::Population` are referred in the generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PopulationRef {
+    /// Not available, e.g. in constants
+    None,
+    /// `tables: &runtime::Snapshot<Tables>` in rules,
+    /// or `tables: &dyn runtime::Population` in the getters of derived attributes
+    Tables,
+    /// `tables: Option<&dyn runtime::Population>` in functions and procedures
+    Optional,
+}
+
+/// What `RETURN` statement returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Returns {
+    /// `RETURN` is not allowed, e.g. in rules
+    None,
+    /// `runtime::Value` of `FUNCTION`
+    Value,
+    /// Nothing from `PROCEDURE`
+    Unit,
+}
+
+impl<'a> Translator<'a> {
+    fn with(
+        schema: &'a Schema,
+        entity: Option<&'a Entity>,
+        self_ref: SelfRef,
+        population: PopulationRef,
+    ) -> Self {
+        Translator {
+            schema,
+            entity,
+            self_ref,
+            population,
+            variables: Vec::new(),
+            var_parameters: Vec::new(),
+            aliases: Vec::new(),
+            returns: Returns::None,
+            loops: 0,
+            enclosing: Enclosing::default(),
+        }
+    }
+
+    pub fn new(schema: &'a Schema) -> Self {
+        Self::with(schema, None, SelfRef::None, PopulationRef::None)
+    }
+
+    /// Translator for derived attributes of an entity, where `SELF` is `self` of the entity instance `id`,
+    /// and `USEDIN` and the derived attributes of other instances are evaluated on `tables`
+    pub fn entity(schema: &'a Schema, entity: &'a Entity) -> Self {
+        Self::with(schema, Some(entity), SelfRef::Struct, PopulationRef::Tables)
+    }
+
+    /// Translator for domain rules of an entity or a defined type (`entity` is `None`),
+    /// where `SELF` is `this: &runtime::Value`,
+    /// and `INVERSE` attributes and `USEDIN(SELF, role)` are evaluated on `tables`
+    pub fn rule(schema: &'a Schema, entity: Option<&'a Entity>) -> Self {
+        Self::with(schema, entity, SelfRef::Value, PopulationRef::Tables)
+    }
+
+    /// Translator for a global rule, where the entities in `FOR` are
+    /// the variables of the sets of their instances in `tables`,
+    /// and the functions and procedures declared in the rule are visible
+    pub fn global_rule(schema: &'a Schema, rule: &'a Rule) -> Self {
+        let mut translator = Self::with(schema, None, SelfRef::None, PopulationRef::Tables);
+        translator.enclosing = Enclosing {
+            functions: rule.functions.iter().collect(),
+            procedures: rule.procedures.iter().collect(),
+        };
+        translator.variables = rule
+            .entities
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => name.clone(),
+                _ => unreachable!(),
+            })
+            .chain(rule.constants.iter().map(|c| c.name.clone()))
+            .chain(rule.variables.iter().map(|v| v.name.clone()))
+            .collect();
+        translator
+    }
+
+    /// Translator for the body of a function or procedure
+    pub(crate) fn algorithm(
+        schema: &'a Schema,
+        parameters: &[Parameter],
+        constants: &[ast::Constant],
+        variables: &[ast::LocalVariable],
+        returns: Returns,
+    ) -> Self {
+        let mut translator = Self::with(schema, None, SelfRef::None, PopulationRef::Optional);
+        translator.variables = parameters
+            .iter()
+            .map(|p| p.name.clone())
+            .chain(constants.iter().map(|c| c.name.clone()))
+            .chain(variables.iter().map(|v| v.name.clone()))
+            .collect();
+        translator.var_parameters = parameters
+            .iter()
+            .filter(|p| p.is_variable)
+            .map(|p| p.name.clone())
+            .collect();
+        translator.returns = returns;
+        translator
+    }
+
+    pub fn translate(&mut self, expr: &ast::Expression) -> Result<TokenStream, Unsupported> {
+        use ast::Expression::*;
+        Ok(match expr {
+            Unary { op, arg } => {
+                let arg = self.translate(arg)?;
+                match op {
+                    ast::UnaryOperator::Plus => arg,
+                    ast::UnaryOperator::Minus => quote! { runtime::negate(#arg) },
+                    ast::UnaryOperator::Not => quote! { runtime::not(#arg) },
+                }
+            }
+            Binary {
+                op: ast::BinaryOperator::ComplexEntityInstanceConstruction,
+                arg1,
+                arg2,
+            } => {
+                let arg1 = self.complex_operand(arg1)?;
+                let arg2 = self.complex_operand(arg2)?;
+                quote! { runtime::complex(#arg1, #arg2) }
+            }
+            Binary { op, arg1, arg2 } => {
+                use ast::BinaryOperator::*;
+                let f = match op {
+                    Mul => "mul",
+                    RealDiv => "real_div",
+                    IntegerDiv => "integer_div",
+                    Mod => "modulo",
+                    And => "and",
+                    ComplexEntityInstanceConstruction => unreachable!(),
+                    Add => "add",
+                    Sub => "sub",
+                    Or => "or",
+                    Xor => "xor",
+                    Power => "power",
+                };
+                let f = format_ident!("{}", f);
+                let arg1 = self.translate(arg1)?;
+                let arg2 = self.translate(arg2)?;
+                quote! { runtime::#f(#arg1, #arg2) }
+            }
+            Relation { op, lhs, rhs } => {
+                use ast::RelationOperator::*;
+                let f = match op {
+                    Equal => "eq",
+                    NotEqual => "ne",
+                    Lt => "lt",
+                    Gt => "gt",
+                    Leq => "le",
+                    Geq => "ge",
+                    InstanceEqual => "instance_eq",
+                    InstanceNotEqual => "instance_ne",
+                    In => "in_",
+                    Like => "like",
+                };
+                let f = format_ident!("{}", f);
+                let lhs = self.translate(lhs)?;
+                let rhs = self.translate(rhs)?;
+                quote! { runtime::#f(#lhs, #rhs) }
+            }
+            Literal(literal) => literal_value(literal),
+            QualifiableFactor { factor, qualifiers } => {
+                let (mut value, qualifiers) = match (factor, qualifiers.split_first()) {
+                    // Enumeration item qualified by its type, e.g. `side.left`
+                    (
+                        ast::QualifiableFactor::Reference(ty),
+                        Some((ast::Qualifier::Attribute(item), rest)),
+                    ) if self.is_enumeration_type(ty) => (enumeration_value(item), rest),
+                    _ => (self.factor(factor)?, qualifiers.as_slice()),
+                };
+                for qualifier in qualifiers {
+                    value = match qualifier {
+                        ast::Qualifier::Attribute(name) => {
+                            let tables = self.tables_argument();
+                            quote! { runtime::attribute_in(#tables, #value, #name) }
+                        }
+                        // Attributes of supertypes are looked up through the entity itself
+                        ast::Qualifier::Group(_) => value,
+                        ast::Qualifier::Index(index) => {
+                            let index = self.translate(index)?;
+                            quote! { runtime::index(#value, #index) }
+                        }
+                        ast::Qualifier::Range { begin, end } => {
+                            let begin = self.translate(begin)?;
+                            let end = self.translate(end)?;
+                            quote! { runtime::range(#value, #begin, #end) }
+                        }
+                    };
+                }
+                value
+            }
+            EntityConstructor { name, values } => self.entity_constructor(name, values)?,
+            Interval {
+                op_low,
+                op_high,
+                high,
+                low,
+                item,
+            } => {
+                let low = self.translate(low)?;
+                let item = self.translate(item)?;
+                let high = self.translate(high)?;
+                let low_strict = *op_low == ast::IntervalOperator::LessThan;
+                let high_strict = *op_high == ast::IntervalOperator::LessThan;
+                quote! { runtime::interval(#low, #low_strict, #item, #high, #high_strict) }
+            }
+            EnumerationReference { enum_ref, .. } => enumeration_value(enum_ref),
+            AggregateInitializer { elements } => {
+                let elements = elements
+                    .iter()
+                    .map(|ast::Element { expr, repetition }| {
+                        let expr = self.translate(expr)?;
+                        Ok(match repetition {
+                            Some(repetition) => {
+                                let repetition = self.translate(repetition)?;
+                                quote! { (#expr, Some(#repetition)) }
+                            }
+                            None => quote! { (#expr, None) },
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Unsupported>>()?;
+                quote! { runtime::aggregate(vec![#(#elements),*]) }
+            }
+            Query {
+                variable,
+                source,
+                expr,
+            } => {
+                let source = self.translate(source)?;
+                self.variables.push(variable.clone());
+                let expr = self.translate(expr);
+                self.variables.pop();
+                let expr = expr?;
+                let variable = variable_ident(variable);
+                quote! { runtime::query(#source, |#variable: runtime::Value| #expr) }
+            }
+        })
+    }
+
+    fn factor(&mut self, factor: &ast::QualifiableFactor) -> Result<TokenStream, Unsupported> {
+        Ok(match factor {
+            ast::QualifiableFactor::Reference(name) => self.reference(name)?,
+            ast::QualifiableFactor::BuiltInConstant(constant) => match constant {
+                ast::BuiltInConstant::Napier => {
+                    quote! { runtime::Value::Real(::std::f64::consts::E) }
+                }
+                ast::BuiltInConstant::Pi => {
+                    quote! { runtime::Value::Real(::std::f64::consts::PI) }
+                }
+                ast::BuiltInConstant::Self_ => match self.self_ref {
+                    SelfRef::None => return Err(Unsupported("SELF out of entity".to_string())),
+                    SelfRef::Struct => quote! { runtime::self_value(self, id, tables) },
+                    SelfRef::Value => quote! { this.clone() },
+                },
+                ast::BuiltInConstant::Indeterminate => quote! { runtime::Value::Indeterminate },
+            },
+            ast::QualifiableFactor::FunctionCall { name, args } => match name {
+                ast::FunctionCallName::BuiltInFunction(f) => self.builtin_function(f, args)?,
+                ast::FunctionCallName::Reference(name) => {
+                    if self.schema.entity(name).is_some() {
+                        self.entity_constructor(name, args)?
+                    } else {
+                        self.function_call(name, args)?
+                    }
+                }
+            },
+        })
+    }
+
+    fn is_enumeration_type(&self, name: &str) -> bool {
+        !self.variables.iter().any(|v| v == name)
+            && !matches!(self.entity, Some(entity) if self.schema.has_attribute(entity, name))
+            && self
+                .schema
+                .types
+                .iter()
+                .any(|ty| matches!(ty, TypeDecl::Enumeration(e) if e.id == name))
+    }
+
+    fn reference(&self, name: &str) -> Result<TokenStream, Unsupported> {
+        if self.variables.iter().any(|v| v == name) {
+            let ident = variable_ident(name);
+            return Ok(quote! { #ident.clone() });
+        }
+        if let Some(entity) = self.entity {
+            if self.population != PopulationRef::None {
+                if let Some(inverse) = self.schema.inverse_attribute(entity, name) {
+                    let attribute = &inverse.attribute;
+                    let dest = match &inverse.dest {
+                        TypeRef::Entity { name, .. } => self.schema.type_name(name),
+                        _ => unreachable!(),
+                    };
+                    let aggregated = inverse.aggregated;
+                    return Ok(quote! {
+                        runtime::inverse_in(tables, id, #attribute, #dest, #aggregated)
+                    });
+                }
+            }
+            if self.schema.has_attribute(entity, name) {
+                return Ok(match self.self_ref {
+                    SelfRef::Value => {
+                        quote! { runtime::attribute_in(Some(tables), this.clone(), #name) }
+                    }
+                    _ => quote! { runtime::attribute_of(self, id, tables, #name) },
+                });
+            }
+        }
+        if let Some(constant) = self.schema.constant(name) {
+            let ident = constant.ident();
+            return Ok(if constant.literal().is_some() {
+                quote! { runtime::ToValue::to_value(&#ident) }
+            } else {
+                quote! { #ident.with(runtime::Value::clone) }
+            });
+        }
+        if self.schema.enumeration_items().any(|item| item == name) {
+            return Ok(enumeration_value(name));
+        }
+        Err(Unsupported(format!("reference to {}", name)))
+    }
+
+    fn builtin_function(
+        &mut self,
+        f: &ast::BuiltInFunction,
+        args: &[ast::Expression],
+    ) -> Result<TokenStream, Unsupported> {
+        use ast::BuiltInFunction::*;
+        let (name, arity) = match f {
+            ABS => ("abs", 1),
+            ACOS => ("acos", 1),
+            ASIN => ("asin", 1),
+            ATAN => ("atan", 2),
+            BLENGTH => ("blength", 1),
+            COS => ("cos", 1),
+            EXISTS => ("exists", 1),
+            EXP => ("exp", 1),
+            FORMAT => ("format", 2),
+            HIBOUND => ("hibound", 1),
+            HIINDEX => ("hiindex", 1),
+            LENGTH => ("length", 1),
+            LOBOUND => ("lobound", 1),
+            LOINDEX => ("loindex", 1),
+            LOG => ("log", 1),
+            LOG2 => ("log2", 1),
+            LOG10 => ("log10", 1),
+            NVL => ("nvl", 2),
+            ODD => ("odd", 1),
+            ROLESOF => ("rolesof", 1),
+            SIN => ("sin", 1),
+            SIZEOF => ("sizeof", 1),
+            SQRT => ("sqrt", 1),
+            TAN => ("tan", 1),
+            TYPEOF => ("type_of", 1),
+            USEDIN => ("usedin", 2),
+            VALUE => ("value", 1),
+            VALUE_IN => ("value_in", 2),
+            VALUE_UNIQUE => ("value_unique", 1),
+        };
+        if args.len() != arity {
+            return Err(Unsupported(format!(
+                "{:?} with {} arguments",
+                f,
+                args.len()
+            )));
+        }
+        if *f == USEDIN {
+            // Entity instances in `runtime::Value` do not know which instances refer them
+            if self.population == PopulationRef::None {
+                return Err(Unsupported("USEDIN out of tables".to_string()));
+            }
+            let role = self.translate(&args[1])?;
+            if self.self_ref != SelfRef::None && is_self(&args[0]) {
+                return Ok(quote! { runtime::usedin_in(tables, id, #role) });
+            }
+            let value = self.translate(&args[0])?;
+            return Ok(match self.population {
+                PopulationRef::Optional => quote! {
+                    match tables {
+                        Some(population) => runtime::usedin_of(population, #value, #role),
+                        None => runtime::Value::Indeterminate,
+                    }
+                },
+                _ => quote! { runtime::usedin_of(tables, #value, #role) },
+            });
+        }
+        let f = format_ident!("{}", name);
+        let args = args
+            .iter()
+            .map(|arg| self.translate(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(quote! { runtime::#f(#(#args),*) })
+    }
+
+    /// Call of a function declared in the schema, which takes the tables as the first argument
+    fn function_call(
+        &mut self,
+        name: &str,
+        args: &[ast::Expression],
+    ) -> Result<TokenStream, Unsupported> {
+        let function = self
+            .enclosing
+            .function(name)
+            .or_else(|| self.schema.functions.iter().find(|f| f.name == name))
+            .ok_or_else(|| Unsupported(format!("function call of {}", name)))?;
+        if function.parameters.len() != args.len() {
+            return Err(Unsupported(format!(
+                "{} with {} arguments",
+                name,
+                args.len()
+            )));
+        }
+        let ident = format_ident!("{}", name.into_safe());
+        let tables = self.tables_argument();
+        let args = args
+            .iter()
+            .map(|arg| self.translate(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(quote! { #ident(#tables #(, #args)*) })
+    }
+
+    /// `tables: Option<&dyn runtime::Population>` passed to functions and procedures
+    pub(super) fn tables_argument(&self) -> TokenStream {
+        match self.population {
+            PopulationRef::None => quote! { None },
+            PopulationRef::Tables => quote! { Some(tables) },
+            PopulationRef::Optional => quote! { tables },
+        }
+    }
+
+    /// Operand of `||`, where an entity constructor may take only the explicit attributes
+    /// declared in the entity itself, e.g. `representation_item('') || geometric_representation_item()`
+    fn complex_operand(&mut self, expr: &ast::Expression) -> Result<TokenStream, Unsupported> {
+        let (name, values) = match expr {
+            ast::Expression::EntityConstructor { name, values } => (name, values),
+            ast::Expression::QualifiableFactor {
+                factor:
+                    ast::QualifiableFactor::FunctionCall {
+                        name: ast::FunctionCallName::Reference(name),
+                        args,
+                    },
+                qualifiers,
+            } if qualifiers.is_empty() => (name, args),
+            _ => return self.translate(expr),
+        };
+        let entity = match self.schema.entity(name) {
+            Some(entity) if entity.attributes.len() == values.len() => entity,
+            _ => return self.translate(expr),
+        };
+        let type_name = self.schema.type_name(&entity.name);
+        let attributes = entity.attributes.iter().map(|attr| &attr.name);
+        let values = values
+            .iter()
+            .map(|value| self.translate(value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(quote! {
+            runtime::Value::entity(runtime::ConstructedEntity {
+                type_names: vec![#type_name.to_string()],
+                attributes: vec![#((#attributes.to_string(), #values)),*],
+            })
+        })
+    }
+
+    /// Entity constructor, e.g. `point(0.0, 0.0)`, which takes the explicit attributes
+    /// of the entity and its supertypes in the order of declaration
+    fn entity_constructor(
+        &mut self,
+        name: &str,
+        values: &[ast::Expression],
+    ) -> Result<TokenStream, Unsupported> {
+        let entity = self
+            .schema
+            .entity(name)
+            .ok_or_else(|| Unsupported(format!("constructor of {}", name)))?;
+        let attributes = self.schema.explicit_attributes(entity);
+        if attributes.len() != values.len() {
+            return Err(Unsupported(format!(
+                "constructor of {} with {} arguments",
+                name,
+                values.len()
+            )));
+        }
+        let type_names = self.schema.type_names(entity);
+        let values = values
+            .iter()
+            .map(|value| self.translate(value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(quote! {
+            runtime::Value::entity(runtime::ConstructedEntity {
+                type_names: vec![#(#type_names.to_string()),*],
+                attributes: vec![#((#attributes.to_string(), #values)),*],
+            })
+        })
+    }
+}
+
+pub(crate) fn variable_ident(name: &str) -> syn::Ident {
+    match name {
+        // Avoid shadowing the arguments of rules
+        "this" | "id" | "tables" => format_ident!("{}_", name),
+        _ => format_ident!("{}", name.into_safe()),
+    }
+}
+
+fn is_self(expr: &ast::Expression) -> bool {
+    matches!(
+        expr,
+        ast::Expression::QualifiableFactor { factor, qualifiers }
+            if qualifiers.is_empty()
+                && *factor == ast::QualifiableFactor::BuiltInConstant(ast::BuiltInConstant::Self_)
+    )
+}
+
+pub(super) fn literal_value(literal: &ast::Literal) -> TokenStream {
+    match literal {
+        // Integer literals are also parsed as real numbers
+        ast::Literal::Real(value) if value.fract() == 0.0 && value.abs() < i64::MAX as f64 => {
+            let value = proc_macro2::Literal::i64_unsuffixed(*value as i64);
+            quote! { runtime::Value::Integer(#value) }
+        }
+        ast::Literal::Real(value) => {
+            let value = proc_macro2::Literal::f64_unsuffixed(*value);
+            quote! { runtime::Value::Real(#value) }
+        }
+        ast::Literal::String(s) => quote! { runtime::Value::String(#s.to_string()) },
+        ast::Literal::Logial(logical) => {
+            let logical = match logical {
+                ast::Logical::True => quote! { True },
+                ast::Logical::False => quote! { False },
+                ast::Logical::Unknown => quote! { Unknown },
+            };
+            quote! { runtime::Value::Logical(Logical::#logical) }
+        }
+    }
+}
+
+fn enumeration_value(item: &str) -> TokenStream {
+    let item = item.to_lowercase();
+    quote! { runtime::Value::Enumeration(#item.to_string()) }
+}
+
+// Lookup of declarations used in the translation
+impl Schema {
+    /// Copy of the schema without the declarations which cannot be translated yet,
+    /// i.e. functions and procedures whose bodies are not supported,
+    /// and constants and derived attributes whose expressions are not supported
+    ///
+    /// References to the removed declarations also fail to be translated,
+    /// and the rules depending on them are reported as not evaluated
+    /// instead of being evaluated as indeterminate.
+    pub(crate) fn translatable(&self) -> Schema {
+        let mut schema = self.clone();
+        loop {
+            let current = schema.clone();
+            schema
+                .functions
+                .retain(|f| current.function_item(f, &Enclosing::default()).is_ok());
+            schema
+                .procedures
+                .retain(|p| current.procedure_item(p, &Enclosing::default()).is_ok());
+            schema.constants.retain(|c| {
+                c.literal().is_some() || Translator::new(&current).translate(&c.expr).is_ok()
+            });
+            for (entity, original) in schema.entities.iter_mut().zip(&current.entities) {
+                entity.derived_attributes.retain(|attr| {
+                    Translator::entity(&current, original)
+                        .translate(&attr.expr)
+                        .is_ok()
+                });
+            }
+            if schema == current {
+                return schema;
+            }
+        }
+    }
+
+    pub fn entity(&self, name: &str) -> Option<&Entity> {
+        self.entities.iter().find(|e| e.name == name)
+    }
+
+    pub fn constant(&self, name: &str) -> Option<&Constant> {
+        self.constants.iter().find(|c| c.name == name)
+    }
+
+    /// Supertypes of the entity declared in this schema
+    pub(crate) fn supertype_entities<'a>(
+        &'a self,
+        entity: &'a Entity,
+    ) -> impl Iterator<Item = &'a Entity> {
+        entity.supertypes.iter().filter_map(move |ty| match ty {
+            TypeRef::Entity { name, .. } => self.entity(name),
+            _ => None,
+        })
+    }
+
+    /// Check if the entity or its supertypes have an explicit, derived, or inverse attribute
+    fn has_attribute(&self, entity: &Entity, name: &str) -> bool {
+        entity.attributes.iter().any(|attr| attr.name == name)
+            || entity
+                .redeclared_attributes
+                .iter()
+                .any(|attr| attr.rename.as_deref() == Some(name))
+            || entity
+                .derived_attributes
+                .iter()
+                .any(|attr| attr.name == name)
+            || entity
+                .inverse_attributes
+                .iter()
+                .any(|attr| attr.name == name)
+            || self
+                .supertype_entities(entity)
+                .any(|sup| self.has_attribute(sup, name))
+    }
+
+    /// `INVERSE` attribute declared in the entity or its supertypes
+    fn inverse_attribute<'a>(
+        &'a self,
+        entity: &'a Entity,
+        name: &str,
+    ) -> Option<&'a InverseAttribute> {
+        entity
+            .inverse_attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .or_else(|| {
+                self.supertype_entities(entity)
+                    .find_map(|sup| self.inverse_attribute(sup, name))
+            })
+    }
+
+    /// Names of explicit attributes of the entity and its supertypes in the order of declaration
+    fn explicit_attributes(&self, entity: &Entity) -> Vec<String> {
+        let mut attributes = Vec::new();
+        for sup in self.supertype_entities(entity) {
+            for attr in self.explicit_attributes(sup) {
+                if !attributes.contains(&attr) {
+                    attributes.push(attr);
+                }
+            }
+        }
+        attributes.extend(entity.attributes.iter().map(|attr| attr.name.clone()));
+        attributes
+    }
+
+    /// Names of the entity and its supertypes returned by `TYPEOF`, e.g. `"SCHEMA.POINT"`
+    pub(crate) fn type_names(&self, entity: &Entity) -> Vec<String> {
+        let mut names = vec![self.type_name(&entity.name)];
+        for sup in self.supertype_entities(entity) {
+            for name in self.type_names(sup) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Name of the entity or type qualified by the schema where it is declared
+    pub(crate) fn type_name(&self, name: &str) -> String {
+        format!("{}.{}", self.declaring_schema(name), name).to_uppercase()
+    }
+
+    fn enumeration_items(&self) -> impl Iterator<Item = &String> {
+        self.types.iter().flat_map(|ty| match ty {
+            TypeDecl::Enumeration(e) => e.items.iter(),
+            _ => [].iter(),
+        })
+    }
+}
@@ -0,0 +1,209 @@
+//! Generate Rust functions from `FUNCTION` and `PROCEDURE` declarations
+
+use super::expression::*;
+use crate::{ast, ir::*};
+
+use check_keyword::CheckKeyword;
+use proc_macro2::TokenStream;
+use quote::*;
+
+impl Schema {
+    /// Functions and procedures taking `tables: Option<&dyn runtime::Population>` and `runtime::Value`s
+    ///
+    /// Functions and procedures whose bodies cannot be translated are not generated,
+    /// see [Schema::translatable].
+    pub(crate) fn function_items(&self) -> TokenStream {
+        let functions = self
+            .functions
+            .iter()
+            .filter_map(|f| self.function_item(f, &Enclosing::default()).ok());
+        let procedures = self
+            .procedures
+            .iter()
+            .filter_map(|p| self.procedure_item(p, &Enclosing::default()).ok());
+        quote! {
+            #(#functions)*
+            #(#procedures)*
+        }
+    }
+
+    pub(crate) fn function_item<'a>(
+        &'a self,
+        function: &'a Function,
+        enclosing: &Enclosing<'a>,
+    ) -> Result<TokenStream, Unsupported> {
+        self.algorithm_item(
+            &function.name,
+            &function.parameters,
+            Body {
+                functions: &function.functions,
+                procedures: &function.procedures,
+                declarations: &function.declarations,
+                constants: &function.constants,
+                variables: &function.variables,
+                statements: &function.statements,
+            },
+            Returns::Value,
+            enclosing,
+        )
+    }
+
+    pub(crate) fn procedure_item<'a>(
+        &'a self,
+        procedure: &'a Procedure,
+        enclosing: &Enclosing<'a>,
+    ) -> Result<TokenStream, Unsupported> {
+        self.algorithm_item(
+            &procedure.name,
+            &procedure.parameters,
+            Body {
+                functions: &procedure.functions,
+                procedures: &procedure.procedures,
+                declarations: &procedure.declarations,
+                constants: &procedure.constants,
+                variables: &procedure.variables,
+                statements: &procedure.statements,
+            },
+            Returns::Unit,
+            enclosing,
+        )
+    }
+
+    /// `fn` item of a function or procedure,
+    /// which is `pub` if it is declared in the schema, and nested in the enclosing `fn` otherwise
+    fn algorithm_item<'a>(
+        &'a self,
+        name: &str,
+        parameters: &[Parameter],
+        body: Body<'a>,
+        returns: Returns,
+        enclosing: &Enclosing<'a>,
+    ) -> Result<TokenStream, Unsupported> {
+        let ident = format_ident!("{}", name.into_safe());
+        let params = parameters.iter().map(|p| {
+            let ident = variable_ident(&p.name);
+            if p.is_variable {
+                quote! { #ident: &mut runtime::Value }
+            } else {
+                quote! { mut #ident: runtime::Value }
+            }
+        });
+        let output = match returns {
+            Returns::Value => quote! { -> runtime::Value },
+            _ => TokenStream::new(),
+        };
+        let visibility = if enclosing.is_empty() {
+            quote! { pub }
+        } else {
+            TokenStream::new()
+        };
+        let body = self.algorithm_body(parameters, &body, returns, enclosing)?;
+        Ok(quote! {
+            #[allow(unused_variables, unused_mut, unused_assignments, unused_labels, unreachable_code, clippy::never_loop)]
+            #visibility fn #ident(tables: Option<&dyn runtime::Population>, #(#params),*) #output {
+                #body
+            }
+        })
+    }
+
+    fn algorithm_body<'a>(
+        &'a self,
+        parameters: &[Parameter],
+        body: &Body<'a>,
+        returns: Returns,
+        enclosing: &Enclosing<'a>,
+    ) -> Result<TokenStream, Unsupported> {
+        let (enclosing, nested) = self.nested_items(
+            enclosing,
+            body.functions,
+            body.procedures,
+            body.declarations,
+        )?;
+        let mut translator =
+            Translator::algorithm(self, parameters, body.constants, body.variables, returns);
+        translator.enclosing = enclosing;
+        let declarations = translator.declarations(body.constants, body.variables)?;
+        let statements = translator.statements(body.statements)?;
+        let returned = matches!(body.statements.last(), Some(ast::Statement::Return { .. }));
+        let tail = if returns == Returns::Value && !returned {
+            quote! { runtime::Value::Indeterminate }
+        } else {
+            TokenStream::new()
+        };
+        Ok(quote! {
+            #nested
+            #declarations
+            #statements
+            #tail
+        })
+    }
+
+    /// `fn` items of the functions and procedures declared in a function, procedure, or rule,
+    /// with the enclosing ones including them
+    pub(crate) fn nested_items<'a>(
+        &'a self,
+        enclosing: &Enclosing<'a>,
+        functions: &'a [Function],
+        procedures: &'a [Procedure],
+        declarations: &[ast::Declaration],
+    ) -> Result<(Enclosing<'a>, TokenStream), Unsupported> {
+        if !declarations.is_empty() {
+            return Err(Unsupported(
+                "TYPE, ENTITY, or SUBTYPE_CONSTRAINT declared in an algorithm".to_string(),
+            ));
+        }
+        let mut enclosing = enclosing.clone();
+        enclosing.functions.extend(functions);
+        enclosing.procedures.extend(procedures);
+        let mut items = TokenStream::new();
+        for function in functions {
+            items.append_all(self.function_item(function, &enclosing)?);
+        }
+        for procedure in procedures {
+            items.append_all(self.procedure_item(procedure, &enclosing)?);
+        }
+        Ok((enclosing, items))
+    }
+}
+
+/// Functions and procedures declared in the enclosing functions, procedures, or rule,
+/// which are visible in nested ones and shadow the ones declared in the schema
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Enclosing<'a> {
+    pub(crate) functions: Vec<&'a Function>,
+    pub(crate) procedures: Vec<&'a Procedure>,
+}
+
+impl<'a> Enclosing<'a> {
+    fn is_empty(&self) -> bool {
+        self.functions.is_empty() && self.procedures.is_empty()
+    }
+
+    /// Innermost function of the name
+    pub(crate) fn function(&self, name: &str) -> Option<&'a Function> {
+        self.functions
+            .iter()
+            .rev()
+            .find(|f| f.name == name)
+            .cloned()
+    }
+
+    /// Innermost procedure of the name
+    pub(crate) fn procedure(&self, name: &str) -> Option<&'a Procedure> {
+        self.procedures
+            .iter()
+            .rev()
+            .find(|p| p.name == name)
+            .cloned()
+    }
+}
+
+/// Body of `FUNCTION` or `PROCEDURE`
+struct Body<'a> {
+    functions: &'a [Function],
+    procedures: &'a [Procedure],
+    declarations: &'a [ast::Declaration],
+    constants: &'a [ast::Constant],
+    variables: &'a [ast::LocalVariable],
+    statements: &'a [ast::Statement],
+}
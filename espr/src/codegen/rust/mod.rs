@@ -1,12 +1,19 @@
 //! Generate Rust code using proc-macro utility crates
 
+mod constant;
 mod entity;
+mod expression;
 mod format;
+mod function;
 mod schema;
 mod simple_type;
+mod statement;
 mod type_decl;
 mod type_ref;
+mod validate;
+mod value;
 
 pub use entity::*;
+pub use expression::*;
 pub use format::rustfmt;
 pub use schema::*;
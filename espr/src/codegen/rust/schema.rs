@@ -34,8 +34,10 @@ impl IR {
 impl Schema {
     pub fn to_token_stream(&self, prefix: CratePrefix) -> TokenStream {
         let name = format_ident!("{}", self.name);
+        let schema_name = &self.name;
         let types = &self.types;
         let entities = &self.entities;
+        let complex_entities = self.complex_entities();
         let type_decls = self.types.iter().filter(|e| match e {
             TypeDecl::Enumeration(_) => false,
             _ => true,
@@ -43,6 +45,7 @@ impl Schema {
         let entity_types: Vec<_> = entities
             .iter()
             .map(|e| format_ident!("{}", e.name.to_pascal_case()))
+            .chain(complex_entities.iter().map(|c| c.name_ident()))
             .chain(
                 type_decls
                     .clone()
@@ -52,6 +55,7 @@ impl Schema {
         let holder_name: Vec<_> = entities
             .iter()
             .map(|e| format_ident!("{}", e.name.as_str().into_safe()))
+            .chain(complex_entities.iter().map(|c| c.field_ident()))
             .chain(
                 type_decls
                     .clone()
@@ -61,21 +65,45 @@ impl Schema {
         let holders_name: Vec<_> = entities
             .iter()
             .map(|e| format_ident!("{}_holders", e.name))
+            .chain(
+                complex_entities
+                    .iter()
+                    .map(|c| format_ident!("{}_holders", c.name)),
+            )
             .chain(type_decls.map(|e| format_ident!("{}_holders", e.id())))
             .collect();
 
         let ruststep_path = prefix.as_path();
+        let inverse_methods = self.inverse_methods(&ruststep_path);
+        // Rules depending on declarations which cannot be translated are not evaluated
+        let translatable = self.translatable();
+        let value_impls = translatable.value_impls();
+        let validate_impls = translatable.validate_impls();
+        let tables_validate_impls = translatable.tables_validate_impls(&ruststep_path);
+        let constant_items = translatable.constant_items();
+        let function_items = translatable.function_items();
+        // Interfaced schemas are also accessible by their names, and export their own declarations
+        let (interfaced, exports): (Vec<_>, Vec<_>) = self
+            .interfaced
+            .iter()
+            .map(|schema| (format_ident!("{}", schema.name), self.exports(schema)))
+            .unzip();
 
         quote! {
+            // Generated code is not written to satisfy clippy, e.g. `Into` implementations for sub-types
+            #[allow(clippy::all)]
             pub mod #name {
-                use #ruststep_path::{as_holder, Holder, TableInit, primitive::*, derive_more::*};
+                use #ruststep_path::{as_holder, Holder, TableInit, primitive::*, derive_more::*, runtime};
                 use std::collections::HashMap;
 
                 #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+                #[table(schema = #schema_name)]
                 pub struct Tables {
                     #(
                     #holder_name: HashMap<u64, as_holder!(#entity_types)>,
                     )*
+                    names: #ruststep_path::tables::Names,
+                    references: #ruststep_path::tables::ReferenceIndex,
                 }
 
                 impl Tables {
@@ -84,11 +112,112 @@ impl Schema {
                         &self.#holder_name
                     }
                     )*
+                    #(#inverse_methods)*
                 }
 
+                #tables_validate_impls
+
                 #(#types)*
                 #(#entities)*
+                #(#complex_entities)*
+                #value_impls
+                #validate_impls
+                #constant_items
+                #function_items
+            }
+            #(
+            pub mod #interfaced {
+                #exports
+            }
+            )*
+        }
+    }
+
+    /// Re-export the entities and types declared in the interfaced schema, e.g.
+    /// `pub use super::topology::{Point, PointHolder};` in the module `geometry`
+    fn exports(&self, interfaced: &InterfacedSchema) -> TokenStream {
+        let name = format_ident!("{}", self.name);
+        let mut items = Vec::new();
+        for declaration in &interfaced.declarations {
+            let id = declaration.to_pascal_case();
+            if let Some(entity) = self.entity(declaration) {
+                items.push(format_ident!("{}", id));
+                items.push(format_ident!("{}Holder", id));
+                if !entity.constraints.is_empty() {
+                    items.push(format_ident!("{}Any", id));
+                    items.push(format_ident!("{}AnyHolder", id));
+                }
+            } else if let Some(ty) = self.types.iter().find(|ty| ty.id() == declaration) {
+                items.push(format_ident!("{}", id));
+                // Enumeration does not have Holder
+                if !matches!(ty, TypeDecl::Enumeration(_)) {
+                    items.push(format_ident!("{}Holder", id));
+                }
             }
         }
+        if items.is_empty() {
+            return TokenStream::new();
+        }
+        quote! { pub use super::#name::{#(#items),*}; }
+    }
+
+    /// Methods on `Tables` for `INVERSE` attributes, e.g. `opens_of(id)` for `opens: door FOR handle;`
+    ///
+    /// The method is prefixed by the entity name, e.g. `knob_opens_of(id)`,
+    /// if another entity declares the inverse attribute of the same name.
+    fn inverse_methods(&self, ruststep_path: &syn::Path) -> Vec<TokenStream> {
+        let inverses: Vec<(&Entity, &InverseAttribute)> = self
+            .entities
+            .iter()
+            .flat_map(|e| e.inverse_attributes.iter().map(move |inv| (e, inv)))
+            .collect();
+        inverses
+            .iter()
+            .map(|(entity, inv)| {
+                let duplicated = inverses
+                    .iter()
+                    .filter(|(_, other)| other.name == inv.name)
+                    .count()
+                    > 1;
+                let method = if duplicated {
+                    format_ident!("{}_{}_of", entity.name, inv.name)
+                } else {
+                    format_ident!("{}_of", inv.name)
+                };
+                let dest = &inv.dest;
+                let attribute = &inv.attribute;
+                let (lower, upper) = inv.bound.cardinality();
+                let lower = proc_macro2::Literal::usize_unsuffixed(lower);
+                let upper = match upper {
+                    Some(upper) => {
+                        let upper = proc_macro2::Literal::usize_unsuffixed(upper);
+                        quote! { Some(#upper) }
+                    }
+                    None => quote! { None },
+                };
+                let instances = quote! {
+                    #ruststep_path::tables::ReferenceTable::inverse::<as_holder!(#dest)>(
+                        self,
+                        id,
+                        #attribute,
+                        #ruststep_path::tables::Bound { lower: #lower, upper: #upper },
+                    )
+                };
+                if inv.aggregated {
+                    quote! {
+                        pub fn #method(&self, id: u64) -> #ruststep_path::error::Result<Vec<#dest>> {
+                            #instances
+                        }
+                    }
+                } else {
+                    quote! {
+                        pub fn #method(&self, id: u64) -> #ruststep_path::error::Result<#dest> {
+                            let mut instances = #instances?;
+                            Ok(instances.remove(0))
+                        }
+                    }
+                }
+            })
+            .collect()
     }
 }
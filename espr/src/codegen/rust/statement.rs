@@ -0,0 +1,386 @@
+//! Translate EXPRESS statements in `FUNCTION`, `PROCEDURE`, and `RULE` into Rust code
+
+use super::expression::*;
+use crate::ast;
+
+use check_keyword::CheckKeyword;
+use proc_macro2::TokenStream;
+use quote::*;
+
+impl<'a> Translator<'a> {
+    /// Local constants and variables, which must be visible in this translator
+    ///
+    /// Variables without initializer are indeterminate.
+    pub fn declarations(
+        &mut self,
+        constants: &[ast::Constant],
+        variables: &[ast::LocalVariable],
+    ) -> Result<TokenStream, Unsupported> {
+        let mut tt = TokenStream::new();
+        for constant in constants {
+            let ident = variable_ident(&constant.name);
+            let expr = self.translate(&constant.expr)?;
+            tt.append_all(quote! { let #ident = #expr; });
+        }
+        for variable in variables {
+            let ident = variable_ident(&variable.name);
+            let expr = match &variable.expr {
+                Some(expr) => self.translate(expr)?,
+                None => quote! { runtime::Value::Indeterminate },
+            };
+            tt.append_all(quote! { let mut #ident = #expr; });
+        }
+        Ok(tt)
+    }
+
+    pub fn statements(
+        &mut self,
+        statements: &[ast::Statement],
+    ) -> Result<TokenStream, Unsupported> {
+        let mut tt = TokenStream::new();
+        for statement in statements {
+            tt.append_all(self.statement(statement)?);
+        }
+        Ok(tt)
+    }
+
+    fn statement(&mut self, statement: &ast::Statement) -> Result<TokenStream, Unsupported> {
+        use ast::Statement::*;
+        Ok(match statement {
+            Alias {
+                name,
+                dest,
+                qualifiers,
+                statements,
+            } => {
+                let value = self.translate(&ast::Expression::QualifiableFactor {
+                    factor: ast::QualifiableFactor::Reference(dest.clone()),
+                    qualifiers: qualifiers.clone(),
+                })?;
+                let ident = variable_ident(name);
+                self.variables.push(name.clone());
+                self.aliases.push(name.clone());
+                let statements = self.statements(statements);
+                self.aliases.pop();
+                self.variables.pop();
+                let statements = statements?;
+                quote! {
+                    {
+                        let #ident = #value;
+                        #statements
+                    }
+                }
+            }
+            Assignment {
+                name,
+                qualifiers,
+                expr,
+            } => self.assignment(name, qualifiers, expr)?,
+            Compound { statements } => {
+                let statements = self.statements(statements)?;
+                quote! { { #statements } }
+            }
+            If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.translate(condition)?;
+                let then_branch = self.statements(then_branch)?;
+                match else_branch {
+                    Some(else_branch) => {
+                        let else_branch = self.statements(else_branch)?;
+                        quote! {
+                            if runtime::Value::is_true(&#condition) {
+                                #then_branch
+                            } else {
+                                #else_branch
+                            }
+                        }
+                    }
+                    None => quote! {
+                        if runtime::Value::is_true(&#condition) {
+                            #then_branch
+                        }
+                    },
+                }
+            }
+            Case {
+                selector,
+                actions,
+                otherwise,
+            } => {
+                let selector = self.translate(selector)?;
+                // The first action whose label equals the selector is executed
+                let mut branches = match otherwise {
+                    Some(otherwise) => self.statement(otherwise)?,
+                    None => TokenStream::new(),
+                };
+                for (labels, action) in actions.iter().rev() {
+                    let labels = labels
+                        .iter()
+                        .map(|label| self.translate(label))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let action = self.statement(action)?;
+                    branches = quote! {
+                        if #(runtime::Value::is_true(&runtime::eq(_selector.clone(), #labels)))||* {
+                            #action
+                        } else {
+                            #branches
+                        }
+                    };
+                }
+                quote! {
+                    {
+                        let _selector = #selector;
+                        #branches
+                    }
+                }
+            }
+            Repeat {
+                control,
+                statements,
+            } => self.repeat(control, statements)?,
+            Return { value } => match (self.returns, value) {
+                (Returns::Value, Some(value)) => {
+                    let value = self.translate(value)?;
+                    quote! { return #value; }
+                }
+                (Returns::Unit, None) => quote! { return; },
+                _ => return Err(Unsupported(format!("RETURN {:?}", value))),
+            },
+            ProcedureCall {
+                procedure,
+                parameters,
+            } => self.procedure_call(procedure, parameters.as_deref().unwrap_or(&[]))?,
+            Skip => {
+                let label = self.loop_label()?;
+                quote! { continue #label; }
+            }
+            Escape => {
+                let label = self.loop_label()?;
+                quote! { break #label; }
+            }
+            Null => TokenStream::new(),
+        })
+    }
+
+    fn assignment(
+        &mut self,
+        name: &str,
+        qualifiers: &[ast::Qualifier],
+        expr: &ast::Expression,
+    ) -> Result<TokenStream, Unsupported> {
+        let target = self.assignable(name)?;
+        let value = self.translate(expr)?;
+        match qualifiers {
+            [] => {
+                let ident = variable_ident(name);
+                Ok(if self.var_parameters.iter().any(|v| v == name) {
+                    quote! { *#ident = #value; }
+                } else {
+                    quote! { #ident = #value; }
+                })
+            }
+            [ast::Qualifier::Index(index)] => {
+                let index = self.translate(index)?;
+                // Evaluate the operands before borrowing the target mutably
+                Ok(quote! {
+                    {
+                        let _index = #index;
+                        let _value = #value;
+                        runtime::set_index(#target, _index, _value);
+                    }
+                })
+            }
+            _ => Err(Unsupported(format!("assignment to qualified {}", name))),
+        }
+    }
+
+    /// `&mut runtime::Value` of a local variable or a `VAR` parameter
+    fn assignable(&self, name: &str) -> Result<TokenStream, Unsupported> {
+        if !self.variables.iter().any(|v| v == name) || self.aliases.iter().any(|v| v == name) {
+            return Err(Unsupported(format!("assignment to {}", name)));
+        }
+        let ident = variable_ident(name);
+        Ok(if self.var_parameters.iter().any(|v| v == name) {
+            quote! { &mut *#ident }
+        } else {
+            quote! { &mut #ident }
+        })
+    }
+
+    /// `REPEAT` statement as a labeled `loop`, where
+    ///
+    /// - the increment control variable is declared in the block of the loop,
+    ///   and `begin`, `end`, and `increment` are evaluated once
+    /// - `UNTIL` is checked at the end of each iteration, i.e. at the beginning of next one
+    ///   before incrementing the variable
+    fn repeat(
+        &mut self,
+        control: &ast::RepeatControl,
+        statements: &[ast::Statement],
+    ) -> Result<TokenStream, Unsupported> {
+        let mut init = TokenStream::new();
+        let mut increment = TokenStream::new();
+        if let Some(inc) = &control.increment {
+            let begin = self.translate(&inc.begin)?;
+            let end = self.translate(&inc.end)?;
+            let step = match &inc.increment {
+                Some(step) => self.translate(step)?,
+                None => quote! { runtime::Value::Integer(1) },
+            };
+            let var = variable_ident(&inc.variable);
+            init = quote! {
+                let _end = #end;
+                let _step = #step;
+                let mut #var = #begin;
+            };
+            increment = quote! { #var = runtime::add(#var.clone(), _step.clone()); };
+            self.variables.push(inc.variable.clone());
+        }
+
+        self.loops += 1;
+        let body = self.repeat_body(control, statements, increment);
+        self.loops -= 1;
+        if control.increment.is_some() {
+            self.variables.pop();
+        }
+        let body = body?;
+
+        Ok(quote! {
+            {
+                #init
+                let mut _first = true;
+                #body
+            }
+        })
+    }
+
+    fn repeat_body(
+        &mut self,
+        control: &ast::RepeatControl,
+        statements: &[ast::Statement],
+        increment: TokenStream,
+    ) -> Result<TokenStream, Unsupported> {
+        let label = self.loop_label()?;
+        let mut checks = TokenStream::new();
+        if let Some(inc) = &control.increment {
+            let var = variable_ident(&inc.variable);
+            checks.append_all(quote! {
+                if !runtime::in_repeat_range(&#var, &_end, &_step) {
+                    break #label;
+                }
+            });
+        }
+        if let Some(while_) = &control.while_ {
+            let while_ = self.translate(while_)?;
+            checks.append_all(quote! {
+                if !runtime::Value::is_true(&#while_) {
+                    break #label;
+                }
+            });
+        }
+        let until = match &control.until {
+            Some(until) => {
+                let until = self.translate(until)?;
+                quote! {
+                    if runtime::Value::is_true(&#until) {
+                        break #label;
+                    }
+                }
+            }
+            None => TokenStream::new(),
+        };
+        let statements = self.statements(statements)?;
+        Ok(quote! {
+            #label: loop {
+                if !_first {
+                    #until
+                    #increment
+                }
+                _first = false;
+                #checks
+                #statements
+            }
+        })
+    }
+
+    /// Label of the innermost `REPEAT`, e.g. `'repeat_1`
+    fn loop_label(&self) -> Result<syn::Lifetime, Unsupported> {
+        if self.loops == 0 {
+            return Err(Unsupported("SKIP or ESCAPE out of REPEAT".to_string()));
+        }
+        Ok(syn::Lifetime::new(
+            &format!("'repeat_{}", self.loops),
+            proc_macro2::Span::call_site(),
+        ))
+    }
+
+    fn procedure_call(
+        &mut self,
+        procedure: &ast::ProcedureCallName,
+        args: &[ast::Expression],
+    ) -> Result<TokenStream, Unsupported> {
+        // Built-in procedures do not take the tables
+        let (call, mut passed, parameters) = match procedure {
+            ast::ProcedureCallName::Insert => (
+                quote! { runtime::insert },
+                Vec::new(),
+                vec![true, false, false],
+            ),
+            ast::ProcedureCallName::Remove => {
+                (quote! { runtime::remove }, Vec::new(), vec![true, false])
+            }
+            ast::ProcedureCallName::Reference(name) => {
+                let procedure = self
+                    .enclosing
+                    .procedure(name)
+                    .or_else(|| self.schema.procedures.iter().find(|p| p.name == *name))
+                    .ok_or_else(|| Unsupported(format!("procedure call of {}", name)))?;
+                let ident = format_ident!("{}", name.as_str().into_safe());
+                let tables = self.tables_argument();
+                let parameters = procedure.parameters.iter().map(|p| p.is_variable).collect();
+                (quote! { #ident }, vec![tables], parameters)
+            }
+        };
+        if parameters.len() != args.len() {
+            return Err(Unsupported(format!(
+                "procedure {:?} with {} arguments",
+                procedure,
+                args.len()
+            )));
+        }
+
+        // Arguments passed by value are evaluated before borrowing variables mutably
+        let mut values = TokenStream::new();
+        let mut names = Vec::new();
+        for (i, (arg, is_variable)) in args.iter().zip(parameters).enumerate() {
+            if is_variable {
+                let name = match arg {
+                    ast::Expression::QualifiableFactor {
+                        factor: ast::QualifiableFactor::Reference(name),
+                        qualifiers,
+                    } if qualifiers.is_empty() => name,
+                    _ => return Err(Unsupported(format!("VAR argument {:?}", arg))),
+                };
+                if names.contains(&name) {
+                    return Err(Unsupported(format!("{} passed twice as VAR", name)));
+                }
+                names.push(name);
+                passed.push(self.assignable(name)?);
+            } else {
+                let ident = format_ident!("_arg{}", i);
+                let arg = self.translate(arg)?;
+                values.append_all(quote! { let #ident = #arg; });
+                passed.push(quote! { #ident });
+            }
+        }
+        Ok(quote! {
+            {
+                #values
+                #call(#(#passed),*);
+            }
+        })
+    }
+}
@@ -45,10 +45,11 @@ impl ToTokens for Rename {
             }
             _ => rename_meta(&field_name),
         };
+        let bound = ty.holder_bound();
 
         tokens.append_all(quote! {
             #derive
-            pub struct #id(#use_place_holder pub #ty);
+            pub struct #id(#use_place_holder #bound pub #ty);
         });
     }
 }
@@ -86,7 +87,7 @@ impl ToTokens for Enumeration {
             .map(|i| format_ident!("{}", i.to_pascal_case()))
             .collect();
         tokens.append_all(quote! {
-            #[derive(Debug, Clone, PartialEq, ::serde::Deserialize)]
+            #[derive(Debug, Clone, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
             pub enum #id {
                 #( #items ),*
             }
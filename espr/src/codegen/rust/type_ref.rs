@@ -2,7 +2,7 @@ use inflector::Inflector;
 use proc_macro2::TokenStream;
 use quote::*;
 
-use crate::ir::*;
+use crate::{ast, ir::*};
 
 impl ToTokens for TypeRef {
     fn to_tokens(&self, tokens: &mut TokenStream) {
@@ -23,9 +23,77 @@ impl ToTokens for TypeRef {
                 };
                 tokens.append_all(quote! { #name });
             }
-            Set { base, .. } | List { base, .. } => {
+            Set { base, .. } | Bag { base, .. } | List { base, .. } => {
                 tokens.append_all(quote! { Vec<#base> });
             }
+            Array {
+                base,
+                bound,
+                optional,
+                ..
+            } => {
+                if *optional {
+                    tokens.append_all(quote! { Vec<Option<#base>> });
+                } else if let Some(size) = bound.as_ref().and_then(Bound::array_size) {
+                    let size = proc_macro2::Literal::usize_unsuffixed(size);
+                    tokens.append_all(quote! { [#base; #size] });
+                } else {
+                    tokens.append_all(quote! { Vec<#base> });
+                }
+            }
         }
     }
 }
+
+// Additional functions to use in codegen/rust for ir::Bound.
+impl Bound {
+    /// Range of the number of elements `(lower, upper)`,
+    /// where the bounds not evaluated are relaxed into `0` and unbounded
+    pub(crate) fn cardinality(&self) -> (usize, Option<usize>) {
+        (
+            self.lower.map_or(0, |lower| lower.max(0) as usize),
+            self.upper.map(|upper| upper.max(0) as usize),
+        )
+    }
+}
+
+// Additional functions to use in codegen/rust for ir::TypeRef.
+impl TypeRef {
+    /// Range of the number of elements as `#[holder(bound = ..)]`
+    /// to be checked when an aggregate is deserialized, or the number of bits of `BINARY (n)`,
+    /// `None` if it is not restricted
+    pub(crate) fn holder_bound(&self) -> Option<TokenStream> {
+        let (lower, upper) = match self {
+            TypeRef::SimpleType(SimpleType(ast::SimpleType::Binary {
+                width_spec: Some(ast::WidthSpec { width, fixed }),
+            })) => (if *fixed { *width } else { 0 }, Some(*width)),
+            TypeRef::Set {
+                bound: Some(bound), ..
+            }
+            | TypeRef::Bag {
+                bound: Some(bound), ..
+            }
+            | TypeRef::List {
+                bound: Some(bound), ..
+            } => bound.cardinality(),
+            TypeRef::Array {
+                bound: Some(bound), ..
+            } => {
+                let size = bound.array_size()?;
+                (size, Some(size))
+            }
+            _ => return None,
+        };
+        if lower == 0 && upper.is_none() {
+            return None;
+        }
+        let lower = proc_macro2::Literal::usize_unsuffixed(lower);
+        Some(match upper {
+            Some(upper) => {
+                let upper = proc_macro2::Literal::usize_unsuffixed(upper);
+                quote! { #[holder(bound = #lower..=#upper)] }
+            }
+            None => quote! { #[holder(bound = #lower..)] },
+        })
+    }
+}
@@ -0,0 +1,569 @@
+//! Generate `ruststep::runtime::Validate` from local `WHERE` rules
+
+use super::{expression::*, function::Enclosing};
+use crate::ir::*;
+
+use check_keyword::CheckKeyword;
+use inflector::Inflector;
+use proc_macro2::TokenStream;
+use quote::*;
+
+impl Schema {
+    /// `Validate` for all entities and types, and `validate` of `Tables`
+    pub(crate) fn validate_impls(&self) -> TokenStream {
+        let entities = self.entities.iter().map(|e| self.entity_validate_impls(e));
+        let complex_entities = self
+            .complex_entities()
+            .into_iter()
+            .map(|c| self.complex_entity_validate_impl(c));
+        let types = self.types.iter().map(|ty| self.type_decl_validate_impl(ty));
+        quote! {
+            #(#entities)*
+            #(#complex_entities)*
+            #(#types)*
+        }
+    }
+
+    /// `Tables::population` resolving all entity instances once,
+    /// `Tables::validate` checking them, and `Tables::check_global_rules`
+    pub(crate) fn tables_validate_impls(&self, ruststep_path: &syn::Path) -> TokenStream {
+        // Complex entity instances are also stored in the tables of their components,
+        // and they are taken from the tables of complex entities
+        let (complex_fields, complex_types): (Vec<_>, Vec<_>) = self
+            .complex_entities()
+            .iter()
+            .map(|c| (c.field_ident(), c.name_ident()))
+            .unzip();
+        let (entity_fields, entity_types): (Vec<_>, Vec<_>) = self
+            .entities
+            .iter()
+            .map(|e| {
+                (
+                    format_ident!("{}", e.name.as_str().into_safe()),
+                    format_ident!("{}", e.name.to_pascal_case()),
+                )
+            })
+            .unzip();
+        let fields = complex_fields.iter().chain(&entity_fields);
+        let types = complex_types.iter().chain(&entity_types);
+        // Entities interfaced by `REFERENCE FROM` are instantiated only as attribute values
+        let (referenced, referenced_in): (Vec<_>, Vec<_>) = self
+            .interfaced
+            .iter()
+            .flat_map(|schema| &schema.referenced)
+            .map(|name| {
+                (
+                    format_ident!("{}", name.as_str().into_safe()),
+                    self.type_name(name),
+                )
+            })
+            .unzip();
+        let (global_rules, rule_methods): (Vec<_>, Vec<_>) = self
+            .rules
+            .iter()
+            .map(|rule| self.global_rule_method(rule))
+            .unzip();
+        let check_global_rules = if global_rules.is_empty() {
+            quote! { Ok(Vec::new()) }
+        } else {
+            quote! {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                #(Self::#global_rules(&population, &mut violations);)*
+                Ok(violations)
+            }
+        };
+        quote! {
+            impl Tables {
+                /// Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,
+                /// which share the instances they refer and are shared by the rules in a validation pass
+                pub fn population(&self) -> #ruststep_path::error::Result<runtime::Snapshot<'_, Tables>> {
+                    let mut resolver = #ruststep_path::tables::Resolver::new(self);
+                    let mut instances = std::collections::BTreeMap::new();
+                    #(
+                    for id in self.#complex_fields.keys() {
+                        let holder = resolver.get_shared::<as_holder!(#complex_types)>(*id)?;
+                        instances.insert(*id, runtime::Value::Entity(holder));
+                    }
+                    )*
+                    let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                    #(
+                    for id in self.#entity_fields.keys() {
+                        if complex.contains(id) {
+                            continue;
+                        }
+                        let holder = resolver.get_shared::<as_holder!(#entity_types)>(*id)?;
+                        let value = match instances.remove(id) {
+                            Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                            None => runtime::Value::Entity(holder),
+                        };
+                        instances.insert(*id, value);
+                    }
+                    )*
+                    let instances = instances
+                        .into_iter()
+                        .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                        .collect();
+                    Ok(runtime::Snapshot::new(self, instances))
+                }
+
+                /// Check the global rules declared by `RULE` over all entity instances
+                pub fn check_global_rules(&self) -> #ruststep_path::error::Result<Vec<runtime::RuleViolation>> {
+                    #check_global_rules
+                }
+
+                #(#rule_methods)*
+
+                /// Check the local `WHERE` rules of all entity instances
+                pub fn validate(&self) -> #ruststep_path::error::Result<Vec<runtime::RuleViolation>> {
+                    let population = self.population()?;
+                    let mut violations = Vec::new();
+                    #(
+                    for id in self.#fields.keys() {
+                        if let Some(holder) = population.instance_of::<as_holder!(#types)>(*id) {
+                            violations.extend(runtime::Validate::validate_rules(holder, Some(*id), &population));
+                        }
+                    }
+                    )*
+                    #(
+                    for id in self.#referenced.keys() {
+                        if self.references.referencing(*id).is_empty() {
+                            violations.push(runtime::RuleViolation {
+                                id: Some(*id),
+                                declared_in: #referenced_in,
+                                label: "REFERENCE FROM",
+                                evaluated: true,
+                            });
+                        }
+                    }
+                    )*
+                    // The rules of common supertypes are checked for each part of complex instances
+                    violations.sort();
+                    violations.dedup();
+                    Ok(violations)
+                }
+            }
+        }
+    }
+
+    /// Method of `Tables` checking a global rule, e.g. `check_rule_single_front_door`
+    ///
+    /// All domain rules are reported as not evaluated
+    /// if the declarations or statements in the rule cannot be translated yet.
+    fn global_rule_method(&self, rule: &Rule) -> (syn::Ident, TokenStream) {
+        let method = format_ident!("check_rule_{}", rule.name);
+        let declared_in = self.type_name(&rule.name);
+        // Local variables and statements are evaluated before the domain rules
+        let prelude = self
+            .nested_items(
+                &Enclosing::default(),
+                &rule.functions,
+                &rule.procedures,
+                &rule.declarations,
+            )
+            .and_then(|(_, nested)| {
+                let mut translator = Translator::global_rule(self, rule);
+                let declarations = translator.declarations(&rule.constants, &rule.variables)?;
+                let statements = translator.statements(&rule.statements)?;
+                Ok(quote! {
+                    #nested
+                    #declarations
+                    #statements
+                })
+            });
+        let checks: Vec<_> = rule
+            .where_rules
+            .iter()
+            .enumerate()
+            .map(|(i, domain_rule)| {
+                let expr = match &prelude {
+                    Ok(_) => Translator::global_rule(self, rule).translate(&domain_rule.expr),
+                    Err(e) => Err(e.clone()),
+                };
+                rule_check(expr, quote! { id: None }, &declared_in, domain_rule, i)
+            })
+            .collect();
+        let prelude = prelude.unwrap_or_default();
+        let (variables, type_names): (Vec<_>, Vec<_>) = rule
+            .entities
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => (variable_ident(name), self.type_name(name)),
+                _ => unreachable!(),
+            })
+            .unzip();
+        (
+            method.clone(),
+            quote! {
+                #[allow(unused_variables, unused_mut, unused_assignments, unused_labels)]
+                fn #method(tables: &runtime::Snapshot<Tables>, violations: &mut Vec<runtime::RuleViolation>) {
+                    #(let #variables = runtime::Population::instances_of(tables, #type_names);)*
+                    #prelude
+                    #(#checks)*
+                }
+            },
+        )
+    }
+
+    fn entity_validate_impls(&self, entity: &Entity) -> TokenStream {
+        let name = format_ident!("{}", entity.name.to_pascal_case());
+        let holder = format_ident!("{}Holder", name);
+        let check = self.entity_where_rules(entity);
+        let validate = self.validate_body(&self.ancestors(entity, quote! { self }, false, false));
+        let validate_holder =
+            self.validate_body(&self.ancestors(entity, quote! { self }, true, false));
+        quote! {
+            #check
+            impl runtime::Validate<Tables> for #name {
+                #validate
+            }
+            impl runtime::Validate<Tables> for #holder {
+                #validate_holder
+            }
+        }
+    }
+
+    fn complex_entity_validate_impl(&self, complex: &ComplexEntity) -> TokenStream {
+        let name = complex.name_ident();
+        let holder = format_ident!("{}Holder", name);
+        let mut tokens = TokenStream::new();
+        for (ty, is_holder) in [(&name, false), (&holder, true)] {
+            let mut ancestors: Vec<Ancestor> = Vec::new();
+            for component in &complex.components {
+                let entity = match component {
+                    TypeRef::Entity { name, .. } => self.entity(name).expect("Unknown component"),
+                    _ => unreachable!(),
+                };
+                let field = format_ident!("{}", entity.name.as_str().into_safe());
+                let root = if is_holder {
+                    quote! { self.#field.resolved() }
+                } else {
+                    quote! { self.#field }
+                };
+                for ancestor in self.ancestors(entity, root, is_holder, is_holder) {
+                    if !ancestors
+                        .iter()
+                        .any(|a| a.entity.name == ancestor.entity.name)
+                    {
+                        ancestors.push(ancestor);
+                    }
+                }
+            }
+            let validate = self.validate_body(&ancestors);
+            tokens.append_all(quote! {
+                impl runtime::Validate<Tables> for #ty {
+                    #validate
+                }
+            });
+        }
+        tokens
+    }
+
+    /// The entity and its supertypes with the path to their structs from `root`,
+    /// e.g. `self.base` for `base` of `sub` with `root = self`
+    ///
+    /// For holders, the supertypes are reached through the resolved holders,
+    /// e.g. `self.base.resolved()` of `Option<&BaseHolder>`, and `optional` means `root` is such an `Option`.
+    fn ancestors<'a>(
+        &'a self,
+        entity: &'a Entity,
+        root: TokenStream,
+        holder: bool,
+        optional: bool,
+    ) -> Vec<Ancestor<'a>> {
+        let mut ancestors = vec![Ancestor {
+            entity,
+            path: root.clone(),
+            optional,
+        }];
+        for sup in self.supertype_entities(entity) {
+            let field = format_ident!("{}", sup.name.as_str().into_safe());
+            let path = match (holder, optional) {
+                (false, _) => quote! { #root.#field },
+                (true, false) => quote! { #root.#field.resolved() },
+                (true, true) => quote! { #root.and_then(|x| x.#field.resolved()) },
+            };
+            for ancestor in self.ancestors(sup, path, holder, holder) {
+                if !ancestors
+                    .iter()
+                    .any(|a| a.entity.name == ancestor.entity.name)
+                {
+                    ancestors.push(ancestor);
+                }
+            }
+        }
+        ancestors
+    }
+
+    /// `check_where_rules` of the entity on `this`, the value of an instance of the entity or its subtypes
+    fn entity_where_rules(&self, entity: &Entity) -> TokenStream {
+        let checks = self.entity_rule_checks(entity);
+        if checks.is_empty() {
+            return quote! {};
+        }
+        let name = format_ident!("{}", entity.name.to_pascal_case());
+        quote! {
+            impl #name {
+                #[allow(unused_variables)]
+                fn check_where_rules(
+                    this: &runtime::Value,
+                    id: Option<u64>,
+                    tables: &runtime::Snapshot<Tables>,
+                    violations: &mut Vec<runtime::RuleViolation>,
+                ) {
+                    #(#checks)*
+                }
+            }
+        }
+    }
+
+    /// Body of `validate_rules` checking the rules of the ancestors and the types of their attributes
+    fn validate_body(&self, ancestors: &[Ancestor]) -> TokenStream {
+        let mut checks = Vec::new();
+        for Ancestor { entity, .. } in ancestors {
+            if !self.entity_rule_checks(entity).is_empty() {
+                let name = format_ident!("{}", entity.name.to_pascal_case());
+                checks
+                    .push(quote! { #name::check_where_rules(&this, id, tables, &mut violations); });
+            }
+        }
+        let mut attributes = Vec::new();
+        for Ancestor {
+            entity,
+            path,
+            optional,
+        } in ancestors
+        {
+            for attr in &entity.attributes {
+                if has_rules(&attr.ty) {
+                    let field = format_ident!("{}", attr.name.as_str().into_safe());
+                    attributes.push(if *optional {
+                        quote! {
+                            if let Some(x) = #path {
+                                violations.extend(runtime::Validate::validate_rules(&x.#field, id, tables));
+                            }
+                        }
+                    } else {
+                        quote! {
+                            violations.extend(runtime::Validate::validate_rules(&#path.#field, id, tables));
+                        }
+                    });
+                }
+            }
+            attributes.extend(self.redeclaration_checks(entity, ancestors));
+        }
+        if checks.is_empty() && attributes.is_empty() {
+            return quote! {
+                fn validate_rules(&self, _id: Option<u64>, _tables: &runtime::Snapshot<Tables>) -> Vec<runtime::RuleViolation> {
+                    Vec::new()
+                }
+            };
+        }
+        let this = if checks.is_empty() {
+            quote! {}
+        } else {
+            quote! { let this = runtime::self_value(self, id, tables); }
+        };
+        quote! {
+            fn validate_rules(&self, id: Option<u64>, tables: &runtime::Snapshot<Tables>) -> Vec<runtime::RuleViolation> {
+                #this
+                let mut violations = Vec::new();
+                #(#checks)*
+                #(#attributes)*
+                violations
+            }
+        }
+    }
+
+    fn type_decl_validate_impl(&self, ty: &TypeDecl) -> TokenStream {
+        let id = format_ident!("{}", ty.id().to_pascal_case());
+        let checks = self.rule_checks(None, ty.id(), ty.where_rules());
+        let underlying = match ty {
+            TypeDecl::Rename(rename) if has_rules(&rename.ty) => {
+                quote! { violations.extend(runtime::Validate::validate_rules(&self.0, id, tables)); }
+            }
+            TypeDecl::Select(select) if select.types.iter().any(has_rules) => {
+                let variants = select
+                    .types
+                    .iter()
+                    .filter(|ty| has_rules(ty))
+                    .map(|ty| match ty {
+                        TypeRef::Named { name, .. } => format_ident!("{}", name.to_pascal_case()),
+                        _ => unreachable!(),
+                    });
+                // Entity instances in select are validated separately
+                let others = if select.types.iter().all(has_rules) {
+                    quote! {}
+                } else {
+                    quote! { _ => {} }
+                };
+                quote! {
+                    match self {
+                        #(Self::#variants(x) => violations.extend(runtime::Validate::validate_rules(x, id, tables)),)*
+                        #others
+                    }
+                }
+            }
+            _ => quote! {},
+        };
+        let validate = if checks.is_empty() && underlying.is_empty() {
+            quote! {
+                fn validate_rules(&self, _id: Option<u64>, _tables: &runtime::Snapshot<Tables>) -> Vec<runtime::RuleViolation> {
+                    Vec::new()
+                }
+            }
+        } else {
+            let this = if checks.is_empty() {
+                quote! {}
+            } else {
+                quote! { let this = &runtime::ToValue::to_value(self); }
+            };
+            quote! {
+                #[allow(unused_variables)]
+                fn validate_rules(&self, id: Option<u64>, tables: &runtime::Snapshot<Tables>) -> Vec<runtime::RuleViolation> {
+                    #this
+                    let mut violations = Vec::new();
+                    #(#checks)*
+                    #underlying
+                    violations
+                }
+            }
+        };
+        let mut tokens = quote! {
+            impl runtime::Validate<Tables> for #id {
+                #validate
+            }
+        };
+        // Enumeration does not have Holder
+        if !matches!(ty, TypeDecl::Enumeration(_)) {
+            let holder = format_ident!("{}Holder", id);
+            tokens.append_all(quote! {
+                impl runtime::Validate<Tables> for #holder {
+                    #validate
+                }
+            });
+        }
+        tokens
+    }
+
+    /// Push a `RuleViolation` labeled like `SELF\base.x` if an `OPTIONAL` attribute
+    /// redeclared as mandatory in the entity is not provided
+    fn redeclaration_checks(&self, entity: &Entity, ancestors: &[Ancestor]) -> Vec<TokenStream> {
+        let declared_in = self.type_name(&entity.name);
+        entity
+            .redeclared_attributes
+            .iter()
+            .filter(|attr| !attr.optional)
+            .filter_map(|attr| {
+                let supertype = ancestors.iter().find(|a| a.entity.name == attr.supertype)?;
+                let path = &supertype.path;
+                let original = supertype
+                    .entity
+                    .attributes
+                    .iter()
+                    .find(|a| a.name == attr.name)?;
+                if !original.optional {
+                    return None;
+                }
+                let field = format_ident!("{}", attr.name.as_str().into_safe());
+                let label = format!("SELF\\{}.{}", attr.supertype, attr.name);
+                let missing = if supertype.optional {
+                    quote! { #path.map_or(false, |x| x.#field.is_none()) }
+                } else {
+                    quote! { #path.#field.is_none() }
+                };
+                Some(quote! {
+                    if #missing {
+                        violations.push(runtime::RuleViolation {
+                            id,
+                            declared_in: #declared_in,
+                            label: #label,
+                            evaluated: true,
+                        });
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn entity_rule_checks(&self, entity: &Entity) -> Vec<TokenStream> {
+        self.rule_checks(Some(entity), &entity.name, &entity.where_rules)
+    }
+
+    /// Push a `RuleViolation` into `violations` for each rule evaluated to `FALSE`,
+    /// or not evaluated since it cannot be translated yet
+    fn rule_checks(
+        &self,
+        entity: Option<&Entity>,
+        declared_in: &str,
+        rules: &[DomainRule],
+    ) -> Vec<TokenStream> {
+        let declared_in = self.type_name(declared_in);
+        rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let expr = Translator::rule(self, entity).translate(&rule.expr);
+                rule_check(expr, quote! { id }, &declared_in, rule, i)
+            })
+            .collect()
+    }
+}
+
+/// An entity or its supertype with the path to its struct, see `Schema::ancestors`
+struct Ancestor<'a> {
+    entity: &'a Entity,
+    path: TokenStream,
+    /// `path` is an `Option` of the resolved holder
+    optional: bool,
+}
+
+/// Push a `RuleViolation` if the `i`-th domain rule is evaluated to `FALSE`,
+/// or push it as not evaluated if the rule cannot be translated.
+/// `id` is the field initializer of `RuleViolation::id`.
+fn rule_check(
+    expr: Result<TokenStream, Unsupported>,
+    id: TokenStream,
+    declared_in: &str,
+    rule: &DomainRule,
+    i: usize,
+) -> TokenStream {
+    let label = match &rule.label {
+        Some(label) => label.clone(),
+        None => format!("#{}", i + 1),
+    };
+    match expr {
+        Ok(expr) => quote! {
+            if runtime::violated(#expr) {
+                violations.push(runtime::RuleViolation {
+                    #id,
+                    declared_in: #declared_in,
+                    label: #label,
+                    evaluated: true,
+                });
+            }
+        },
+        Err(_) => quote! {
+            violations.push(runtime::RuleViolation {
+                #id,
+                declared_in: #declared_in,
+                label: #label,
+                evaluated: false,
+            });
+        },
+    }
+}
+
+/// Check if the values of the type may have rules to be validated,
+/// i.e. defined types or aggregates of them.
+/// Referred entity instances are validated separately.
+fn has_rules(ty: &TypeRef) -> bool {
+    match ty {
+        TypeRef::Named { .. } => true,
+        TypeRef::Set { base, .. }
+        | TypeRef::Bag { base, .. }
+        | TypeRef::List { base, .. }
+        | TypeRef::Array { base, .. } => has_rules(base),
+        TypeRef::SimpleType(_) | TypeRef::Entity { .. } => false,
+    }
+}
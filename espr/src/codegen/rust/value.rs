@@ -0,0 +1,344 @@
+//! Generate the implementations of `ruststep::runtime` traits and derived attributes
+
+use super::expression::*;
+use crate::ir::*;
+
+use check_keyword::CheckKeyword;
+use inflector::Inflector;
+use proc_macro2::TokenStream;
+use quote::*;
+
+impl Schema {
+    /// `ToValue` and `EntityValue` for all entities and types, and getters of derived attributes
+    pub(crate) fn value_impls(&self) -> TokenStream {
+        let entities = self.entities.iter().map(|e| self.entity_value_impls(e));
+        let complex_entities = self
+            .complex_entities()
+            .into_iter()
+            .map(|c| self.complex_entity_value_impls(c));
+        let types = self.types.iter().map(type_decl_value_impls);
+        quote! {
+            #(#entities)*
+            #(#complex_entities)*
+            #(#types)*
+        }
+    }
+
+    fn entity_value_impls(&self, entity: &Entity) -> TokenStream {
+        let name = format_ident!("{}", entity.name.to_pascal_case());
+        let type_name = self.type_name(&entity.name);
+
+        let mut attr_names = Vec::new();
+        let mut attr_values = Vec::new();
+        for attr in &entity.attributes {
+            let field = format_ident!("{}", attr.name.as_str().into_safe());
+            attr_names.push(attr.name.clone());
+            attr_values.push(quote! { runtime::ToValue::to_value(&self.#field) });
+        }
+        // `RENAMED` attribute refers the value of the original attribute stored in the supertype
+        for attr in &entity.redeclared_attributes {
+            if let Some(rename) = &attr.rename {
+                let original = &attr.name;
+                attr_names.push(rename.clone());
+                attr_values.push(quote! { runtime::EntityValue::attribute(self, #original) });
+            }
+        }
+
+        let mut getters = Vec::new();
+        let mut derived_names = Vec::new();
+        let mut derived_values = Vec::new();
+        for attr in &entity.derived_attributes {
+            let mut translator = Translator::entity(self, entity);
+            // Derived attributes whose expression cannot be translated yet are indeterminate
+            if let Ok(expr) = translator.translate(&attr.expr) {
+                let getter = format_ident!("{}", attr.name.as_str().into_safe());
+                getters.push(match getter_type(&attr.ty) {
+                    Some(ty) => quote! {
+                        #[allow(unused_variables)]
+                        pub fn #getter(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<#ty> {
+                            runtime::FromValue::from_value(#expr)
+                        }
+                    },
+                    None => quote! {
+                        #[allow(unused_variables)]
+                        pub fn #getter(&self, id: Option<u64>, tables: &dyn runtime::Population) -> runtime::Value {
+                            #expr
+                        }
+                    },
+                });
+                derived_names.push(attr.name.clone());
+                derived_values
+                    .push(quote! { runtime::ToValue::to_value(&self.#getter(id, population)) });
+            }
+        }
+
+        let supertypes: Vec<_> = entity
+            .supertypes
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.as_str().into_safe()),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        let type_names = if supertypes.is_empty() {
+            quote! { vec![#type_name.to_string()] }
+        } else {
+            quote! {
+                let mut names = vec![#type_name.to_string()];
+                #(names.extend(runtime::EntityValue::type_names(&self.#supertypes));)*
+                names
+            }
+        };
+
+        // Without derived attributes in the entity and its supertypes, the default is enough
+        let derived_attribute = if derived_names.is_empty() && supertypes.is_empty() {
+            TokenStream::new()
+        } else {
+            quote! {
+                fn derived_attribute(
+                    &self,
+                    name: &str,
+                    id: Option<u64>,
+                    population: &dyn runtime::Population,
+                ) -> runtime::Value {
+                    match name {
+                        #(#derived_names => #derived_values,)*
+                        _ => {
+                            #(
+                            let value = runtime::EntityValue::derived_attribute(&self.#supertypes, name, id, population);
+                            if !value.is_indeterminate() {
+                                return value;
+                            }
+                            )*
+                            runtime::Value::Indeterminate
+                        }
+                    }
+                }
+            }
+        };
+
+        // The holder resolved by `Resolver` is also an entity instance sharing the instances it refers
+        let holder = format_ident!("{}Holder", name);
+        let mut tokens = TokenStream::new();
+        for name in [&name, &holder] {
+            if !getters.is_empty() {
+                tokens.append_all(quote! {
+                    impl #name {
+                        #(#getters)*
+                    }
+                });
+            }
+            tokens.append_all(quote! {
+                impl runtime::ToValue for #name {
+                    fn to_value(&self) -> runtime::Value {
+                        runtime::Value::entity(self.clone())
+                    }
+                }
+                impl runtime::EntityValue for #name {
+                    fn type_names(&self) -> Vec<String> {
+                        #type_names
+                    }
+                    fn attribute(&self, name: &str) -> runtime::Value {
+                        match name {
+                            #(#attr_names => #attr_values,)*
+                            _ => {
+                                #(
+                                let value = runtime::EntityValue::attribute(&self.#supertypes, name);
+                                if !value.is_indeterminate() {
+                                    return value;
+                                }
+                                )*
+                                runtime::Value::Indeterminate
+                            }
+                        }
+                    }
+                    #derived_attribute
+                    fn as_any(&self) -> &dyn std::any::Any {
+                        self
+                    }
+                    fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                        other.as_any().downcast_ref::<Self>() == Some(self)
+                    }
+                }
+            });
+        }
+
+        if !entity.constraints.is_empty() {
+            let any = format_ident!("{}Any", entity.name.to_pascal_case());
+            let mut variants = Vec::new();
+            if !entity.is_abstract {
+                variants.push(name);
+            }
+            variants.extend(entity.complex_constraints.iter().map(|c| c.name_ident()));
+            variants.extend(entity.constraints.iter().map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.to_pascal_case()),
+                _ => unreachable!(),
+            }));
+            let any_holder = format_ident!("{}Holder", any);
+            for any in [&any, &any_holder] {
+                tokens.append_all(quote! {
+                    impl runtime::ToValue for #any {
+                        fn to_value(&self) -> runtime::Value {
+                            match self {
+                                #(#any::#variants(x) => runtime::ToValue::to_value(&**x),)*
+                            }
+                        }
+                    }
+                });
+            }
+        }
+        tokens
+    }
+
+    fn complex_entity_value_impls(&self, complex: &ComplexEntity) -> TokenStream {
+        let name = complex.name_ident();
+        let components: Vec<_> = complex
+            .components
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => format_ident!("{}", name.as_str().into_safe()),
+                _ => unreachable!(),
+            })
+            .collect();
+        let holder = format_ident!("{}Holder", name);
+        let mut tokens = TokenStream::new();
+        for name in [&name, &holder] {
+            tokens.append_all(quote! {
+                impl runtime::ToValue for #name {
+                    fn to_value(&self) -> runtime::Value {
+                        runtime::Value::entity(self.clone())
+                    }
+                }
+                impl runtime::EntityValue for #name {
+                    fn type_names(&self) -> Vec<String> {
+                        let mut names = Vec::new();
+                        #(
+                        for name in runtime::EntityValue::type_names(&self.#components) {
+                            if !names.contains(&name) {
+                                names.push(name);
+                            }
+                        }
+                        )*
+                        names
+                    }
+                    fn attribute(&self, name: &str) -> runtime::Value {
+                        #(
+                        let value = runtime::EntityValue::attribute(&self.#components, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        )*
+                        runtime::Value::Indeterminate
+                    }
+                    fn derived_attribute(
+                        &self,
+                        name: &str,
+                        id: Option<u64>,
+                        population: &dyn runtime::Population,
+                    ) -> runtime::Value {
+                        #(
+                        let value = runtime::EntityValue::derived_attribute(&self.#components, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        )*
+                        runtime::Value::Indeterminate
+                    }
+                    fn as_any(&self) -> &dyn std::any::Any {
+                        self
+                    }
+                    fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                        other.as_any().downcast_ref::<Self>() == Some(self)
+                    }
+                }
+            });
+        }
+        tokens
+    }
+}
+
+fn type_decl_value_impls(ty: &TypeDecl) -> TokenStream {
+    let id = format_ident!("{}", ty.id().to_pascal_case());
+    let body = match ty {
+        TypeDecl::Simple(_) | TypeDecl::Rename(_) => {
+            quote! { runtime::ToValue::to_value(&self.0) }
+        }
+        TypeDecl::Enumeration(e) => {
+            let items: Vec<_> = e
+                .items
+                .iter()
+                .map(|i| format_ident!("{}", i.to_pascal_case()))
+                .collect();
+            let values: Vec<_> = e.items.iter().map(|i| i.to_lowercase()).collect();
+            quote! {
+                match self {
+                    #(#id::#items => runtime::Value::Enumeration(#values.to_string()),)*
+                }
+            }
+        }
+        TypeDecl::Select(select) => {
+            let variants: Vec<_> = select
+                .types
+                .iter()
+                .map(|ty| match ty {
+                    TypeRef::Entity { name, .. } | TypeRef::Named { name, .. } => {
+                        format_ident!("{}", name.to_pascal_case())
+                    }
+                    _ => unreachable!(),
+                })
+                .collect();
+            quote! {
+                match self {
+                    #(Self::#variants(x) => runtime::ToValue::to_value(x),)*
+                }
+            }
+        }
+    };
+    let mut tokens = quote! {
+        impl runtime::ToValue for #id {
+            fn to_value(&self) -> runtime::Value {
+                #body
+            }
+        }
+    };
+    // Enumeration does not have Holder
+    if !matches!(ty, TypeDecl::Enumeration(_)) {
+        let holder = format_ident!("{}Holder", id);
+        tokens.append_all(quote! {
+            impl runtime::ToValue for #holder {
+                fn to_value(&self) -> runtime::Value {
+                    #body
+                }
+            }
+        });
+    }
+    tokens
+}
+
+/// Rust type of a derived attribute getter, `None` if the getter returns `runtime::Value`
+fn getter_type(ty: &TypeRef) -> Option<TokenStream> {
+    match ty {
+        TypeRef::SimpleType(ty) => Some(quote! { #ty }),
+        TypeRef::Set { base, .. } | TypeRef::Bag { base, .. } | TypeRef::List { base, .. } => {
+            let base = getter_type(base)?;
+            Some(quote! { Vec<#base> })
+        }
+        TypeRef::Array {
+            base,
+            bound,
+            optional: false,
+            ..
+        } => {
+            let base = getter_type(base)?;
+            match bound.as_ref().and_then(Bound::array_size) {
+                Some(size) => {
+                    let size = proc_macro2::Literal::usize_unsuffixed(size);
+                    Some(quote! { [#base; #size] })
+                }
+                None => Some(quote! { Vec<#base> }),
+            }
+        }
+        _ => None,
+    }
+}
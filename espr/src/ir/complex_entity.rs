@@ -318,7 +318,7 @@ impl Instantiables {
         // | + | + | A & B |
         //
         let n = factors.len() as u32;
-        let mut constrait = Self::default();
+        let mut parts = Vec::new();
         for mut i in 1..(2usize.pow(n)) {
             // i=0b01 -> A
             // i=0b10 -> B
@@ -334,9 +334,10 @@ impl Instantiables {
                 }
                 i >>= 1;
             }
-            constrait = constrait + c.unwrap();
+            parts.append(&mut c.unwrap().parts);
         }
-        constrait
+        // Sort once at last since `+` sorts the whole list for each term
+        parts.iter().collect()
     }
 
     pub fn from_constraint_expr(
@@ -0,0 +1,70 @@
+use super::*;
+use crate::ast;
+
+/// Legalized constant declared in `CONSTANT` block of a schema, e.g.
+///
+/// ```text
+/// CONSTANT
+///   dummy_gri : geometric_representation_item := representation_item('') ||
+///                                                geometric_representation_item();
+/// END_CONSTANT;
+/// ```
+///
+/// The initializer is kept as AST, and its names are resolved in code generation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Constant {
+    pub name: String,
+    pub ty: TypeRef,
+    pub expr: ast::Expression,
+}
+
+impl Legalize for Constant {
+    type Input = ast::Constant;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        constant: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        Ok(Constant {
+            name: constant.name.clone(),
+            ty: TypeRef::legalize(ns, ss, scope, &constant.ty)?,
+            expr: constant.expr.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legalize_constant() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              CONSTANT
+                origin : point := point(0.0, 0.0);
+                unit_length : distance := 1.0;
+              END_CONSTANT;
+              ENTITY point;
+                x : REAL;
+                y : REAL;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+
+        let origin = Constant::legalize(&ns, &ss, &scope, &st.schemas[0].constants[0]).unwrap();
+        assert!(matches!(origin.ty, TypeRef::Entity { ref name, .. } if name == "point"));
+
+        let err = Constant::legalize(&ns, &ss, &scope, &st.schemas[0].constants[1]).unwrap_err();
+        assert!(matches!(err, SemanticError::TypeNotFound { .. }));
+    }
+}
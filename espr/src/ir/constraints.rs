@@ -3,7 +3,7 @@
 use super::*;
 use crate::ast;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Expression appears in `SUBTYPE_CONSTRAINT` with resolved [Path]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -96,12 +96,28 @@ impl ConstraintExpr {
 pub struct Constraints {
     /// Each super-type can be instantiable as its subtypes,
     /// but possible subtypes cannot be determined from local description in EXPRESS.
+    ///
+    /// This lists the combinations of subtypes in the subtype constraints,
+    /// and each subtype in [Constraints::andor_subtypes] by itself.
     pub instantiables: HashMap<Path, Vec<Vec<Path>>>,
+
+    /// Subtypes combined by the default `ANDOR` constraint, which may be instantiated
+    /// together with each other and with one of [Constraints::instantiables]
+    pub andor_subtypes: HashMap<Path, Vec<Path>>,
+
+    /// Entities declared by `ABSTRACT` or `ABSTRACT SUPERTYPE`,
+    /// which cannot be instantiated without their subtypes
+    pub abstracts: HashSet<Path>,
+
+    /// Explicit attributes redeclared as `DERIVE` in subtypes, e.g. `SELF\named_unit.dimensions`,
+    /// as the entity declaring the attribute and its name, paired with the subtypes
+    pub derived_in_subtypes: HashMap<(Path, String), Vec<Path>>,
 }
 
-// Execute b), c), and d) steps of the algorithm described in the section B.3
+// Execute b) and d) steps of the algorithm described in the section B.3
 //
 // - Step a) has been done while namespace creation.
+// - Step c) is done by `gather_default_subtypes`.
 //
 pub fn gather_constraint_expr(
     ns: &Namespace,
@@ -129,7 +145,8 @@ pub fn gather_constraint_expr(
         let scope = root.schema(&schema.name);
         for entity in &schema.entities {
             match &entity.constraint {
-                Some(ast::Constraint::SuperTypeRule(expr)) => {
+                Some(ast::Constraint::SuperTypeRule(expr))
+                | Some(ast::Constraint::AbstractSuperType(Some(expr))) => {
                     let result = exprs.insert(
                         Path::entity(&scope, &entity.name),
                         ConstraintExpr::from_ast_expr(ns, &scope, expr)?,
@@ -150,7 +167,6 @@ pub fn gather_constraint_expr(
     // d) Combine `SUBTYPE_CONSTRAINT` into single expression by `ANDOR`
     //
     // The step d) is done before c) because c) have to look up all `SUBTYPE_CONSTRAINT`.
-    // New `SUBTYPE_CONSTRAINT` introduced in the step c) will be merged while c).
     //
     for schema in &st.schemas {
         let scope = root.schema(&schema.name);
@@ -170,9 +186,26 @@ pub fn gather_constraint_expr(
         }
     }
 
-    // c) Add default constraint (described in 9.2.5.6),
-    //    i.e. convert `SUBTYPE OF` into `SUBTYPE_CONSTRAINT`
-    //
+    Ok(exprs)
+}
+
+// Execute c) step of the algorithm described in the section B.3
+//
+// c) Add default constraint (described in 9.2.5.6),
+//    i.e. convert `SUBTYPE OF` into `SUBTYPE_CONSTRAINT`
+//
+// The default constraint combines subtypes which do not occur in `exprs` by `ANDOR`.
+// They are kept as a list of subtypes instead of `ConstraintExpr::AndOr`
+// since `ANDOR` of n-subtypes expands into 2^n - 1 instantiables,
+// e.g. 14 subtypes of `geometric_representation_item` in AP201 are not in its `ONEOF`.
+//
+pub fn gather_default_subtypes(
+    ns: &Namespace,
+    st: &SyntaxTree,
+    exprs: &HashMap<Path, ConstraintExpr>,
+) -> Result<HashMap<Path, Vec<Path>>, SemanticError> {
+    let root = Scope::root();
+
     // We'd like to list up subtypes for each supertype,
     // but `SUBTYPE OF` description exists on subtype's `ENTITY` declaration.
     //
@@ -192,49 +225,102 @@ pub fn gather_constraint_expr(
         }
     }
     // c-2) and reverse it.
+    let mut defaults = HashMap::new();
     for (sup, subs) in super_to_sub {
-        match exprs.entry(sup) {
-            Entry::Occupied(mut e) => {
-                // Gather subtype does not occur in other `SUBTYPE_CONSTRAINT`
-                let subs: Vec<ConstraintExpr> = subs
-                    .into_iter()
-                    .filter_map(|sub| {
-                        if e.get().is_in(&sub) {
-                            None
-                        } else {
-                            Some(ConstraintExpr::Reference(sub))
-                        }
-                    })
-                    .collect();
-                if !subs.is_empty() {
-                    e.get_mut().andor_mut(ConstraintExpr::AndOr(subs));
-                }
+        // Gather subtype does not occur in other `SUBTYPE_CONSTRAINT`
+        let subs: Vec<Path> = match exprs.get(&sup) {
+            Some(expr) => subs.into_iter().filter(|sub| !expr.is_in(sub)).collect(),
+            None => subs,
+        };
+        if !subs.is_empty() {
+            defaults.insert(sup, subs);
+        }
+    }
+    Ok(defaults)
+}
+
+/// Gather entities declared as abstract in `ENTITY` declarations and `SUBTYPE_CONSTRAINT`
+pub fn gather_abstracts(ns: &Namespace, st: &SyntaxTree) -> Result<HashSet<Path>, SemanticError> {
+    let root = Scope::root();
+    let mut abstracts = HashSet::new();
+    for schema in &st.schemas {
+        let scope = root.schema(&schema.name);
+        for entity in &schema.entities {
+            if let Some(ast::Constraint::AbstractEntity | ast::Constraint::AbstractSuperType(_)) =
+                &entity.constraint
+            {
+                abstracts.insert(Path::entity(&scope, &entity.name));
             }
-            Entry::Vacant(e) => {
-                // No `SUBTYPE_CONSTRAINT` for this supertype
-                let subs = subs.into_iter().map(ConstraintExpr::Reference).collect();
-                e.insert(ConstraintExpr::AndOr(subs));
+        }
+        for constraint in &schema.subtype_constraints {
+            if constraint.is_abstract {
+                abstracts.insert(ns.resolve(&scope, &constraint.entity)?.0);
             }
         }
     }
+    Ok(abstracts)
+}
 
-    Ok(exprs)
+/// Gather explicit attributes redeclared as `DERIVE` in subtypes
+pub fn gather_derived_in_subtypes(
+    ns: &Namespace,
+    st: &SyntaxTree,
+) -> Result<HashMap<(Path, String), Vec<Path>>, SemanticError> {
+    let root = Scope::root();
+    let mut derived: HashMap<(Path, String), Vec<Path>> = HashMap::new();
+    for schema in &st.schemas {
+        let scope = root.schema(&schema.name);
+        for entity in &schema.entities {
+            let derive = match &entity.derive_clause {
+                Some(derive) => derive,
+                None => continue,
+            };
+            for attr in &derive.attributes {
+                if let ast::AttributeDecl::Qualified {
+                    group, attribute, ..
+                } = &attr.attr
+                {
+                    let declaring = declaring_entity(ns, &scope, group, attribute)?;
+                    derived
+                        .entry((declaring, attribute.clone()))
+                        .or_default()
+                        .push(Path::entity(&scope, &entity.name));
+                }
+            }
+        }
+    }
+    Ok(derived)
 }
 
 impl Constraints {
     pub fn new(ns: &Namespace, st: &SyntaxTree) -> Result<Self, SemanticError> {
         let exprs = gather_constraint_expr(ns, st)?;
+        let andor_subtypes = gather_default_subtypes(ns, st, &exprs)?;
+        let mut instantiables = exprs
+            .into_iter()
+            .map(|(path, expr)| Ok((path, expr.as_instantiables(ns)?)))
+            .collect::<Result<HashMap<_, _>, SemanticError>>()?;
+        for (sup, subs) in &andor_subtypes {
+            instantiables
+                .entry(sup.clone())
+                .or_default()
+                .extend(subs.iter().map(|sub| vec![sub.clone()]));
+        }
         Ok(Constraints {
-            instantiables: exprs
-                .into_iter()
-                .map(|(path, expr)| Ok((path, expr.as_instantiables(ns)?)))
-                .collect::<Result<_, SemanticError>>()?,
+            instantiables,
+            andor_subtypes,
+            abstracts: gather_abstracts(ns, st)?,
+            derived_in_subtypes: gather_derived_in_subtypes(ns, st)?,
         })
     }
 
     pub fn is_supertype(&self, path: &Path) -> bool {
         self.instantiables.contains_key(path)
     }
+
+    pub fn is_abstract(&self, path: &Path) -> bool {
+        self.abstracts.contains(path)
+    }
 }
 
 #[cfg(test)]
@@ -461,15 +547,17 @@ mod tests {
         let st = ast::SyntaxTree::parse(PERSON_DEFAULT).unwrap();
         let ns = Namespace::new(&st);
         let exprs = gather_constraint_expr(&ns, &st).unwrap();
+        assert!(exprs.is_empty());
+        let defaults = gather_default_subtypes(&ns, &st, &exprs).unwrap();
         let scope = Scope::root().schema("test_schema");
         let person = Path::entity(&scope, "person");
         assert_eq!(
-            dbg!(exprs),
+            dbg!(defaults),
             maplit::hashmap! {
-                person => ConstraintExpr::AndOr(vec![
-                    ConstraintExpr::Reference(Path::entity(&scope, "employee")),
-                    ConstraintExpr::Reference(Path::entity(&scope, "student")),
-                ])
+                person => vec![
+                    Path::entity(&scope, "employee"),
+                    Path::entity(&scope, "student"),
+                ]
             }
         );
     }
@@ -537,7 +625,10 @@ mod tests {
                         vec![Path::entity(&scope, "rabbit")],
                         vec![Path::entity(&scope, "dog")],
                     ]
-                }
+                },
+                andor_subtypes: HashMap::new(),
+                abstracts: maplit::hashset! { Path::entity(&scope, "pet") },
+                derived_in_subtypes: HashMap::new(),
             }
         );
     }
@@ -556,7 +647,10 @@ mod tests {
                         vec![Path::entity(&scope, "sub1")],
                         vec![Path::entity(&scope, "sub2")],
                     ]
-                }
+                },
+                andor_subtypes: HashMap::new(),
+                abstracts: HashSet::new(),
+                derived_in_subtypes: HashMap::new(),
             }
         );
     }
@@ -576,7 +670,10 @@ mod tests {
                         vec![Path::entity(&scope, "student")],
                         vec![Path::entity(&scope, "employee"), Path::entity(&scope, "student")],
                     ]
-                }
+                },
+                andor_subtypes: HashMap::new(),
+                abstracts: HashSet::new(),
+                derived_in_subtypes: HashMap::new(),
             }
         );
     }
@@ -597,7 +694,10 @@ mod tests {
                         vec![Path::entity(&scope, "female"), Path::entity(&scope, "citizen")],
                         vec![Path::entity(&scope, "female"), Path::entity(&scope, "alien")],
                     ]
-                }
+                },
+                andor_subtypes: HashMap::new(),
+                abstracts: HashSet::new(),
+                derived_in_subtypes: HashMap::new(),
             }
         );
     }
@@ -615,9 +715,63 @@ mod tests {
                     Path::entity(&scope, "person") => vec![
                         vec![Path::entity(&scope, "employee")],
                         vec![Path::entity(&scope, "student")],
-                        vec![Path::entity(&scope, "employee"), Path::entity(&scope, "student")],
                     ]
-                }
+                },
+                andor_subtypes: maplit::hashmap! {
+                    Path::entity(&scope, "person") => vec![
+                        Path::entity(&scope, "employee"),
+                        Path::entity(&scope, "student"),
+                    ]
+                },
+                abstracts: HashSet::new(),
+                derived_in_subtypes: HashMap::new(),
+            }
+        );
+    }
+
+    /// `ABSTRACT SUPERTYPE OF` declaration with `ONEOF`
+    const ABSTRACT_SUPERTYPE_OF: &str = r#"
+    SCHEMA test_schema;
+      ENTITY pet ABSTRACT SUPERTYPE OF (ONEOF (cat, dog));
+      END_ENTITY;
+
+      ENTITY cat SUBTYPE OF (pet);
+      END_ENTITY;
+
+      ENTITY dog SUBTYPE OF (pet);
+      END_ENTITY;
+
+      ENTITY sphynx ABSTRACT SUBTYPE OF (cat);
+      END_ENTITY;
+    END_SCHEMA;
+    "#;
+
+    #[test]
+    fn abstract_supertype_of() {
+        let st = ast::SyntaxTree::parse(ABSTRACT_SUPERTYPE_OF).unwrap();
+        let ns = Namespace::new(&st);
+        let c = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().schema("test_schema");
+        assert_eq!(
+            dbg!(c),
+            Constraints {
+                instantiables: maplit::hashmap! {
+                    Path::entity(&scope, "pet") => vec![
+                        vec![Path::entity(&scope, "cat")],
+                        vec![Path::entity(&scope, "dog")],
+                    ],
+                    Path::entity(&scope, "cat") => vec![
+                        vec![Path::entity(&scope, "sphynx")],
+                    ]
+                },
+                andor_subtypes: maplit::hashmap! {
+                    Path::entity(&scope, "cat") => vec![Path::entity(&scope, "sphynx")],
+                },
+                abstracts: maplit::hashset! {
+                    Path::entity(&scope, "pet"),
+                    Path::entity(&scope, "sphynx"),
+                },
+                derived_in_subtypes: HashMap::new(),
             }
         );
     }
@@ -1,16 +1,33 @@
 use super::{namespace::*, scope::*, type_ref::*, *};
 use crate::ast;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Entity {
     /// Name of entity in snake_case
     pub name: String,
     pub attributes: Vec<EntityAttribute>,
 
+    /// Explicit attributes of supertypes redeclared in this entity,
+    /// e.g. `SELF\representation.items: SET [1:?] OF geometric_representation_item;`
+    pub redeclared_attributes: Vec<RedeclaredAttribute>,
+
     /// List of constraints corresponding to `SUBTYPE_CONSTRAINTS`
     /// and `SUPERTYPE OF` declaration in EXPRESS schema
     pub constraints: Vec<TypeRef>,
 
+    /// Instantiable combinations of two or more subtypes,
+    /// e.g. `employee & student` for `SUPERTYPE OF (employee ANDOR student)`
+    pub complex_constraints: Vec<ComplexEntity>,
+
+    /// Subtypes combined by the default `ANDOR` constraint,
+    /// whose combinations are not listed in `complex_constraints`
+    pub andor_subtypes: Vec<TypeRef>,
+
+    /// Declared by `ABSTRACT` or `ABSTRACT SUPERTYPE`,
+    /// and thus instantiated only as a part of its subtypes
+    pub is_abstract: bool,
+
     /// List of types to be inherited by this entity
     ///
     /// When this entity is `sub` defined like:
@@ -23,13 +40,76 @@ pub struct Entity {
     /// then this `supertypes` is `[base]`.
     ///
     pub supertypes: Vec<TypeRef>,
+
+    /// Attributes declared in `DERIVE` clause
+    pub derived_attributes: Vec<DerivedAttribute>,
+
+    /// Attributes declared in `INVERSE` clause
+    pub inverse_attributes: Vec<InverseAttribute>,
+
+    /// Domain rules declared in `WHERE` clause
+    pub where_rules: Vec<DomainRule>,
+}
+
+impl Entity {
+    /// Types referred by this entity, i.e. its supertypes, subtypes, and the types of attributes
+    pub fn referred_types(&self) -> Vec<&TypeRef> {
+        let mut types: Vec<&TypeRef> = Vec::new();
+        types.extend(&self.supertypes);
+        types.extend(&self.constraints);
+        types.extend(self.complex_constraints.iter().flat_map(|c| &c.components));
+        types.extend(&self.andor_subtypes);
+        for attr in &self.attributes {
+            types.push(&attr.ty);
+            types.extend(&attr.derived_in);
+        }
+        types.extend(self.redeclared_attributes.iter().map(|attr| &attr.ty));
+        types.extend(self.derived_attributes.iter().map(|attr| &attr.ty));
+        types.extend(self.inverse_attributes.iter().map(|attr| &attr.dest));
+        types
+    }
 }
 
+/// Partial complex entity consists of two or more leaf entities, e.g. `employee & student`
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct ComplexEntity {
+    /// Name in snake_case joined by `_and_`, e.g. `employee_and_student`
+    pub name: String,
+    /// Leaf entities sorted by their names
+    pub components: Vec<TypeRef>,
+}
+
+impl ComplexEntity {
+    pub fn from_paths(
+        ns: &Namespace,
+        ss: &Constraints,
+        paths: &[Path],
+    ) -> Result<Self, SemanticError> {
+        let mut paths = paths.to_vec();
+        paths.sort_by(|a, b| a.name.cmp(&b.name));
+        let name = paths
+            .iter()
+            .map(|path| path.name.as_str())
+            .collect::<Vec<_>>()
+            .join("_and_");
+        let components = paths
+            .iter()
+            .map(|path| TypeRef::from_path(ns, ss, path))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ComplexEntity { name, components })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct EntityAttribute {
     pub name: String,
     pub ty: TypeRef,
     pub optional: bool,
+    /// Subtypes redeclaring this attribute as `DERIVE`,
+    /// whose instances omit its value as `*`
+    pub derived_in: Vec<TypeRef>,
 }
 
 impl Legalize for EntityAttribute {
@@ -44,16 +124,242 @@ impl Legalize for EntityAttribute {
         let ty = TypeRef::legalize(ns, ss, scope, &attr.ty)?;
         let name = match &attr.name {
             ast::AttributeDecl::Reference(name) => name.clone(),
-            _ => unimplemented!(),
+            ast::AttributeDecl::Qualified { .. } => {
+                unreachable!("Redeclared attribute is legalized as RedeclaredAttribute")
+            }
         };
         Ok(EntityAttribute {
             name,
             ty,
             optional: attr.optional,
+            // Determined in `Entity::legalize` since it depends on the subtypes
+            derived_in: Vec::new(),
+        })
+    }
+}
+
+/// Legalized explicit attribute redeclared in a subtype,
+/// e.g. `SELF\named_unit.dimensions: dimensional_exponents;`
+///
+/// The value is stored in the struct of the supertype declaring the attribute,
+/// and the subtype does not have its own field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct RedeclaredAttribute {
+    /// The supertype declaring the original attribute, e.g. `named_unit`
+    pub supertype: String,
+    /// Name of the original attribute, e.g. `dimensions`
+    pub name: String,
+    /// New name given by `RENAMED`
+    pub rename: Option<String>,
+    /// Redeclared type, which specializes the original type
+    pub ty: TypeRef,
+    /// `false` if the attribute is mandatory in this entity even when it is `OPTIONAL` in the supertype
+    pub optional: bool,
+}
+
+impl Legalize for RedeclaredAttribute {
+    type Input = ast::EntityAttribute;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        attr: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        let (group, attribute, rename) = match &attr.name {
+            ast::AttributeDecl::Qualified {
+                group,
+                attribute,
+                rename,
+            } => (group, attribute, rename),
+            ast::AttributeDecl::Reference(_) => {
+                unreachable!("Plain attribute is legalized as EntityAttribute")
+            }
+        };
+        let supertype = declaring_entity(ns, scope, group, attribute)?;
+        let ty = TypeRef::legalize(ns, ss, scope, &attr.ty)?;
+        Ok(RedeclaredAttribute {
+            supertype: supertype.name,
+            name: attribute.clone(),
+            rename: rename.clone(),
+            ty,
+            optional: attr.optional,
+        })
+    }
+}
+
+/// Legalized `DERIVE` attribute, e.g. `dim: INTEGER := SIZEOF(coordinates);`
+///
+/// The expression is kept as AST, and its names are resolved in code generation.
+/// An explicit attribute of a supertype redeclared as derived, e.g. `SELF\named_unit.dimensions`,
+/// is named by the original name or its `RENAMED` name.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct DerivedAttribute {
+    pub name: String,
+    pub ty: TypeRef,
+    pub expr: ast::Expression,
+}
+
+impl Legalize for DerivedAttribute {
+    type Input = ast::DerivedAttribute;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        attr: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        let name = match &attr.attr {
+            ast::AttributeDecl::Reference(name) => name.clone(),
+            ast::AttributeDecl::Qualified {
+                group,
+                attribute,
+                rename,
+            } => {
+                declaring_entity(ns, scope, group, attribute)?;
+                rename.as_ref().unwrap_or(attribute).clone()
+            }
+        };
+        let ty = TypeRef::legalize(ns, ss, scope, &attr.ty)?;
+        Ok(DerivedAttribute {
+            name,
+            ty,
+            expr: attr.expr.clone(),
+        })
+    }
+}
+
+/// Legalized `INVERSE` attribute, e.g. `opens: door FOR handle;` in `knob`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct InverseAttribute {
+    /// Name of this inverse relationship, e.g. `opens`
+    pub name: String,
+    /// The entity which refers `SELF`, e.g. `door`
+    pub dest: TypeRef,
+    /// The attribute of `dest` referring `SELF`, e.g. `handle`
+    pub attribute: String,
+    /// Bound of the number of referencing instances, `[1:1]` if not aggregated
+    pub bound: Bound,
+    /// Declared as `SET` or `BAG`. Otherwise exactly one instance refers `SELF`.
+    pub aggregated: bool,
+}
+
+impl Legalize for InverseAttribute {
+    type Input = ast::InverseAttribute;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        attr: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        let name = match &attr.name {
+            ast::AttributeDecl::Reference(name) => name.clone(),
+            ast::AttributeDecl::Qualified {
+                group, attribute, ..
+            } => {
+                return Err(SemanticError::Unsupported(format!(
+                    "INVERSE attribute redeclared as SELF\\{}.{}",
+                    group, attribute
+                )))
+            }
+        };
+
+        let (path, _index) = ns.resolve(scope, &attr.dest)?;
+        if path.ty != ScopeType::Entity {
+            return Err(SemanticError::NotEntity(path));
+        }
+        let dest = TypeRef::from_path(ns, ss, &path)?;
+
+        // `FOR prefix.attribute` looks up the attribute in the supertype `prefix` of `dest`
+        let owner = match &attr.attribute_prefix {
+            Some(prefix) => ns.resolve(scope, prefix)?.0,
+            None => path,
+        };
+        if !has_attribute(ns, &owner, &attr.attribute)? {
+            return Err(SemanticError::AttributeNotFound {
+                entity: owner.name,
+                attribute: attr.attribute.clone(),
+            });
+        }
+
+        let (bound, aggregated) = match &attr.dest_aggregation {
+            ast::AggregationOption::Set { bound } | ast::AggregationOption::Bag { bound } => {
+                match bound {
+                    Some(bound) => (Bound::legalize(ns, ss, scope, bound)?, true),
+                    None => (
+                        Bound {
+                            lower: Some(0),
+                            upper: None,
+                        },
+                        true,
+                    ),
+                }
+            }
+            ast::AggregationOption::None => (
+                Bound {
+                    lower: Some(1),
+                    upper: Some(1),
+                },
+                false,
+            ),
+        };
+
+        Ok(InverseAttribute {
+            name,
+            dest,
+            attribute: attr.attribute.clone(),
+            bound,
+            aggregated,
         })
     }
 }
 
+/// Check if the entity of `path` or its supertypes declare the attribute
+fn has_attribute(ns: &Namespace, path: &Path, attribute: &str) -> Result<bool, SemanticError> {
+    Ok(find_attribute(ns, path, attribute)?.is_some())
+}
+
+/// The entity of `path` or its supertype declaring the explicit attribute
+fn find_attribute(
+    ns: &Namespace,
+    path: &Path,
+    attribute: &str,
+) -> Result<Option<Path>, SemanticError> {
+    let entity = match ns.get(path)?.0 {
+        Named::Entity(entity) => entity,
+        Named::Type(_) => return Err(SemanticError::NotEntity(path.clone())),
+    };
+    if entity.attributes.iter().any(|attr| attr.name == attribute) {
+        return Ok(Some(path.clone()));
+    }
+    if let Some(supertypes) = &entity.subtype_of {
+        for sup in &supertypes.entity_references {
+            if let Some(found) = find_attribute(ns, &ns.resolve(&path.scope, sup)?.0, attribute)? {
+                return Ok(Some(found));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The entity declaring the attribute referred as `SELF\group.attribute` in `scope`
+pub(crate) fn declaring_entity(
+    ns: &Namespace,
+    scope: &Scope,
+    group: &str,
+    attribute: &str,
+) -> Result<Path, SemanticError> {
+    let (path, _index) = ns.resolve(scope, group)?;
+    find_attribute(ns, &path, attribute)?.ok_or_else(|| SemanticError::AttributeNotFound {
+        entity: path.name,
+        attribute: attribute.to_string(),
+    })
+}
+
 impl Legalize for Entity {
     type Input = ast::Entity;
 
@@ -64,11 +370,29 @@ impl Legalize for Entity {
         entity: &ast::Entity,
     ) -> Result<Self, SemanticError> {
         let name = entity.name.clone();
-        let attributes = entity
-            .attributes
-            .iter()
-            .map(|attr| EntityAttribute::legalize(ns, ss, scope, attr))
-            .collect::<Result<Vec<_>, _>>()?;
+        let path = Path::entity(scope, &entity.name);
+        let mut attributes = Vec::new();
+        let mut redeclared_attributes = Vec::new();
+        for attr in &entity.attributes {
+            match attr.name {
+                ast::AttributeDecl::Reference(_) => {
+                    let mut attr = EntityAttribute::legalize(ns, ss, scope, attr)?;
+                    if let Some(subtypes) = ss
+                        .derived_in_subtypes
+                        .get(&(path.clone(), attr.name.clone()))
+                    {
+                        attr.derived_in = subtypes
+                            .iter()
+                            .map(|sub| TypeRef::from_path(ns, ss, sub))
+                            .collect::<Result<Vec<_>, _>>()?;
+                    }
+                    attributes.push(attr);
+                }
+                ast::AttributeDecl::Qualified { .. } => {
+                    redeclared_attributes.push(RedeclaredAttribute::legalize(ns, ss, scope, attr)?)
+                }
+            }
+        }
 
         let supertypes = if let Some(supertypes) = &entity.subtype_of {
             supertypes
@@ -80,25 +404,59 @@ impl Legalize for Entity {
             Vec::new()
         };
 
-        let path = Path::entity(scope, &entity.name);
-        let constraints = if let Some(instantiables) = ss.instantiables.get(&path) {
-            instantiables
+        let derived_attributes = if let Some(derive) = &entity.derive_clause {
+            derive
+                .attributes
                 .iter()
-                .filter_map(|pce| match pce.len() {
-                    // FIXME ignore complex entity case
-                    1 => Some(TypeRef::from_path(ns, ss, &pce[0])),
-                    _ => None,
-                })
-                .collect::<Result<Vec<TypeRef>, SemanticError>>()?
+                .map(|attr| DerivedAttribute::legalize(ns, ss, scope, attr))
+                .collect::<Result<Vec<_>, _>>()?
         } else {
             Vec::new()
         };
 
+        let inverse_attributes = if let Some(inverse) = &entity.inverse_clause {
+            inverse
+                .attributes
+                .iter()
+                .map(|attr| InverseAttribute::legalize(ns, ss, scope, attr))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+
+        let where_rules =
+            DomainRule::legalize_where_clause(ns, ss, scope, entity.where_clause.as_ref())?;
+
+        let mut constraints = Vec::new();
+        let mut complex_constraints = Vec::new();
+        if let Some(instantiables) = ss.instantiables.get(&path) {
+            for pce in instantiables {
+                match pce.len() {
+                    1 => constraints.push(TypeRef::from_path(ns, ss, &pce[0])?),
+                    _ => complex_constraints.push(ComplexEntity::from_paths(ns, ss, pce)?),
+                }
+            }
+        }
+        let andor_subtypes = match ss.andor_subtypes.get(&path) {
+            Some(subtypes) => subtypes
+                .iter()
+                .map(|path| TypeRef::from_path(ns, ss, path))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
         Ok(Entity {
             name,
             attributes,
+            redeclared_attributes,
             constraints,
+            complex_constraints,
+            andor_subtypes,
+            is_abstract: ss.is_abstract(&path),
             supertypes,
+            derived_attributes,
+            inverse_attributes,
+            where_rules,
         })
     }
 }
@@ -118,4 +476,191 @@ mod tests {
         let entity = Entity::legalize(&ns, &ss, &scope, entity).unwrap();
         dbg!(&entity);
     }
+
+    #[test]
+    fn legalize_complex_constraints() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY person SUPERTYPE OF (student ANDOR employee);
+              END_ENTITY;
+              ENTITY employee SUBTYPE OF (person);
+              END_ENTITY;
+              ENTITY student SUBTYPE OF (person);
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+        let person = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[0]).unwrap();
+        assert_eq!(person.constraints.len(), 2);
+        assert_eq!(person.complex_constraints.len(), 1);
+
+        let complex = &person.complex_constraints[0];
+        assert_eq!(complex.name, "employee_and_student");
+        let names: Vec<_> = complex
+            .components
+            .iter()
+            .map(|ty| match ty {
+                TypeRef::Entity { name, .. } => name.as_str(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(names, ["employee", "student"]);
+    }
+
+    #[test]
+    fn legalize_inverse() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY door;
+                handle: knob;
+              END_ENTITY;
+              ENTITY knob;
+              INVERSE
+                opens: SET [0:?] OF door FOR handle;
+                locks: door FOR lock;
+                SELF\knob.opens: SET [0:1] OF door FOR handle;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+        let inverse = &st.schemas[0].entities[1].inverse_clause.as_ref().unwrap();
+
+        let opens = InverseAttribute::legalize(&ns, &ss, &scope, &inverse.attributes[0]).unwrap();
+        assert_eq!(opens.attribute, "handle");
+        assert_eq!(
+            (opens.bound.lower, opens.bound.upper, opens.aggregated),
+            (Some(0), None, true)
+        );
+
+        let err = InverseAttribute::legalize(&ns, &ss, &scope, &inverse.attributes[1]).unwrap_err();
+        assert!(matches!(
+            err,
+            SemanticError::AttributeNotFound { entity, attribute } if entity == "door" && attribute == "lock"
+        ));
+
+        let err = InverseAttribute::legalize(&ns, &ss, &scope, &inverse.attributes[2]).unwrap_err();
+        assert!(matches!(err, SemanticError::Unsupported(_)));
+    }
+
+    #[test]
+    fn legalize_derive() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY base;
+                x: REAL;
+              END_ENTITY;
+              ENTITY sub SUBTYPE OF (base);
+              DERIVE
+                SELF\base.x: REAL := 0.0;
+                y: REAL := 2.0 * x;
+              END_ENTITY;
+              ENTITY unsupported;
+              DERIVE
+                z: AGGREGATE OF REAL := [1.0];
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+        let base = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[0]).unwrap();
+        // `x` is omitted as `*` in the instances of `sub`
+        assert!(matches!(
+            base.attributes[0].derived_in.as_slice(),
+            [TypeRef::Entity { name, .. }] if name == "sub"
+        ));
+        let sub = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[1]).unwrap();
+        let names: Vec<_> = sub
+            .derived_attributes
+            .iter()
+            .map(|attr| attr.name.as_str())
+            .collect();
+        assert_eq!(names, ["x", "y"]);
+
+        let err = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[2]).unwrap_err();
+        assert!(matches!(err, SemanticError::Unsupported(_)));
+    }
+
+    #[test]
+    fn legalize_redeclared() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY base;
+                x: OPTIONAL REAL;
+              END_ENTITY;
+              ENTITY mid SUBTYPE OF (base);
+              END_ENTITY;
+              ENTITY sub SUBTYPE OF (mid);
+                SELF\mid.x RENAMED y: REAL;
+              END_ENTITY;
+              ENTITY wrong SUBTYPE OF (base);
+                SELF\base.z: REAL;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+
+        let base = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[0]).unwrap();
+        assert!(base.attributes[0].derived_in.is_empty());
+
+        let sub = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[2]).unwrap();
+        assert!(sub.attributes.is_empty());
+        let attr = &sub.redeclared_attributes[0];
+        // `x` is declared in `base` rather than `mid`
+        assert_eq!(attr.supertype, "base");
+        assert_eq!(attr.name, "x");
+        assert_eq!(attr.rename.as_deref(), Some("y"));
+        assert!(!attr.optional);
+
+        let err = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[3]).unwrap_err();
+        assert!(matches!(
+            err,
+            SemanticError::AttributeNotFound { entity, attribute } if entity == "base" && attribute == "z"
+        ));
+    }
+
+    #[test]
+    fn legalize_where() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY circle;
+                radius: REAL;
+              WHERE
+                wr1: radius > 0.0;
+                radius < 100.0;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+        let circle = Entity::legalize(&ns, &ss, &scope, &st.schemas[0].entities[0]).unwrap();
+        let labels: Vec<_> = circle
+            .where_rules
+            .iter()
+            .map(|rule| rule.label.as_deref())
+            .collect();
+        assert_eq!(labels, vec![Some("wr1"), None]);
+    }
 }
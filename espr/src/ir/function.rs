@@ -0,0 +1,204 @@
+use super::*;
+use crate::ast;
+
+/// Formal parameter of `FUNCTION` or `PROCEDURE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Parameter {
+    pub name: String,
+    /// Declared with `VAR` in `PROCEDURE`, i.e. the argument is passed by reference
+    pub is_variable: bool,
+}
+
+impl From<&ast::FormalParameter> for Parameter {
+    fn from(param: &ast::FormalParameter) -> Self {
+        Parameter {
+            name: param.name.clone(),
+            is_variable: param.is_variable,
+        }
+    }
+}
+
+/// Legalized `FUNCTION` declaration
+///
+/// Values are dynamically typed in the generated code,
+/// and thus the types of parameters and return value are not kept.
+/// The body is kept as AST, and its names are resolved in code generation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    /// Functions declared in the function
+    pub functions: Vec<Function>,
+    /// Procedures declared in the function
+    pub procedures: Vec<Procedure>,
+    /// Other nested declarations, e.g. `TYPE`, kept as AST
+    pub declarations: Vec<ast::Declaration>,
+    pub constants: Vec<ast::Constant>,
+    pub variables: Vec<ast::LocalVariable>,
+    pub statements: Vec<ast::Statement>,
+}
+
+impl Legalize for Function {
+    type Input = ast::Function;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        function: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        let here = scope.pushed(ScopeType::Function, &function.name);
+        let (functions, procedures, declarations) =
+            legalize_nested(ns, ss, &here, &function.declarations)?;
+        Ok(Function {
+            name: function.name.clone(),
+            parameters: function.parameters.iter().map(Parameter::from).collect(),
+            functions,
+            procedures,
+            declarations,
+            constants: function.constants.clone(),
+            variables: function.variables.clone(),
+            statements: function.statements.clone(),
+        })
+    }
+}
+
+/// Legalized `PROCEDURE` declaration, see [Function]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Procedure {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    /// Functions declared in the procedure
+    pub functions: Vec<Function>,
+    /// Procedures declared in the procedure
+    pub procedures: Vec<Procedure>,
+    /// Other nested declarations, e.g. `TYPE`, kept as AST
+    pub declarations: Vec<ast::Declaration>,
+    pub constants: Vec<ast::Constant>,
+    pub variables: Vec<ast::LocalVariable>,
+    pub statements: Vec<ast::Statement>,
+}
+
+impl Legalize for Procedure {
+    type Input = ast::Procedure;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        procedure: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        let here = scope.pushed(ScopeType::Procedure, &procedure.name);
+        let (functions, procedures, declarations) =
+            legalize_nested(ns, ss, &here, &procedure.declarations)?;
+        Ok(Procedure {
+            name: procedure.name.clone(),
+            parameters: procedure.parameters.iter().map(Parameter::from).collect(),
+            functions,
+            procedures,
+            declarations,
+            constants: procedure.constants.clone(),
+            variables: procedure.variables.clone(),
+            statements: procedure.statements.clone(),
+        })
+    }
+}
+
+/// Functions, procedures, and other declarations nested in an algorithm
+pub(crate) type Nested = (Vec<Function>, Vec<Procedure>, Vec<ast::Declaration>);
+
+/// Split nested declarations in `FUNCTION`, `PROCEDURE`, or `RULE`
+/// into legalized functions and procedures, and the others kept as AST
+pub(crate) fn legalize_nested(
+    ns: &Namespace,
+    ss: &Constraints,
+    scope: &Scope,
+    declarations: &[ast::Declaration],
+) -> Result<Nested, SemanticError> {
+    let mut functions = Vec::new();
+    let mut procedures = Vec::new();
+    let mut others = Vec::new();
+    for declaration in declarations {
+        match declaration {
+            ast::Declaration::Function(function) => {
+                functions.push(Function::legalize(ns, ss, scope, function)?)
+            }
+            ast::Declaration::Procedure(procedure) => {
+                procedures.push(Procedure::legalize(ns, ss, scope, procedure)?)
+            }
+            _ => others.push(declaration.clone()),
+        }
+    }
+    Ok((functions, procedures, others))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legalize_procedure() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              PROCEDURE push(VAR values: LIST OF REAL; item: REAL);
+                INSERT(values, item, 0);
+              END_PROCEDURE;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+
+        let procedure =
+            Procedure::legalize(&ns, &ss, &scope, &st.schemas[0].procedures[0]).unwrap();
+        assert_eq!(
+            procedure.parameters,
+            vec![
+                Parameter {
+                    name: "values".to_string(),
+                    is_variable: true
+                },
+                Parameter {
+                    name: "item".to_string(),
+                    is_variable: false
+                },
+            ]
+        );
+        assert_eq!(procedure.statements.len(), 1);
+    }
+
+    #[test]
+    fn legalize_nested() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              FUNCTION triangular(n: INTEGER): INTEGER;
+                FUNCTION partial_sum(n: INTEGER; total: INTEGER): INTEGER;
+                  RETURN (total + n);
+                END_FUNCTION;
+                TYPE local_measure = REAL;
+                END_TYPE;
+                RETURN (partial_sum(n, 0));
+              END_FUNCTION;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+
+        let function = Function::legalize(&ns, &ss, &scope, &st.schemas[0].functions[0]).unwrap();
+        assert_eq!(function.functions.len(), 1);
+        assert_eq!(function.functions[0].name, "partial_sum");
+        assert_eq!(function.functions[0].parameters.len(), 2);
+        // `TYPE` is kept as AST
+        assert_eq!(function.declarations.len(), 1);
+    }
+}
@@ -166,24 +166,31 @@
 //!
 
 mod complex_entity;
+mod constant;
 mod constraints;
 mod entity;
+mod function;
 mod namespace;
+mod rule;
 mod schema;
 mod scope;
 mod type_decl;
 mod type_ref;
 
 pub use complex_entity::*;
+pub use constant::*;
 pub use constraints::*;
 pub use entity::*;
+pub use function::*;
 pub use namespace::*;
+pub use rule::*;
 pub use schema::*;
 pub use scope::*;
 pub use type_decl::*;
 pub use type_ref::*;
 
-use crate::ast::SyntaxTree;
+use crate::ast::{self, SyntaxTree};
+use std::collections::HashSet;
 use thiserror::Error;
 
 /// Semantic errors
@@ -197,6 +204,15 @@ pub enum SemanticError {
 
     #[error("Same item ({0}) is declared multiple times")]
     DuplicatedDeclaration(Path),
+
+    #[error("{0} is not an entity")]
+    NotEntity(Path),
+
+    #[error("Attribute '{attribute}' is not declared in entity '{entity}'")]
+    AttributeNotFound { entity: String, attribute: String },
+
+    #[error("Unsupported declaration: {0}")]
+    Unsupported(String),
 }
 
 /// Legalize partial AST input into corresponding intermediate representation
@@ -213,7 +229,8 @@ pub trait Legalize: Sized {
 }
 
 /// Intermediate Representation
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct IR {
     pub schemas: Vec<Schema>,
 }
@@ -240,6 +257,182 @@ impl Legalize for IR {
             .iter()
             .map(|schema| Schema::legalize(ns, ss, scope, schema))
             .collect::<Result<Vec<Schema>, SemanticError>>()?;
+        let schemas = merge_interfaced(ns, syn, schemas)?;
         Ok(IR { schemas })
     }
 }
+
+/// Merge schemas connected by `USE FROM` or `REFERENCE FROM` into the schema interfacing others
+///
+/// The root schema of connected schemas is the first one not interfaced by others.
+/// Declarations of the other schemas are merged only if they are visible in the root schemas,
+/// or referred from those merged, e.g. attribute types, supertypes, and subtypes.
+fn merge_interfaced(
+    ns: &Namespace,
+    st: &SyntaxTree,
+    schemas: Vec<Schema>,
+) -> Result<Vec<Schema>, SemanticError> {
+    // Interfaces between loaded schemas, (interfacing, interfaced) as indices of schemas
+    let mut edges = Vec::new();
+    for (i, schema) in st.schemas.iter().enumerate() {
+        for interface in &schema.interfaces {
+            let (name, _) = interface_resources(interface);
+            if let Some(j) = st.schemas.iter().position(|s| s.name == *name) {
+                if i != j {
+                    edges.push((i, j));
+                }
+            }
+        }
+    }
+
+    // Connected schemas are labeled by the smallest index in them
+    let mut labels: Vec<usize> = (0..schemas.len()).collect();
+    loop {
+        let mut updated = false;
+        for &(i, j) in &edges {
+            let label = labels[i].min(labels[j]);
+            if labels[i] != label || labels[j] != label {
+                labels[i] = label;
+                labels[j] = label;
+                updated = true;
+            }
+        }
+        if !updated {
+            break;
+        }
+    }
+
+    let mut schemas: Vec<Option<Schema>> = schemas.into_iter().map(Some).collect();
+    let mut merged = Vec::new();
+    for label in 0..schemas.len() {
+        let members: Vec<usize> = (0..schemas.len()).filter(|&i| labels[i] == label).collect();
+        match members.len() {
+            0 => continue,
+            1 => {
+                merged.push(schemas[label].take().unwrap());
+                continue;
+            }
+            _ => {}
+        }
+        let roots: Vec<usize> = members
+            .iter()
+            .copied()
+            .filter(|&m| edges.iter().all(|&(_, j)| j != m))
+            .collect();
+        let roots = if roots.is_empty() {
+            vec![members[0]]
+        } else {
+            roots
+        };
+
+        let mut visible = HashSet::new();
+        let mut instantiable = HashSet::new();
+        for &root in &roots {
+            let scope = Scope::root().schema(&st.schemas[root].name);
+            for (_ty, _name, index) in &ns[&scope] {
+                visible.insert(ns[*index].0.clone());
+            }
+            used_entities(ns, st, root, &mut Vec::new(), &mut instantiable)?;
+        }
+        let member_schemas: Vec<&Schema> = members
+            .iter()
+            .map(|&m| schemas[m].as_ref().unwrap())
+            .collect();
+        referred_closure(&member_schemas, &mut visible);
+
+        let mut schema = schemas[roots[0]].take().unwrap();
+        for m in members {
+            if m != roots[0] {
+                schema.merge(schemas[m].take().unwrap(), &visible, &instantiable)?;
+            }
+        }
+        merged.push(schema);
+    }
+    Ok(merged)
+}
+
+fn interface_resources(
+    interface: &ast::InterfaceSpec,
+) -> (&String, &Vec<(String, Option<String>)>) {
+    match interface {
+        ast::InterfaceSpec::Use { name, types } => (name, types),
+        ast::InterfaceSpec::Reference { name, resources } => (name, resources),
+    }
+}
+
+/// Entities declared in the schema of `index`, or interfaced into it by `USE FROM`,
+/// which are instantiable in the schema
+fn used_entities(
+    ns: &Namespace,
+    st: &SyntaxTree,
+    index: usize,
+    visited: &mut Vec<usize>,
+    entities: &mut HashSet<Path>,
+) -> Result<(), SemanticError> {
+    if visited.contains(&index) {
+        return Ok(());
+    }
+    visited.push(index);
+    let schema = &st.schemas[index];
+    let scope = Scope::root().schema(&schema.name);
+    for entity in &schema.entities {
+        entities.insert(Path::entity(&scope, &entity.name));
+    }
+    for interface in &schema.interfaces {
+        let (name, types) = match interface {
+            ast::InterfaceSpec::Use { name, types } => (name, types),
+            ast::InterfaceSpec::Reference { .. } => continue,
+        };
+        let from = match st.schemas.iter().position(|s| s.name == *name) {
+            Some(from) => from,
+            // Schema which is not loaded
+            None => continue,
+        };
+        if types.is_empty() {
+            used_entities(ns, st, from, visited, entities)?;
+        } else {
+            let scope = Scope::root().schema(name);
+            for (item, _rename) in types {
+                let (path, _index) = ns.resolve(&scope, item)?;
+                if path.ty == ScopeType::Entity {
+                    entities.insert(path);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Extend `visible` by entities and types referred from them in `schemas` recursively
+fn referred_closure(schemas: &[&Schema], visible: &mut HashSet<Path>) {
+    let mut stack: Vec<Path> = visible.iter().cloned().collect();
+    while let Some(path) = stack.pop() {
+        let schema = match schemas
+            .iter()
+            .find(|schema| Scope::root().schema(&schema.name) == path.scope)
+        {
+            Some(schema) => schema,
+            None => continue,
+        };
+        let referred = match path.ty {
+            ScopeType::Entity => schema
+                .entities
+                .iter()
+                .find(|e| e.name == path.name)
+                .map(|e| e.referred_types()),
+            ScopeType::Type => schema
+                .types
+                .iter()
+                .find(|ty| ty.id() == path.name)
+                .map(|ty| ty.referred_types()),
+            _ => None,
+        };
+        for ty in referred.unwrap_or_default() {
+            if let Some(path) = ty.path() {
+                if visible.insert(path.clone()) {
+                    stack.push(path);
+                }
+            }
+        }
+    }
+}
@@ -18,6 +18,10 @@ pub enum Named<'st> {
 /// - Resolving name in each [Scope] into [Path]
 /// - Get a reference to AST portion corresponding to [Path]
 ///
+/// Entities and types interfaced by `USE FROM` or `REFERENCE FROM`
+/// are registered in the schema scope with their renamed names,
+/// and resolved into the path where they are declared.
+///
 #[derive(Debug, Clone)]
 pub struct Namespace<'st> {
     pub names: HashMap<Scope, Vec<(ScopeType, String, usize)>>,
@@ -65,6 +69,47 @@ impl<'st> Namespace<'st> {
             names.insert(here, current_names);
         }
 
+        // Interfaced items may be interfaced again into another schema,
+        // and thus this is repeated until no new name is found.
+        loop {
+            let mut updated = false;
+            for schema in &st.schemas {
+                let here = root.pushed(ScopeType::Schema, &schema.name);
+                for interface in &schema.interfaces {
+                    let (from, resources) = match interface {
+                        ast::InterfaceSpec::Use { name, types } => (name, types),
+                        ast::InterfaceSpec::Reference { name, resources } => (name, resources),
+                    };
+                    // Schema which is not loaded, or functions and constants are not registered
+                    let visible = match names.get(&root.pushed(ScopeType::Schema, from)) {
+                        Some(visible) => visible.clone(),
+                        None => continue,
+                    };
+                    let interfaced: Vec<_> = if resources.is_empty() {
+                        visible
+                    } else {
+                        resources
+                            .iter()
+                            .filter_map(|(name, rename)| {
+                                let (ty, _, index) = visible.iter().find(|(_, n, _)| n == name)?;
+                                Some((*ty, rename.as_ref().unwrap_or(name).clone(), *index))
+                            })
+                            .collect()
+                    };
+                    let current = names.get_mut(&here).expect("Schema is registered above");
+                    for (ty, name, index) in interfaced {
+                        if current.iter().all(|(_, n, _)| *n != name) {
+                            current.push((ty, name, index));
+                            updated = true;
+                        }
+                    }
+                }
+            }
+            if !updated {
+                break;
+            }
+        }
+
         Namespace { names, ast }
     }
 
@@ -77,7 +122,7 @@ impl<'st> Namespace<'st> {
         self.ast.len()
     }
 
-    /// Resolve a `name` referred in a `scope` into the full path.
+    /// Resolve a `name` referred in a `scope` into the full path where it is declared.
     ///
     /// Error
     /// ------
@@ -87,9 +132,9 @@ impl<'st> Namespace<'st> {
         let mut scope = scope.clone();
         loop {
             if let Some(names) = self.names.get(&scope) {
-                for (ty, n, index) in names {
+                for (_ty, n, index) in names {
                     if name == n {
-                        return Ok((Path::new(&scope, *ty, n), *index));
+                        return Ok((self.ast[*index].0.clone(), *index));
                     }
                 }
             }
@@ -157,6 +202,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resolve_interfaced() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA geometry;
+              ENTITY point;
+                x, y: REAL;
+              END_ENTITY;
+              TYPE label = STRING;
+              END_TYPE;
+            END_SCHEMA;
+
+            SCHEMA topology;
+              USE FROM geometry (point AS vertex_point);
+            END_SCHEMA;
+
+            SCHEMA model;
+              USE FROM topology;
+              REFERENCE FROM geometry (label);
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let root = Scope::root();
+        let geometry = root.pushed(ScopeType::Schema, "geometry");
+        let model = root.pushed(ScopeType::Schema, "model");
+
+        let (path, _) = ns.resolve(&model, "vertex_point").unwrap();
+        assert_eq!(path, Path::new(&geometry, ScopeType::Entity, "point"));
+        let (path, _) = ns.resolve(&model, "label").unwrap();
+        assert_eq!(path, Path::new(&geometry, ScopeType::Type, "label"));
+        // Not interfaced by the original name
+        assert!(ns.resolve(&model, "point").is_err());
+    }
+
     #[test]
     fn namespace_debug() {
         let st = ast::SyntaxTree::parse(
@@ -0,0 +1,157 @@
+use super::*;
+use crate::ast;
+
+/// Legalized domain rule in `WHERE` clause, e.g. `wr1: SELF > 0;`
+///
+/// The expression is kept as AST, and its names are resolved in code generation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct DomainRule {
+    /// Label of the rule, `None` for an unlabeled rule
+    pub label: Option<String>,
+    pub expr: ast::Expression,
+}
+
+impl Legalize for DomainRule {
+    type Input = ast::DomainRule;
+
+    fn legalize(
+        _ns: &Namespace,
+        _ss: &Constraints,
+        _scope: &Scope,
+        rule: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        Ok(DomainRule {
+            label: rule.label.clone(),
+            expr: rule.expr.clone(),
+        })
+    }
+}
+
+impl DomainRule {
+    /// Legalize all rules in an optional `WHERE` clause
+    pub fn legalize_where_clause(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        where_clause: Option<&ast::WhereClause>,
+    ) -> Result<Vec<Self>, SemanticError> {
+        match where_clause {
+            Some(where_clause) => where_clause
+                .rules
+                .iter()
+                .map(|rule| DomainRule::legalize(ns, ss, scope, rule))
+                .collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Legalized global rule, e.g.
+///
+/// ```text
+/// RULE single_front_door FOR (door);
+/// WHERE
+///   wr1: SIZEOF(QUERY(d <* door | d.name = 'front')) <= 1;
+/// END_RULE;
+/// ```
+///
+/// The entities in `FOR` are the sets of all instances of them in the population.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Rule {
+    pub name: String,
+    /// Entities in `FOR` clause
+    pub entities: Vec<TypeRef>,
+    /// Functions declared in the rule
+    pub functions: Vec<Function>,
+    /// Procedures declared in the rule
+    pub procedures: Vec<Procedure>,
+    /// Other nested declarations, e.g. `TYPE`, kept as AST
+    pub declarations: Vec<ast::Declaration>,
+    /// Constants in `CONSTANT` block, kept as AST
+    pub constants: Vec<ast::Constant>,
+    /// Local variables in `LOCAL` block, kept as AST
+    pub variables: Vec<ast::LocalVariable>,
+    /// Statements executed before evaluating `where_rules`, kept as AST
+    pub statements: Vec<ast::Statement>,
+    pub where_rules: Vec<DomainRule>,
+}
+
+impl Legalize for Rule {
+    type Input = ast::Rule;
+
+    fn legalize(
+        ns: &Namespace,
+        ss: &Constraints,
+        scope: &Scope,
+        rule: &Self::Input,
+    ) -> Result<Self, SemanticError> {
+        let entities = rule
+            .references
+            .iter()
+            .map(|name| {
+                let (path, _index) = ns.resolve(scope, name)?;
+                if path.ty != ScopeType::Entity {
+                    return Err(SemanticError::NotEntity(path));
+                }
+                TypeRef::from_path(ns, ss, &path)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let here = scope.pushed(ScopeType::Rule, &rule.name);
+        let where_rules =
+            DomainRule::legalize_where_clause(ns, ss, &here, Some(&rule.where_clause))?;
+        let (functions, procedures, declarations) =
+            legalize_nested(ns, ss, &here, &rule.declarations)?;
+        Ok(Rule {
+            name: rule.name.clone(),
+            entities,
+            functions,
+            procedures,
+            declarations,
+            constants: rule.constants.clone(),
+            variables: rule.variables.clone(),
+            statements: rule.statements.clone(),
+            where_rules,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legalize_rule() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              TYPE label = STRING;
+              END_TYPE;
+              ENTITY door;
+                name: label;
+              END_ENTITY;
+              RULE single_front_door FOR (door);
+              WHERE
+                wr1: SIZEOF(QUERY(d <* door | d.name = 'front')) <= 1;
+              END_RULE;
+              RULE rule_for_type FOR (label);
+              WHERE
+                wr1: TRUE;
+              END_RULE;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+
+        let rule = Rule::legalize(&ns, &ss, &scope, &st.schemas[0].rules[0]).unwrap();
+        assert_eq!(rule.entities.len(), 1);
+        assert_eq!(rule.where_rules[0].label.as_deref(), Some("wr1"));
+
+        let err = Rule::legalize(&ns, &ss, &scope, &st.schemas[0].rules[1]).unwrap_err();
+        assert!(matches!(err, SemanticError::NotEntity(_)));
+    }
+}
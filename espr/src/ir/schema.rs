@@ -1,11 +1,155 @@
 use super::{entity::*, namespace::*, scope::*, type_decl::*, *};
 use crate::ast;
+use std::collections::HashSet;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Schema {
     pub name: String,
     pub entities: Vec<Entity>,
     pub types: Vec<TypeDecl>,
+    /// Constants declared in `CONSTANT` block
+    pub constants: Vec<Constant>,
+    /// Global rules declared by `RULE`
+    pub rules: Vec<Rule>,
+    pub functions: Vec<Function>,
+    pub procedures: Vec<Procedure>,
+    /// Schemas interfaced by `USE FROM` or `REFERENCE FROM` and merged into this schema
+    pub interfaced: Vec<InterfacedSchema>,
+}
+
+/// Schema merged into another schema interfacing it, see [Schema::merge]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct InterfacedSchema {
+    pub name: String,
+    /// Names of entities, types, and rules declared in this schema and merged
+    pub declarations: Vec<String>,
+    /// Entities interfaced by `REFERENCE FROM` or implicitly rather than `USE FROM`,
+    /// which are instantiated only as attribute values of other instances
+    pub referenced: Vec<String>,
+}
+
+impl Schema {
+    /// Complex entities in the constraints of all entities without duplication
+    pub fn complex_entities(&self) -> Vec<&ComplexEntity> {
+        let mut complex_entities: Vec<&ComplexEntity> = Vec::new();
+        for entity in &self.entities {
+            for complex in &entity.complex_constraints {
+                if complex_entities.iter().all(|c| c.name != complex.name) {
+                    complex_entities.push(complex);
+                }
+            }
+        }
+        complex_entities
+    }
+
+    /// Name of the schema where the entity, type, or rule is declared
+    pub fn declaring_schema(&self, name: &str) -> &str {
+        self.interfaced
+            .iter()
+            .find(|schema| schema.declarations.iter().any(|n| n == name))
+            .map(|schema| schema.name.as_str())
+            .unwrap_or(&self.name)
+    }
+
+    /// Merge a schema interfaced by this schema
+    ///
+    /// Since interfaced entities and types refer each other across schemas,
+    /// they are generated in a single module of this schema,
+    /// and thus every declaration is emitted once.
+    ///
+    /// Only the entities and types in `visible`, i.e. those interfaced into this schema
+    /// and those referred from them, are merged with the rules on them.
+    /// Entities not in `instantiable` are listed in [InterfacedSchema::referenced].
+    /// Functions, procedures, and constants are merged unless this schema has those of the same name.
+    ///
+    /// Error
+    /// ------
+    /// - Visible entities or types of the same name are declared in both schemas
+    ///
+    pub fn merge(
+        &mut self,
+        other: Schema,
+        visible: &HashSet<Path>,
+        instantiable: &HashSet<Path>,
+    ) -> Result<(), SemanticError> {
+        let scope = Scope::root().pushed(ScopeType::Schema, &other.name);
+        let entities: Vec<Entity> = other
+            .entities
+            .into_iter()
+            .filter(|e| visible.contains(&Path::entity(&scope, &e.name)))
+            .collect();
+        let types: Vec<TypeDecl> = other
+            .types
+            .into_iter()
+            .filter(|ty| visible.contains(&Path::r#type(&scope, ty.id())))
+            .collect();
+        for e in &entities {
+            if self.entity_or_type_exists(&e.name) {
+                return Err(SemanticError::DuplicatedDeclaration(Path::entity(
+                    &scope, &e.name,
+                )));
+            }
+        }
+        for ty in &types {
+            if self.entity_or_type_exists(ty.id()) {
+                return Err(SemanticError::DuplicatedDeclaration(Path::r#type(
+                    &scope,
+                    ty.id(),
+                )));
+            }
+        }
+        let rules: Vec<Rule> = other
+            .rules
+            .into_iter()
+            .filter(|rule| {
+                rule.entities
+                    .iter()
+                    .all(|ty| ty.path().map_or(false, |path| visible.contains(&path)))
+            })
+            .collect();
+
+        let referenced = entities
+            .iter()
+            .filter(|e| !instantiable.contains(&Path::entity(&scope, &e.name)))
+            .map(|e| e.name.clone())
+            .collect();
+        let declarations = entities
+            .iter()
+            .map(|e| e.name.clone())
+            .chain(types.iter().map(|ty| ty.id().to_string()))
+            .chain(rules.iter().map(|rule| rule.name.clone()))
+            .collect();
+        self.entities.extend(entities);
+        self.types.extend(types);
+        self.rules.extend(rules);
+        for constant in other.constants {
+            if self.constants.iter().all(|c| c.name != constant.name) {
+                self.constants.push(constant);
+            }
+        }
+        for function in other.functions {
+            if self.functions.iter().all(|f| f.name != function.name) {
+                self.functions.push(function);
+            }
+        }
+        for procedure in other.procedures {
+            if self.procedures.iter().all(|p| p.name != procedure.name) {
+                self.procedures.push(procedure);
+            }
+        }
+        self.interfaced.push(InterfacedSchema {
+            name: other.name,
+            declarations,
+            referenced,
+        });
+        Ok(())
+    }
+
+    fn entity_or_type_exists(&self, name: &str) -> bool {
+        self.entities.iter().any(|e| e.name == name) || self.types.iter().any(|ty| ty.id() == name)
+    }
 }
 
 impl Legalize for Schema {
@@ -28,10 +172,35 @@ impl Legalize for Schema {
             .iter()
             .map(|entity| TypeDecl::legalize(ns, ss, &here, entity))
             .collect::<Result<Vec<TypeDecl>, _>>()?;
+        let constants = schema
+            .constants
+            .iter()
+            .map(|constant| Constant::legalize(ns, ss, &here, constant))
+            .collect::<Result<Vec<Constant>, _>>()?;
+        let rules = schema
+            .rules
+            .iter()
+            .map(|rule| Rule::legalize(ns, ss, &here, rule))
+            .collect::<Result<Vec<Rule>, _>>()?;
+        let functions = schema
+            .functions
+            .iter()
+            .map(|function| Function::legalize(ns, ss, &here, function))
+            .collect::<Result<Vec<Function>, _>>()?;
+        let procedures = schema
+            .procedures
+            .iter()
+            .map(|procedure| Procedure::legalize(ns, ss, &here, procedure))
+            .collect::<Result<Vec<Procedure>, _>>()?;
         Ok(Schema {
             name,
             entities,
             types,
+            constants,
+            rules,
+            functions,
+            procedures,
+            interfaced: Vec::new(),
         })
     }
 }
@@ -51,4 +220,112 @@ mod tests {
         let schema = Schema::legalize(&ns, &ss, &scope, schema).unwrap();
         dbg!(&schema);
     }
+
+    #[test]
+    fn merge_interfaced() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA geometry;
+              ENTITY point;
+                x, y: REAL;
+              END_ENTITY;
+              ENTITY circle;
+                center: point;
+                radius: REAL;
+              END_ENTITY;
+              TYPE label = STRING;
+              END_TYPE;
+            END_SCHEMA;
+
+            SCHEMA model;
+              USE FROM topology;
+              REFERENCE FROM geometry (label);
+              ENTITY circle;
+              END_ENTITY;
+            END_SCHEMA;
+
+            SCHEMA topology;
+              USE FROM geometry (point AS vertex_point);
+              ENTITY edge;
+                head, tail: vertex_point;
+              END_ENTITY;
+            END_SCHEMA;
+
+            SCHEMA other;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ir = IR::from_syntax_tree(&st).unwrap();
+        assert_eq!(ir.schemas.len(), 2);
+
+        let model = &ir.schemas[0];
+        assert_eq!(model.name, "model");
+        // `geometry.circle` is not interfaced, and does not conflict with `model.circle`
+        let entities: Vec<_> = model.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(entities, ["circle", "point", "edge"]);
+        assert_eq!(model.declaring_schema("circle"), "model");
+        assert_eq!(model.declaring_schema("point"), "geometry");
+        assert_eq!(model.declaring_schema("label"), "geometry");
+        assert_eq!(model.declaring_schema("edge"), "topology");
+        // Renamed type refers the original declaration
+        assert!(matches!(
+            &model.entity("edge").unwrap().attributes[0].ty,
+            TypeRef::Entity { name, .. } if name == "point"
+        ));
+        // `point` is instantiable in `model` since it is interfaced by `USE FROM` through `topology`
+        assert!(model.interfaced.iter().all(|s| s.referenced.is_empty()));
+        assert_eq!(ir.schemas[1].name, "other");
+    }
+
+    #[test]
+    fn merge_referenced() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA geometry;
+              ENTITY point;
+                x, y: REAL;
+              END_ENTITY;
+              ENTITY circle;
+                center: point;
+              END_ENTITY;
+            END_SCHEMA;
+
+            SCHEMA model;
+              USE FROM geometry (circle);
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ir = IR::from_syntax_tree(&st).unwrap();
+        let model = &ir.schemas[0];
+        // `point` is interfaced implicitly as the attribute type of `circle`
+        assert_eq!(model.interfaced[0].declarations, ["point", "circle"]);
+        assert_eq!(model.interfaced[0].referenced, ["point"]);
+    }
+
+    #[test]
+    fn merge_duplicated() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA a;
+              ENTITY point;
+              END_ENTITY;
+              ENTITY line;
+                start: point;
+              END_ENTITY;
+            END_SCHEMA;
+
+            SCHEMA b;
+              USE FROM a (line);
+              ENTITY point;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        // `a.point` is required by `a.line`, and conflicts with `b.point`
+        let err = IR::from_syntax_tree(&st).unwrap_err();
+        assert!(matches!(err, SemanticError::DuplicatedDeclaration(_)));
+    }
 }
@@ -4,6 +4,7 @@ use std::{cmp, fmt};
 /// Identifier in EXPRESS language must be one of scopes described in
 /// "Table 9 – Scope and identifier defining items"
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum ScopeType {
     Entity,
     Alias,
@@ -47,6 +48,7 @@ pub enum ScopeType {
 /// assert!(!(schema1 >= schema2));
 /// ```
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Scope(Vec<(ScopeType, String)>);
 
 // Custom debug output like: `schema.entity`
@@ -130,6 +132,7 @@ impl Scope {
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Path {
     pub scope: Scope,
     pub ty: ScopeType,
@@ -3,37 +3,46 @@ use crate::ast;
 
 /// Rename of primitive type,
 /// e.g. `TYPE label = STRING; ENDTYPE;`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Simple {
     pub id: String,
     pub ty: SimpleType,
+    pub where_rules: Vec<DomainRule>,
 }
 
 /// Rename of user defined type,
 /// e.g. `TYPE box_height = positive_ratio_measure; END_TYPE;`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Rename {
     pub id: String,
     pub ty: TypeRef,
+    pub where_rules: Vec<DomainRule>,
 }
 
 /// Enumeration of values,
 /// e.g. `TYPE text_path = ENUMERATION OF (up, right, down, left); END_TYPE;`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Enumeration {
     pub id: String,
     pub items: Vec<String>,
+    pub where_rules: Vec<DomainRule>,
 }
 
 /// Select of user defined types,
 /// e.g. `TYPE geometric_set_select = SELECT (point, curve); END_TYPE;`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Select {
     pub id: String,
     pub types: Vec<TypeRef>,
+    pub where_rules: Vec<DomainRule>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum TypeDecl {
     Simple(Simple),
     Rename(Rename),
@@ -50,6 +59,25 @@ impl TypeDecl {
             TypeDecl::Select(e) => &e.id,
         }
     }
+
+    /// Types referred by this declaration, i.e. the underlying type or the types in select list
+    pub fn referred_types(&self) -> Vec<&TypeRef> {
+        match self {
+            TypeDecl::Rename(e) => vec![&e.ty],
+            TypeDecl::Select(e) => e.types.iter().collect(),
+            TypeDecl::Simple(_) | TypeDecl::Enumeration(_) => Vec::new(),
+        }
+    }
+
+    /// Domain rules declared in `WHERE` clause
+    pub fn where_rules(&self) -> &[DomainRule] {
+        match self {
+            TypeDecl::Simple(e) => &e.where_rules,
+            TypeDecl::Rename(e) => &e.where_rules,
+            TypeDecl::Enumeration(e) => &e.where_rules,
+            TypeDecl::Select(e) => &e.where_rules,
+        }
+    }
 }
 
 impl Legalize for TypeDecl {
@@ -62,16 +90,20 @@ impl Legalize for TypeDecl {
     ) -> Result<Self, SemanticError> {
         use ast::Type;
         let id = type_decl.type_id.clone();
+        let where_rules =
+            DomainRule::legalize_where_clause(ns, ss, scope, type_decl.where_clause.as_ref())?;
         Ok(match &type_decl.underlying_type {
             Type::Simple(ty) => TypeDecl::Simple(Simple {
                 id,
                 ty: SimpleType(*ty),
+                where_rules,
             }),
             Type::Named(name) => {
                 let (path, _index) = ns.resolve(scope, name)?;
                 TypeDecl::Rename(Rename {
                     id,
                     ty: TypeRef::from_path(ns, ss, &path)?,
+                    where_rules,
                 })
             }
             Type::Enumeration {
@@ -80,6 +112,7 @@ impl Legalize for TypeDecl {
             } => TypeDecl::Enumeration(Enumeration {
                 id,
                 items: items.clone(),
+                where_rules,
             }),
             Type::Select {
                 types,
@@ -92,41 +125,17 @@ impl Legalize for TypeDecl {
                         TypeRef::from_path(ns, ss, &path)
                     })
                     .collect::<Result<Vec<_>, _>>()?;
-                TypeDecl::Select(Select { id, types })
-            }
-            Type::Set { base, bound } => {
-                let base = TypeRef::legalize(ns, ss, scope, base.as_ref())?;
-                let bound = if let Some(bound) = bound {
-                    Some(Legalize::legalize(ns, ss, scope, bound)?)
-                } else {
-                    None
-                };
-                TypeDecl::Rename(Rename {
+                TypeDecl::Select(Select {
                     id,
-                    ty: TypeRef::Set {
-                        base: Box::new(base),
-                        bound,
-                    },
+                    types,
+                    where_rules,
                 })
             }
-            Type::List {
-                base,
-                bound,
-                unique,
-            } => {
-                let base = TypeRef::legalize(ns, ss, scope, base.as_ref())?;
-                let bound = if let Some(bound) = bound {
-                    Some(Legalize::legalize(ns, ss, scope, bound)?)
-                } else {
-                    None
-                };
+            Type::Set { .. } | Type::Bag { .. } | Type::List { .. } | Type::Array { .. } => {
                 TypeDecl::Rename(Rename {
                     id,
-                    ty: TypeRef::List {
-                        base: Box::new(base),
-                        bound,
-                        unique: *unique,
-                    },
+                    ty: TypeRef::legalize(ns, ss, scope, &type_decl.underlying_type)?,
+                    where_rules,
                 })
             }
             _ => panic!(),
@@ -2,6 +2,7 @@ use super::{namespace::*, scope::*, *};
 use crate::ast;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct SimpleType(pub ast::SimpleType);
 
 impl Legalize for SimpleType {
@@ -16,8 +17,26 @@ impl Legalize for SimpleType {
     }
 }
 
+/// Bound `[lower:upper]` of an aggregation type, e.g. `[2:?]` of `LIST [2:?] OF REAL`
+///
+/// Bounds consist of integer literals and arithmetic operators are evaluated in legalization.
+/// Others, e.g. `[1:n]` using an attribute `n` of the entity, are kept as `None` and not checked.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Bound {}
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+pub struct Bound {
+    /// Lower bound, `None` if not evaluated
+    pub lower: Option<i64>,
+    /// Upper bound, `None` for `?` or if not evaluated
+    pub upper: Option<i64>,
+}
+
+impl Bound {
+    /// Number of elements of `ARRAY` indexed by this bound, `None` if not evaluated
+    pub fn array_size(&self) -> Option<usize> {
+        let size = self.upper?.checked_sub(self.lower?)?.checked_add(1)?;
+        usize::try_from(size).ok()
+    }
+}
 
 impl Legalize for Bound {
     type Input = ast::Bound;
@@ -25,14 +44,46 @@ impl Legalize for Bound {
         _ns: &Namespace,
         _ss: &Constraints,
         _scope: &Scope,
-        _input: &Self::Input,
+        input: &Self::Input,
     ) -> Result<Self, SemanticError> {
-        // FIXME
-        Ok(Bound {})
+        Ok(Bound {
+            lower: evaluate_bound(&input.lower),
+            upper: evaluate_bound(&input.upper),
+        })
+    }
+}
+
+/// Evaluate an integer expression in bound, `None` for `?` or an expression depending on other values
+fn evaluate_bound(expr: &ast::Expression) -> Option<i64> {
+    use ast::{BinaryOperator, Expression, UnaryOperator};
+    match expr {
+        // Integer literals are also parsed as real numbers
+        Expression::Literal(ast::Literal::Real(value))
+            if value.fract() == 0.0 && value.abs() < i64::MAX as f64 =>
+        {
+            Some(*value as i64)
+        }
+        Expression::Unary { op, arg } => match op {
+            UnaryOperator::Plus => evaluate_bound(arg),
+            UnaryOperator::Minus => evaluate_bound(arg)?.checked_neg(),
+            UnaryOperator::Not => None,
+        },
+        Expression::Binary { op, arg1, arg2 } => {
+            let (a, b) = (evaluate_bound(arg1)?, evaluate_bound(arg2)?);
+            match op {
+                BinaryOperator::Add => a.checked_add(b),
+                BinaryOperator::Sub => a.checked_sub(b),
+                BinaryOperator::Mul => a.checked_mul(b),
+                BinaryOperator::IntegerDiv => a.checked_div(b),
+                _ => None,
+            }
+        }
+        _ => None,
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub enum TypeRef {
     SimpleType(SimpleType),
     Named {
@@ -67,24 +118,58 @@ pub enum TypeRef {
         base: Box<TypeRef>,
         bound: Option<Bound>,
     },
+    Bag {
+        base: Box<TypeRef>,
+        bound: Option<Bound>,
+    },
     List {
         base: Box<TypeRef>,
         bound: Option<Bound>,
         unique: bool,
     },
+    Array {
+        base: Box<TypeRef>,
+        bound: Option<Bound>,
+        unique: bool,
+        /// Declared as `ARRAY [..] OF OPTIONAL`, i.e. elements may be indeterminate
+        optional: bool,
+    },
 }
 
 impl TypeRef {
     /// Returns `true` iff `self` is:
     /// - a simple type,
     /// - a named type whose underlying type is simple, or,
-    /// - a set or list of a type `x` such that `x.is_simple() == true`.
+    /// - an aggregate of a type `x` such that `x.is_simple() == true`.
     pub fn is_simple(&self) -> bool {
         match self {
             TypeRef::SimpleType(..) => true,
             TypeRef::Named { is_simple, .. } => *is_simple,
-            TypeRef::Set { base, .. } | TypeRef::List { base, .. } => base.is_simple(),
-            _ => false,
+            _ => match self.base() {
+                Some(base) => base.is_simple(),
+                None => false,
+            },
+        }
+    }
+
+    /// Base type of an aggregate, `None` if `self` is not an aggregate
+    pub fn base(&self) -> Option<&TypeRef> {
+        match self {
+            TypeRef::Set { base, .. }
+            | TypeRef::Bag { base, .. }
+            | TypeRef::List { base, .. }
+            | TypeRef::Array { base, .. } => Some(base),
+            _ => None,
+        }
+    }
+
+    /// Path of the entity or the named type, or those of the base type of an aggregate
+    pub fn path(&self) -> Option<Path> {
+        match self {
+            TypeRef::SimpleType(_) => None,
+            TypeRef::Named { name, scope, .. } => Some(Path::r#type(scope, name)),
+            TypeRef::Entity { name, scope, .. } => Some(Path::entity(scope, name)),
+            _ => self.base()?.path(),
         }
     }
 
@@ -146,7 +231,10 @@ impl TypeRef {
                     is_enumerate,
                 })
             }
-            _ => unimplemented!("Path to TypeRef conversion only supports Entity and Types yet."),
+            _ => Err(SemanticError::Unsupported(format!(
+                "{} referred as a type",
+                path
+            ))),
         }
     }
 }
@@ -167,36 +255,124 @@ impl Legalize for TypeRef {
                 let (path, _index) = ns.resolve(scope, name)?;
                 Self::from_path(ns, ss, &path)?
             }
-            Set { base, bound } => {
-                let base = TypeRef::legalize(ns, ss, scope, base.as_ref())?;
-                let bound = if let Some(bound) = bound {
-                    Some(Legalize::legalize(ns, ss, scope, bound)?)
-                } else {
-                    None
-                };
-                Self::Set {
-                    base: Box::new(base),
-                    bound,
-                }
-            }
+            Set { base, bound } => Self::Set {
+                base: Box::new(TypeRef::legalize(ns, ss, scope, base.as_ref())?),
+                bound: legalize_bound(ns, ss, scope, bound.as_ref())?,
+            },
+            Bag { base, bound } => Self::Bag {
+                base: Box::new(TypeRef::legalize(ns, ss, scope, base.as_ref())?),
+                bound: legalize_bound(ns, ss, scope, bound.as_ref())?,
+            },
             List {
                 base,
                 bound,
                 unique,
-            } => {
-                let base = TypeRef::legalize(ns, ss, scope, base.as_ref())?;
-                let bound = if let Some(bound) = bound {
-                    Some(Legalize::legalize(ns, ss, scope, bound)?)
-                } else {
-                    None
-                };
-                Self::List {
-                    base: Box::new(base),
-                    bound,
-                    unique: *unique,
-                }
+            } => Self::List {
+                base: Box::new(TypeRef::legalize(ns, ss, scope, base.as_ref())?),
+                bound: legalize_bound(ns, ss, scope, bound.as_ref())?,
+                unique: *unique,
+            },
+            Array {
+                base,
+                bound,
+                unique,
+                optional,
+            } => Self::Array {
+                base: Box::new(TypeRef::legalize(ns, ss, scope, base.as_ref())?),
+                bound: legalize_bound(ns, ss, scope, bound.as_ref())?,
+                unique: *unique,
+                optional: *optional,
+            },
+            Enumeration { .. } | Select { .. } => {
+                return Err(SemanticError::Unsupported(
+                    "ENUMERATION or SELECT out of TYPE declaration".to_string(),
+                ))
+            }
+            Aggregate { .. } | GenericEntity(_) | Generic(_) => {
+                return Err(SemanticError::Unsupported(
+                    "AGGREGATE or GENERIC type out of parameters of FUNCTION or PROCEDURE"
+                        .to_string(),
+                ))
             }
-            _ => todo!(),
         })
     }
 }
+
+fn legalize_bound(
+    ns: &Namespace,
+    ss: &Constraints,
+    scope: &Scope,
+    bound: Option<&ast::Bound>,
+) -> Result<Option<Bound>, SemanticError> {
+    bound
+        .map(|bound| Bound::legalize(ns, ss, scope, bound))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legalize_bound() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY a;
+                n: INTEGER;
+                x: ARRAY [-1:2 * 2] OF REAL;
+                y: BAG [2:?] OF REAL;
+                z: ARRAY [1:n] OF OPTIONAL REAL;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+        let attributes = &st.schemas[0].entities[0].attributes;
+        let bound = |i: usize| match TypeRef::legalize(&ns, &ss, &scope, &attributes[i].ty) {
+            Ok(TypeRef::Array { bound, .. }) | Ok(TypeRef::Bag { bound, .. }) => bound.unwrap(),
+            ty => panic!("Unexpected type: {:?}", ty),
+        };
+
+        let x = bound(1);
+        assert_eq!((x.lower, x.upper), (Some(-1), Some(4)));
+        assert_eq!(x.array_size(), Some(6));
+
+        let y = bound(2);
+        assert_eq!((y.lower, y.upper), (Some(2), None));
+
+        // Depends on the attribute `n`
+        let z = bound(3);
+        assert_eq!((z.lower, z.upper), (Some(1), None));
+        assert_eq!(z.array_size(), None);
+    }
+
+    #[test]
+    fn unsupported_type() {
+        let st = SyntaxTree::parse(
+            r#"
+            SCHEMA test_schema;
+              ENTITY a;
+                x: AGGREGATE OF REAL;
+              END_ENTITY;
+            END_SCHEMA;
+            "#,
+        )
+        .unwrap();
+        let ns = Namespace::new(&st);
+        let ss = Constraints::new(&ns, &st).unwrap();
+        let scope = Scope::root().pushed(ScopeType::Schema, "test_schema");
+        let attributes = &st.schemas[0].entities[0].attributes;
+        assert!(matches!(
+            TypeRef::legalize(&ns, &ss, &scope, &attributes[0].ty),
+            Err(SemanticError::Unsupported(_))
+        ));
+        assert!(matches!(
+            TypeRef::from_path(&ns, &ss, &Path::new(&scope, ScopeType::Function, "f")),
+            Err(SemanticError::Unsupported(_))
+        ));
+    }
+}
@@ -29,6 +29,9 @@
 //!   - Read EXPRESS input and parse into abstract syntax tree (AST)
 //!   - [ast] module defines AST structs
 //!   - [parser] module defines parser combinator for parsing EXPRESS language
+//!   - [printer] module prints AST back into EXPRESS language
+//!   - [locator] module finds and loads EXPRESS files of schemas interfaced by `USE FROM` and `REFERENCE FROM`
+//!   - [longform] module expands a short form schema and the schemas it interfaces into a long form schema
 //! - Legalize
 //!   - Convert AST to IR (intermediate representation) to ready the following code generation
 //!   - [ir] module defines IR structs, and they implements [ir::Legalize] trait for legalizing from AST
@@ -154,4 +157,7 @@
 pub mod ast;
 pub mod codegen;
 pub mod ir;
+pub mod locator;
+pub mod longform;
 pub mod parser;
+pub mod printer;
@@ -0,0 +1,285 @@
+//! Find EXPRESS files declaring schemas referred by `USE FROM` and `REFERENCE FROM`
+//!
+//! ```no_run
+//! use espr::{ir::IR, locator::SchemaLocator};
+//!
+//! let mut locator = SchemaLocator::default();
+//! locator.add_search_path("schemas/modules").unwrap();
+//! locator.add_search_path("schemas/IRs").unwrap();
+//!
+//! // Load the file declaring the root schema, and the files it depends on
+//! let st = locator.load("ap242_managed_model_based_3d_engineering_mim_lf").unwrap();
+//! let ir = IR::from_syntax_tree(&st).unwrap();
+//! ```
+
+use crate::ast::{InterfaceSpec, SyntaxTree, TokenizeFailed};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Errors while locating and loading EXPRESS files
+#[derive(Debug, Error)]
+pub enum LocatorError {
+    #[error("Failed to read {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+
+    #[error("Syntax error in {path}: {error}")]
+    Syntax {
+        path: PathBuf,
+        error: TokenizeFailed,
+    },
+
+    #[error(
+        "Schema '{name}' required by '{required_by}' is not declared in any file in search paths"
+    )]
+    Unresolved { name: String, required_by: String },
+
+    #[error("Schema '{0}' is not declared in any file in search paths")]
+    RootNotFound(String),
+}
+
+/// Index of `SCHEMA <name>;` declarations in `.exp` files under search paths
+///
+/// Schema names are case-insensitive as other identifiers in EXPRESS.
+/// When a schema is declared in several files, the first one found is used,
+/// where search paths are scanned in the order they are added,
+/// and files in a directory in the order of their paths.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaLocator {
+    files: HashMap<String, PathBuf>,
+}
+
+impl SchemaLocator {
+    /// Index a `.exp` file, or `.exp` files under a directory recursively
+    pub fn add_search_path(&mut self, path: impl AsRef<Path>) -> Result<(), LocatorError> {
+        let path = path.as_ref();
+        let io_error = |source| LocatorError::Io {
+            path: path.to_owned(),
+            source,
+        };
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)
+                .map_err(io_error)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(io_error)?;
+            entries.sort();
+            for entry in entries {
+                if entry.is_dir() || entry.extension().map_or(false, |ext| ext == "exp") {
+                    self.add_search_path(entry)?;
+                }
+            }
+        } else {
+            // Some schemas in the wild are not encoded in UTF-8
+            let bytes = fs::read(path).map_err(io_error)?;
+            for name in declared_schemas(&String::from_utf8_lossy(&bytes)) {
+                self.files
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| path.to_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Path of the file declaring the schema
+    pub fn locate(&self, name: &str) -> Option<&Path> {
+        self.files.get(&name.to_lowercase()).map(PathBuf::as_path)
+    }
+
+    /// Load the file declaring the root schema, and the files of schemas interfaced from it transitively
+    pub fn load(&self, root: &str) -> Result<SyntaxTree, LocatorError> {
+        let path = self
+            .locate(root)
+            .ok_or_else(|| LocatorError::RootNotFound(root.to_string()))?;
+        self.resolve(parse_file(path)?)
+    }
+
+    /// Append the files of schemas interfaced from the syntax tree transitively,
+    /// which are not declared in the syntax tree itself
+    pub fn resolve(&self, mut st: SyntaxTree) -> Result<SyntaxTree, LocatorError> {
+        let mut loaded: HashSet<String> =
+            st.schemas.iter().map(|s| s.name.to_lowercase()).collect();
+        let mut checked = 0;
+        while checked < st.schemas.len() {
+            let schema = &st.schemas[checked];
+            checked += 1;
+            let mut missing = Vec::new();
+            for interface in &schema.interfaces {
+                let name = match interface {
+                    InterfaceSpec::Use { name, .. } | InterfaceSpec::Reference { name, .. } => name,
+                };
+                if !loaded.contains(&name.to_lowercase()) {
+                    let path = self.locate(name).ok_or_else(|| LocatorError::Unresolved {
+                        name: name.clone(),
+                        required_by: schema.name.clone(),
+                    })?;
+                    missing.push(path.to_owned());
+                }
+            }
+            for path in missing {
+                let mut other = parse_file(&path)?;
+                // A file may declare several schemas, and some of them may be loaded already
+                other
+                    .schemas
+                    .retain(|s| loaded.insert(s.name.to_lowercase()));
+                st.append(other);
+            }
+        }
+        Ok(st)
+    }
+}
+
+fn parse_file(path: &Path) -> Result<SyntaxTree, LocatorError> {
+    let bytes = fs::read(path).map_err(|source| LocatorError::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    let src = String::from_utf8_lossy(&bytes);
+    SyntaxTree::parse(&src).map_err(|err| LocatorError::Syntax {
+        path: path.to_owned(),
+        error: TokenizeFailed::new(&src, err),
+    })
+}
+
+/// Names of schemas declared in EXPRESS source, found without parsing it entirely
+///
+/// Remarks and string literals are skipped since they may contain `SCHEMA` word.
+pub fn declared_schemas(src: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = src.chars().peekable();
+    let flush = |word: &mut String, words: &mut Vec<String>| {
+        if !word.is_empty() {
+            words.push(std::mem::take(word));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            // Embedded remark `(* ... *)`, which can be nested
+            '(' if chars.peek() == Some(&'*') => {
+                flush(&mut word, &mut words);
+                chars.next();
+                let mut depth = 1;
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    match (prev, c) {
+                        ('(', '*') => {
+                            depth += 1;
+                            prev = ' ';
+                            continue;
+                        }
+                        ('*', ')') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                            prev = ' ';
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    prev = c;
+                }
+            }
+            // Tail remark `-- ...` until the end of line
+            '-' if chars.peek() == Some(&'-') => {
+                flush(&mut word, &mut words);
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            // String literal, where `''` in simple string is read as two literals
+            '\'' | '"' => {
+                flush(&mut word, &mut words);
+                for d in chars.by_ref() {
+                    if d == c {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => word.push(c),
+            _ => flush(&mut word, &mut words),
+        }
+    }
+    flush(&mut word, &mut words);
+
+    for pair in words.windows(2) {
+        if pair[0].eq_ignore_ascii_case("schema") {
+            names.push(pair[1].clone());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_schemas_in_source() {
+        let src = r#"
+        (* SCHEMA in_remark; (* nested *) SCHEMA in_remark2; *)
+        SCHEMA geometry_schema; -- SCHEMA in_tail_remark;
+          CONSTANT
+            name : STRING := 'SCHEMA in_string';
+          END_CONSTANT;
+        END_SCHEMA;
+
+        schema Topology_Schema 'version';
+        END_SCHEMA;
+        "#;
+        assert_eq!(
+            declared_schemas(src),
+            vec!["geometry_schema".to_string(), "Topology_Schema".to_string()]
+        );
+    }
+
+    #[test]
+    fn load_interfaced() {
+        let dir = std::env::temp_dir().join("espr_locator_load_interfaced");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(
+            dir.join("topology.exp"),
+            "SCHEMA topology_schema; USE FROM geometry_schema; END_SCHEMA;",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("sub/geometry.exp"),
+            "SCHEMA geometry_schema; END_SCHEMA; SCHEMA unused_schema; END_SCHEMA;",
+        )
+        .unwrap();
+        fs::write(dir.join("readme.txt"), "SCHEMA not_indexed;").unwrap();
+
+        let mut locator = SchemaLocator::default();
+        locator.add_search_path(&dir).unwrap();
+        assert_eq!(
+            locator.locate("Geometry_Schema"),
+            Some(dir.join("sub/geometry.exp").as_path())
+        );
+        assert!(locator.locate("not_indexed").is_none());
+
+        let st = locator.load("topology_schema").unwrap();
+        let names: Vec<_> = st.schemas.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["topology_schema", "geometry_schema", "unused_schema"]
+        );
+
+        fs::write(
+            dir.join("topology.exp"),
+            "SCHEMA topology_schema; USE FROM missing_schema; END_SCHEMA;",
+        )
+        .unwrap();
+        assert!(matches!(
+            locator.load("topology_schema"),
+            Err(LocatorError::Unresolved { name, required_by })
+                if name == "missing_schema" && required_by == "topology_schema"
+        ));
+    }
+}
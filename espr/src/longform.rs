@@ -0,0 +1,1177 @@
+//! Expand a short form schema into a long form schema
+//!
+//! A short form schema interfaces declarations in other schemas by `USE FROM` and `REFERENCE FROM`,
+//! while its long form declares all of them by itself as described in ISO 10303-11 Annex A:
+//!
+//! - Declarations of the short form schema are all kept.
+//! - Explicitly interfaced declarations are copied. Interfaces without resource list
+//!   interface all entities and types for `USE FROM`,
+//!   and also constants, functions, and procedures for `REFERENCE FROM`.
+//!   These are resolved transitively through the interfaces of the interfaced schema.
+//! - Implicitly interfaced declarations, i.e. those referred from copied declarations,
+//!   e.g. supertypes, attribute types, and functions called in domain rules, are copied.
+//! - `SUBTYPE_CONSTRAINT` is copied if the constrained entity is copied,
+//!   and global `RULE` is copied if all entities it refers are copied.
+//! - Declarations renamed by `AS` take the new name, and every reference to them is replaced.
+//!   Schema names in string literals for `TYPEOF` and `USEDIN`, e.g. `'GEOMETRY_SCHEMA.POINT'`,
+//!   are replaced by the name of the long form schema.
+//! - Entities in select list and supertype expression are not implicitly interfaced,
+//!   and those not copied are removed from them.
+//!
+//! ```
+//! use espr::{ast::SyntaxTree, longform, printer::Printer};
+//!
+//! let mut st = SyntaxTree::parse(r#"
+//! SCHEMA topology_schema;
+//!   USE FROM geometry_schema (point AS vertex_point);
+//!   ENTITY edge;
+//!     head, tail: vertex_point;
+//!   END_ENTITY;
+//! END_SCHEMA;
+//! "#).unwrap();
+//! st.append(SyntaxTree::parse(r#"
+//! SCHEMA geometry_schema;
+//!   ENTITY point;
+//!     x, y: length_measure;
+//!   END_ENTITY;
+//!   TYPE length_measure = REAL;
+//!   END_TYPE;
+//! END_SCHEMA;
+//! "#).unwrap());
+//!
+//! let schema = longform::long_form(&st, "topology_schema").unwrap();
+//! let entities: Vec<_> = schema.entities.iter().map(|e| e.name.as_str()).collect();
+//! assert_eq!(entities, ["edge", "vertex_point"]);
+//! assert_eq!(schema.types[0].type_id, "length_measure");
+//! assert!(schema.interfaces.is_empty());
+//!
+//! let mut printer = Printer::default();
+//! printer.schema(&schema);
+//! println!("{}", printer.finish());
+//! ```
+
+use crate::ast::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use thiserror::Error;
+
+/// Errors while expanding a short form schema
+#[derive(Debug, Error)]
+pub enum LongFormError {
+    #[error("Schema '{0}' is not found in the syntax tree")]
+    SchemaNotFound(String),
+
+    #[error("Schema '{name}' interfaced by '{required_by}' is not found in the syntax tree")]
+    Unresolved { name: String, required_by: String },
+
+    #[error("'{item}' interfaced by '{required_by}' is not declared in schema '{schema}'")]
+    ItemNotFound {
+        item: String,
+        schema: String,
+        required_by: String,
+    },
+
+    #[error("'{name}' is declared in both '{schema1}' and '{schema2}'")]
+    Conflict {
+        name: String,
+        schema1: String,
+        schema2: String,
+    },
+}
+
+/// Expand the schema of given name in the syntax tree into its long form
+pub fn long_form(st: &SyntaxTree, root: &str) -> Result<Schema, LongFormError> {
+    let root = st
+        .schemas
+        .iter()
+        .position(|s| s.name.eq_ignore_ascii_case(root))
+        .ok_or_else(|| LongFormError::SchemaNotFound(root.to_string()))?;
+    let resolver = Resolver::new(st, root)?;
+    let included = resolver.include()?;
+    resolver.build(&included)
+}
+
+/// Kind of declarations in a schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Constant,
+    Type,
+    Entity,
+    Function,
+    Procedure,
+    Rule,
+    SubTypeConstraint,
+}
+
+impl Kind {
+    fn is_named_type(&self) -> bool {
+        matches!(self, Kind::Type | Kind::Entity)
+    }
+}
+
+/// Declaration identified by the index of schema, its kind, and the index in the list of the kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct DeclId {
+    schema: usize,
+    kind: Kind,
+    index: usize,
+}
+
+/// Declarations in the long form in the order of inclusion
+#[derive(Default)]
+struct Included {
+    ids: Vec<DeclId>,
+    seen: HashSet<DeclId>,
+}
+
+impl Included {
+    fn push(&mut self, id: DeclId) {
+        if self.seen.insert(id) {
+            self.ids.push(id);
+        }
+    }
+}
+
+struct Resolver<'st> {
+    st: &'st SyntaxTree,
+    root: usize,
+    /// Schemas reachable from the root schema through interfaces, in breadth-first order
+    schemas: Vec<usize>,
+    /// Declarations of each schema by lowercase name, without rules and subtype constraints
+    declared: Vec<HashMap<String, DeclId>>,
+    /// Name of the declaration in the long form
+    names: HashMap<DeclId, String>,
+}
+
+impl<'st> Resolver<'st> {
+    fn new(st: &'st SyntaxTree, root: usize) -> Result<Self, LongFormError> {
+        let declared = st
+            .schemas
+            .iter()
+            .enumerate()
+            .map(|(schema, s)| {
+                let mut declared = HashMap::new();
+                let mut insert = |kind, names: Vec<&String>| {
+                    for (index, name) in names.into_iter().enumerate() {
+                        declared.insert(
+                            name.to_lowercase(),
+                            DeclId {
+                                schema,
+                                kind,
+                                index,
+                            },
+                        );
+                    }
+                };
+                insert(
+                    Kind::Constant,
+                    s.constants.iter().map(|c| &c.name).collect(),
+                );
+                insert(Kind::Type, s.types.iter().map(|t| &t.type_id).collect());
+                insert(Kind::Entity, s.entities.iter().map(|e| &e.name).collect());
+                insert(
+                    Kind::Function,
+                    s.functions.iter().map(|f| &f.name).collect(),
+                );
+                insert(
+                    Kind::Procedure,
+                    s.procedures.iter().map(|p| &p.name).collect(),
+                );
+                declared
+            })
+            .collect();
+
+        let mut resolver = Resolver {
+            st,
+            root,
+            schemas: Vec::new(),
+            declared,
+            names: HashMap::new(),
+        };
+
+        let mut queue = VecDeque::from([root]);
+        let mut visited = HashSet::from([root]);
+        while let Some(i) = queue.pop_front() {
+            resolver.schemas.push(i);
+            for interface in &st.schemas[i].interfaces {
+                let (name, _) = interface_items(interface);
+                let j = resolver.find_schema(name, i)?;
+                if visited.insert(j) {
+                    queue.push_back(j);
+                }
+            }
+        }
+
+        // Renames in schemas closer to the root take precedence
+        for &i in &resolver.schemas {
+            for interface in &st.schemas[i].interfaces {
+                let (name, items) = interface_items(interface);
+                let j = resolver.find_schema(name, i)?;
+                for (item, rename) in items {
+                    let id = resolver
+                        .resolve(j, item, false, &mut HashSet::new())
+                        .ok_or_else(|| LongFormError::ItemNotFound {
+                            item: item.clone(),
+                            schema: st.schemas[j].name.clone(),
+                            required_by: st.schemas[i].name.clone(),
+                        })?;
+                    if let Some(rename) = rename {
+                        resolver.names.entry(id).or_insert_with(|| rename.clone());
+                    }
+                }
+            }
+        }
+        Ok(resolver)
+    }
+
+    fn find_schema(&self, name: &str, required_by: usize) -> Result<usize, LongFormError> {
+        self.st
+            .schemas
+            .iter()
+            .position(|s| s.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| LongFormError::Unresolved {
+                name: name.to_string(),
+                required_by: self.st.schemas[required_by].name.clone(),
+            })
+    }
+
+    /// Find the declaration visible by the name in the schema
+    ///
+    /// `named_type_only` is set when it is looked up through `USE FROM` without resource list,
+    /// while resources listed explicitly are accepted whatever they are.
+    fn resolve(
+        &self,
+        schema: usize,
+        name: &str,
+        named_type_only: bool,
+        visited: &mut HashSet<usize>,
+    ) -> Option<DeclId> {
+        if !visited.insert(schema) {
+            return None;
+        }
+        let accept = |id: &DeclId| !named_type_only || id.kind.is_named_type();
+        if let Some(id) = self.declared[schema].get(&name.to_lowercase()) {
+            return Some(*id).filter(accept);
+        }
+        for interface in &self.st.schemas[schema].interfaces {
+            let (target, items) = interface_items(interface);
+            let target = match self
+                .st
+                .schemas
+                .iter()
+                .position(|s| s.name.eq_ignore_ascii_case(target))
+            {
+                Some(target) => target,
+                None => continue,
+            };
+            let found = if items.is_empty() {
+                let named_type_only = named_type_only || is_use(interface);
+                self.resolve(target, name, named_type_only, &mut visited.clone())
+            } else {
+                items.iter().find_map(|(item, rename)| {
+                    let visible = rename.as_ref().unwrap_or(item);
+                    if visible.eq_ignore_ascii_case(name) {
+                        self.resolve(target, item, named_type_only, &mut visited.clone())
+                    } else {
+                        None
+                    }
+                })
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    fn name(&self, id: &DeclId) -> String {
+        if let Some(name) = self.names.get(id) {
+            return name.clone();
+        }
+        let s = &self.st.schemas[id.schema];
+        match id.kind {
+            Kind::Constant => s.constants[id.index].name.clone(),
+            Kind::Type => s.types[id.index].type_id.clone(),
+            Kind::Entity => s.entities[id.index].name.clone(),
+            Kind::Function => s.functions[id.index].name.clone(),
+            Kind::Procedure => s.procedures[id.index].name.clone(),
+            Kind::Rule => s.rules[id.index].name.clone(),
+            Kind::SubTypeConstraint => s.subtype_constraints[id.index].name.clone(),
+        }
+    }
+
+    /// Declarations in the long form in the order of inclusion
+    fn include(&self) -> Result<Vec<DeclId>, LongFormError> {
+        let mut included = Included::default();
+
+        // All declarations of the root schema
+        let root = &self.st.schemas[self.root];
+        let kinds = [
+            (Kind::Constant, root.constants.len()),
+            (Kind::Type, root.types.len()),
+            (Kind::Entity, root.entities.len()),
+            (Kind::Function, root.functions.len()),
+            (Kind::Procedure, root.procedures.len()),
+            (Kind::Rule, root.rules.len()),
+            (Kind::SubTypeConstraint, root.subtype_constraints.len()),
+        ];
+        for (kind, len) in kinds {
+            for index in 0..len {
+                included.push(DeclId {
+                    schema: self.root,
+                    kind,
+                    index,
+                });
+            }
+        }
+
+        // Explicitly interfaced declarations
+        for interface in &root.interfaces {
+            let (name, items) = interface_items(interface);
+            let j = self.find_schema(name, self.root)?;
+            if items.is_empty() {
+                for id in self.exported(j, is_use(interface)) {
+                    included.push(id);
+                }
+            }
+            for (item, _rename) in items {
+                if let Some(id) = self.resolve(j, item, false, &mut HashSet::new()) {
+                    included.push(id);
+                }
+            }
+        }
+
+        let mut visited = 0;
+        loop {
+            // Implicitly interfaced declarations
+            while visited < included.ids.len() {
+                let id = included.ids[visited];
+                visited += 1;
+                for name in self.references(&id) {
+                    if let Some(found) = self.resolve(id.schema, &name, false, &mut HashSet::new())
+                    {
+                        included.push(found);
+                    }
+                }
+            }
+
+            // Rules and subtype constraints on the included entities
+            let entities: HashSet<DeclId> = included
+                .ids
+                .iter()
+                .filter(|id| id.kind == Kind::Entity)
+                .copied()
+                .collect();
+            let is_included = |schema: usize, name: &str| {
+                self.resolve(schema, name, true, &mut HashSet::new())
+                    .map_or(false, |id| entities.contains(&id))
+            };
+            // Select of no items copied is invalid, and thus all entities in it are copied
+            let selects: Vec<DeclId> = included
+                .ids
+                .iter()
+                .filter(|id| id.kind == Kind::Type)
+                .copied()
+                .collect();
+            for id in selects {
+                if let Type::Select { types, .. } =
+                    &self.st.schemas[id.schema].types[id.index].underlying_type
+                {
+                    let items: Vec<DeclId> = types
+                        .iter()
+                        .filter_map(|name| {
+                            self.resolve(id.schema, name, false, &mut HashSet::new())
+                        })
+                        .collect();
+                    if items.iter().all(|item| !included.seen.contains(item)) {
+                        for item in items {
+                            included.push(item);
+                        }
+                    }
+                }
+            }
+            for &i in &self.schemas {
+                let s = &self.st.schemas[i];
+                for (index, rule) in s.rules.iter().enumerate() {
+                    if rule.references.iter().all(|name| is_included(i, name)) {
+                        included.push(DeclId {
+                            schema: i,
+                            kind: Kind::Rule,
+                            index,
+                        });
+                    }
+                }
+                for (index, constraint) in s.subtype_constraints.iter().enumerate() {
+                    if is_included(i, &constraint.entity) {
+                        included.push(DeclId {
+                            schema: i,
+                            kind: Kind::SubTypeConstraint,
+                            index,
+                        });
+                    }
+                }
+            }
+            if visited == included.ids.len() {
+                break;
+            }
+        }
+
+        Ok(included.ids)
+    }
+
+    /// Declarations interfaced from the schema by `USE FROM` or `REFERENCE FROM` without resource list
+    fn exported(&self, schema: usize, named_type_only: bool) -> Vec<DeclId> {
+        let mut exported = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_exported(schema, named_type_only, &mut visited, &mut exported);
+        exported
+    }
+
+    fn collect_exported(
+        &self,
+        schema: usize,
+        named_type_only: bool,
+        visited: &mut HashSet<usize>,
+        exported: &mut Vec<DeclId>,
+    ) {
+        if !visited.insert(schema) {
+            return;
+        }
+        let mut ids: Vec<DeclId> = self.declared[schema]
+            .values()
+            .filter(|id| !named_type_only || id.kind.is_named_type())
+            .copied()
+            .collect();
+        ids.sort_by_key(|id| (id.kind as usize, id.index));
+        exported.extend(ids);
+        for interface in &self.st.schemas[schema].interfaces {
+            let (target, items) = interface_items(interface);
+            if let Some(target) = self
+                .st
+                .schemas
+                .iter()
+                .position(|s| s.name.eq_ignore_ascii_case(target))
+            {
+                if items.is_empty() {
+                    let named_type_only = named_type_only || is_use(interface);
+                    self.collect_exported(target, named_type_only, visited, exported);
+                } else {
+                    exported.extend(items.iter().filter_map(|(item, _rename)| {
+                        self.resolve(target, item, named_type_only, &mut HashSet::new())
+                    }));
+                }
+            }
+        }
+    }
+
+    /// Names referred from the declaration
+    fn references(&self, id: &DeclId) -> Vec<String> {
+        let mut references = Vec::new();
+        let mut push = |name: &mut String| references.push(name.clone());
+        let mut walker = Walker::new(&mut push);
+        let s = &self.st.schemas[id.schema];
+        match id.kind {
+            Kind::Constant => {
+                let mut constant = s.constants[id.index].clone();
+                walker.type_(&mut constant.ty);
+                walker.expression(&mut constant.expr);
+            }
+            Kind::Type => {
+                let mut ty = s.types[id.index].clone();
+                // Entities in select list are not implicitly interfaced
+                if let Type::Select { types, .. } = &mut ty.underlying_type {
+                    types.retain(|name| {
+                        self.resolve(id.schema, name, false, &mut HashSet::new())
+                            .map_or(true, |found| found.kind != Kind::Entity)
+                    });
+                }
+                walker.type_decl(&mut ty);
+            }
+            Kind::Entity => walker.entity(&mut s.entities[id.index].clone()),
+            Kind::Function => walker.function(&mut s.functions[id.index].clone()),
+            Kind::Procedure => walker.procedure(&mut s.procedures[id.index].clone()),
+            Kind::Rule => walker.rule(&mut s.rules[id.index].clone()),
+            // Entities in supertype expression are not implicitly interfaced
+            Kind::SubTypeConstraint => {}
+        }
+        references
+    }
+
+    /// Rename a reference in the schema into the name in the long form
+    fn rename(&self, schema: usize, name: &mut String) {
+        if let Some(id) = self.resolve(schema, name, false, &mut HashSet::new()) {
+            *name = self.name(&id);
+        }
+    }
+
+    /// Rename a reference in string literal into the long form schema,
+    /// e.g. `'SCHEMA.NAME'` for `TYPEOF` and `'SCHEMA.NAME.ATTR'` for `USEDIN`
+    fn rename_literal(&self, literal: &mut String) {
+        let (schema, path) = match literal.split_once('.') {
+            Some(split) => split,
+            None => return,
+        };
+        let schema = match self
+            .schemas
+            .iter()
+            .find(|&&i| self.st.schemas[i].name.eq_ignore_ascii_case(schema))
+        {
+            Some(&schema) => schema,
+            None => return,
+        };
+        let (item, attr) = match path.split_once('.') {
+            Some((item, attr)) => (item, Some(attr)),
+            None => (path, None),
+        };
+        let mut renamed = format!(
+            "{}.{}",
+            self.st.schemas[self.root].name.to_uppercase(),
+            match self.declared[schema].get(&item.to_lowercase()) {
+                Some(id) => self.name(id).to_uppercase(),
+                None => item.to_string(),
+            }
+        );
+        if let Some(attr) = attr {
+            renamed.push('.');
+            renamed.push_str(attr);
+        }
+        *literal = renamed;
+    }
+
+    fn build(&self, included: &[DeclId]) -> Result<Schema, LongFormError> {
+        let entities: HashSet<String> = included
+            .iter()
+            .filter(|id| id.kind == Kind::Entity)
+            .map(|id| self.name(id).to_lowercase())
+            .collect();
+        let named_types: HashSet<String> = included
+            .iter()
+            .filter(|id| id.kind.is_named_type())
+            .map(|id| self.name(id).to_lowercase())
+            .collect();
+
+        let mut schema = Schema {
+            name: self.st.schemas[self.root].name.clone(),
+            entities: Vec::new(),
+            types: Vec::new(),
+            functions: Vec::new(),
+            procedures: Vec::new(),
+            rules: Vec::new(),
+            constants: Vec::new(),
+            interfaces: Vec::new(),
+            subtype_constraints: Vec::new(),
+        };
+        // Names must be unique in the long form,
+        // except for the same declaration copied into several schemas, e.g. `dummy_gri`
+        let mut names = HashMap::new();
+        for id in included {
+            let rename = &mut |name: &mut String| self.rename(id.schema, name);
+            let rename_literal = &mut |name: &mut String| self.rename_literal(name);
+            let mut walker = Walker::new(rename).with_string_literal(rename_literal);
+            let s = &self.st.schemas[id.schema];
+            match id.kind {
+                Kind::Constant => {
+                    let mut constant = s.constants[id.index].clone();
+                    walker.type_(&mut constant.ty);
+                    walker.expression(&mut constant.expr);
+                    constant.name = self.name(id);
+                    if self.is_unique(&mut names, id, &schema.constants, &constant, |x| &x.name)? {
+                        schema.constants.push(constant);
+                    }
+                }
+                Kind::Type => {
+                    let mut ty = s.types[id.index].clone();
+                    walker.type_decl(&mut ty);
+                    ty.type_id = self.name(id);
+                    if let Type::Select { types, .. } = &mut ty.underlying_type {
+                        types.retain(|name| named_types.contains(&name.to_lowercase()));
+                    }
+                    if self.is_unique(&mut names, id, &schema.types, &ty, |x| &x.type_id)? {
+                        schema.types.push(ty);
+                    }
+                }
+                Kind::Entity => {
+                    let mut entity = s.entities[id.index].clone();
+                    walker.entity(&mut entity);
+                    entity.name = self.name(id);
+                    entity.constraint = entity.constraint.and_then(|constraint| match constraint {
+                        Constraint::AbstractEntity => Some(Constraint::AbstractEntity),
+                        Constraint::AbstractSuperType(expr) => Some(Constraint::AbstractSuperType(
+                            expr.and_then(|expr| self.prune(id.schema, expr, &entities)),
+                        )),
+                        Constraint::SuperTypeRule(expr) => self
+                            .prune(id.schema, expr, &entities)
+                            .map(Constraint::SuperTypeRule),
+                    });
+                    if self.is_unique(&mut names, id, &schema.entities, &entity, |x| &x.name)? {
+                        schema.entities.push(entity);
+                    }
+                }
+                Kind::Function => {
+                    let mut function = s.functions[id.index].clone();
+                    walker.function(&mut function);
+                    function.name = self.name(id);
+                    if self.is_unique(&mut names, id, &schema.functions, &function, |x| &x.name)? {
+                        schema.functions.push(function);
+                    }
+                }
+                Kind::Procedure => {
+                    let mut procedure = s.procedures[id.index].clone();
+                    walker.procedure(&mut procedure);
+                    procedure.name = self.name(id);
+                    if self
+                        .is_unique(&mut names, id, &schema.procedures, &procedure, |x| &x.name)?
+                    {
+                        schema.procedures.push(procedure);
+                    }
+                }
+                Kind::Rule => {
+                    let mut rule = s.rules[id.index].clone();
+                    walker.rule(&mut rule);
+                    schema.rules.push(rule);
+                }
+                Kind::SubTypeConstraint => {
+                    let mut constraint = s.subtype_constraints[id.index].clone();
+                    self.rename(id.schema, &mut constraint.entity);
+                    constraint.total_over = constraint.total_over.map(|total_over| {
+                        total_over
+                            .into_iter()
+                            .filter_map(|mut name| {
+                                self.rename(id.schema, &mut name);
+                                entities.contains(&name.to_lowercase()).then(|| name)
+                            })
+                            .collect()
+                    });
+                    constraint.expr = constraint
+                        .expr
+                        .and_then(|expr| self.prune(id.schema, expr, &entities));
+                    schema.subtype_constraints.push(constraint);
+                }
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Check the name of declaration is not used in the long form,
+    /// and returns `false` if the same declaration is already there
+    fn is_unique<T: PartialEq>(
+        &self,
+        names: &mut HashMap<String, DeclId>,
+        id: &DeclId,
+        items: &[T],
+        item: &T,
+        name: fn(&T) -> &String,
+    ) -> Result<bool, LongFormError> {
+        let lower = name(item).to_lowercase();
+        let other = match names.get(&lower) {
+            Some(other) => other,
+            None => {
+                names.insert(lower, *id);
+                return Ok(true);
+            }
+        };
+        if other.kind == id.kind
+            && items
+                .iter()
+                .any(|x| name(x).eq_ignore_ascii_case(&lower) && x == item)
+        {
+            return Ok(false);
+        }
+        Err(LongFormError::Conflict {
+            name: name(item).clone(),
+            schema1: self.st.schemas[other.schema].name.clone(),
+            schema2: self.st.schemas[id.schema].name.clone(),
+        })
+    }
+
+    /// Rename entities in supertype expression, and remove those not in the long form
+    fn prune(
+        &self,
+        schema: usize,
+        expr: SuperTypeExpression,
+        entities: &HashSet<String>,
+    ) -> Option<SuperTypeExpression> {
+        let prune_all = |exprs: Vec<SuperTypeExpression>| -> Vec<SuperTypeExpression> {
+            exprs
+                .into_iter()
+                .filter_map(|expr| self.prune(schema, expr, entities))
+                .collect()
+        };
+        // Combination of single expression is the expression itself
+        let combine =
+            |mut exprs: Vec<SuperTypeExpression>,
+             f: fn(Vec<SuperTypeExpression>) -> SuperTypeExpression| {
+                match exprs.len() {
+                    0 => None,
+                    1 => exprs.pop(),
+                    _ => Some(f(exprs)),
+                }
+            };
+        match expr {
+            SuperTypeExpression::Reference(mut name) => {
+                self.rename(schema, &mut name);
+                entities
+                    .contains(&name.to_lowercase())
+                    .then(|| SuperTypeExpression::Reference(name))
+            }
+            SuperTypeExpression::AndOr { factors } => combine(prune_all(factors), |factors| {
+                SuperTypeExpression::AndOr { factors }
+            }),
+            SuperTypeExpression::And { terms } => {
+                combine(prune_all(terms), |terms| SuperTypeExpression::And { terms })
+            }
+            SuperTypeExpression::OneOf { exprs } => {
+                // ONEOF of single entity is meaningful, and kept as it is
+                let exprs = prune_all(exprs);
+                (!exprs.is_empty()).then(|| SuperTypeExpression::OneOf { exprs })
+            }
+        }
+    }
+}
+
+fn interface_items(interface: &InterfaceSpec) -> (&String, &Vec<(String, Option<String>)>) {
+    match interface {
+        InterfaceSpec::Use { name, types } => (name, types),
+        InterfaceSpec::Reference { name, resources } => (name, resources),
+    }
+}
+
+fn is_use(interface: &InterfaceSpec) -> bool {
+    matches!(interface, InterfaceSpec::Use { .. })
+}
+
+/// Visit references to global declarations in AST
+///
+/// Names declared locally, e.g. attributes, parameters, local variables, and query variables,
+/// shadow global declarations, and thus they are not visited.
+struct Walker<'f> {
+    reference: &'f mut dyn FnMut(&mut String),
+    string_literal: Option<&'f mut dyn FnMut(&mut String)>,
+    locals: Vec<HashSet<String>>,
+}
+
+impl<'f> Walker<'f> {
+    fn new(reference: &'f mut dyn FnMut(&mut String)) -> Self {
+        Walker {
+            reference,
+            string_literal: None,
+            locals: Vec::new(),
+        }
+    }
+
+    fn with_string_literal(mut self, string_literal: &'f mut dyn FnMut(&mut String)) -> Self {
+        self.string_literal = Some(string_literal);
+        self
+    }
+
+    fn reference(&mut self, name: &mut String) {
+        let lower = name.to_lowercase();
+        if self.locals.iter().all(|scope| !scope.contains(&lower)) {
+            (self.reference)(name);
+        }
+    }
+
+    fn scoped<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a String>,
+        f: impl FnOnce(&mut Self),
+    ) {
+        self.locals
+            .push(names.into_iter().map(|name| name.to_lowercase()).collect());
+        f(self);
+        self.locals.pop();
+    }
+
+    fn declaration(&mut self, decl: &mut Declaration) {
+        match decl {
+            Declaration::Entity(entity) => self.entity(entity),
+            Declaration::Type(ty) => self.type_decl(ty),
+            Declaration::Function(function) => self.function(function),
+            Declaration::Procedure(procedure) => self.procedure(procedure),
+            Declaration::Rule(rule) => self.rule(rule),
+            Declaration::SubTypeConstraint(constraint) => {
+                self.reference(&mut constraint.entity);
+            }
+        }
+    }
+
+    fn type_decl(&mut self, decl: &mut TypeDecl) {
+        self.type_(&mut decl.underlying_type);
+        if let Some(where_clause) = &mut decl.where_clause {
+            self.where_clause(where_clause);
+        }
+    }
+
+    fn entity(&mut self, entity: &mut Entity) {
+        if let Some(subtype) = &mut entity.subtype_of {
+            for name in &mut subtype.entity_references {
+                self.reference(name);
+            }
+        }
+        let mut attributes: Vec<String> = Vec::new();
+        for attr in &mut entity.attributes {
+            self.attribute_decl(&mut attr.name, &mut attributes);
+            self.type_(&mut attr.ty);
+        }
+        if let Some(derive) = &mut entity.derive_clause {
+            for attr in &mut derive.attributes {
+                self.attribute_decl(&mut attr.attr, &mut attributes);
+                self.type_(&mut attr.ty);
+            }
+        }
+        if let Some(inverse) = &mut entity.inverse_clause {
+            for attr in &mut inverse.attributes {
+                self.attribute_decl(&mut attr.name, &mut attributes);
+                self.reference(&mut attr.dest);
+                if let AggregationOption::Set { bound: Some(bound) }
+                | AggregationOption::Bag { bound: Some(bound) } = &mut attr.dest_aggregation
+                {
+                    self.bound(bound);
+                }
+                if let Some(prefix) = &mut attr.attribute_prefix {
+                    self.reference(prefix);
+                }
+            }
+        }
+        self.scoped(&attributes, |w| {
+            if let Some(derive) = &mut entity.derive_clause {
+                for attr in &mut derive.attributes {
+                    w.expression(&mut attr.expr);
+                }
+            }
+            if let Some(unique) = &mut entity.unique_clause {
+                for rule in &mut unique.rules {
+                    for attr in &mut rule.attributes {
+                        w.attribute_decl(attr, &mut Vec::new());
+                    }
+                }
+            }
+            if let Some(where_clause) = &mut entity.where_clause {
+                w.where_clause(where_clause);
+            }
+        });
+    }
+
+    /// Visit the group of `SELF\group.attr`, and push the attribute name declared
+    fn attribute_decl(&mut self, attr: &mut AttributeDecl, declared: &mut Vec<String>) {
+        match attr {
+            AttributeDecl::Reference(name) => declared.push(name.clone()),
+            AttributeDecl::Qualified {
+                group,
+                attribute,
+                rename,
+            } => {
+                self.reference(group);
+                declared.push(rename.as_ref().unwrap_or(attribute).clone());
+            }
+        }
+    }
+
+    fn function(&mut self, function: &mut Function) {
+        for parameter in &mut function.parameters {
+            self.type_(&mut parameter.ty);
+        }
+        self.type_(&mut function.return_type);
+        let parameters: Vec<String> = function.parameters.iter().map(|p| p.name.clone()).collect();
+        self.scoped(&parameters, |w| {
+            w.algorithm(
+                &mut function.declarations,
+                &mut function.constants,
+                &mut function.variables,
+                &mut function.statements,
+            )
+        });
+    }
+
+    fn procedure(&mut self, procedure: &mut Procedure) {
+        for parameter in &mut procedure.parameters {
+            self.type_(&mut parameter.ty);
+        }
+        let parameters: Vec<String> = procedure
+            .parameters
+            .iter()
+            .map(|p| p.name.clone())
+            .collect();
+        self.scoped(&parameters, |w| {
+            w.algorithm(
+                &mut procedure.declarations,
+                &mut procedure.constants,
+                &mut procedure.variables,
+                &mut procedure.statements,
+            )
+        });
+    }
+
+    fn rule(&mut self, rule: &mut Rule) {
+        for name in &mut rule.references {
+            self.reference(name);
+        }
+        let Rule {
+            declarations,
+            constants,
+            variables,
+            statements,
+            where_clause,
+            ..
+        } = rule;
+        self.algorithm(declarations, constants, variables, statements);
+        // Local variables are visible in the where clause
+        let variables: Vec<String> = variables.iter().map(|v| v.name.clone()).collect();
+        self.scoped(&variables, |w| w.where_clause(where_clause));
+    }
+
+    fn algorithm(
+        &mut self,
+        declarations: &mut [Declaration],
+        constants: &mut [Constant],
+        variables: &mut [LocalVariable],
+        statements: &mut [Statement],
+    ) {
+        let mut locals: Vec<String> = declarations
+            .iter()
+            .map(|decl| match decl {
+                Declaration::Entity(e) => e.name.clone(),
+                Declaration::Type(t) => t.type_id.clone(),
+                Declaration::Function(f) => f.name.clone(),
+                Declaration::Procedure(p) => p.name.clone(),
+                Declaration::Rule(r) => r.name.clone(),
+                Declaration::SubTypeConstraint(c) => c.name.clone(),
+            })
+            .collect();
+        locals.extend(constants.iter().map(|c| c.name.clone()));
+        locals.extend(variables.iter().map(|v| v.name.clone()));
+        self.scoped(&locals, |w| {
+            for decl in declarations {
+                w.declaration(decl);
+            }
+            for constant in constants {
+                w.type_(&mut constant.ty);
+                w.expression(&mut constant.expr);
+            }
+            for variable in variables {
+                w.type_(&mut variable.ty);
+                if let Some(expr) = &mut variable.expr {
+                    w.expression(expr);
+                }
+            }
+            w.statements(statements);
+        });
+    }
+
+    fn where_clause(&mut self, where_clause: &mut WhereClause) {
+        for rule in &mut where_clause.rules {
+            self.expression(&mut rule.expr);
+        }
+    }
+
+    fn statements(&mut self, statements: &mut [Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Alias {
+                name,
+                dest,
+                qualifiers,
+                statements,
+            } => {
+                self.reference(dest);
+                self.qualifiers(qualifiers);
+                self.scoped([&*name], |w| w.statements(statements));
+            }
+            Statement::Assignment {
+                name,
+                qualifiers,
+                expr,
+            } => {
+                self.reference(name);
+                self.qualifiers(qualifiers);
+                self.expression(expr);
+            }
+            Statement::Compound { statements } => self.statements(statements),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition);
+                self.statements(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statements(else_branch);
+                }
+            }
+            Statement::Case {
+                selector,
+                actions,
+                otherwise,
+            } => {
+                self.expression(selector);
+                for (labels, action) in actions {
+                    for label in labels {
+                        self.expression(label);
+                    }
+                    self.statement(action);
+                }
+                if let Some(otherwise) = otherwise {
+                    self.statement(otherwise);
+                }
+            }
+            Statement::Repeat {
+                control,
+                statements,
+            } => {
+                let mut variables = Vec::new();
+                if let Some(increment) = &mut control.increment {
+                    self.expression(&mut increment.begin);
+                    self.expression(&mut increment.end);
+                    if let Some(step) = &mut increment.increment {
+                        self.expression(step);
+                    }
+                    variables.push(increment.variable.clone());
+                }
+                self.scoped(&variables, |w| {
+                    if let Some(while_) = &mut control.while_ {
+                        w.expression(while_);
+                    }
+                    if let Some(until) = &mut control.until {
+                        w.expression(until);
+                    }
+                    w.statements(statements);
+                });
+            }
+            Statement::Return { value } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+            Statement::ProcedureCall {
+                procedure,
+                parameters,
+            } => {
+                if let ProcedureCallName::Reference(name) = procedure {
+                    self.reference(name);
+                }
+                for parameter in parameters.iter_mut().flatten() {
+                    self.expression(parameter);
+                }
+            }
+            Statement::Skip | Statement::Escape | Statement::Null => {}
+        }
+    }
+
+    fn type_(&mut self, ty: &mut Type) {
+        match ty {
+            Type::Simple(_) => {}
+            Type::Named(name) => self.reference(name),
+            Type::Set { base, bound } | Type::Bag { base, bound } => {
+                self.type_(base);
+                if let Some(bound) = bound {
+                    self.bound(bound);
+                }
+            }
+            Type::List { base, bound, .. } | Type::Array { base, bound, .. } => {
+                self.type_(base);
+                if let Some(bound) = bound {
+                    self.bound(bound);
+                }
+            }
+            Type::Select { types, .. } => {
+                for name in types {
+                    self.reference(name);
+                }
+            }
+            Type::Aggregate { base, .. } => self.type_(base),
+            Type::Enumeration { .. } | Type::GenericEntity(_) | Type::Generic(_) => {}
+        }
+    }
+
+    fn bound(&mut self, bound: &mut Bound) {
+        self.expression(&mut bound.lower);
+        self.expression(&mut bound.upper);
+    }
+
+    fn qualifiers(&mut self, qualifiers: &mut [Qualifier]) {
+        for qualifier in qualifiers {
+            match qualifier {
+                Qualifier::Attribute(_) => {}
+                Qualifier::Group(name) => self.reference(name),
+                Qualifier::Index(index) => self.expression(index),
+                Qualifier::Range { begin, end } => {
+                    self.expression(begin);
+                    self.expression(end);
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &mut Expression) {
+        match expr {
+            Expression::Unary { arg, .. } => self.expression(arg),
+            Expression::Binary { arg1, arg2, .. } => {
+                self.expression(arg1);
+                self.expression(arg2);
+            }
+            Expression::Relation { lhs, rhs, .. } => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::Literal(Literal::String(s)) => {
+                if let Some(f) = &mut self.string_literal {
+                    f(s);
+                }
+            }
+            Expression::Literal(_) => {}
+            Expression::QualifiableFactor { factor, qualifiers } => {
+                match factor {
+                    QualifiableFactor::Reference(name) => self.reference(name),
+                    QualifiableFactor::BuiltInConstant(_) => {}
+                    QualifiableFactor::FunctionCall { name, args } => {
+                        if let FunctionCallName::Reference(name) = name {
+                            self.reference(name);
+                        }
+                        for arg in args {
+                            self.expression(arg);
+                        }
+                    }
+                }
+                self.qualifiers(qualifiers);
+            }
+            Expression::EntityConstructor { name, values } => {
+                self.reference(name);
+                for value in values {
+                    self.expression(value);
+                }
+            }
+            Expression::Interval {
+                high, low, item, ..
+            } => {
+                self.expression(low);
+                self.expression(item);
+                self.expression(high);
+            }
+            Expression::EnumerationReference { ty, .. } => {
+                if let Some(ty) = ty {
+                    self.reference(ty);
+                }
+            }
+            Expression::AggregateInitializer { elements } => {
+                for element in elements {
+                    self.expression(&mut element.expr);
+                    if let Some(repetition) = &mut element.repetition {
+                        self.expression(repetition);
+                    }
+                }
+            }
+            Expression::Query {
+                variable,
+                source,
+                expr,
+            } => {
+                self.expression(source);
+                self.scoped([&*variable], |w| w.expression(expr));
+            }
+        }
+    }
+}
@@ -17,16 +17,16 @@ pub fn literal(input: &str) -> ParseResult<Literal> {
 
 /// 255 logical_literal = `FALSE` | `TRUE` | `UNKNOWN` .
 pub fn logical_literal(input: &str) -> ParseResult<Logical> {
-    tuple((
-        alt((
-            value(Logical::True, tag("TRUE")),
-            value(Logical::False, tag("FALSE")),
-            value(Logical::Unknown, tag("UNKNOWN")),
-        )),
-        peek(not(remarked(nom::character::complete::alpha1))),
+    let (input, logical) = alt((
+        value(Logical::True, tag("TRUE")),
+        value(Logical::False, tag("FALSE")),
+        value(Logical::Unknown, tag("UNKNOWN")),
     ))
-    .map(|(logical, _non_alpha)| logical)
-    .parse(input)
+    .parse(input)?;
+    // Keyword must not be followed by alphabets, e.g. `TRUEx` is an identifier.
+    // This is checked without `tuple` since it skips spaces, e.g. `FALSE THEN`.
+    let (input, _) = nom::combinator::not(nom::character::complete::alpha1)(input)?;
+    Ok((input, logical))
 }
 
 /// 141 integer_literal = digits .
@@ -64,6 +64,15 @@ mod tests {
         assert_eq!(residual, "");
     }
 
+    #[test]
+    fn logical_literal() {
+        let (residual, (value, _remarks)) = super::logical_literal("FALSE THEN").finish().unwrap();
+        assert_eq!(value, super::Logical::False);
+        assert_eq!(residual, " THEN");
+
+        assert!(super::logical_literal("TRUEx").finish().is_err());
+    }
+
     #[test]
     fn real_literal() {
         let (residual, (value, _remarks)) = super::real_literal("123").finish().unwrap();
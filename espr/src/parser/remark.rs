@@ -1,5 +1,5 @@
 use super::{basis::simple_id, combinator::RawParseResult};
-use crate::ast::Remark;
+use crate::ast::{Remark, RemarkPosition};
 use itertools::Itertools;
 use nom::{
     branch::alt, bytes::complete::*, character::complete::*, combinator::opt, multi::*,
@@ -73,6 +73,7 @@ pub fn embedded_remark(input: &str) -> RawParseResult<Remark> {
         remark: format!("{}{}", chars.iter().join(""), end)
             .trim()
             .to_string(),
+        position: RemarkPosition::Head,
     })
     .parse(input)
 }
@@ -98,6 +99,7 @@ pub fn tail_remark(input: &str) -> RawParseResult<Remark> {
         |(_start, _sp, tag, chars, _newline): (_, _, _, &str, _)| Remark {
             tag,
             remark: chars.trim().to_string(),
+            position: RemarkPosition::Head,
         },
     )
     .parse(input)
@@ -4,53 +4,82 @@ use super::{
 use crate::ast::*;
 
 /// 296 schema_decl = SCHEMA [schema_id] \[ schema_version_id \] `;` [schema_body] END_SCHEMA `;` .
+///
+/// [Remark::position] of the remarks in the schema is set here.
 pub fn schema_decl(input: &str) -> ParseResult<Schema> {
     // FIXME schema_version_id
     let schema_head =
         tuple((tag("SCHEMA "), schema_id, char(';'))).map(|(_start, id, _semicolon)| id);
-    tuple((schema_head, schema_body, tag("END_SCHEMA"), char(';')))
-        .map(|(name, (interfaces, constants, decls), _end, _semicolon)| {
-            let mut entities = Vec::new();
-            let mut types = Vec::new();
-            let mut functions = Vec::new();
-            let mut procedures = Vec::new();
-            let mut rules = Vec::new();
-            let mut subtype_constraints = Vec::new();
-
-            for decl in decls {
-                match decl {
-                    Declaration::Entity(e) => entities.push(e),
-                    Declaration::Type(ty) => types.push(ty),
-                    Declaration::Function(f) => functions.push(f),
-                    Declaration::Procedure(p) => procedures.push(p),
-                    Declaration::Rule(r) => rules.push(r),
-                    Declaration::SubTypeConstraint(sub) => subtype_constraints.push(sub),
-                }
-            }
-
-            Schema {
-                name,
-                entities,
-                types,
-                functions,
-                procedures,
-                rules,
-                constants,
-                interfaces,
-                subtype_constraints,
-            }
-        })
-        .parse(input)
+    let (input, ((name, (interfaces, constants, decls)), mut remarks)) =
+        tuple((schema_head, schema_body)).parse(input)?;
+    let (input, (_end, end_remarks)) = tuple((tag("END_SCHEMA"), char(';'))).parse(input)?;
+
+    for remark in &mut remarks {
+        remark.position = RemarkPosition::Schema(name.clone());
+    }
+
+    let mut entities = Vec::new();
+    let mut types = Vec::new();
+    let mut functions = Vec::new();
+    let mut procedures = Vec::new();
+    let mut rules = Vec::new();
+    let mut subtype_constraints = Vec::new();
+
+    for (decl, decl_remarks) in decls {
+        for mut remark in decl_remarks {
+            remark.position = RemarkPosition::Declaration {
+                schema: name.clone(),
+                name: decl.name().to_string(),
+            };
+            remarks.push(remark);
+        }
+        match decl {
+            Declaration::Entity(e) => entities.push(e),
+            Declaration::Type(ty) => types.push(ty),
+            Declaration::Function(f) => functions.push(f),
+            Declaration::Procedure(p) => procedures.push(p),
+            Declaration::Rule(r) => rules.push(r),
+            Declaration::SubTypeConstraint(sub) => subtype_constraints.push(sub),
+        }
+    }
+
+    for mut remark in end_remarks {
+        remark.position = RemarkPosition::EndSchema(name.clone());
+        remarks.push(remark);
+    }
+
+    let schema = Schema {
+        name,
+        entities,
+        types,
+        functions,
+        procedures,
+        rules,
+        constants,
+        interfaces,
+        subtype_constraints,
+    };
+    Ok((input, (schema, remarks)))
 }
 
+/// Declaration with the remarks written in it or just after it
+pub type RemarkedDeclaration = (Declaration, Vec<Remark>);
+
 /// 295 schema_body = { [interface_specification] } \[ [constant_decl] \] { [declaration] | [rule_decl] } .
+///
+/// The remarks in or just after each declaration are returned with it.
 pub fn schema_body(
     input: &str,
-) -> ParseResult<(Vec<InterfaceSpec>, Vec<Constant>, Vec<Declaration>)> {
+) -> ParseResult<(Vec<InterfaceSpec>, Vec<Constant>, Vec<RemarkedDeclaration>)> {
+    let remarked_declaration = |input| {
+        let (input, (decl, remarks)) =
+            alt((declaration, rule_decl.map(Declaration::Rule))).parse(input)?;
+        Ok((input, ((decl, remarks), Vec::new())))
+    };
     tuple((
         many0(interface_specification),
         opt(constant_decl).map(|opt| opt.unwrap_or_default()),
-        many0(alt((declaration, rule_decl.map(Declaration::Rule)))),
+        many0(remarked_declaration),
     ))
     .parse(input)
 }
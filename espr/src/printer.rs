@@ -0,0 +1,1016 @@
+//! Print [crate::ast] back into EXPRESS language
+//!
+//! This module is the inverse of [crate::parser].
+//! Declarations are written into [Printer] line by line with indentation,
+//! and inline components, e.g. types and expressions, are formatted into a string
+//! by the function of the same name as the corresponding parser.
+//!
+//! Layout
+//! -------
+//!
+//! The output uses a fixed layout:
+//!
+//! - Each declaration, attribute, clause keyword, and statement is written in its own line.
+//! - Nested items are indented by two spaces.
+//! - Declarations in a schema are written in the order of
+//!   interfaces, constants, types, entities, subtype constraints, functions, procedures, and rules.
+//! - Operators are surrounded by spaces, and parentheses are added only where precedence requires them.
+//! - [SyntaxTree::remarks] are written at their [Remark::position]:
+//!   before the first schema, just after `SCHEMA schema_id;`, just after the declaration they are in or follow,
+//!   or just after `END_SCHEMA;`.
+//!   Remarks in a declaration are moved after it, and remarks in an interface or constant
+//!   after the schema head.
+//!
+//! ```
+//! use espr::{ast::SyntaxTree, printer};
+//!
+//! let exp_str = r#"
+//! SCHEMA my_first_schema;
+//!   ENTITY a;
+//!     x : REAL;
+//!   WHERE
+//!     wr1 : x > 0;
+//!   END_ENTITY;
+//! END_SCHEMA;
+//! "#;
+//! let st = SyntaxTree::parse(exp_str).unwrap();
+//! let printed = printer::to_string(&st);
+//! assert_eq!(SyntaxTree::parse(&printed).unwrap().schemas, st.schemas);
+//! ```
+
+use crate::ast::*;
+use itertools::Itertools;
+
+/// Print entire syntax tree
+pub fn to_string(st: &SyntaxTree) -> String {
+    let mut printer = Printer::with_remarks(&st.remarks);
+    let head = printer.remarks_at(&RemarkPosition::Head);
+    for (i, schema) in st.schemas.iter().enumerate() {
+        if i > 0 || head {
+            printer.empty_line();
+        }
+        printer.schema(schema);
+    }
+    // Remarks whose positions are not found, e.g. in a schema appended twice
+    let rest = std::mem::take(&mut printer.remarks);
+    if !rest.is_empty() {
+        printer.empty_line();
+        for remark in &rest {
+            printer.remark(remark);
+        }
+    }
+    printer.finish()
+}
+
+/// Line-oriented writer of EXPRESS declarations
+#[derive(Debug, Clone, Default)]
+pub struct Printer {
+    out: String,
+    indent: usize,
+    /// Remarks not written yet
+    remarks: Vec<Remark>,
+}
+
+impl Printer {
+    /// Printer which writes the remarks at their [Remark::position]
+    /// while writing schemas and declarations
+    pub fn with_remarks(remarks: &[Remark]) -> Self {
+        Printer {
+            remarks: remarks.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Printed EXPRESS string
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    fn line(&mut self, line: impl AsRef<str>) {
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(line.as_ref());
+        self.out.push('\n');
+    }
+
+    fn empty_line(&mut self) {
+        self.out.push('\n');
+    }
+
+    fn indented(&mut self, f: impl FnOnce(&mut Self)) {
+        self.indent += 1;
+        f(self);
+        self.indent -= 1;
+    }
+
+    /// Write the remarks at the position in their order, and return if any is written
+    fn remarks_at(&mut self, position: &RemarkPosition) -> bool {
+        let (here, rest) = std::mem::take(&mut self.remarks)
+            .into_iter()
+            .partition::<Vec<_>, _>(|remark| &remark.position == position);
+        self.remarks = rest;
+        for remark in &here {
+            self.remark(remark);
+        }
+        !here.is_empty()
+    }
+
+    /// tail_remark = `--` [ remark_tag ] { \a | \s | \x9 | \xA | \xD } \n .
+    ///
+    /// A remark in a single line is written as tail_remark,
+    /// since it may have come from a tail_remark including `*)` or an unpaired backquote.
+    /// Otherwise it is written as embedded_remark `(* ... *)`,
+    /// or as tail_remark for each line if embedded_remark cannot contain it,
+    /// e.g. it includes `*)` out of backquotes.
+    pub fn remark(&mut self, remark: &Remark) {
+        let tag = match &remark.tag {
+            Some(tag) => format!("\"{}\"", tag.join(".")),
+            None => String::new(),
+        };
+        if !remark.remark.contains('\n') {
+            self.line(format!("--{} {}", tag, remark.remark));
+            return;
+        }
+        let embedded = format!("(*{} {} *)", tag, remark.remark);
+        match crate::parser::embedded_remark(&embedded) {
+            Ok(("", parsed))
+                if parsed.tag == remark.tag && parsed.remark == remark.remark.trim() =>
+            {
+                self.line(embedded)
+            }
+            _ => {
+                for line in remark.remark.lines() {
+                    self.line(format!("--{} {}", tag, line.trim()));
+                }
+            }
+        }
+    }
+
+    /// schema_decl = SCHEMA schema_id `;` schema_body END_SCHEMA `;` .
+    pub fn schema(&mut self, schema: &Schema) {
+        self.line(format!("SCHEMA {};", schema.name));
+        self.indented(|p| {
+            p.remarks_at(&RemarkPosition::Schema(schema.name.clone()));
+            for interface in &schema.interfaces {
+                p.empty_line();
+                p.interface_specification(interface);
+            }
+            if !schema.constants.is_empty() {
+                p.empty_line();
+                p.constant_decl(&schema.constants);
+            }
+            for ty in &schema.types {
+                p.empty_line();
+                p.type_decl(ty);
+                p.declaration_remarks(&schema.name, &ty.type_id);
+            }
+            for entity in &schema.entities {
+                p.empty_line();
+                p.entity_decl(entity);
+                p.declaration_remarks(&schema.name, &entity.name);
+            }
+            for constraint in &schema.subtype_constraints {
+                p.empty_line();
+                p.subtype_constraint_decl(constraint);
+                p.declaration_remarks(&schema.name, &constraint.name);
+            }
+            for function in &schema.functions {
+                p.empty_line();
+                p.function_decl(function);
+                p.declaration_remarks(&schema.name, &function.name);
+            }
+            for procedure in &schema.procedures {
+                p.empty_line();
+                p.procedure_decl(procedure);
+                p.declaration_remarks(&schema.name, &procedure.name);
+            }
+            for rule in &schema.rules {
+                p.empty_line();
+                p.rule_decl(rule);
+                p.declaration_remarks(&schema.name, &rule.name);
+            }
+        });
+        self.empty_line();
+        self.line("END_SCHEMA;");
+        self.remarks_at(&RemarkPosition::EndSchema(schema.name.clone()));
+    }
+
+    fn declaration_remarks(&mut self, schema: &str, name: &str) {
+        self.remarks_at(&RemarkPosition::Declaration {
+            schema: schema.to_string(),
+            name: name.to_string(),
+        });
+    }
+
+    /// interface_specification = reference_clause | use_clause .
+    pub fn interface_specification(&mut self, interface: &InterfaceSpec) {
+        let (keyword, name, items) = match interface {
+            InterfaceSpec::Use { name, types } => ("USE", name, types),
+            InterfaceSpec::Reference { name, resources } => ("REFERENCE", name, resources),
+        };
+        if items.is_empty() {
+            self.line(format!("{} FROM {};", keyword, name));
+            return;
+        }
+        self.line(format!("{} FROM {}", keyword, name));
+        self.indented(|p| {
+            let last = items.len() - 1;
+            for (i, (item, rename)) in items.iter().enumerate() {
+                let open = if i == 0 { "(" } else { " " };
+                let close = if i == last { ");" } else { "," };
+                match rename {
+                    Some(rename) => p.line(format!("{}{} AS {}{}", open, item, rename, close)),
+                    None => p.line(format!("{}{}{}", open, item, close)),
+                }
+            }
+        });
+    }
+
+    /// constant_decl = CONSTANT constant_body { constant_body } END_CONSTANT `;` .
+    pub fn constant_decl(&mut self, constants: &[Constant]) {
+        self.line("CONSTANT");
+        self.indented(|p| {
+            for constant in constants {
+                p.line(format!(
+                    "{} : {} := {};",
+                    constant.name,
+                    type_(&constant.ty),
+                    expression(&constant.expr)
+                ));
+            }
+        });
+        self.line("END_CONSTANT;");
+    }
+
+    /// type_decl = TYPE type_id `=` underlying_type `;` \[ where_clause \] END_TYPE `;` .
+    pub fn type_decl(&mut self, decl: &TypeDecl) {
+        self.line(format!(
+            "TYPE {} = {};",
+            decl.type_id,
+            type_(&decl.underlying_type)
+        ));
+        if let Some(where_clause) = &decl.where_clause {
+            self.where_clause(where_clause);
+        }
+        self.line("END_TYPE;");
+    }
+
+    /// entity_decl = entity_head entity_body END_ENTITY `;` .
+    pub fn entity_decl(&mut self, entity: &Entity) {
+        let mut head = vec![format!("ENTITY {}", entity.name)];
+        if let Some(constraint) = &entity.constraint {
+            head.push(supertype_constraint(constraint));
+        }
+        if let Some(subtype) = &entity.subtype_of {
+            head.push(format!(
+                "SUBTYPE OF ({})",
+                subtype.entity_references.join(", ")
+            ));
+        }
+        // The first line is the entity name, and the followings are sub/super-type declarations
+        let last = head.len() - 1;
+        for (i, line) in head.iter().enumerate() {
+            let semicolon = if i == last { ";" } else { "" };
+            if i == 0 {
+                self.line(format!("{}{}", line, semicolon));
+            } else {
+                self.indented(|p| p.line(format!("{}{}", line, semicolon)));
+            }
+        }
+
+        self.indented(|p| {
+            for attr in &entity.attributes {
+                let optional = if attr.optional { "OPTIONAL " } else { "" };
+                p.line(format!(
+                    "{} : {}{};",
+                    attribute_decl(&attr.name),
+                    optional,
+                    type_(&attr.ty)
+                ));
+            }
+        });
+        if let Some(derive) = &entity.derive_clause {
+            self.line("DERIVE");
+            self.indented(|p| {
+                for attr in &derive.attributes {
+                    p.line(format!(
+                        "{} : {} := {};",
+                        attribute_decl(&attr.attr),
+                        type_(&attr.ty),
+                        expression(&attr.expr)
+                    ));
+                }
+            });
+        }
+        if let Some(inverse) = &entity.inverse_clause {
+            self.line("INVERSE");
+            self.indented(|p| {
+                for attr in &inverse.attributes {
+                    p.line(inverse_attr(attr));
+                }
+            });
+        }
+        if let Some(unique) = &entity.unique_clause {
+            self.line("UNIQUE");
+            self.indented(|p| {
+                for rule in &unique.rules {
+                    let attributes = rule.attributes.iter().map(attribute_decl).join(", ");
+                    match &rule.name {
+                        Some(name) => p.line(format!("{} : {};", name, attributes)),
+                        None => p.line(format!("{};", attributes)),
+                    }
+                }
+            });
+        }
+        if let Some(where_clause) = &entity.where_clause {
+            self.where_clause(where_clause);
+        }
+        self.line("END_ENTITY;");
+    }
+
+    /// subtype_constraint_decl = SUBTYPE_CONSTRAINT subtype_constraint_id FOR entity_ref `;`
+    ///                           subtype_constraint_body END_SUBTYPE_CONSTRAINT `;` .
+    pub fn subtype_constraint_decl(&mut self, constraint: &SubTypeConstraint) {
+        self.line(format!(
+            "SUBTYPE_CONSTRAINT {} FOR {};",
+            constraint.name, constraint.entity
+        ));
+        self.indented(|p| {
+            if constraint.is_abstract {
+                p.line("ABSTRACT SUPERTYPE;");
+            }
+            if let Some(total_over) = &constraint.total_over {
+                p.line(format!("TOTAL_OVER ({});", total_over.join(", ")));
+            }
+            if let Some(expr) = &constraint.expr {
+                p.line(format!("{};", supertype_expression(expr)));
+            }
+        });
+        self.line("END_SUBTYPE_CONSTRAINT;");
+    }
+
+    /// function_decl = function_head algorithm_head stmt { stmt } END_FUNCTION `;` .
+    pub fn function_decl(&mut self, function: &Function) {
+        self.line(format!(
+            "FUNCTION {}{} : {};",
+            function.name,
+            formal_parameters(&function.parameters),
+            type_(&function.return_type)
+        ));
+        self.indented(|p| {
+            p.algorithm_head(
+                &function.declarations,
+                &function.constants,
+                &function.variables,
+            );
+            p.statements(&function.statements);
+        });
+        self.line("END_FUNCTION;");
+    }
+
+    /// procedure_decl = procedure_head algorithm_head { stmt } END_PROCEDURE `;` .
+    pub fn procedure_decl(&mut self, procedure: &Procedure) {
+        self.line(format!(
+            "PROCEDURE {}{};",
+            procedure.name,
+            formal_parameters(&procedure.parameters)
+        ));
+        self.indented(|p| {
+            p.algorithm_head(
+                &procedure.declarations,
+                &procedure.constants,
+                &procedure.variables,
+            );
+            p.statements(&procedure.statements);
+        });
+        self.line("END_PROCEDURE;");
+    }
+
+    /// rule_decl = rule_head algorithm_head { stmt } where_clause END_RULE `;` .
+    pub fn rule_decl(&mut self, rule: &Rule) {
+        self.line(format!(
+            "RULE {} FOR ({});",
+            rule.name,
+            rule.references.join(", ")
+        ));
+        self.indented(|p| {
+            p.algorithm_head(&rule.declarations, &rule.constants, &rule.variables);
+            p.statements(&rule.statements);
+        });
+        self.where_clause(&rule.where_clause);
+        self.line("END_RULE;");
+    }
+
+    /// algorithm_head = { declaration } \[ constant_decl \] \[ local_decl \] .
+    fn algorithm_head(
+        &mut self,
+        declarations: &[Declaration],
+        constants: &[Constant],
+        variables: &[LocalVariable],
+    ) {
+        for declaration in declarations {
+            self.declaration(declaration);
+        }
+        if !constants.is_empty() {
+            self.constant_decl(constants);
+        }
+        if !variables.is_empty() {
+            self.line("LOCAL");
+            self.indented(|p| {
+                for variable in variables {
+                    match &variable.expr {
+                        Some(expr) => p.line(format!(
+                            "{} : {} := {};",
+                            variable.name,
+                            type_(&variable.ty),
+                            expression(expr)
+                        )),
+                        None => p.line(format!("{} : {};", variable.name, type_(&variable.ty))),
+                    }
+                }
+            });
+            self.line("END_LOCAL;");
+        }
+    }
+
+    /// declaration = entity_decl | function_decl | procedure_decl | subtype_constraint_decl | type_decl .
+    pub fn declaration(&mut self, declaration: &Declaration) {
+        match declaration {
+            Declaration::Entity(entity) => self.entity_decl(entity),
+            Declaration::Type(ty) => self.type_decl(ty),
+            Declaration::Function(function) => self.function_decl(function),
+            Declaration::Procedure(procedure) => self.procedure_decl(procedure),
+            Declaration::Rule(rule) => self.rule_decl(rule),
+            Declaration::SubTypeConstraint(constraint) => self.subtype_constraint_decl(constraint),
+        }
+    }
+
+    /// where_clause = WHERE domain_rule `;` { domain_rule `;` } .
+    pub fn where_clause(&mut self, where_clause: &WhereClause) {
+        self.line("WHERE");
+        self.indented(|p| {
+            for rule in &where_clause.rules {
+                match &rule.label {
+                    Some(label) => p.line(format!("{} : {};", label, expression(&rule.expr))),
+                    None => p.line(format!("{};", expression(&rule.expr))),
+                }
+            }
+        });
+    }
+
+    pub fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.stmt(statement);
+        }
+    }
+
+    /// stmt = alias_stmt | assignment_stmt | case_stmt | compound_stmt | escape_stmt | if_stmt
+    ///      | null_stmt | procedure_call_stmt | repeat_stmt | return_stmt | skip_stmt .
+    pub fn stmt(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Alias {
+                name,
+                dest,
+                qualifiers,
+                statements,
+            } => {
+                self.line(format!(
+                    "ALIAS {} FOR {}{};",
+                    name,
+                    dest,
+                    qualifiers.iter().map(qualifier).join("")
+                ));
+                self.indented(|p| p.statements(statements));
+                self.line("END_ALIAS;");
+            }
+            Statement::Assignment {
+                name,
+                qualifiers,
+                expr,
+            } => self.line(format!(
+                "{}{} := {};",
+                name,
+                qualifiers.iter().map(qualifier).join(""),
+                expression(expr)
+            )),
+            Statement::Compound { statements } => {
+                self.line("BEGIN");
+                self.indented(|p| p.statements(statements));
+                self.line("END;");
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.line(format!("IF {} THEN", expression(condition)));
+                self.indented(|p| p.statements(then_branch));
+                if let Some(else_branch) = else_branch {
+                    self.line("ELSE");
+                    self.indented(|p| p.statements(else_branch));
+                }
+                self.line("END_IF;");
+            }
+            Statement::Case {
+                selector,
+                actions,
+                otherwise,
+            } => {
+                self.line(format!("CASE {} OF", expression(selector)));
+                self.indented(|p| {
+                    for (labels, action) in actions {
+                        p.line(format!("{} :", labels.iter().map(expression).join(", ")));
+                        p.indented(|p| p.stmt(action));
+                    }
+                    if let Some(otherwise) = otherwise {
+                        p.line("OTHERWISE :");
+                        p.indented(|p| p.stmt(otherwise));
+                    }
+                });
+                self.line("END_CASE;");
+            }
+            Statement::Repeat {
+                control,
+                statements,
+            } => {
+                self.line(format!("REPEAT{};", repeat_control(control)));
+                self.indented(|p| p.statements(statements));
+                self.line("END_REPEAT;");
+            }
+            Statement::Return { value } => match value {
+                Some(value) => self.line(format!("RETURN ({});", expression(value))),
+                None => self.line("RETURN;"),
+            },
+            Statement::ProcedureCall {
+                procedure,
+                parameters,
+            } => {
+                let name = match procedure {
+                    ProcedureCallName::Reference(name) => name.as_str(),
+                    ProcedureCallName::Insert => "INSERT",
+                    ProcedureCallName::Remove => "REMOVE",
+                };
+                match parameters {
+                    Some(parameters) => self.line(format!(
+                        "{}({});",
+                        name,
+                        parameters.iter().map(expression).join(", ")
+                    )),
+                    None => self.line(format!("{};", name)),
+                }
+            }
+            Statement::Skip => self.line("SKIP;"),
+            Statement::Escape => self.line("ESCAPE;"),
+            Statement::Null => self.line(";"),
+        }
+    }
+}
+
+/// supertype_constraint = abstract_entity_declaration | abstract_supertype_declaration | supertype_rule .
+pub fn supertype_constraint(constraint: &Constraint) -> String {
+    match constraint {
+        Constraint::AbstractEntity => "ABSTRACT".to_string(),
+        Constraint::AbstractSuperType(None) => "ABSTRACT SUPERTYPE".to_string(),
+        Constraint::AbstractSuperType(Some(expr)) => {
+            format!("ABSTRACT SUPERTYPE OF ({})", supertype_expression(expr))
+        }
+        Constraint::SuperTypeRule(expr) => format!("SUPERTYPE OF ({})", supertype_expression(expr)),
+    }
+}
+
+/// supertype_expression = supertype_factor { ANDOR supertype_factor } .
+pub fn supertype_expression(expr: &SuperTypeExpression) -> String {
+    match expr {
+        SuperTypeExpression::AndOr { factors } => {
+            factors.iter().map(supertype_factor).join(" ANDOR ")
+        }
+        _ => supertype_factor(expr),
+    }
+}
+
+/// supertype_factor = supertype_term { AND supertype_term } .
+fn supertype_factor(expr: &SuperTypeExpression) -> String {
+    match expr {
+        SuperTypeExpression::And { terms } => terms.iter().map(supertype_term).join(" AND "),
+        _ => supertype_term(expr),
+    }
+}
+
+/// supertype_term = entity_ref | one_of | `(` supertype_expression `)` .
+fn supertype_term(expr: &SuperTypeExpression) -> String {
+    match expr {
+        SuperTypeExpression::Reference(name) => name.clone(),
+        SuperTypeExpression::OneOf { exprs } => {
+            format!(
+                "ONEOF ({})",
+                exprs.iter().map(supertype_expression).join(", ")
+            )
+        }
+        _ => format!("({})", supertype_expression(expr)),
+    }
+}
+
+/// attribute_decl = attribute_id | redeclared_attribute .
+pub fn attribute_decl(attr: &AttributeDecl) -> String {
+    match attr {
+        AttributeDecl::Reference(name) => name.clone(),
+        AttributeDecl::Qualified {
+            group,
+            attribute,
+            rename,
+        } => match rename {
+            Some(rename) => format!("SELF\\{}.{} RENAMED {}", group, attribute, rename),
+            None => format!("SELF\\{}.{}", group, attribute),
+        },
+    }
+}
+
+/// inverse_attr = attribute_decl `:` \[ ( SET | BAG ) \[ bound_spec \] OF \] entity_ref FOR \[ entity_ref `.` \] attribute_ref `;` .
+fn inverse_attr(attr: &InverseAttribute) -> String {
+    let aggregation = match &attr.dest_aggregation {
+        AggregationOption::Set { bound } => format!("SET{} OF ", optional_bound_spec(bound)),
+        AggregationOption::Bag { bound } => format!("BAG{} OF ", optional_bound_spec(bound)),
+        AggregationOption::None => String::new(),
+    };
+    let prefix = match &attr.attribute_prefix {
+        Some(prefix) => format!("{}.", prefix),
+        None => String::new(),
+    };
+    format!(
+        "{} : {}{} FOR {}{};",
+        attribute_decl(&attr.name),
+        aggregation,
+        attr.dest,
+        prefix,
+        attr.attribute
+    )
+}
+
+/// formal_parameter list in `FUNCTION` and `PROCEDURE` head, empty if no parameter
+fn formal_parameters(parameters: &[FormalParameter]) -> String {
+    if parameters.is_empty() {
+        return String::new();
+    }
+    let mut parameters = parameters.iter().map(|p| {
+        let var = if p.is_variable { "VAR " } else { "" };
+        format!("{}{} : {}", var, p.name, type_(&p.ty))
+    });
+    format!(" ({})", parameters.join("; "))
+}
+
+/// repeat_control = \[ increment_control \] \[ while_control \] \[ until_control \] .
+///
+/// Returns with a leading space if some control exists.
+fn repeat_control(control: &RepeatControl) -> String {
+    let mut out = String::new();
+    if let Some(inc) = &control.increment {
+        out.push_str(&format!(
+            " {} := {} TO {}",
+            inc.variable,
+            expression(&inc.begin),
+            expression(&inc.end)
+        ));
+        if let Some(increment) = &inc.increment {
+            out.push_str(&format!(" BY {}", expression(increment)));
+        }
+    }
+    if let Some(while_) = &control.while_ {
+        out.push_str(&format!(" WHILE {}", expression(while_)));
+    }
+    if let Some(until) = &control.until {
+        out.push_str(&format!(" UNTIL {}", expression(until)));
+    }
+    out
+}
+
+/// Types appears in type declaration, attributes, parameters, and local variables
+pub fn type_(ty: &Type) -> String {
+    match ty {
+        Type::Simple(ty) => simple_types(ty),
+        Type::Named(name) => name.clone(),
+        Type::Set { base, bound } => {
+            format!("SET{} OF {}", optional_bound_spec(bound), type_(base))
+        }
+        Type::Bag { base, bound } => {
+            format!("BAG{} OF {}", optional_bound_spec(bound), type_(base))
+        }
+        Type::List {
+            base,
+            bound,
+            unique,
+        } => format!(
+            "LIST{} OF {}{}",
+            optional_bound_spec(bound),
+            if *unique { "UNIQUE " } else { "" },
+            type_(base)
+        ),
+        Type::Array {
+            base,
+            bound,
+            unique,
+            optional,
+        } => format!(
+            "ARRAY{} OF {}{}{}",
+            optional_bound_spec(bound),
+            if *optional { "OPTIONAL " } else { "" },
+            if *unique { "UNIQUE " } else { "" },
+            type_(base)
+        ),
+        Type::Enumeration {
+            extensibility,
+            items,
+        } => {
+            let extensible = match extensibility {
+                Extensibility::None => "",
+                _ => "EXTENSIBLE ",
+            };
+            format!("{}ENUMERATION OF ({})", extensible, items.join(", "))
+        }
+        Type::Select {
+            extensibility,
+            types,
+        } => {
+            let extensible = match extensibility {
+                Extensibility::None => "",
+                Extensibility::Extensible => "EXTENSIBLE ",
+                Extensibility::GenericEntity => "EXTENSIBLE GENERIC_ENTITY ",
+            };
+            format!("{}SELECT ({})", extensible, types.join(", "))
+        }
+        Type::Aggregate { base, label } => {
+            format!("AGGREGATE{} OF {}", type_label(label), type_(base))
+        }
+        Type::GenericEntity(label) => format!("GENERIC_ENTITY{}", type_label(label)),
+        Type::Generic(label) => format!("GENERIC{}", type_label(label)),
+    }
+}
+
+fn type_label(label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!(" : {}", label),
+        None => String::new(),
+    }
+}
+
+/// simple_types = binary_type | boolean_type | integer_type | logical_type | number_type | real_type | string_type .
+pub fn simple_types(ty: &SimpleType) -> String {
+    match ty {
+        SimpleType::Number => "NUMBER".to_string(),
+        SimpleType::Real => "REAL".to_string(),
+        SimpleType::Integer => "INTEGER".to_string(),
+        SimpleType::Logical => "LOGICAL".to_string(),
+        SimpleType::Boolen => "BOOLEAN".to_string(),
+        SimpleType::String_ { width_spec } => format!("STRING{}", optional_width_spec(width_spec)),
+        SimpleType::Binary { width_spec } => format!("BINARY{}", optional_width_spec(width_spec)),
+    }
+}
+
+fn optional_width_spec(width_spec: &Option<WidthSpec>) -> String {
+    match width_spec {
+        Some(WidthSpec { width, fixed: true }) => format!("({}) FIXED", width),
+        Some(WidthSpec {
+            width,
+            fixed: false,
+        }) => format!("({})", width),
+        None => String::new(),
+    }
+}
+
+/// bound_spec with a leading space, or empty if not specified
+fn optional_bound_spec(bound: &Option<Bound>) -> String {
+    match bound {
+        Some(bound) => format!(" {}", bound_spec(bound)),
+        None => String::new(),
+    }
+}
+
+/// bound_spec = `[` bound_1 `:` bound_2 `]` .
+pub fn bound_spec(bound: &Bound) -> String {
+    format!(
+        "[{} : {}]",
+        expression(&bound.lower),
+        expression(&bound.upper)
+    )
+}
+
+/// Precedence of operators in ISO 10303-11 12.1, larger binds tighter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    /// `expression = simple_expression [ rel_op_extended simple_expression ]`
+    Relation,
+    /// `simple_expression = term { add_like_op term }`
+    Add,
+    /// `term = factor { multiplication_like_op factor }`
+    Mul,
+    /// `factor = simple_factor [ ** simple_factor ]`
+    Power,
+    /// `simple_factor` including unary operators
+    Unary,
+    /// `primary` and others never need parentheses
+    Primary,
+}
+
+fn precedence(expr: &Expression) -> Precedence {
+    match expr {
+        Expression::Relation { .. } => Precedence::Relation,
+        Expression::Binary { op, .. } => match op {
+            BinaryOperator::Add
+            | BinaryOperator::Sub
+            | BinaryOperator::Or
+            | BinaryOperator::Xor => Precedence::Add,
+            BinaryOperator::Power => Precedence::Power,
+            _ => Precedence::Mul,
+        },
+        Expression::Unary { .. } => Precedence::Unary,
+        _ => Precedence::Primary,
+    }
+}
+
+/// Format the expression with parentheses if it binds looser than `required`
+fn operand(expr: &Expression, required: Precedence) -> String {
+    if precedence(expr) < required {
+        format!("({})", expression(expr))
+    } else {
+        expression(expr)
+    }
+}
+
+/// expression = simple_expression \[ rel_op_extended simple_expression \] .
+pub fn expression(expr: &Expression) -> String {
+    match expr {
+        Expression::Unary { op, arg } => {
+            let op = match op {
+                UnaryOperator::Plus => "+",
+                UnaryOperator::Minus => "-",
+                UnaryOperator::Not => "NOT ",
+            };
+            // unary_op can be applied only to primary or parenthesized expression
+            match arg.as_ref() {
+                Expression::Literal(_) | Expression::QualifiableFactor { .. } => {
+                    format!("{}{}", op, expression(arg))
+                }
+                _ => format!("{}({})", op, expression(arg)),
+            }
+        }
+        Expression::Binary { op, arg1, arg2 } => {
+            let level = precedence(expr);
+            let (lhs, rhs) = if level == Precedence::Power {
+                // `**` is not associative in the grammar
+                (
+                    operand(arg1, Precedence::Unary),
+                    operand(arg2, Precedence::Unary),
+                )
+            } else {
+                // Left-associative, and thus the right operand of same precedence requires parentheses
+                (operand(arg1, level), operand(arg2, next(level)))
+            };
+            format!("{} {} {}", lhs, binary_operator(op), rhs)
+        }
+        Expression::Relation { op, lhs, rhs } => format!(
+            "{} {} {}",
+            operand(lhs, Precedence::Add),
+            relation_operator(op),
+            operand(rhs, Precedence::Add)
+        ),
+        Expression::Literal(literal) => self::literal(literal),
+        Expression::QualifiableFactor { factor, qualifiers } => {
+            let factor = match factor {
+                QualifiableFactor::Reference(name) => name.clone(),
+                QualifiableFactor::BuiltInConstant(constant) => {
+                    built_in_constant(constant).to_string()
+                }
+                QualifiableFactor::FunctionCall { name, args } => {
+                    let name = match name {
+                        FunctionCallName::BuiltInFunction(f) => format!("{:?}", f),
+                        FunctionCallName::Reference(name) => name.clone(),
+                    };
+                    format!("{}({})", name, args.iter().map(expression).join(", "))
+                }
+            };
+            format!("{}{}", factor, qualifiers.iter().map(qualifier).join(""))
+        }
+        Expression::EntityConstructor { name, values } => {
+            format!("{}({})", name, values.iter().map(expression).join(", "))
+        }
+        Expression::Interval {
+            op_low,
+            op_high,
+            high,
+            low,
+            item,
+        } => format!(
+            "{{{} {} {} {} {}}}",
+            operand(low, Precedence::Add),
+            interval_op(op_low),
+            operand(item, Precedence::Add),
+            interval_op(op_high),
+            operand(high, Precedence::Add)
+        ),
+        Expression::EnumerationReference { ty, enum_ref } => match ty {
+            Some(ty) => format!("{}.{}", ty, enum_ref),
+            None => enum_ref.clone(),
+        },
+        Expression::AggregateInitializer { elements } => {
+            let mut elements = elements.iter().map(|element| match &element.repetition {
+                Some(repetition) => {
+                    format!("{} : {}", expression(&element.expr), expression(repetition))
+                }
+                None => expression(&element.expr),
+            });
+            format!("[{}]", elements.join(", "))
+        }
+        Expression::Query {
+            variable,
+            source,
+            expr,
+        } => format!(
+            "QUERY({} <* {} | {})",
+            variable,
+            operand(source, Precedence::Add),
+            expression(expr)
+        ),
+    }
+}
+
+fn next(level: Precedence) -> Precedence {
+    match level {
+        Precedence::Relation => Precedence::Add,
+        Precedence::Add => Precedence::Mul,
+        Precedence::Mul => Precedence::Power,
+        Precedence::Power => Precedence::Unary,
+        Precedence::Unary | Precedence::Primary => Precedence::Primary,
+    }
+}
+
+/// qualifier = attribute_qualifier | group_qualifier | index_qualifier .
+pub fn qualifier(qualifier: &Qualifier) -> String {
+    match qualifier {
+        Qualifier::Attribute(name) => format!(".{}", name),
+        Qualifier::Group(name) => format!("\\{}", name),
+        Qualifier::Index(index) => format!("[{}]", expression(index)),
+        Qualifier::Range { begin, end } => {
+            format!("[{} : {}]", expression(begin), expression(end))
+        }
+    }
+}
+
+/// literal = binary_literal | logical_literal | real_literal | string_literal .
+pub fn literal(literal: &Literal) -> String {
+    match literal {
+        // Integers are also parsed as real numbers
+        Literal::Real(value) if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", value),
+        Literal::Real(value) => format!("{:?}", value),
+        Literal::String(s) => format!("'{}'", s.replace('\'', "''")),
+        Literal::Logial(Logical::True) => "TRUE".to_string(),
+        Literal::Logial(Logical::False) => "FALSE".to_string(),
+        Literal::Logial(Logical::Unknown) => "UNKNOWN".to_string(),
+    }
+}
+
+fn built_in_constant(constant: &BuiltInConstant) -> &'static str {
+    match constant {
+        BuiltInConstant::Napier => "CONST_E",
+        BuiltInConstant::Pi => "PI",
+        BuiltInConstant::Self_ => "SELF",
+        BuiltInConstant::Indeterminate => "?",
+    }
+}
+
+fn binary_operator(op: &BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Mul => "*",
+        BinaryOperator::RealDiv => "/",
+        BinaryOperator::IntegerDiv => "DIV",
+        BinaryOperator::Mod => "MOD",
+        BinaryOperator::And => "AND",
+        BinaryOperator::ComplexEntityInstanceConstruction => "||",
+        BinaryOperator::Add => "+",
+        BinaryOperator::Sub => "-",
+        BinaryOperator::Or => "OR",
+        BinaryOperator::Xor => "XOR",
+        BinaryOperator::Power => "**",
+    }
+}
+
+fn relation_operator(op: &RelationOperator) -> &'static str {
+    match op {
+        RelationOperator::Equal => "=",
+        RelationOperator::NotEqual => "<>",
+        RelationOperator::Lt => "<",
+        RelationOperator::Gt => ">",
+        RelationOperator::Leq => "<=",
+        RelationOperator::Geq => ">=",
+        RelationOperator::InstanceEqual => ":=:",
+        RelationOperator::InstanceNotEqual => ":<>:",
+        RelationOperator::In => "IN",
+        RelationOperator::Like => "LIKE",
+    }
+}
+
+fn interval_op(op: &IntervalOperator) -> &'static str {
+    match op {
+        IntervalOperator::LessThan => "<",
+        IntervalOperator::LessThanEqual => "<=",
+    }
+}
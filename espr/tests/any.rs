@@ -25,14 +25,18 @@ fn any() {
     let tt = rustfmt(tt);
 
     insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
     pub mod test_schema {
-        use ruststep::{as_holder, derive_more::*, primitive::*, Holder, TableInit};
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
         use std::collections::HashMap;
         #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
         pub struct Tables {
             base: HashMap<u64, as_holder!(Base)>,
             sub1: HashMap<u64, as_holder!(Sub1)>,
             sub2: HashMap<u64, as_holder!(Sub2)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
         }
         impl Tables {
             pub fn base_holders(&self) -> &HashMap<u64, as_holder!(Base)> {
@@ -45,10 +49,98 @@ fn any() {
                 &self.sub2
             }
         }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.base.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Base)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.sub1.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Sub1)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.sub2.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Sub2)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.base.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Base)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.sub1.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Sub1)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.sub2.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Sub2)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
         #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
         # [holder (table = Tables)]
         # [holder (field = base)]
         #[holder(generate_deserialize)]
+        #[holder(instantiable(Sub1))]
+        #[holder(instantiable(Sub2))]
         pub struct Base {
             pub x: f64,
         }
@@ -99,6 +191,7 @@ fn any() {
             #[deref]
             #[deref_mut]
             #[holder(use_place_holder)]
+            #[holder(supertype)]
             pub base: Base,
             pub y1: f64,
         }
@@ -114,9 +207,314 @@ fn any() {
             #[deref]
             #[deref_mut]
             #[holder(use_place_holder)]
+            #[holder(supertype)]
             pub base: Base,
             pub y2: f64,
         }
+        impl runtime::ToValue for Base {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Base {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.BASE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for BaseHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for BaseHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.BASE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for BaseAny {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    BaseAny::Base(x) => runtime::ToValue::to_value(&**x),
+                    BaseAny::Sub1(x) => runtime::ToValue::to_value(&**x),
+                    BaseAny::Sub2(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for BaseAnyHolder {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    BaseAnyHolder::Base(x) => runtime::ToValue::to_value(&**x),
+                    BaseAnyHolder::Sub1(x) => runtime::ToValue::to_value(&**x),
+                    BaseAnyHolder::Sub2(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for Sub1 {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Sub1 {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUB1".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.base));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "y1" => runtime::ToValue::to_value(&self.y1),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.base, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.base, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for Sub1Holder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Sub1Holder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUB1".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.base));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "y1" => runtime::ToValue::to_value(&self.y1),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.base, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.base, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for Sub2 {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Sub2 {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUB2".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.base));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "y2" => runtime::ToValue::to_value(&self.y2),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.base, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.base, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for Sub2Holder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Sub2Holder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUB2".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.base));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "y2" => runtime::ToValue::to_value(&self.y2),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.base, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.base, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for Base {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for BaseHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Sub1 {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Sub1Holder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Sub2 {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Sub2Holder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
     }
     "###);
 }
@@ -0,0 +1,696 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  ENTITY person SUPERTYPE OF (employee ANDOR student);
+    name: STRING;
+  END_ENTITY;
+
+  ENTITY employee SUBTYPE OF (person);
+    pay: INTEGER;
+  END_ENTITY;
+
+  ENTITY student SUBTYPE OF (person);
+    school_name: STRING;
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn complex_entity() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod test_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
+        pub struct Tables {
+            person: HashMap<u64, as_holder!(Person)>,
+            employee: HashMap<u64, as_holder!(Employee)>,
+            student: HashMap<u64, as_holder!(Student)>,
+            employee_and_student: HashMap<u64, as_holder!(EmployeeAndStudent)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {
+            pub fn person_holders(&self) -> &HashMap<u64, as_holder!(Person)> {
+                &self.person
+            }
+            pub fn employee_holders(&self) -> &HashMap<u64, as_holder!(Employee)> {
+                &self.employee
+            }
+            pub fn student_holders(&self) -> &HashMap<u64, as_holder!(Student)> {
+                &self.student
+            }
+            pub fn employee_and_student_holders(
+                &self,
+            ) -> &HashMap<u64, as_holder!(EmployeeAndStudent)> {
+                &self.employee_and_student
+            }
+        }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                for id in self.employee_and_student.keys() {
+                    let holder = resolver.get_shared::<as_holder!(EmployeeAndStudent)>(*id)?;
+                    instances.insert(*id, runtime::Value::Entity(holder));
+                }
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.person.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Person)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.employee.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Employee)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.student.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Student)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.employee_and_student.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(EmployeeAndStudent)>(*id)
+                    {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.person.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Person)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.employee.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Employee)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.student.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Student)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = person)]
+        #[holder(generate_deserialize)]
+        #[holder(instantiable(Employee))]
+        #[holder(instantiable(Student))]
+        #[holder(instantiable(Employee, Student))]
+        pub struct Person {
+            pub name: String,
+        }
+        #[derive(Debug, Clone, PartialEq, Holder)]
+        # [holder (table = Tables)]
+        #[holder(generate_deserialize)]
+        pub enum PersonAny {
+            #[holder(use_place_holder)]
+            Person(Box<Person>),
+            #[holder(use_place_holder)]
+            EmployeeAndStudent(Box<EmployeeAndStudent>),
+            #[holder(use_place_holder)]
+            Employee(Box<Employee>),
+            #[holder(use_place_holder)]
+            Student(Box<Student>),
+        }
+        impl Into<PersonAny> for Person {
+            fn into(self) -> PersonAny {
+                PersonAny::Person(Box::new(self))
+            }
+        }
+        impl Into<PersonAny> for EmployeeAndStudent {
+            fn into(self) -> PersonAny {
+                PersonAny::EmployeeAndStudent(Box::new(self))
+            }
+        }
+        impl Into<PersonAny> for Employee {
+            fn into(self) -> PersonAny {
+                PersonAny::Employee(Box::new(self.into()))
+            }
+        }
+        impl Into<PersonAny> for Student {
+            fn into(self) -> PersonAny {
+                PersonAny::Student(Box::new(self.into()))
+            }
+        }
+        impl AsRef<Person> for PersonAny {
+            fn as_ref(&self) -> &Person {
+                match self {
+                    PersonAny::Person(x) => x.as_ref(),
+                    PersonAny::EmployeeAndStudent(x) => x.employee.as_ref(),
+                    PersonAny::Employee(x) => (**x).as_ref(),
+                    PersonAny::Student(x) => (**x).as_ref(),
+                }
+            }
+        }
+        #[derive(
+            Debug, Clone, PartialEq, :: derive_new :: new, Holder, AsRef, AsMut, Deref, DerefMut,
+        )]
+        # [holder (table = Tables)]
+        # [holder (field = employee)]
+        #[holder(generate_deserialize)]
+        pub struct Employee {
+            #[as_ref]
+            #[as_mut]
+            #[deref]
+            #[deref_mut]
+            #[holder(use_place_holder)]
+            #[holder(supertype)]
+            pub person: Person,
+            pub pay: i64,
+        }
+        #[derive(
+            Debug, Clone, PartialEq, :: derive_new :: new, Holder, AsRef, AsMut, Deref, DerefMut,
+        )]
+        # [holder (table = Tables)]
+        # [holder (field = student)]
+        #[holder(generate_deserialize)]
+        pub struct Student {
+            #[as_ref]
+            #[as_mut]
+            #[deref]
+            #[deref_mut]
+            #[holder(use_place_holder)]
+            #[holder(supertype)]
+            pub person: Person,
+            pub school_name: String,
+        }
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder, AsRef, AsMut)]
+        # [holder (table = Tables)]
+        # [holder (field = employee_and_student)]
+        #[holder(complex)]
+        #[holder(generate_deserialize)]
+        pub struct EmployeeAndStudent {
+            #[as_ref]
+            #[as_mut]
+            #[holder(use_place_holder)]
+            pub employee: Employee,
+            #[as_ref]
+            #[as_mut]
+            #[holder(use_place_holder)]
+            pub student: Student,
+        }
+        impl runtime::ToValue for Person {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Person {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.PERSON".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for PersonHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for PersonHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.PERSON".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for PersonAny {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    PersonAny::Person(x) => runtime::ToValue::to_value(&**x),
+                    PersonAny::EmployeeAndStudent(x) => runtime::ToValue::to_value(&**x),
+                    PersonAny::Employee(x) => runtime::ToValue::to_value(&**x),
+                    PersonAny::Student(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for PersonAnyHolder {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    PersonAnyHolder::Person(x) => runtime::ToValue::to_value(&**x),
+                    PersonAnyHolder::EmployeeAndStudent(x) => runtime::ToValue::to_value(&**x),
+                    PersonAnyHolder::Employee(x) => runtime::ToValue::to_value(&**x),
+                    PersonAnyHolder::Student(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for Employee {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Employee {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.EMPLOYEE".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.person));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "pay" => runtime::ToValue::to_value(&self.pay),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.person, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.person, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for EmployeeHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for EmployeeHolder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.EMPLOYEE".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.person));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "pay" => runtime::ToValue::to_value(&self.pay),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.person, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.person, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for Student {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Student {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.STUDENT".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.person));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "school_name" => runtime::ToValue::to_value(&self.school_name),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.person, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.person, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for StudentHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for StudentHolder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.STUDENT".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.person));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "school_name" => runtime::ToValue::to_value(&self.school_name),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.person, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.person, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for EmployeeAndStudent {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for EmployeeAndStudent {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = Vec::new();
+                for name in runtime::EntityValue::type_names(&self.employee) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                for name in runtime::EntityValue::type_names(&self.student) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                let value = runtime::EntityValue::attribute(&self.employee, name);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                let value = runtime::EntityValue::attribute(&self.student, name);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                runtime::Value::Indeterminate
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                let value =
+                    runtime::EntityValue::derived_attribute(&self.employee, name, id, population);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                let value =
+                    runtime::EntityValue::derived_attribute(&self.student, name, id, population);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                runtime::Value::Indeterminate
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for EmployeeAndStudentHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for EmployeeAndStudentHolder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = Vec::new();
+                for name in runtime::EntityValue::type_names(&self.employee) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                for name in runtime::EntityValue::type_names(&self.student) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                let value = runtime::EntityValue::attribute(&self.employee, name);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                let value = runtime::EntityValue::attribute(&self.student, name);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                runtime::Value::Indeterminate
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                let value =
+                    runtime::EntityValue::derived_attribute(&self.employee, name, id, population);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                let value =
+                    runtime::EntityValue::derived_attribute(&self.student, name, id, population);
+                if !value.is_indeterminate() {
+                    return value;
+                }
+                runtime::Value::Indeterminate
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for Person {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for PersonHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Employee {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for EmployeeHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Student {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for StudentHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for EmployeeAndStudent {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for EmployeeAndStudentHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+    }
+    "###);
+}
@@ -0,0 +1,390 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  CONSTANT
+    max_items : INTEGER := 3;
+    dummy_gri : geometric_representation_item := representation_item('') ||
+                                                 geometric_representation_item();
+  END_CONSTANT;
+
+  ENTITY representation_item;
+    name: STRING;
+  END_ENTITY;
+
+  ENTITY geometric_representation_item SUBTYPE OF (representation_item);
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn constant() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod test_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
+        pub struct Tables {
+            representation_item: HashMap<u64, as_holder!(RepresentationItem)>,
+            geometric_representation_item: HashMap<u64, as_holder!(GeometricRepresentationItem)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {
+            pub fn representation_item_holders(&self) -> &HashMap<u64, as_holder!(RepresentationItem)> {
+                &self.representation_item
+            }
+            pub fn geometric_representation_item_holders(
+                &self,
+            ) -> &HashMap<u64, as_holder!(GeometricRepresentationItem)> {
+                &self.geometric_representation_item
+            }
+        }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.representation_item.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(RepresentationItem)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.geometric_representation_item.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(GeometricRepresentationItem)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.representation_item.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(RepresentationItem)>(*id)
+                    {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.geometric_representation_item.keys() {
+                    if let Some(holder) =
+                        population.instance_of::<as_holder!(GeometricRepresentationItem)>(*id)
+                    {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = representation_item)]
+        #[holder(generate_deserialize)]
+        #[holder(instantiable(GeometricRepresentationItem))]
+        #[holder(andor(GeometricRepresentationItem))]
+        pub struct RepresentationItem {
+            pub name: String,
+        }
+        #[derive(Debug, Clone, PartialEq, Holder)]
+        # [holder (table = Tables)]
+        #[holder(generate_deserialize)]
+        pub enum RepresentationItemAny {
+            #[holder(use_place_holder)]
+            RepresentationItem(Box<RepresentationItem>),
+            #[holder(use_place_holder)]
+            GeometricRepresentationItem(Box<GeometricRepresentationItem>),
+        }
+        impl Into<RepresentationItemAny> for RepresentationItem {
+            fn into(self) -> RepresentationItemAny {
+                RepresentationItemAny::RepresentationItem(Box::new(self))
+            }
+        }
+        impl Into<RepresentationItemAny> for GeometricRepresentationItem {
+            fn into(self) -> RepresentationItemAny {
+                RepresentationItemAny::GeometricRepresentationItem(Box::new(self.into()))
+            }
+        }
+        impl AsRef<RepresentationItem> for RepresentationItemAny {
+            fn as_ref(&self) -> &RepresentationItem {
+                match self {
+                    RepresentationItemAny::RepresentationItem(x) => x.as_ref(),
+                    RepresentationItemAny::GeometricRepresentationItem(x) => (**x).as_ref(),
+                }
+            }
+        }
+        #[derive(
+            Debug, Clone, PartialEq, :: derive_new :: new, Holder, AsRef, AsMut, Deref, DerefMut,
+        )]
+        # [holder (table = Tables)]
+        # [holder (field = geometric_representation_item)]
+        #[holder(generate_deserialize)]
+        pub struct GeometricRepresentationItem {
+            #[as_ref]
+            #[as_mut]
+            #[deref]
+            #[deref_mut]
+            #[holder(use_place_holder)]
+            #[holder(supertype)]
+            pub representation_item: RepresentationItem,
+        }
+        impl runtime::ToValue for RepresentationItem {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for RepresentationItem {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.REPRESENTATION_ITEM".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for RepresentationItemHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for RepresentationItemHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.REPRESENTATION_ITEM".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for RepresentationItemAny {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    RepresentationItemAny::RepresentationItem(x) => runtime::ToValue::to_value(&**x),
+                    RepresentationItemAny::GeometricRepresentationItem(x) => {
+                        runtime::ToValue::to_value(&**x)
+                    }
+                }
+            }
+        }
+        impl runtime::ToValue for RepresentationItemAnyHolder {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    RepresentationItemAnyHolder::RepresentationItem(x) => {
+                        runtime::ToValue::to_value(&**x)
+                    }
+                    RepresentationItemAnyHolder::GeometricRepresentationItem(x) => {
+                        runtime::ToValue::to_value(&**x)
+                    }
+                }
+            }
+        }
+        impl runtime::ToValue for GeometricRepresentationItem {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for GeometricRepresentationItem {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.GEOMETRIC_REPRESENTATION_ITEM".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.representation_item));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.representation_item, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value = runtime::EntityValue::derived_attribute(
+                            &self.representation_item,
+                            name,
+                            id,
+                            population,
+                        );
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for GeometricRepresentationItemHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for GeometricRepresentationItemHolder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.GEOMETRIC_REPRESENTATION_ITEM".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.representation_item));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.representation_item, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value = runtime::EntityValue::derived_attribute(
+                            &self.representation_item,
+                            name,
+                            id,
+                            population,
+                        );
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for RepresentationItem {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for RepresentationItemHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for GeometricRepresentationItem {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for GeometricRepresentationItemHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        #[doc = " `CONSTANT max_items`"]
+        pub const MAX_ITEMS: i64 = 3;
+        thread_local! { # [doc = " `CONSTANT dummy_gri`"] pub static DUMMY_GRI : runtime :: Value = constant_dummy_gri () ; }
+        fn constant_dummy_gri() -> runtime::Value {
+            runtime::complex(
+                runtime::Value::entity(runtime::ConstructedEntity {
+                    type_names: vec!["TEST_SCHEMA.REPRESENTATION_ITEM".to_string()],
+                    attributes: vec![("name".to_string(), runtime::Value::String("".to_string()))],
+                }),
+                runtime::Value::entity(runtime::ConstructedEntity {
+                    type_names: vec!["TEST_SCHEMA.GEOMETRIC_REPRESENTATION_ITEM".to_string()],
+                    attributes: vec![],
+                }),
+            )
+        }
+    }
+    "###);
+}
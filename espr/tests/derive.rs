@@ -0,0 +1,246 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  ENTITY point;
+    coordinates: LIST [1:3] OF REAL;
+  DERIVE
+    dim: INTEGER := SIZEOF(coordinates);
+    origin: BOOLEAN := SIZEOF(QUERY(c <* coordinates | c <> 0.0)) = 0;
+    first: REAL := NVL(coordinates[1], 0.0);
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn derive() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod test_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
+        pub struct Tables {
+            point: HashMap<u64, as_holder!(Point)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {
+            pub fn point_holders(&self) -> &HashMap<u64, as_holder!(Point)> {
+                &self.point
+            }
+        }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.point.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Point)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.point.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Point)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = point)]
+        #[holder(generate_deserialize)]
+        pub struct Point {
+            # [holder (bound = 1 ..= 3)]
+            pub coordinates: Vec<f64>,
+        }
+        impl Point {
+            #[allow(unused_variables)]
+            pub fn dim(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<i64> {
+                runtime::FromValue::from_value(runtime::sizeof(runtime::attribute_of(
+                    self,
+                    id,
+                    tables,
+                    "coordinates",
+                )))
+            }
+            #[allow(unused_variables)]
+            pub fn origin(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<bool> {
+                runtime::FromValue::from_value(runtime::eq(
+                    runtime::sizeof(runtime::query(
+                        runtime::attribute_of(self, id, tables, "coordinates"),
+                        |c: runtime::Value| runtime::ne(c.clone(), runtime::Value::Integer(0)),
+                    )),
+                    runtime::Value::Integer(0),
+                ))
+            }
+            #[allow(unused_variables)]
+            pub fn first(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<f64> {
+                runtime::FromValue::from_value(runtime::nvl(
+                    runtime::index(
+                        runtime::attribute_of(self, id, tables, "coordinates"),
+                        runtime::Value::Integer(1),
+                    ),
+                    runtime::Value::Integer(0),
+                ))
+            }
+        }
+        impl runtime::ToValue for Point {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Point {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.POINT".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "coordinates" => runtime::ToValue::to_value(&self.coordinates),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    "dim" => runtime::ToValue::to_value(&self.dim(id, population)),
+                    "origin" => runtime::ToValue::to_value(&self.origin(id, population)),
+                    "first" => runtime::ToValue::to_value(&self.first(id, population)),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl PointHolder {
+            #[allow(unused_variables)]
+            pub fn dim(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<i64> {
+                runtime::FromValue::from_value(runtime::sizeof(runtime::attribute_of(
+                    self,
+                    id,
+                    tables,
+                    "coordinates",
+                )))
+            }
+            #[allow(unused_variables)]
+            pub fn origin(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<bool> {
+                runtime::FromValue::from_value(runtime::eq(
+                    runtime::sizeof(runtime::query(
+                        runtime::attribute_of(self, id, tables, "coordinates"),
+                        |c: runtime::Value| runtime::ne(c.clone(), runtime::Value::Integer(0)),
+                    )),
+                    runtime::Value::Integer(0),
+                ))
+            }
+            #[allow(unused_variables)]
+            pub fn first(&self, id: Option<u64>, tables: &dyn runtime::Population) -> Option<f64> {
+                runtime::FromValue::from_value(runtime::nvl(
+                    runtime::index(
+                        runtime::attribute_of(self, id, tables, "coordinates"),
+                        runtime::Value::Integer(1),
+                    ),
+                    runtime::Value::Integer(0),
+                ))
+            }
+        }
+        impl runtime::ToValue for PointHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for PointHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.POINT".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "coordinates" => runtime::ToValue::to_value(&self.coordinates),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    "dim" => runtime::ToValue::to_value(&self.dim(id, population)),
+                    "origin" => runtime::ToValue::to_value(&self.origin(id, population)),
+                    "first" => runtime::ToValue::to_value(&self.first(id, population)),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for Point {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for PointHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+    }
+    "###);
+}
@@ -23,13 +23,17 @@ fn entity() {
     let tt = rustfmt(tt);
 
     insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
     pub mod test_schema {
-        use ruststep::{as_holder, derive_more::*, primitive::*, Holder, TableInit};
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
         use std::collections::HashMap;
         #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
         pub struct Tables {
             a: HashMap<u64, as_holder!(A)>,
             b: HashMap<u64, as_holder!(B)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
         }
         impl Tables {
             pub fn a_holders(&self) -> &HashMap<u64, as_holder!(A)> {
@@ -39,6 +43,72 @@ fn entity() {
                 &self.b
             }
         }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.a.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(A)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.b.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(B)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.a.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(A)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.b.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(B)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
         #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
         # [holder (table = Tables)]
         # [holder (field = a)]
@@ -56,6 +126,134 @@ fn entity() {
             #[holder(use_place_holder)]
             pub a: A,
         }
+        impl runtime::ToValue for A {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for A {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.A".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    "y" => runtime::ToValue::to_value(&self.y),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for AHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for AHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.A".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    "y" => runtime::ToValue::to_value(&self.y),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for B {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for B {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.B".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "z" => runtime::ToValue::to_value(&self.z),
+                    "a" => runtime::ToValue::to_value(&self.a),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for BHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for BHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.B".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "z" => runtime::ToValue::to_value(&self.z),
+                    "a" => runtime::ToValue::to_value(&self.a),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for A {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for AHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for B {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for BHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
     }
     "###);
 }
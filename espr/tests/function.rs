@@ -0,0 +1,119 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  FUNCTION sum(values: LIST OF REAL): REAL;
+  LOCAL
+    total: REAL := 0.0;
+  END_LOCAL;
+    REPEAT i := 1 TO SIZEOF(values);
+      total := total + values[i];
+    END_REPEAT;
+    RETURN (total);
+  END_FUNCTION;
+
+  PROCEDURE push(VAR values: LIST OF REAL; item: REAL);
+    INSERT(values, item, SIZEOF(values));
+  END_PROCEDURE;
+END_SCHEMA;
+"#;
+
+#[test]
+fn function() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod test_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
+        pub struct Tables {
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {}
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[allow(
+            unused_variables,
+            unused_mut,
+            unused_assignments,
+            unused_labels,
+            unreachable_code,
+            clippy::never_loop
+        )]
+        pub fn sum(
+            tables: Option<&dyn runtime::Population>,
+            mut values: runtime::Value,
+        ) -> runtime::Value {
+            let mut total = runtime::Value::Integer(0);
+            {
+                let _end = runtime::sizeof(values.clone());
+                let _step = runtime::Value::Integer(1);
+                let mut i = runtime::Value::Integer(1);
+                let mut _first = true;
+                'repeat_1: loop {
+                    if !_first {
+                        i = runtime::add(i.clone(), _step.clone());
+                    }
+                    _first = false;
+                    if !runtime::in_repeat_range(&i, &_end, &_step) {
+                        break 'repeat_1;
+                    }
+                    total = runtime::add(total.clone(), runtime::index(values.clone(), i.clone()));
+                }
+            }
+            return total.clone();
+        }
+        #[allow(
+            unused_variables,
+            unused_mut,
+            unused_assignments,
+            unused_labels,
+            unreachable_code,
+            clippy::never_loop
+        )]
+        pub fn push(
+            tables: Option<&dyn runtime::Population>,
+            values: &mut runtime::Value,
+            mut item: runtime::Value,
+        ) {
+            {
+                let _arg1 = item.clone();
+                let _arg2 = runtime::sizeof(values.clone());
+                runtime::insert(&mut *values, _arg1, _arg2);
+            }
+        }
+    }
+    "###);
+}
@@ -0,0 +1,184 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  ENTITY door;
+    name: STRING;
+  END_ENTITY;
+
+  RULE single_front_door FOR (door);
+  WHERE
+    wr1: SIZEOF(QUERY(d <* door | d.name = 'front')) <= 1;
+  END_RULE;
+END_SCHEMA;
+"#;
+
+#[test]
+fn global_rule() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod test_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
+        pub struct Tables {
+            door: HashMap<u64, as_holder!(Door)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {
+            pub fn door_holders(&self) -> &HashMap<u64, as_holder!(Door)> {
+                &self.door
+            }
+        }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.door.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Door)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                Self::check_rule_single_front_door(&population, &mut violations);
+                Ok(violations)
+            }
+            #[allow(unused_variables, unused_mut, unused_assignments, unused_labels)]
+            fn check_rule_single_front_door(
+                tables: &runtime::Snapshot<Tables>,
+                violations: &mut Vec<runtime::RuleViolation>,
+            ) {
+                let door = runtime::Population::instances_of(tables, "TEST_SCHEMA.DOOR");
+                if runtime::violated(runtime::le(
+                    runtime::sizeof(runtime::query(door.clone(), |d: runtime::Value| {
+                        runtime::eq(
+                            runtime::attribute_in(Some(tables), d.clone(), "name"),
+                            runtime::Value::String("front".to_string()),
+                        )
+                    })),
+                    runtime::Value::Integer(1),
+                )) {
+                    violations.push(runtime::RuleViolation {
+                        id: None,
+                        declared_in: "TEST_SCHEMA.SINGLE_FRONT_DOOR",
+                        label: "wr1",
+                        evaluated: true,
+                    });
+                }
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.door.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Door)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = door)]
+        #[holder(generate_deserialize)]
+        pub struct Door {
+            pub name: String,
+        }
+        impl runtime::ToValue for Door {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Door {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.DOOR".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for DoorHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for DoorHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.DOOR".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for Door {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for DoorHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+    }
+    "###);
+}
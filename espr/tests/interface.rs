@@ -0,0 +1,322 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const GEOMETRY: &str = r#"
+SCHEMA geometry_schema;
+  ENTITY point;
+    x, y: REAL;
+  END_ENTITY;
+
+  TYPE label = STRING;
+  END_TYPE;
+END_SCHEMA;
+"#;
+
+const TOPOLOGY: &str = r#"
+SCHEMA topology_schema;
+  USE FROM geometry_schema (point AS vertex_point);
+  REFERENCE FROM geometry_schema (label);
+
+  ENTITY edge;
+    name: label;
+    head: vertex_point;
+    tail: vertex_point;
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn interface() {
+    // Each schema is read from its own file
+    let mut st = SyntaxTree::parse(TOPOLOGY).unwrap();
+    st.append(SyntaxTree::parse(GEOMETRY).unwrap());
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod topology_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "topology_schema")]
+        pub struct Tables {
+            edge: HashMap<u64, as_holder!(Edge)>,
+            point: HashMap<u64, as_holder!(Point)>,
+            label: HashMap<u64, as_holder!(Label)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {
+            pub fn edge_holders(&self) -> &HashMap<u64, as_holder!(Edge)> {
+                &self.edge
+            }
+            pub fn point_holders(&self) -> &HashMap<u64, as_holder!(Point)> {
+                &self.point
+            }
+            pub fn label_holders(&self) -> &HashMap<u64, as_holder!(Label)> {
+                &self.label
+            }
+        }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.edge.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Edge)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.point.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Point)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.edge.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Edge)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.point.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Point)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[derive(
+            Clone, Debug, PartialEq, AsRef, Deref, DerefMut, Into, From, :: ruststep_derive :: Holder,
+        )]
+        # [holder (table = Tables)]
+        # [holder (field = label)]
+        #[holder(generate_deserialize)]
+        pub struct Label(pub String);
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = edge)]
+        #[holder(generate_deserialize)]
+        pub struct Edge {
+            #[holder(use_place_holder)]
+            pub name: Label,
+            #[holder(use_place_holder)]
+            pub head: Point,
+            #[holder(use_place_holder)]
+            pub tail: Point,
+        }
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = point)]
+        #[holder(generate_deserialize)]
+        pub struct Point {
+            pub x: f64,
+            pub y: f64,
+        }
+        impl runtime::ToValue for Edge {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Edge {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TOPOLOGY_SCHEMA.EDGE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    "head" => runtime::ToValue::to_value(&self.head),
+                    "tail" => runtime::ToValue::to_value(&self.tail),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for EdgeHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for EdgeHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TOPOLOGY_SCHEMA.EDGE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "name" => runtime::ToValue::to_value(&self.name),
+                    "head" => runtime::ToValue::to_value(&self.head),
+                    "tail" => runtime::ToValue::to_value(&self.tail),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for Point {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Point {
+            fn type_names(&self) -> Vec<String> {
+                vec!["GEOMETRY_SCHEMA.POINT".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    "y" => runtime::ToValue::to_value(&self.y),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for PointHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for PointHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["GEOMETRY_SCHEMA.POINT".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    "y" => runtime::ToValue::to_value(&self.y),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for Label {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for LabelHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::Validate<Tables> for Edge {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.name, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for EdgeHolder {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.name, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for Point {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for PointHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Label {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for LabelHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+    }
+    pub mod geometry_schema {
+        pub use super::topology_schema::{Label, LabelHolder, Point, PointHolder};
+    }
+    "###);
+}
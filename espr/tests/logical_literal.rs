@@ -20,13 +20,17 @@ fn logical_literal() {
     let tt = rustfmt(tt);
 
     insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
     pub mod IFC4X3_DEV_6a23ae8 {
-        use ruststep::{as_holder, derive_more::*, primitive::*, Holder, TableInit};
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
         use std::collections::HashMap;
         #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "IFC4X3_DEV_6a23ae8")]
         pub struct Tables {
             IfcGeometricRepresentationContext:
                 HashMap<u64, as_holder!(IfcGeometricRepresentationContext)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
         }
         impl Tables {
             pub fn IfcGeometricRepresentationContext_holders(
@@ -35,6 +39,55 @@ fn logical_literal() {
                 &self.IfcGeometricRepresentationContext
             }
         }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.IfcGeometricRepresentationContext.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder =
+                        resolver.get_shared::<as_holder!(IfcGeometricRepresentationContext)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.IfcGeometricRepresentationContext.keys() {
+                    if let Some(holder) =
+                        population.instance_of::<as_holder!(IfcGeometricRepresentationContext)>(*id)
+                    {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
         #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
         # [holder (table = Tables)]
         # [holder (field = IfcGeometricRepresentationContext)]
@@ -42,6 +95,116 @@ fn logical_literal() {
         pub struct IfcGeometricRepresentationContext {
             pub TrueNorth: Option<bool>,
         }
+        impl runtime::ToValue for IfcGeometricRepresentationContext {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for IfcGeometricRepresentationContext {
+            fn type_names(&self) -> Vec<String> {
+                vec!["IFC4X3_DEV_6A23AE8.IFCGEOMETRICREPRESENTATIONCONTEXT".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "TrueNorth" => runtime::ToValue::to_value(&self.TrueNorth),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for IfcGeometricRepresentationContextHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for IfcGeometricRepresentationContextHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["IFC4X3_DEV_6A23AE8.IFCGEOMETRICREPRESENTATIONCONTEXT".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "TrueNorth" => runtime::ToValue::to_value(&self.TrueNorth),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl IfcGeometricRepresentationContext {
+            #[allow(unused_variables)]
+            fn check_where_rules(
+                this: &runtime::Value,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+                violations: &mut Vec<runtime::RuleViolation>,
+            ) {
+                if runtime::violated(runtime::or(
+                    runtime::not(runtime::exists(runtime::attribute_in(
+                        Some(tables),
+                        this.clone(),
+                        "TrueNorth",
+                    ))),
+                    runtime::eq(
+                        runtime::hiindex(runtime::attribute_in(
+                            Some(tables),
+                            runtime::attribute_in(Some(tables), this.clone(), "TrueNorth"),
+                            "DirectionRatios",
+                        )),
+                        runtime::Value::Integer(2),
+                    ),
+                )) {
+                    violations.push(runtime::RuleViolation {
+                        id,
+                        declared_in: "IFC4X3_DEV_6A23AE8.IFCGEOMETRICREPRESENTATIONCONTEXT",
+                        label: "North2D",
+                        evaluated: true,
+                    });
+                }
+            }
+        }
+        impl runtime::Validate<Tables> for IfcGeometricRepresentationContext {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let this = runtime::self_value(self, id, tables);
+                let mut violations = Vec::new();
+                IfcGeometricRepresentationContext::check_where_rules(
+                    &this,
+                    id,
+                    tables,
+                    &mut violations,
+                );
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for IfcGeometricRepresentationContextHolder {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let this = runtime::self_value(self, id, tables);
+                let mut violations = Vec::new();
+                IfcGeometricRepresentationContext::check_where_rules(
+                    &this,
+                    id,
+                    tables,
+                    &mut violations,
+                );
+                violations
+            }
+        }
     }
     "###);
 }
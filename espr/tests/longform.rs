@@ -0,0 +1,135 @@
+use espr::{ast::SyntaxTree, longform, printer::Printer};
+
+const GEOMETRY: &str = r#"
+SCHEMA geometry_schema;
+  CONSTANT
+    origin : cartesian_point := cartesian_point(0.0, 0.0);
+  END_CONSTANT;
+
+  TYPE length_measure = REAL;
+  END_TYPE;
+
+  TYPE geometric_item = SELECT (point, vector);
+  END_TYPE;
+
+  ENTITY point
+    SUPERTYPE OF (ONEOF (cartesian_point, polar_point));
+  END_ENTITY;
+
+  ENTITY cartesian_point
+    SUBTYPE OF (point);
+    x, y : length_measure;
+  WHERE
+    wr1 : 'GEOMETRY_SCHEMA.CARTESIAN_POINT' IN TYPEOF(SELF);
+  END_ENTITY;
+
+  ENTITY polar_point
+    SUBTYPE OF (point);
+    r : length_measure;
+  END_ENTITY;
+
+  ENTITY vector;
+    magnitude : length_measure;
+  END_ENTITY;
+
+  FUNCTION norm (p : cartesian_point) : length_measure;
+    LOCAL
+      point : REAL := p.x ** 2 + p.y ** 2;
+    END_LOCAL;
+    RETURN (SQRT(point));
+  END_FUNCTION;
+
+  RULE unique_origin FOR (cartesian_point);
+  WHERE
+    wr1 : SIZEOF(QUERY(p <* cartesian_point | norm(p) = 0.0)) <= 1;
+  END_RULE;
+
+  RULE no_vector FOR (vector);
+  WHERE
+    wr1 : SIZEOF(vector) = 0;
+  END_RULE;
+END_SCHEMA;
+"#;
+
+const TOPOLOGY: &str = r#"
+SCHEMA topology_schema;
+  USE FROM geometry_schema (cartesian_point AS vertex_point);
+  REFERENCE FROM geometry_schema (norm, geometric_item);
+
+  ENTITY edge;
+    head, tail : vertex_point;
+    item : geometric_item;
+  WHERE
+    wr1 : norm(head) > 0.0;
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn longform() {
+    let mut st = SyntaxTree::parse(TOPOLOGY).unwrap();
+    st.append(SyntaxTree::parse(GEOMETRY).unwrap());
+    let schema = longform::long_form(&st, "topology_schema").unwrap();
+
+    let mut printer = Printer::default();
+    printer.schema(&schema);
+    let printed = printer.finish();
+
+    // Long form is a valid EXPRESS schema
+    let reparsed = SyntaxTree::parse(&printed).unwrap();
+    assert_eq!(reparsed.schemas, vec![schema]);
+
+    insta::assert_snapshot!(printed, @r###"
+    SCHEMA topology_schema;
+
+      TYPE geometric_item = SELECT (point);
+      END_TYPE;
+
+      TYPE length_measure = REAL;
+      END_TYPE;
+
+      ENTITY edge;
+        head : vertex_point;
+        tail : vertex_point;
+        item : geometric_item;
+      WHERE
+        wr1 : norm(head) > 0;
+      END_ENTITY;
+
+      ENTITY vertex_point
+        SUBTYPE OF (point);
+        x : length_measure;
+        y : length_measure;
+      WHERE
+        wr1 : 'TOPOLOGY_SCHEMA.VERTEX_POINT' IN TYPEOF(SELF);
+      END_ENTITY;
+
+      ENTITY point
+        SUPERTYPE OF (ONEOF (vertex_point));
+      END_ENTITY;
+
+      FUNCTION norm (p : vertex_point) : length_measure;
+        LOCAL
+          point : REAL := p.x ** 2 + p.y ** 2;
+        END_LOCAL;
+        RETURN (SQRT(point));
+      END_FUNCTION;
+
+      RULE unique_origin FOR (vertex_point);
+      WHERE
+        wr1 : SIZEOF(QUERY(p <* vertex_point | norm(p) = 0)) <= 1;
+      END_RULE;
+
+    END_SCHEMA;
+    "###);
+}
+
+#[test]
+fn unresolved_schema() {
+    let st = SyntaxTree::parse(TOPOLOGY).unwrap();
+    assert!(matches!(
+        longform::long_form(&st, "topology_schema"),
+        Err(longform::LongFormError::Unresolved { name, required_by })
+            if name == "geometry_schema" && required_by == "topology_schema"
+    ));
+}
@@ -0,0 +1,195 @@
+use espr::{
+    ast::{Remark, RemarkPosition, SyntaxTree},
+    printer,
+};
+use std::{fs, path::*};
+
+const EXPRESS: &str = r#"
+(* Remark before schema
+   in two lines *)
+SCHEMA test_schema;
+  -- remark on test_schema
+  ENTITY point; -- tail remark with `*)`
+    x, y : REAL;
+  END_ENTITY;
+  TYPE label = STRING;
+  END_TYPE;
+  (*"test_schema.point" Tagged remark on `point` *)
+END_SCHEMA;
+-- remark after test_schema
+
+SCHEMA another_schema;
+  ENTITY line;
+    -- remark in line
+    length_ : REAL;
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn remarks() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let printed = printer::to_string(&st);
+    insta::assert_snapshot!(printed, @r###"
+    (* Remark before schema
+       in two lines *)
+
+    SCHEMA test_schema;
+      -- remark on test_schema
+
+      TYPE label = STRING;
+      END_TYPE;
+      --"test_schema.point" Tagged remark on `point`
+
+      ENTITY point;
+        x : REAL;
+        y : REAL;
+      END_ENTITY;
+      -- tail remark with `*)`
+
+    END_SCHEMA;
+    -- remark after test_schema
+
+    SCHEMA another_schema;
+
+      ENTITY line;
+        length_ : REAL;
+      END_ENTITY;
+      -- remark in line
+
+    END_SCHEMA;
+    "###);
+
+    let reparsed = SyntaxTree::parse(&printed).unwrap();
+    assert_eq!(reparsed.schemas, st.schemas);
+    for remark in &st.remarks {
+        assert!(reparsed.remarks.contains(remark), "{:?}", remark);
+    }
+    assert_eq!(reparsed.remarks.len(), st.remarks.len());
+}
+
+#[test]
+fn remark_closing_embedded() {
+    let mut st = SyntaxTree::parse("SCHEMA s; END_SCHEMA;").unwrap();
+    st.remarks.push(Remark {
+        tag: None,
+        remark: "`*)` closes\n*) an embedded remark".to_string(),
+        position: RemarkPosition::Head,
+    });
+    let printed = printer::to_string(&st);
+    insta::assert_snapshot!(printed, @r###"
+    -- `*)` closes
+    -- *) an embedded remark
+
+    SCHEMA s;
+
+    END_SCHEMA;
+    "###);
+    let reparsed = SyntaxTree::parse(&printed).unwrap();
+    let remarks: Vec<&str> = reparsed.remarks.iter().map(|r| r.remark.as_str()).collect();
+    assert_eq!(remarks, ["`*)` closes", "*) an embedded remark"]);
+}
+
+/// Schemas in `schemas/` directory at the top of this repository
+fn schemas_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../schemas")
+}
+
+/// Check that printed EXPRESS is parsed into the same syntax tree,
+/// and printed again into the same string
+fn round_trip(path: &Path) -> Result<(), String> {
+    // A few files are written in Latin-1
+    let src = String::from_utf8_lossy(&fs::read(path).unwrap()).to_string();
+    let st = SyntaxTree::parse(&src).map_err(|e| match e.errors.first() {
+        Some((code, _kind)) => format!("not parsed at `{}`", code.lines().next().unwrap_or("")),
+        None => "not parsed".to_string(),
+    })?;
+    let printed = printer::to_string(&st);
+    let reparsed = SyntaxTree::parse(&printed).map_err(|_| "printed EXPRESS is not parsed")?;
+    if reparsed.schemas != st.schemas {
+        return Err("schemas are changed".to_string());
+    }
+    // Remarks are reordered as declarations are
+    let sorted = |st: &SyntaxTree| {
+        let mut remarks: Vec<String> = st.remarks.iter().map(|r| format!("{:?}", r)).collect();
+        remarks.sort();
+        remarks
+    };
+    if sorted(&reparsed) != sorted(&st) {
+        return Err("remarks are changed".to_string());
+    }
+    if printer::to_string(&reparsed) != printed {
+        return Err("printed again into another string".to_string());
+    }
+    Ok(())
+}
+
+#[test]
+fn round_trip_ap201() {
+    round_trip(&schemas_dir().join("APs/10303-201-aim-long.exp")).unwrap();
+}
+
+#[test]
+fn round_trip_ap203() {
+    round_trip(&schemas_dir().join("APs/10303-203-aim-long.exp")).unwrap();
+}
+
+fn exp_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            exp_files(&path, files);
+        } else if path.extension().map_or(false, |ext| ext == "exp") {
+            files.push(path);
+        }
+    }
+}
+
+/// Files in `schemas/` which espr cannot parse yet
+const UNPARSED: &str = include_str!("unparsed_schemas.txt");
+
+/// Every schema in `schemas/` except [UNPARSED]
+///
+/// This fails also when a file in [UNPARSED] is parsed, to keep the list up to date.
+#[test]
+fn round_trip_all() {
+    let mut files = Vec::new();
+    exp_files(&schemas_dir(), &mut files);
+    files.sort();
+
+    // Split into a few threads since this takes a minute without `--release`
+    let chunks: Vec<Vec<PathBuf>> = files
+        .chunks(files.len() / 8 + 1)
+        .map(<[_]>::to_vec)
+        .collect();
+    let handles: Vec<_> = chunks
+        .into_iter()
+        .map(|chunk| {
+            std::thread::spawn(move || {
+                chunk
+                    .into_iter()
+                    .filter_map(|path| {
+                        let name = path
+                            .strip_prefix(schemas_dir())
+                            .unwrap()
+                            .to_string_lossy()
+                            .replace('\\', "/");
+                        let unparsed = UNPARSED.lines().any(|line| line == name);
+                        match (round_trip(&path), unparsed) {
+                            (Ok(()), false) => None,
+                            (Ok(()), true) => Some(format!("{}: parsed", name)),
+                            (Err(e), false) => Some(format!("{}: {}", name, e)),
+                            (Err(e), true) if e.starts_with("not parsed") => None,
+                            (Err(e), true) => Some(format!("{}: {}", name, e)),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let failed: Vec<String> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    assert!(failed.is_empty(), "{}", failed.join("\n"));
+}
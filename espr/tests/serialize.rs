@@ -0,0 +1,57 @@
+#![cfg(feature = "serialize")]
+
+use espr::{ast::SyntaxTree, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  ENTITY person SUPERTYPE OF (employee ANDOR student);
+    name: STRING;
+  END_ENTITY;
+
+  ENTITY employee SUBTYPE OF (person);
+  END_ENTITY;
+
+  ENTITY student SUBTYPE OF (person);
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn ast() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let json = serde_json::to_value(&st).unwrap();
+    let entities = &json["schemas"][0]["entities"];
+    assert_eq!(json["schemas"][0]["name"], "test_schema");
+    assert_eq!(entities[0]["name"], "person");
+    assert_eq!(
+        entities[0]["attributes"][0]["name"],
+        serde_json::json!({ "Reference": "name" })
+    );
+}
+
+#[test]
+fn ir() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let json = serde_json::to_value(&ir).unwrap();
+    let person = &json["schemas"][0]["entities"][0];
+    assert_eq!(person["name"], "person");
+    assert_eq!(
+        person["attributes"][0]["ty"],
+        serde_json::json!({ "SimpleType": { "String_": { "width_spec": null } } })
+    );
+    assert_eq!(
+        person["complex_constraints"][0]["name"],
+        "employee_and_student"
+    );
+
+    let employee = &json["schemas"][0]["entities"][1];
+    assert_eq!(
+        employee["supertypes"][0]["Entity"],
+        serde_json::json!({
+            "name": "person",
+            "scope": [["Schema", "test_schema"]],
+            "is_supertype": true,
+        })
+    );
+}
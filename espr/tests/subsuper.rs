@@ -27,14 +27,18 @@ fn subsuper() {
     let tt = rustfmt(tt);
 
     insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
     pub mod test_schema {
-        use ruststep::{as_holder, derive_more::*, primitive::*, Holder, TableInit};
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
         use std::collections::HashMap;
         #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
         pub struct Tables {
             base: HashMap<u64, as_holder!(Base)>,
             sub: HashMap<u64, as_holder!(Sub)>,
             subsub: HashMap<u64, as_holder!(Subsub)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
         }
         impl Tables {
             pub fn base_holders(&self) -> &HashMap<u64, as_holder!(Base)> {
@@ -47,10 +51,97 @@ fn subsuper() {
                 &self.subsub
             }
         }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.base.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Base)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.sub.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Sub)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                for id in self.subsub.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Subsub)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.base.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Base)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.sub.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Sub)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                for id in self.subsub.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Subsub)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
         #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
         # [holder (table = Tables)]
         # [holder (field = base)]
         #[holder(generate_deserialize)]
+        #[holder(instantiable(Sub))]
         pub struct Base {
             pub x: f64,
         }
@@ -87,12 +178,14 @@ fn subsuper() {
         # [holder (table = Tables)]
         # [holder (field = sub)]
         #[holder(generate_deserialize)]
+        #[holder(instantiable(Subsub))]
         pub struct Sub {
             #[as_ref]
             #[as_mut]
             #[deref]
             #[deref_mut]
             #[holder(use_place_holder)]
+            #[holder(supertype)]
             pub base: Base,
             pub y: f64,
         }
@@ -143,9 +236,328 @@ fn subsuper() {
             #[deref]
             #[deref_mut]
             #[holder(use_place_holder)]
+            #[holder(supertype)]
             pub sub: Sub,
             pub z: f64,
         }
+        impl runtime::ToValue for Base {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Base {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.BASE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for BaseHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for BaseHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.BASE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "x" => runtime::ToValue::to_value(&self.x),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for BaseAny {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    BaseAny::Base(x) => runtime::ToValue::to_value(&**x),
+                    BaseAny::Sub(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for BaseAnyHolder {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    BaseAnyHolder::Base(x) => runtime::ToValue::to_value(&**x),
+                    BaseAnyHolder::Sub(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for Sub {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Sub {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUB".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.base));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "y" => runtime::ToValue::to_value(&self.y),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.base, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.base, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for SubHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for SubHolder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUB".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.base));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "y" => runtime::ToValue::to_value(&self.y),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.base, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.base, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for SubAny {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    SubAny::Sub(x) => runtime::ToValue::to_value(&**x),
+                    SubAny::Subsub(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for SubAnyHolder {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    SubAnyHolder::Sub(x) => runtime::ToValue::to_value(&**x),
+                    SubAnyHolder::Subsub(x) => runtime::ToValue::to_value(&**x),
+                }
+            }
+        }
+        impl runtime::ToValue for Subsub {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Subsub {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUBSUB".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.sub));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "z" => runtime::ToValue::to_value(&self.z),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.sub, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.sub, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for SubsubHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for SubsubHolder {
+            fn type_names(&self) -> Vec<String> {
+                let mut names = vec!["TEST_SCHEMA.SUBSUB".to_string()];
+                names.extend(runtime::EntityValue::type_names(&self.sub));
+                names
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "z" => runtime::ToValue::to_value(&self.z),
+                    _ => {
+                        let value = runtime::EntityValue::attribute(&self.sub, name);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn derived_attribute(
+                &self,
+                name: &str,
+                id: Option<u64>,
+                population: &dyn runtime::Population,
+            ) -> runtime::Value {
+                match name {
+                    _ => {
+                        let value =
+                            runtime::EntityValue::derived_attribute(&self.sub, name, id, population);
+                        if !value.is_indeterminate() {
+                            return value;
+                        }
+                        runtime::Value::Indeterminate
+                    }
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::Validate<Tables> for Base {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for BaseHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Sub {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for SubHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for Subsub {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for SubsubHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
     }
     "###);
 }
@@ -36,15 +36,19 @@ fn type_decl() {
     let tt = rustfmt(tt);
 
     insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
     pub mod test_schema {
-        use ruststep::{as_holder, derive_more::*, primitive::*, Holder, TableInit};
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
         use std::collections::HashMap;
         #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
         pub struct Tables {
             e: HashMap<u64, as_holder!(E)>,
             a: HashMap<u64, as_holder!(A)>,
             c: HashMap<u64, as_holder!(C)>,
             d: HashMap<u64, as_holder!(D)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
         }
         impl Tables {
             pub fn e_holders(&self) -> &HashMap<u64, as_holder!(E)> {
@@ -60,6 +64,52 @@ fn type_decl() {
                 &self.d
             }
         }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.e.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(E)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.e.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(E)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
         #[derive(
             Clone, Debug, PartialEq, AsRef, Deref, DerefMut, Into, From, :: ruststep_derive :: Holder,
         )]
@@ -67,7 +117,7 @@ fn type_decl() {
         # [holder (field = a)]
         #[holder(generate_deserialize)]
         pub struct A(pub String);
-        #[derive(Debug, Clone, PartialEq, :: serde :: Deserialize)]
+        #[derive(Debug, Clone, PartialEq, :: serde :: Deserialize, :: serde :: Serialize)]
         pub enum B {
             Are,
             Sore,
@@ -100,6 +150,198 @@ fn type_decl() {
             #[holder(use_place_holder)]
             pub d: D,
         }
+        impl runtime::ToValue for E {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for E {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.E".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "a" => runtime::ToValue::to_value(&self.a),
+                    "b" => runtime::ToValue::to_value(&self.b),
+                    "c" => runtime::ToValue::to_value(&self.c),
+                    "d" => runtime::ToValue::to_value(&self.d),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for EHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for EHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.E".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "a" => runtime::ToValue::to_value(&self.a),
+                    "b" => runtime::ToValue::to_value(&self.b),
+                    "c" => runtime::ToValue::to_value(&self.c),
+                    "d" => runtime::ToValue::to_value(&self.d),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for A {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for AHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for B {
+            fn to_value(&self) -> runtime::Value {
+                match self {
+                    B::Are => runtime::Value::Enumeration("are".to_string()),
+                    B::Sore => runtime::Value::Enumeration("sore".to_string()),
+                    B::Dore => runtime::Value::Enumeration("dore".to_string()),
+                }
+            }
+        }
+        impl runtime::ToValue for C {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for CHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for D {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for DHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::Validate<Tables> for E {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.a, id, tables));
+                violations.extend(runtime::Validate::validate_rules(&self.b, id, tables));
+                violations.extend(runtime::Validate::validate_rules(&self.c, id, tables));
+                violations.extend(runtime::Validate::validate_rules(&self.d, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for EHolder {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.a, id, tables));
+                violations.extend(runtime::Validate::validate_rules(&self.b, id, tables));
+                violations.extend(runtime::Validate::validate_rules(&self.c, id, tables));
+                violations.extend(runtime::Validate::validate_rules(&self.d, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for A {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for AHolder {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for B {
+            fn validate_rules(
+                &self,
+                _id: Option<u64>,
+                _tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                Vec::new()
+            }
+        }
+        impl runtime::Validate<Tables> for C {
+            #[allow(unused_variables)]
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.0, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for CHolder {
+            #[allow(unused_variables)]
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.0, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for D {
+            #[allow(unused_variables)]
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.0, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for DHolder {
+            #[allow(unused_variables)]
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let mut violations = Vec::new();
+                violations.extend(runtime::Validate::validate_rules(&self.0, id, tables));
+                violations
+            }
+        }
     }
     "###);
 }
@@ -0,0 +1,253 @@
+use espr::{ast::SyntaxTree, codegen::rust::*, ir::IR};
+
+const EXPRESS: &str = r#"
+SCHEMA test_schema;
+  TYPE positive_length_measure = REAL;
+  WHERE
+    wr1: SELF > 0.0;
+  END_TYPE;
+
+  ENTITY circle;
+    radius: positive_length_measure;
+  WHERE
+    wr1: radius < 100.0;
+  END_ENTITY;
+END_SCHEMA;
+"#;
+
+#[test]
+fn where_rule() {
+    let st = SyntaxTree::parse(EXPRESS).unwrap();
+    let ir = IR::from_syntax_tree(&st).unwrap();
+    let tt = ir.to_token_stream(CratePrefix::External).to_string();
+
+    let tt = rustfmt(tt);
+
+    insta::assert_snapshot!(tt, @r###"
+    #[allow(clippy::all)]
+    pub mod test_schema {
+        use ruststep::{as_holder, derive_more::*, primitive::*, runtime, Holder, TableInit};
+        use std::collections::HashMap;
+        #[derive(Debug, Clone, PartialEq, Default, TableInit)]
+        #[table(schema = "test_schema")]
+        pub struct Tables {
+            circle: HashMap<u64, as_holder!(Circle)>,
+            positive_length_measure: HashMap<u64, as_holder!(PositiveLengthMeasure)>,
+            names: ::ruststep::tables::Names,
+            references: ::ruststep::tables::ReferenceIndex,
+        }
+        impl Tables {
+            pub fn circle_holders(&self) -> &HashMap<u64, as_holder!(Circle)> {
+                &self.circle
+            }
+            pub fn positive_length_measure_holders(
+                &self,
+            ) -> &HashMap<u64, as_holder!(PositiveLengthMeasure)> {
+                &self.positive_length_measure
+            }
+        }
+        impl Tables {
+            #[doc = r" Entity instances as `runtime::Value`s of the holders resolved by `Resolver`,"]
+            #[doc = r" which share the instances they refer and are shared by the rules in a validation pass"]
+            pub fn population(&self) -> ::ruststep::error::Result<runtime::Snapshot<'_, Tables>> {
+                let mut resolver = ::ruststep::tables::Resolver::new(self);
+                let mut instances = std::collections::BTreeMap::new();
+                let complex: std::collections::HashSet<u64> = instances.keys().cloned().collect();
+                for id in self.circle.keys() {
+                    if complex.contains(id) {
+                        continue;
+                    }
+                    let holder = resolver.get_shared::<as_holder!(Circle)>(*id)?;
+                    let value = match instances.remove(id) {
+                        Some(other) => runtime::complex_instance(other, runtime::Value::Entity(holder)),
+                        None => runtime::Value::Entity(holder),
+                    };
+                    instances.insert(*id, value);
+                }
+                let instances = instances
+                    .into_iter()
+                    .map(|(id, instance)| (id, runtime::identified(id, instance)))
+                    .collect();
+                Ok(runtime::Snapshot::new(self, instances))
+            }
+            #[doc = r" Check the global rules declared by `RULE` over all entity instances"]
+            pub fn check_global_rules(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                Ok(Vec::new())
+            }
+            #[doc = r" Check the local `WHERE` rules of all entity instances"]
+            pub fn validate(&self) -> ::ruststep::error::Result<Vec<runtime::RuleViolation>> {
+                let population = self.population()?;
+                let mut violations = Vec::new();
+                for id in self.circle.keys() {
+                    if let Some(holder) = population.instance_of::<as_holder!(Circle)>(*id) {
+                        violations.extend(runtime::Validate::validate_rules(
+                            holder,
+                            Some(*id),
+                            &population,
+                        ));
+                    }
+                }
+                violations.sort();
+                violations.dedup();
+                Ok(violations)
+            }
+        }
+        #[derive(
+            Clone, Debug, PartialEq, AsRef, Deref, DerefMut, Into, From, :: ruststep_derive :: Holder,
+        )]
+        # [holder (table = Tables)]
+        # [holder (field = positive_length_measure)]
+        #[holder(generate_deserialize)]
+        pub struct PositiveLengthMeasure(pub f64);
+        #[derive(Debug, Clone, PartialEq, :: derive_new :: new, Holder)]
+        # [holder (table = Tables)]
+        # [holder (field = circle)]
+        #[holder(generate_deserialize)]
+        pub struct Circle {
+            #[holder(use_place_holder)]
+            pub radius: PositiveLengthMeasure,
+        }
+        impl runtime::ToValue for Circle {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for Circle {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.CIRCLE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "radius" => runtime::ToValue::to_value(&self.radius),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for CircleHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::Value::entity(self.clone())
+            }
+        }
+        impl runtime::EntityValue for CircleHolder {
+            fn type_names(&self) -> Vec<String> {
+                vec!["TEST_SCHEMA.CIRCLE".to_string()]
+            }
+            fn attribute(&self, name: &str) -> runtime::Value {
+                match name {
+                    "radius" => runtime::ToValue::to_value(&self.radius),
+                    _ => runtime::Value::Indeterminate,
+                }
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn value_eq(&self, other: &dyn runtime::EntityValue) -> bool {
+                other.as_any().downcast_ref::<Self>() == Some(self)
+            }
+        }
+        impl runtime::ToValue for PositiveLengthMeasure {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl runtime::ToValue for PositiveLengthMeasureHolder {
+            fn to_value(&self) -> runtime::Value {
+                runtime::ToValue::to_value(&self.0)
+            }
+        }
+        impl Circle {
+            #[allow(unused_variables)]
+            fn check_where_rules(
+                this: &runtime::Value,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+                violations: &mut Vec<runtime::RuleViolation>,
+            ) {
+                if runtime::violated(runtime::lt(
+                    runtime::attribute_in(Some(tables), this.clone(), "radius"),
+                    runtime::Value::Integer(100),
+                )) {
+                    violations.push(runtime::RuleViolation {
+                        id,
+                        declared_in: "TEST_SCHEMA.CIRCLE",
+                        label: "wr1",
+                        evaluated: true,
+                    });
+                }
+            }
+        }
+        impl runtime::Validate<Tables> for Circle {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let this = runtime::self_value(self, id, tables);
+                let mut violations = Vec::new();
+                Circle::check_where_rules(&this, id, tables, &mut violations);
+                violations.extend(runtime::Validate::validate_rules(&self.radius, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for CircleHolder {
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let this = runtime::self_value(self, id, tables);
+                let mut violations = Vec::new();
+                Circle::check_where_rules(&this, id, tables, &mut violations);
+                violations.extend(runtime::Validate::validate_rules(&self.radius, id, tables));
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for PositiveLengthMeasure {
+            #[allow(unused_variables)]
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let this = &runtime::ToValue::to_value(self);
+                let mut violations = Vec::new();
+                if runtime::violated(runtime::gt(this.clone(), runtime::Value::Integer(0))) {
+                    violations.push(runtime::RuleViolation {
+                        id,
+                        declared_in: "TEST_SCHEMA.POSITIVE_LENGTH_MEASURE",
+                        label: "wr1",
+                        evaluated: true,
+                    });
+                }
+                violations
+            }
+        }
+        impl runtime::Validate<Tables> for PositiveLengthMeasureHolder {
+            #[allow(unused_variables)]
+            fn validate_rules(
+                &self,
+                id: Option<u64>,
+                tables: &runtime::Snapshot<Tables>,
+            ) -> Vec<runtime::RuleViolation> {
+                let this = &runtime::ToValue::to_value(self);
+                let mut violations = Vec::new();
+                if runtime::violated(runtime::gt(this.clone(), runtime::Value::Integer(0))) {
+                    violations.push(runtime::RuleViolation {
+                        id,
+                        declared_in: "TEST_SCHEMA.POSITIVE_LENGTH_MEASURE",
+                        label: "wr1",
+                        evaluated: true,
+                    });
+                }
+                violations
+            }
+        }
+    }
+    "###);
+}
@@ -3,6 +3,7 @@ use proc_macro2::{Span, TokenStream as TokenStream2};
 use proc_macro_error::OptionExt;
 use quote::quote;
 use std::convert::*;
+use syn::ext::IdentExt;
 
 use super::*;
 
@@ -22,6 +23,8 @@ pub fn derive_holder(ident: &syn::Ident, st: &syn::DataStruct, attr: &HolderAttr
     let def_holder_tt = def_holder(ident, st);
     let impl_holder_tt = impl_holder(ident, attr, st);
     let impl_entity_table_tt = impl_entity_table(ident, attr);
+    let impl_serialize_tt = impl_serialize(ident, &name, st, attr.complex);
+    let impl_holder_serialize_tt = impl_serialize(&holder_ident, &name, st, attr.complex);
     if attr.generate_deserialize {
         let def_visitor_tt = def_visitor(&holder_ident, &name, st);
         let impl_deserialize_tt = impl_deserialize(&holder_ident, &name, st);
@@ -30,6 +33,8 @@ pub fn derive_holder(ident: &syn::Ident, st: &syn::DataStruct, attr: &HolderAttr
             #def_holder_tt
             #impl_holder_tt
             #impl_entity_table_tt
+            #impl_serialize_tt
+            #impl_holder_serialize_tt
             #def_visitor_tt
             #impl_deserialize_tt
             #impl_with_visitor_tt
@@ -39,6 +44,8 @@ pub fn derive_holder(ident: &syn::Ident, st: &syn::DataStruct, attr: &HolderAttr
             #def_holder_tt
             #impl_holder_tt
             #impl_entity_table_tt
+            #impl_serialize_tt
+            #impl_holder_serialize_tt
         }
     }
 }
@@ -48,19 +55,42 @@ fn table_arg() -> syn::Ident {
     syn::Ident::new("table", Span::call_site())
 }
 
+/// This must be same between codegens
+pub fn resolver_arg() -> syn::Ident {
+    syn::Ident::new("resolver", Span::call_site())
+}
+
 struct FieldEntries {
     attributes: Vec<syn::Ident>,
     holder_types: Vec<syn::Type>,
     into_owned: Vec<TokenStream2>,
+    resolve: Vec<TokenStream2>,
+    /// Holder types of supertypes
+    supertypes: Vec<syn::Type>,
+    /// Place holder fields except supertypes
+    place_holders: Vec<(syn::Ident, FieldType)>,
+    /// Supertype fields
+    supertype_fields: Vec<syn::Ident>,
+    /// Fields omitted as `*` in the instances of subtypes, paired with the subtypes
+    derived_fields: Vec<(syn::Ident, Vec<syn::Type>)>,
+    /// Checks of the number of elements in aggregate fields
+    bound_checks: Vec<TokenStream2>,
 }
 
 impl FieldEntries {
     fn parse(st: &syn::DataStruct) -> Self {
         let table_arg = table_arg();
+        let resolver_arg = resolver_arg();
 
         let mut attributes = Vec::new();
         let mut holder_types = Vec::new();
         let mut into_owned = Vec::new();
+        let mut resolve = Vec::new();
+        let mut supertypes = Vec::new();
+        let mut place_holders = Vec::new();
+        let mut supertype_fields = Vec::new();
+        let mut derived_fields = Vec::new();
+        let mut bound_checks = Vec::new();
 
         for field in &st.fields {
             let ident = field.ident.as_ref().expect_or_abort("st is not struct");
@@ -68,7 +98,25 @@ impl FieldEntries {
 
             let ft: FieldType = field.ty.clone().try_into().unwrap();
 
-            let HolderAttr { place_holder, .. } = HolderAttr::parse(&field.attrs);
+            let HolderAttr {
+                place_holder,
+                supertype,
+                bound,
+                derived_in,
+                ..
+            } = HolderAttr::parse(&field.attrs);
+            if !derived_in.is_empty() {
+                derived_fields.push((ident.clone(), derived_in));
+            }
+            if let Some(bound) = bound {
+                bound_checks.push(ft.check_bound(quote! { value.#ident }, &bound));
+            }
+            if supertype {
+                supertypes.push(ft.clone().as_holder().into());
+                supertype_fields.push(ident.clone());
+            } else if place_holder {
+                place_holders.push((ident.clone(), ft.clone()));
+            }
             if place_holder {
                 match &ft {
                     FieldType::Path(_) => {
@@ -83,11 +131,17 @@ impl FieldEntries {
                             .map(|v| v.into_owned(#table_arg))
                             .collect::<::std::result::Result<Vec<_>, _>>()?
                     }),
+                    FieldType::Array(..) => {
+                        into_owned.push(quote! { #ident.into_owned(#table_arg)? });
+                    }
                     FieldType::Boxed(_) => abort_call_site!("Unexpected Box<T>"),
                 }
+                let expr = ft.resolve_expr(quote! { #ident }, &resolver_arg);
+                resolve.push(quote! { #expr? });
                 holder_types.push(ft.as_holder().as_place_holder().into());
             } else {
                 into_owned.push(quote! { #ident });
+                resolve.push(quote! { #ident });
                 holder_types.push(ft.into());
             }
         }
@@ -95,6 +149,12 @@ impl FieldEntries {
             attributes,
             holder_types,
             into_owned,
+            resolve,
+            supertypes,
+            place_holders,
+            supertype_fields,
+            derived_fields,
+            bound_checks,
         }
     }
 }
@@ -121,13 +181,131 @@ pub fn impl_holder(ident: &syn::Ident, table: &HolderAttr, st: &syn::DataStruct)
     let FieldEntries {
         attributes,
         into_owned,
+        resolve,
+        supertypes,
+        place_holders,
+        supertype_fields,
+        derived_fields,
         ..
     } = FieldEntries::parse(st);
     let attr_len = attributes.len();
-    let HolderAttr { table, .. } = table;
+    let HolderAttr {
+        table,
+        complex,
+        abstract_entity,
+        instantiables,
+        andor_subtypes,
+        ..
+    } = table;
     let table_arg = table_arg();
+    let resolver_arg = resolver_arg();
     let ruststep = ruststep_crate();
 
+    // References in supertypes are gathered from their holders,
+    // and all fields of complex entity are its components
+    let (place_holders, supertype_fields) = if *complex {
+        (Vec::new(), attributes.clone())
+    } else {
+        (place_holders, supertype_fields)
+    };
+    let visit_place_holders: Vec<TokenStream2> = place_holders
+        .iter()
+        .map(|(attr, ft)| {
+            let attr_name = attr.unraw().to_string();
+            ft.for_each_place_holder(quote! { &self.#attr }, &|place_holder| {
+                quote! {
+                    if let Some(target) = #ruststep::tables::PlaceHolder::entity_id(#place_holder) {
+                        references.push((target, #ruststep::tables::Reference {
+                            id,
+                            entity: #name,
+                            attribute: #attr_name,
+                        }));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    // All fields of complex entity are its components
+    let (supertypes, internal_mapping, components) = if *complex {
+        let components: Vec<syn::Type> = st
+            .fields
+            .iter()
+            .map(|field| {
+                let ft: FieldType = field.ty.clone().try_into().unwrap();
+                ft.as_holder().into()
+            })
+            .collect();
+        (
+            components.clone(),
+            quote! { #ruststep::tables::complex_mapping(#name, supertypes) },
+            quote! {
+                fn components() -> Vec<&'static str> {
+                    vec![ #( <#components as #ruststep::tables::Holder>::name() ),* ]
+                }
+            },
+        )
+    } else {
+        (
+            supertypes,
+            quote! { #ruststep::tables::internal_mapping(#name, subsuper, supertypes) },
+            quote! {},
+        )
+    };
+
+    let is_abstract = if *abstract_entity {
+        quote! {
+            fn is_abstract() -> bool {
+                true
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let instantiables = if instantiables.is_empty() {
+        quote! {}
+    } else {
+        let instantiables = instantiables.iter().map(|subtypes| {
+            let subtypes = subtypes.iter().map(as_holder_path);
+            quote! { vec![ #( <#subtypes as #ruststep::tables::Holder>::name() ),* ] }
+        });
+        quote! {
+            fn instantiables() -> Vec<Vec<&'static str>> {
+                vec![ #(#instantiables),* ]
+            }
+        }
+    };
+    let andor_subtypes = if andor_subtypes.is_empty() {
+        quote! {}
+    } else {
+        let subtypes = andor_subtypes.iter().map(as_holder_path);
+        quote! {
+            fn andor_subtypes() -> Vec<&'static str> {
+                vec![ #( <#subtypes as #ruststep::tables::Holder>::name() ),* ]
+            }
+        }
+    };
+
+    let check_derived_fields: Vec<TokenStream2> = derived_fields
+        .iter()
+        .map(|(attr, subtypes)| {
+            let attr_name = attr.unraw().to_string();
+            let subtypes = subtypes.iter().map(as_holder_path);
+            quote! {
+                let derived = [#( <#subtypes as #ruststep::tables::Holder>::name() ),*]
+                    .iter()
+                    .any(|subtype| names.contains(subtype));
+                if derived == self.#attr.is_some() {
+                    return Err(#ruststep::error::Error::DerivedAttribute {
+                        entity: #name.to_string(),
+                        attribute: #attr_name.to_string(),
+                        derived,
+                    });
+                }
+            }
+        })
+        .collect();
+
     quote! {
         #[automatically_derived]
         impl #ruststep::tables::IntoOwned for #holder_ident {
@@ -146,6 +324,56 @@ pub fn impl_holder(ident: &syn::Ident, table: &HolderAttr, st: &syn::DataStruct)
             fn attr_len() -> usize {
                 #attr_len
             }
+            fn entity_names() -> Vec<&'static str> {
+                let mut names = vec![#name];
+                #(
+                for name in <#supertypes as #ruststep::tables::Holder>::entity_names() {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                )*
+                names
+            }
+            fn internal_mapping(
+                subsuper: &#ruststep::ast::SubSuperRecord
+            ) -> #ruststep::error::Result<#ruststep::ast::Record> {
+                let supertypes = vec![
+                    #( <#supertypes as #ruststep::tables::Holder>::internal_mapping(subsuper)? ),*
+                ];
+                #internal_mapping
+            }
+            #components
+            #is_abstract
+            #instantiables
+            #andor_subtypes
+            fn resolve(
+                self,
+                #resolver_arg: &mut #ruststep::tables::Resolver<Self::Table>
+            ) -> #ruststep::error::Result<Self> {
+                let #holder_ident { #(#attributes),* } = self;
+                Ok(#holder_ident { #(#attributes: #resolve),* })
+            }
+            fn references(&self, id: u64) -> Vec<(u64, #ruststep::tables::Reference)> {
+                #[allow(unused_mut)]
+                let mut references = Vec::new();
+                #(
+                if let Some(holder) = self.#supertype_fields.resolved() {
+                    references.extend(holder.references(id));
+                }
+                )*
+                #(#visit_place_holders)*
+                references
+            }
+            fn check_derived(&self, names: &[&str]) -> #ruststep::error::Result<()> {
+                #(
+                if let Some(holder) = self.#supertype_fields.resolved() {
+                    holder.check_derived(names)?;
+                }
+                )*
+                #(#check_derived_fields)*
+                Ok(())
+            }
         }
     } // quote!
 }
@@ -158,6 +386,9 @@ pub fn impl_entity_table(ident: &syn::Ident, table: &HolderAttr) -> TokenStream2
     quote! {
         #[automatically_derived]
         impl #ruststep::tables::EntityTable<#holder_ident> for #table {
+            fn get_holder(&self, entity_id: u64) -> #ruststep::error::Result<#holder_ident> {
+                #ruststep::tables::get_holder(&self.#field, entity_id)
+            }
             fn get_owned(&self, entity_id: u64) -> #ruststep::error::Result<#ident> {
                 #ruststep::tables::get_owned(self, &self.#field, entity_id)
             }
@@ -172,7 +403,11 @@ pub fn impl_entity_table(ident: &syn::Ident, table: &HolderAttr) -> TokenStream2
 // because this will be used for both Entity struct and its `*Holder` struct.
 fn def_visitor(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> TokenStream2 {
     let visitor_ident = as_visitor_ident(ident);
-    let FieldEntries { attributes, .. } = FieldEntries::parse(st);
+    let FieldEntries {
+        attributes,
+        bound_checks,
+        ..
+    } = FieldEntries::parse(st);
     let attr_len = attributes.len();
     let serde = serde_crate();
     quote! {
@@ -197,7 +432,9 @@ fn def_visitor(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> TokenStr
                     }
                 }
                 #( let #attributes = seq.next_element()?.unwrap(); )*
-                Ok(#ident { #(#attributes),* })
+                let value = #ident { #(#attributes),* };
+                #(#bound_checks)*
+                Ok(value)
             }
 
             // Entry point for Record or Parameter::Typed
@@ -239,6 +476,51 @@ fn impl_deserialize(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> Tok
     } // quote!
 }
 
+// `name` may be different from `ident`
+// because this will be used for both Entity struct and its `*Holder` struct.
+fn impl_serialize(
+    ident: &syn::Ident,
+    name: &str,
+    st: &syn::DataStruct,
+    complex: bool,
+) -> TokenStream2 {
+    let FieldEntries {
+        attributes,
+        supertype_fields,
+        ..
+    } = FieldEntries::parse(st);
+    let attr_len = attributes.len();
+    let keys: Vec<_> = attributes.iter().map(|attr| attr.to_string()).collect();
+    let serde = serde_crate();
+    let ruststep = ruststep_crate();
+    // Supertypes are written in place, e.g. `SUB(BASE((1.0)), 2.0)`, since they are not entity instances,
+    // and so are the components of a complex entity
+    let values: Vec<TokenStream2> = attributes
+        .iter()
+        .map(|attr| {
+            if complex || supertype_fields.contains(attr) {
+                quote! { &#ruststep::ast::ser::Inline(&self.#attr) }
+            } else {
+                quote! { &self.#attr }
+            }
+        })
+        .collect();
+    quote! {
+        #[automatically_derived]
+        impl #serde::ser::Serialize for #ident {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: #serde::ser::Serializer,
+            {
+                use #serde::ser::SerializeStruct;
+                let mut state = serializer.serialize_struct(#name, #attr_len)?;
+                #( state.serialize_field(#keys, #values)?; )*
+                state.end()
+            }
+        }
+    } // quote!
+}
+
 fn impl_with_visitor(ident: &syn::Ident) -> TokenStream2 {
     let ruststep = ruststep_crate();
 
@@ -12,6 +12,8 @@ pub enum FieldType {
     Optional(Box<FieldType>),
     /// Like `Vec<T>`
     List(Box<FieldType>),
+    /// Like `[T; 3]`
+    Array(Box<FieldType>, syn::Expr),
     /// Like `Box<T>`
     Boxed(Box<FieldType>),
 }
@@ -44,6 +46,10 @@ impl FieldType {
                 let holder = ty.as_holder();
                 FieldType::List(Box::new(holder))
             }
+            FieldType::Array(ty, len) => {
+                let holder = ty.as_holder();
+                FieldType::Array(Box::new(holder), len)
+            }
             FieldType::Boxed(ty) => {
                 let holder = ty.as_holder();
                 FieldType::Boxed(Box::new(holder))
@@ -66,6 +72,10 @@ impl FieldType {
                 let place_holder = ty.as_place_holder();
                 FieldType::List(Box::new(place_holder))
             }
+            FieldType::Array(ty, len) => {
+                let place_holder = ty.as_place_holder();
+                FieldType::Array(Box::new(place_holder), len)
+            }
             FieldType::Boxed(ty) => {
                 let place_holder = ty.as_place_holder();
                 FieldType::Boxed(Box::new(place_holder))
@@ -74,6 +84,111 @@ impl FieldType {
     }
 }
 
+impl FieldType {
+    /// Expression replacing references in `expr` of this type by shared holders through `resolver`,
+    /// which returns `Result<Self>`
+    pub fn resolve_expr(&self, expr: TokenStream2, resolver: &syn::Ident) -> TokenStream2 {
+        match self {
+            FieldType::Path(_) => quote! { #expr.resolve(#resolver) },
+            FieldType::Optional(ty) => {
+                let inner = ty.resolve_expr(quote! { v }, resolver);
+                quote! { #expr.map(|v| #inner).transpose() }
+            }
+            FieldType::List(ty) => {
+                let inner = ty.resolve_expr(quote! { v }, resolver);
+                quote! {
+                    #expr
+                        .into_iter()
+                        .map(|v| #inner)
+                        .collect::<::std::result::Result<Vec<_>, _>>()
+                }
+            }
+            FieldType::Array(ty, _) => {
+                let inner = ty.resolve_expr(quote! { v }, resolver);
+                quote! {
+                    ::std::iter::IntoIterator::into_iter(#expr)
+                        .map(|v| #inner)
+                        .collect::<::std::result::Result<Vec<_>, _>>()
+                        .map(|v| v.try_into().ok().expect("Length of array is kept"))
+                }
+            }
+            FieldType::Boxed(_) => abort_call_site!("Unexpected Box<T>"),
+        }
+    }
+}
+
+impl FieldType {
+    /// Statement applying `body` to each place holder in `expr`, a reference to the value of this type
+    pub fn for_each_place_holder(
+        &self,
+        expr: TokenStream2,
+        body: &dyn Fn(TokenStream2) -> TokenStream2,
+    ) -> TokenStream2 {
+        match self {
+            FieldType::Path(_) => body(expr),
+            FieldType::Optional(ty) => {
+                let inner = ty.for_each_place_holder(quote! { v }, body);
+                quote! { if let Some(v) = #expr { #inner } }
+            }
+            FieldType::List(ty) | FieldType::Array(ty, _) => {
+                let inner = ty.for_each_place_holder(quote! { v }, body);
+                quote! { for v in #expr { #inner } }
+            }
+            FieldType::Boxed(_) => abort_call_site!("Unexpected Box<T>"),
+        }
+    }
+}
+
+impl FieldType {
+    /// Statement returning `Err` from the visitor of `#[derive(Holder)]`
+    /// if the number of elements in `expr`, a reference to the value of this type, is out of `bound`
+    ///
+    /// A path type is assumed to be `Binary`, whose number of bits is checked.
+    pub fn check_bound(&self, expr: TokenStream2, bound: &syn::ExprRange) -> TokenStream2 {
+        let serde = serde_crate();
+        let unit = match self {
+            FieldType::Optional(ty) => {
+                let inner = ty.check_bound(quote! { v }, bound);
+                return quote! { if let Some(v) = &#expr { #inner } };
+            }
+            FieldType::List(_) | FieldType::Array(..) => "elements",
+            FieldType::Path(_) => "bits",
+            FieldType::Boxed(_) => {
+                abort_call_site!("`bound` is only for Vec<T>, [T; N], or Binary")
+            }
+        };
+        let expected = bound_expected(bound, unit);
+        let ruststep = ruststep_crate();
+        let lower = match &bound.start {
+            Some(start) => quote! { #start },
+            None => quote! { 0 },
+        };
+        let upper = match &bound.end {
+            Some(end) => quote! { Some(#end) },
+            None => quote! { None },
+        };
+        quote! {
+            let bound = #ruststep::tables::Bound { lower: #lower, upper: #upper };
+            if !bound.contains(#expr.len()) {
+                use #serde::de::Error;
+                return Err(A::Error::invalid_length(#expr.len(), &#expected));
+            }
+        }
+    }
+}
+
+/// Message for `bound`, e.g. `2 to 3 elements` for `2..=3`
+fn bound_expected(bound: &syn::ExprRange, unit: &str) -> String {
+    let lower = match &bound.start {
+        Some(start) => quote! { #start }.to_string(),
+        None => "0".to_string(),
+    };
+    match &bound.end {
+        Some(end) => format!("{} to {} {}", lower, quote! { #end }, unit),
+        None => format!("at least {} {}", lower, unit),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UnsupportedTypeError {}
 
@@ -98,6 +213,10 @@ impl Into<syn::Type> for FieldType {
                 let ty: syn::Type = (*ty).into();
                 syn::parse_quote! { Vec<#ty> }
             }
+            FieldType::Array(ty, len) => {
+                let ty: syn::Type = (*ty).into();
+                return syn::parse_quote! { [#ty; #len] };
+            }
             FieldType::Boxed(ty) => {
                 let ty: syn::Type = (*ty).into();
                 syn::parse_quote! { Box<#ty> }
@@ -111,10 +230,12 @@ impl TryFrom<syn::Type> for FieldType {
     type Error = UnsupportedTypeError;
 
     fn try_from(ty: syn::Type) -> Result<Self, Self::Error> {
-        let path = if let syn::Type::Path(syn::TypePath { path, .. }) = ty {
-            path
-        } else {
-            return Err(UnsupportedTypeError {});
+        let path = match ty {
+            syn::Type::Path(syn::TypePath { path, .. }) => path,
+            syn::Type::Array(syn::TypeArray { elem, len, .. }) => {
+                return Ok(FieldType::Array(Box::new((*elem).try_into()?), len));
+            }
+            _ => return Err(UnsupportedTypeError {}),
         };
 
         let syn::Path { segments, .. } = &path;
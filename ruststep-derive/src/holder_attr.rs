@@ -1,11 +1,18 @@
 //! Parse the associated attribute `#[holder(...)]` with `#[derive(Holder)]`
 //!
-//! There are three options:
+//! There are eleven options:
 //!
 //! - `#[holder(table = {path::to::table::struct})]`
 //! - `#[holder(field = {field_ident})]`
 //! - `#[holder(use_place_holder)]`
+//! - `#[holder(supertype)]`
+//! - `#[holder(complex)]`
 //! - `#[holder(generate_deserialize)]`
+//! - `#[holder(bound = {lower}..={upper})]` or `#[holder(bound = {lower}..)]`
+//! - `#[holder(abstract_entity)]`
+//! - `#[holder(instantiable({SubType1}, {SubType2}, ...))]`
+//! - `#[holder(andor({SubType1}, {SubType2}, ...))]`
+//! - `#[holder(derived_in({SubType1}, {SubType2}, ...))]`
 //!
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,7 +20,18 @@ pub struct HolderAttr {
     pub table: Option<syn::Path>,
     pub field: Option<syn::Ident>,
     pub place_holder: bool,
+    pub supertype: bool,
+    pub complex: bool,
     pub generate_deserialize: bool,
+    /// Number of elements of an aggregate field, or bits of a binary field, checked in deserialization
+    pub bound: Option<syn::ExprRange>,
+    pub abstract_entity: bool,
+    /// Combinations of subtypes which can be instantiated together
+    pub instantiables: Vec<Vec<syn::Type>>,
+    /// Subtypes combined by the default `ANDOR` constraint
+    pub andor_subtypes: Vec<syn::Type>,
+    /// Subtypes redeclaring the attribute as `DERIVE`, whose instances omit it as `*`
+    pub derived_in: Vec<syn::Type>,
 }
 
 impl HolderAttr {
@@ -21,7 +39,14 @@ impl HolderAttr {
         let mut table = None;
         let mut field = None;
         let mut place_holder = false;
+        let mut supertype = false;
+        let mut complex = false;
         let mut generate_deserialize = false;
+        let mut bound = None;
+        let mut abstract_entity = false;
+        let mut instantiables = Vec::new();
+        let mut andor_subtypes = Vec::new();
+        let mut derived_in = Vec::new();
 
         for attr in attrs {
             // Only read `#[holder(...)]`
@@ -43,16 +68,44 @@ impl HolderAttr {
                 Attr::PlaceHolder => {
                     place_holder = true;
                 }
+                Attr::Supertype => {
+                    supertype = true;
+                }
+                Attr::Complex => {
+                    complex = true;
+                }
                 Attr::GenerateDeserialize => {
                     generate_deserialize = true;
                 }
+                Attr::Bound(range) => {
+                    bound = Some(range);
+                }
+                Attr::AbstractEntity => {
+                    abstract_entity = true;
+                }
+                Attr::Instantiable(subtypes) => {
+                    instantiables.push(subtypes);
+                }
+                Attr::AndOr(mut subtypes) => {
+                    andor_subtypes.append(&mut subtypes);
+                }
+                Attr::DerivedIn(mut subtypes) => {
+                    derived_in.append(&mut subtypes);
+                }
             }
         }
         HolderAttr {
             table,
             field,
             place_holder,
+            supertype,
+            complex,
             generate_deserialize,
+            bound,
+            abstract_entity,
+            instantiables,
+            andor_subtypes,
+            derived_in,
         }
     }
 }
@@ -62,7 +115,14 @@ enum Attr {
     Table(syn::Path),
     Field(syn::Ident),
     PlaceHolder,
+    Supertype,
+    Complex,
     GenerateDeserialize,
+    Bound(syn::ExprRange),
+    AbstractEntity,
+    Instantiable(Vec<syn::Type>),
+    AndOr(Vec<syn::Type>),
+    DerivedIn(Vec<syn::Type>),
 }
 
 impl syn::parse::Parse for Attr {
@@ -80,15 +140,52 @@ impl syn::parse::Parse for Attr {
                 Ok(Attr::Field(ident))
             }
             "use_place_holder" => Ok(Attr::PlaceHolder),
+            "supertype" => Ok(Attr::Supertype),
+            "complex" => Ok(Attr::Complex),
             "generate_deserialize" => Ok(Attr::GenerateDeserialize),
+            "bound" => {
+                let _eq: syn::Token![=] = input.parse()?;
+                let range: syn::ExprRange = input.parse()?;
+                match (&range.limits, &range.end) {
+                    (syn::RangeLimits::Closed(_), Some(_)) | (syn::RangeLimits::HalfOpen(_), None) => {
+                        Ok(Attr::Bound(range))
+                    }
+                    _ => Err(syn::parse::Error::new(
+                        ident.span(),
+                        "bound must be `lower..=upper` or `lower..`",
+                    )),
+                }
+            }
+            "abstract_entity" => Ok(Attr::AbstractEntity),
+            "instantiable" => Ok(Attr::Instantiable(parse_subtypes(&ident, input)?)),
+            "andor" => Ok(Attr::AndOr(parse_subtypes(&ident, input)?)),
+            "derived_in" => Ok(Attr::DerivedIn(parse_subtypes(&ident, input)?)),
             _ => Err(syn::parse::Error::new(
                 ident.span(),
-                "expected `table`, `field`, or `use_place_holder`",
+                "expected `table`, `field`, `use_place_holder`, `supertype`, `complex`, `generate_deserialize`, `bound`, `abstract_entity`, `instantiable`, `andor`, or `derived_in`",
             )),
         }
     }
 }
 
+/// Non-empty list of subtypes, e.g. `(Employee, Student)`
+fn parse_subtypes(
+    ident: &syn::Ident,
+    input: syn::parse::ParseStream,
+) -> syn::parse::Result<Vec<syn::Type>> {
+    let content;
+    syn::parenthesized!(content in input);
+    let subtypes =
+        syn::punctuated::Punctuated::<syn::Type, syn::Token![,]>::parse_terminated(&content)?;
+    if subtypes.is_empty() {
+        return Err(syn::parse::Error::new(
+            ident.span(),
+            format!("{} must take one or more subtypes", ident),
+        ));
+    }
+    Ok(subtypes.into_iter().collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,4 +229,77 @@ mod tests {
         // typo
         assert!(syn::parse_str::<Attr>("use_place_helder").is_err());
     }
+
+    #[test]
+    fn parse_attr_supertype() {
+        let attr: Attr = syn::parse_str("supertype").unwrap();
+        assert_eq!(attr, Attr::Supertype);
+
+        assert!(syn::parse_str::<Attr>("supertype = base").is_err());
+    }
+
+    #[test]
+    fn parse_attr_complex() {
+        let attr: Attr = syn::parse_str("complex").unwrap();
+        assert_eq!(attr, Attr::Complex);
+    }
+
+    #[test]
+    fn parse_attr_bound() {
+        let attr: Attr = syn::parse_str("bound = 2..=3").unwrap();
+        assert_eq!(attr, Attr::Bound(syn::parse_str("2..=3").unwrap()));
+        let attr: Attr = syn::parse_str("bound = 1..").unwrap();
+        assert_eq!(attr, Attr::Bound(syn::parse_str("1..").unwrap()));
+
+        // upper bound must be inclusive
+        assert!(syn::parse_str::<Attr>("bound = 2..3").is_err());
+        assert!(syn::parse_str::<Attr>("bound").is_err());
+    }
+
+    #[test]
+    fn parse_attr_abstract_entity() {
+        let attr: Attr = syn::parse_str("abstract_entity").unwrap();
+        assert_eq!(attr, Attr::AbstractEntity);
+    }
+
+    #[test]
+    fn parse_attr_instantiable() {
+        let attr: Attr = syn::parse_str("instantiable(Employee, Student)").unwrap();
+        assert_eq!(
+            attr,
+            Attr::Instantiable(vec![
+                syn::parse_str("Employee").unwrap(),
+                syn::parse_str("Student").unwrap()
+            ])
+        );
+
+        assert!(syn::parse_str::<Attr>("instantiable()").is_err());
+        assert!(syn::parse_str::<Attr>("instantiable").is_err());
+    }
+
+    #[test]
+    fn parse_attr_andor() {
+        let attr: Attr = syn::parse_str("andor(Employee, Student)").unwrap();
+        assert_eq!(
+            attr,
+            Attr::AndOr(vec![
+                syn::parse_str("Employee").unwrap(),
+                syn::parse_str("Student").unwrap()
+            ])
+        );
+
+        assert!(syn::parse_str::<Attr>("andor()").is_err());
+    }
+
+    #[test]
+    fn parse_attr_derived_in() {
+        let attr: Attr = syn::parse_str("derived_in(SiUnit)").unwrap();
+        assert_eq!(
+            attr,
+            Attr::DerivedIn(vec![syn::parse_str("SiUnit").unwrap()])
+        );
+
+        assert!(syn::parse_str::<Attr>("derived_in").is_err());
+        assert!(syn::parse_str::<Attr>("derived_in()").is_err());
+    }
 }
@@ -42,14 +42,23 @@ use table_init::*;
 
 /// Derive `TableInit` for tables
 ///
+/// The optional `#[table(schema = "...")]` gives the name of the schema,
+/// which is reported for unknown entities. The name of the struct is used without it.
+///
 /// ```
 /// use ruststep_derive::{as_holder, Holder, TableInit};
+/// use ruststep::tables::{Names, ReferenceIndex};
 /// use std::collections::HashMap;
 ///
 /// #[derive(TableInit, Default)]
+/// #[table(schema = "example")]
 /// pub struct Table {
 ///     a: HashMap<u64, as_holder!(A)>,
 ///     b: HashMap<u64, as_holder!(B)>,
+///     // Value instances and constants
+///     names: Names,
+///     // Inverse references
+///     references: ReferenceIndex,
 /// }
 ///
 /// #[derive(Debug, Clone, PartialEq, Holder)]
@@ -72,7 +81,7 @@ use table_init::*;
 /// }
 /// ```
 #[proc_macro_error]
-#[proc_macro_derive(TableInit)]
+#[proc_macro_derive(TableInit, attributes(table))]
 pub fn derive_table_init_entry(input: TokenStream) -> TokenStream {
     derive_table_init(&syn::parse(input).unwrap()).into()
 }
@@ -131,6 +140,32 @@ fn derive_deserialize(ast: &syn::DeriveInput) -> TokenStream2 {
 /// - `#[holder(use_place_holder)]`
 ///   - This can be both in field or variant attribute
 ///   - Specify the field is not a simple type
+/// - `#[holder(supertype)]`
+///   - This must be a field attribute
+///   - Specify the field is a supertype of the entity,
+///     which is used for composing complex entity instance
+/// - `#[holder(complex)]`
+///   - This must be a container attribute
+///   - Specify the struct is a complex entity consists of its fields, e.g. `employee & student`,
+///     which has no partial record of itself
+/// - `#[holder(abstract_entity)]`
+///   - This must be a container attribute
+///   - Specify the entity is declared as `ABSTRACT`, and its record is rejected in `TableInit`
+///     unless one of its subtypes is also instantiated
+/// - `#[holder(instantiable(Employee, Student))]`
+///   - This must be a container attribute, and can be repeated
+///   - Specify a combination of subtypes which can be instantiated together,
+///     e.g. `employee & student` for `employee ANDOR student`.
+///     `TableInit` rejects a complex entity instance with the other combinations.
+/// - `#[holder(andor(Employee, Student))]`
+///   - This must be a container attribute
+///   - Specify subtypes combined by the default `ANDOR` constraint,
+///     which can be instantiated together with each other and with one of `instantiable` combinations.
+/// - `#[holder(derived_in(SiUnit))]`
+///   - This must be a field attribute of `Option<T>` type
+///   - Specify subtypes redeclaring the attribute as `DERIVE`.
+///     `TableInit` rejects an instance of them giving the attribute,
+///     and an instance of the others omitting it as `*`.
 ///
 #[proc_macro_error]
 #[proc_macro_derive(Holder, attributes(holder))]
@@ -210,6 +245,43 @@ mod snapshot_tests {
             fn attr_len() -> usize {
                 0
             }
+            fn resolve(
+                self,
+                resolver: &mut ::ruststep::tables::Resolver<Self::Table>,
+            ) -> ::ruststep::error::Result<Self> {
+                Ok(match self {
+                    S1Holder::A(sub) => S1Holder::A(Box::new(sub.resolve(resolver)?)),
+                    S1Holder::B(sub) => S1Holder::B(Box::new(sub.resolve(resolver)?)),
+                })
+            }
+            fn references(&self, id: u64) -> Vec<(u64, ::ruststep::tables::Reference)> {
+                match self {
+                    S1Holder::A(sub) => sub.references(id),
+                    S1Holder::B(sub) => sub.references(id),
+                }
+            }
+        }
+        impl ::ruststep::serde::ser::Serialize for S1 {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::ruststep::serde::ser::Serializer,
+            {
+                match self {
+                    S1::A(sub) => serializer.serialize_newtype_variant("S1", 0, "A", sub),
+                    S1::B(sub) => serializer.serialize_newtype_variant("S1", 1, "B", sub),
+                }
+            }
+        }
+        impl ::ruststep::serde::ser::Serialize for S1Holder {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::ruststep::serde::ser::Serializer,
+            {
+                match self {
+                    S1Holder::A(sub) => serializer.serialize_newtype_variant("S1", 0, "A", sub),
+                    S1Holder::B(sub) => serializer.serialize_newtype_variant("S1", 1, "B", sub),
+                }
+            }
         }
         impl<'de> ::ruststep::serde::de::Deserialize<'de> for S1Holder {
             fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
@@ -255,6 +327,15 @@ mod snapshot_tests {
             }
         }
         impl ::ruststep::tables::EntityTable<S1Holder> for Table {
+            fn get_holder(&self, entity_id: u64) -> ::ruststep::error::Result<S1Holder> {
+                if let Ok(owned) = ::ruststep::tables::EntityTable::<AHolder>::get_holder(self, entity_id) {
+                    return Ok(S1Holder::A(Box::new(owned)));
+                }
+                if let Ok(owned) = ::ruststep::tables::EntityTable::<BHolder>::get_holder(self, entity_id) {
+                    return Ok(S1Holder::B(Box::new(owned)));
+                }
+                Err(::ruststep::error::Error::UnknownEntity(entity_id))
+            }
             fn get_owned(&self, entity_id: u64) -> ::ruststep::error::Result<S1> {
                 if let Ok(owned) = ::ruststep::tables::EntityTable::<AHolder>::get_owned(self, entity_id) {
                     return Ok(S1::A(Box::new(owned.into())));
@@ -322,6 +403,47 @@ mod snapshot_tests {
             fn attr_len() -> usize {
                 0
             }
+            fn resolve(
+                self,
+                resolver: &mut ::ruststep::tables::Resolver<Self::Table>,
+            ) -> ::ruststep::error::Result<Self> {
+                Ok(match self {
+                    BaseAnyHolder::Base(sub) => BaseAnyHolder::Base(Box::new(sub.resolve(resolver)?)),
+                    BaseAnyHolder::Sub(sub) => BaseAnyHolder::Sub(Box::new(sub.resolve(resolver)?)),
+                })
+            }
+            fn references(&self, id: u64) -> Vec<(u64, ::ruststep::tables::Reference)> {
+                match self {
+                    BaseAnyHolder::Base(sub) => sub.references(id),
+                    BaseAnyHolder::Sub(sub) => sub.references(id),
+                }
+            }
+        }
+        impl ::ruststep::serde::ser::Serialize for BaseAny {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::ruststep::serde::ser::Serializer,
+            {
+                match self {
+                    BaseAny::Base(sub) => serializer.serialize_newtype_variant("BASE_ANY", 0, "BASE", sub),
+                    BaseAny::Sub(sub) => serializer.serialize_newtype_variant("BASE_ANY", 1, "SUB", sub),
+                }
+            }
+        }
+        impl ::ruststep::serde::ser::Serialize for BaseAnyHolder {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::ruststep::serde::ser::Serializer,
+            {
+                match self {
+                    BaseAnyHolder::Base(sub) => {
+                        serializer.serialize_newtype_variant("BASE_ANY", 0, "BASE", sub)
+                    }
+                    BaseAnyHolder::Sub(sub) => {
+                        serializer.serialize_newtype_variant("BASE_ANY", 1, "SUB", sub)
+                    }
+                }
+            }
         }
         impl<'de> ::ruststep::serde::de::Deserialize<'de> for BaseAnyHolder {
             fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
@@ -367,6 +489,19 @@ mod snapshot_tests {
             }
         }
         impl ::ruststep::tables::EntityTable<BaseAnyHolder> for Tables {
+            fn get_holder(&self, entity_id: u64) -> ::ruststep::error::Result<BaseAnyHolder> {
+                if let Ok(owned) =
+                    ::ruststep::tables::EntityTable::<BaseHolder>::get_holder(self, entity_id)
+                {
+                    return Ok(BaseAnyHolder::Base(Box::new(owned)));
+                }
+                if let Ok(owned) =
+                    ::ruststep::tables::EntityTable::<SubAnyHolder>::get_holder(self, entity_id)
+                {
+                    return Ok(BaseAnyHolder::Sub(Box::new(owned)));
+                }
+                Err(::ruststep::error::Error::UnknownEntity(entity_id))
+            }
             fn get_owned(&self, entity_id: u64) -> ::ruststep::error::Result<BaseAny> {
                 if let Ok(owned) = ::ruststep::tables::EntityTable::<BaseHolder>::get_owned(self, entity_id)
                 {
@@ -409,6 +544,7 @@ mod snapshot_tests {
                 #[deref]
                 #[deref_mut]
                 #[holder(use_place_holder)]
+                #[holder(supertype)]
                 pub base: Base,
                 pub y1: f64,
             }
@@ -446,9 +582,52 @@ mod snapshot_tests {
             fn attr_len() -> usize {
                 2usize
             }
+            fn entity_names() -> Vec<&'static str> {
+                let mut names = vec!["SUB_1"];
+                for name in <BaseHolder as ::ruststep::tables::Holder>::entity_names() {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                names
+            }
+            fn internal_mapping(
+                subsuper: &::ruststep::ast::SubSuperRecord,
+            ) -> ::ruststep::error::Result<::ruststep::ast::Record> {
+                let supertypes =
+                    vec![<BaseHolder as ::ruststep::tables::Holder>::internal_mapping(subsuper)?];
+                ::ruststep::tables::internal_mapping("SUB_1", subsuper, supertypes)
+            }
+            fn resolve(
+                self,
+                resolver: &mut ::ruststep::tables::Resolver<Self::Table>,
+            ) -> ::ruststep::error::Result<Self> {
+                let Sub1Holder { base, y1 } = self;
+                Ok(Sub1Holder {
+                    base: base.resolve(resolver)?,
+                    y1: y1,
+                })
+            }
+            fn references(&self, id: u64) -> Vec<(u64, ::ruststep::tables::Reference)> {
+                #[allow(unused_mut)]
+                let mut references = Vec::new();
+                if let Some(holder) = self.base.resolved() {
+                    references.extend(holder.references(id));
+                }
+                references
+            }
+            fn check_derived(&self, names: &[&str]) -> ::ruststep::error::Result<()> {
+                if let Some(holder) = self.base.resolved() {
+                    holder.check_derived(names)?;
+                }
+                Ok(())
+            }
         }
         #[automatically_derived]
         impl ::ruststep::tables::EntityTable<Sub1Holder> for Tables {
+            fn get_holder(&self, entity_id: u64) -> ::ruststep::error::Result<Sub1Holder> {
+                ::ruststep::tables::get_holder(&self.sub1, entity_id)
+            }
             fn get_owned(&self, entity_id: u64) -> ::ruststep::error::Result<Sub1> {
                 ::ruststep::tables::get_owned(self, &self.sub1, entity_id)
             }
@@ -458,6 +637,32 @@ mod snapshot_tests {
                 ::ruststep::tables::owned_iter(self, &self.sub1)
             }
         }
+        #[automatically_derived]
+        impl ::ruststep::serde::ser::Serialize for Sub1 {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::ruststep::serde::ser::Serializer,
+            {
+                use ruststep::serde::ser::SerializeStruct;
+                let mut state = serializer.serialize_struct("SUB_1", 2usize)?;
+                state.serialize_field("base", &::ruststep::ast::ser::Inline(&self.base))?;
+                state.serialize_field("y1", &self.y1)?;
+                state.end()
+            }
+        }
+        #[automatically_derived]
+        impl ::ruststep::serde::ser::Serialize for Sub1Holder {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: ::ruststep::serde::ser::Serializer,
+            {
+                use ruststep::serde::ser::SerializeStruct;
+                let mut state = serializer.serialize_struct("SUB_1", 2usize)?;
+                state.serialize_field("base", &::ruststep::ast::ser::Inline(&self.base))?;
+                state.serialize_field("y1", &self.y1)?;
+                state.end()
+            }
+        }
         #[doc(hidden)]
         pub struct Sub1HolderVisitor;
         #[automatically_derived]
@@ -478,7 +683,8 @@ mod snapshot_tests {
                 }
                 let base = seq.next_element()?.unwrap();
                 let y1 = seq.next_element()?.unwrap();
-                Ok(Sub1Holder { base, y1 })
+                let value = Sub1Holder { base, y1 };
+                Ok(value)
             }
             fn visit_map<A>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error>
             where
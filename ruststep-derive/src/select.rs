@@ -16,6 +16,7 @@ struct Input {
     variant_into_exprs: Vec<TokenStream2>,
     holder_types: Vec<syn::Type>,
     holder_exprs: Vec<TokenStream2>,
+    resolve_exprs: Vec<TokenStream2>,
     place_holders: Vec<bool>,
 }
 
@@ -35,6 +36,7 @@ impl Input {
             .expect_or_abort("table attribute is lacked");
 
         let mut holder_exprs = Vec::new();
+        let mut resolve_exprs = Vec::new();
         let mut holder_types = Vec::new();
         let mut variant_exprs = Vec::new();
         let mut variant_into_exprs = Vec::new();
@@ -51,6 +53,7 @@ impl Input {
                         // ENTITY case
                         holder_types.push(as_holder_path(&f.ty));
                         holder_exprs.push(quote! { Box::new(sub.into_owned(table)?) });
+                        resolve_exprs.push(quote! { Box::new(sub.resolve(resolver)?) });
                         variant_exprs.push(quote! { Box::new(owned) });
                         variant_into_exprs.push(quote! { Box::new(owned.into()) });
                     } else {
@@ -63,10 +66,12 @@ impl Input {
                         // *Any case
                         holder_types.push(as_holder_path(&f.ty));
                         holder_exprs.push(quote! { sub.into_owned(table)? });
+                        resolve_exprs.push(quote! { sub.resolve(resolver)? });
                     } else {
                         // SimpleType case
                         holder_types.push(f.ty.clone());
                         holder_exprs.push(quote! { sub });
+                        resolve_exprs.push(quote! { sub });
                     }
                 }
             }
@@ -84,6 +89,7 @@ impl Input {
             variant_into_exprs,
             holder_types,
             holder_exprs,
+            resolve_exprs,
             place_holders,
         }
     }
@@ -111,8 +117,20 @@ impl Input {
             variants,
             table,
             holder_exprs,
+            resolve_exprs,
+            place_holders,
             ..
         } = self;
+        let references_arms = variants
+            .iter()
+            .zip(place_holders)
+            .map(|(var, place_holder)| {
+                if *place_holder {
+                    quote! { #holder_ident::#var(sub) => sub.references(id) }
+                } else {
+                    quote! { #holder_ident::#var(_) => Vec::new() }
+                }
+            });
         let ruststep = ruststep_crate();
 
         quote! {
@@ -132,6 +150,48 @@ impl Input {
                 fn attr_len() -> usize {
                     0
                 }
+                fn resolve(
+                    self,
+                    resolver: &mut #ruststep::tables::Resolver<Self::Table>
+                ) -> #ruststep::error::Result<Self> {
+                    Ok(match self {
+                        #(#holder_ident::#variants(sub) => #holder_ident::#variants(#resolve_exprs)),*
+                    })
+                }
+                fn references(&self, id: u64) -> Vec<(u64, #ruststep::tables::Reference)> {
+                    match self {
+                        #(#references_arms),*
+                    }
+                }
+            }
+        } // quote!
+    }
+
+    // `ident` may be different from `self.ident`
+    // because this will be used for both enum and its `*Holder` enum.
+    fn impl_serialize(&self, ident: &syn::Ident) -> TokenStream2 {
+        let Input {
+            name,
+            variants,
+            variant_names,
+            ..
+        } = self;
+        let indices = (0..variants.len()).map(proc_macro2::Literal::usize_unsuffixed);
+        let serde = serde_crate();
+        quote! {
+            impl #serde::ser::Serialize for #ident {
+                fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+                where
+                    S: #serde::ser::Serializer,
+                {
+                    match self {
+                        #(
+                        #ident::#variants(sub) => {
+                            serializer.serialize_newtype_variant(#name, #indices, #variant_names, sub)
+                        }
+                        )*
+                    }
+                }
             }
         } // quote!
     }
@@ -217,6 +277,7 @@ impl Input {
             variants,
             holder_types,
             table,
+            variant_exprs,
             variant_into_exprs,
             place_holders,
             ..
@@ -225,15 +286,18 @@ impl Input {
         let itertools = itertools_crate();
         let mut vars = Vec::new();
         let mut holders = Vec::<syn::Type>::new();
+        let mut holder_exprs = Vec::new();
         let mut exprs = Vec::new();
-        for (((var, holder), expr), place_holder) in variants
+        for ((((var, holder), holder_expr), expr), place_holder) in variants
             .iter()
             .zip(holder_types)
+            .zip(variant_exprs)
             .zip(variant_into_exprs)
             .zip(place_holders)
         {
             if *place_holder {
                 vars.push(var);
+                holder_exprs.push(holder_expr);
                 exprs.push(expr);
                 if let FieldType::Boxed(path) = holder.clone().try_into().unwrap() {
                     holders.push(path.as_ref().clone().into());
@@ -245,6 +309,14 @@ impl Input {
 
         quote! {
             impl #ruststep::tables::EntityTable<#holder_ident> for #table {
+                fn get_holder(&self, entity_id: u64) -> #ruststep::error::Result<#holder_ident> {
+                    #(
+                    if let Ok(owned) = #ruststep::tables::EntityTable::<#holders>::get_holder(self, entity_id) {
+                        return Ok(#holder_ident::#vars(#holder_exprs));
+                    }
+                    )*
+                    Err(#ruststep::error::Error::UnknownEntity(entity_id))
+                }
                 fn get_owned(&self, entity_id: u64) -> #ruststep::error::Result<#ident> {
                     #(
                     if let Ok(owned) = #ruststep::tables::EntityTable::<#holders>::get_owned(self, entity_id) {
@@ -270,6 +342,8 @@ pub fn derive_holder(ident: &syn::Ident, e: &syn::DataEnum, attr: &HolderAttr) -
     let input = Input::parse(ident, e, attr);
     let def_holder_tt = input.def_holder();
     let impl_holder_tt = input.impl_holder();
+    let impl_serialize_tt = input.impl_serialize(ident);
+    let impl_holder_serialize_tt = input.impl_serialize(&input.holder_ident);
 
     if attr.generate_deserialize {
         let impl_deserialize_tt = input.impl_deserialize();
@@ -278,6 +352,8 @@ pub fn derive_holder(ident: &syn::Ident, e: &syn::DataEnum, attr: &HolderAttr) -
         quote! {
             #def_holder_tt
             #impl_holder_tt
+            #impl_serialize_tt
+            #impl_holder_serialize_tt
             #impl_deserialize_tt
             #def_visitor_tt
             #impl_entity_table_tt
@@ -286,6 +362,8 @@ pub fn derive_holder(ident: &syn::Ident, e: &syn::DataEnum, attr: &HolderAttr) -
         quote! {
             #def_holder_tt
             #impl_holder_tt
+            #impl_serialize_tt
+            #impl_holder_serialize_tt
         } // quote!
     }
 }
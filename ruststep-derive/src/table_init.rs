@@ -7,72 +7,265 @@ use crate::common::ruststep_crate;
 
 pub fn derive_table_init(ast: &syn::DeriveInput) -> TokenStream2 {
     let ident = &ast.ident;
+    let schema = schema_name(&ast.attrs).unwrap_or_else(|| ident.to_string());
     match &ast.data {
         syn::Data::Struct(st) => match st.fields {
-            syn::Fields::Named(_) => entity_impl_table_init(ident, st),
-            syn::Fields::Unnamed(_) => tuple_impl_table_init(ident, st),
+            syn::Fields::Named(_) => entity_impl_table_init(ident, &schema, st),
+            syn::Fields::Unnamed(_) => tuple_impl_table_init(ident, &schema, st),
             syn::Fields::Unit => panic!("Unit struct is not supported."),
         },
         _ => abort_call_site!("Only struct is supprted currently"),
     }
 }
 
-fn entity_impl_table_init(ident: &syn::Ident, st: &syn::DataStruct) -> TokenStream2 {
+/// Read the schema name from `#[table(schema = "...")]`
+fn schema_name(attrs: &[syn::Attribute]) -> Option<String> {
+    let attr = attrs.iter().find(|attr| attr.path().is_ident("table"))?;
+    let mut schema = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("schema") {
+            let name: syn::LitStr = meta.value()?.parse()?;
+            schema = Some(name.value());
+            Ok(())
+        } else {
+            Err(meta.error("expected `schema`"))
+        }
+    })
+    .unwrap_or_else(|e| abort_call_site!("{}", e));
+    schema
+}
+
+fn entity_impl_table_init(ident: &syn::Ident, schema: &str, st: &syn::DataStruct) -> TokenStream2 {
     let mut table_names = Vec::new();
     let mut entity_names = Vec::new();
+    let mut holder_types = Vec::new();
+    let mut names_field = None;
+    let mut references_field = None;
     for field in &st.fields {
         let ident = field.ident.as_ref().expect_or_abort("unreachable!");
+        if is_type_of(&field.ty, "Names") {
+            names_field = Some(ident);
+            continue;
+        }
+        if is_type_of(&field.ty, "ReferenceIndex") {
+            references_field = Some(ident);
+            continue;
+        }
         let name = ident.to_string().to_screaming_snake_case();
         table_names.push(ident);
         entity_names.push(name);
+        holder_types.push(table_value_type(&field.ty));
     }
     assert_eq!(table_names.len(), entity_names.len());
 
     let ruststep = ruststep_crate();
 
+    // Value instances and constants are not available in the table without `Names`
+    let names_impl = match names_field {
+        Some(names_field) => quote! {
+            fn names(&self) -> Option<&#ruststep::tables::Names> {
+                Some(&self.#names_field)
+            }
+            fn names_mut(&mut self) -> Option<&mut #ruststep::tables::Names> {
+                Some(&mut self.#names_field)
+            }
+        },
+        None => TokenStream2::new(),
+    };
+
+    // Inverse references are not indexed in the table without `ReferenceIndex`
+    let (references_impl, index_references) = match references_field {
+        Some(references_field) => (
+            quote! {
+                fn references(&self) -> Option<&#ruststep::tables::ReferenceIndex> {
+                    Some(&self.#references_field)
+                }
+                fn references_mut(&mut self) -> Option<&mut #ruststep::tables::ReferenceIndex> {
+                    Some(&mut self.#references_field)
+                }
+            },
+            table_names
+                .iter()
+                .map(|table| {
+                    quote! { #ruststep::tables::index_references(&mut self.#references_field, &self.#table, *id); }
+                })
+                .collect(),
+        ),
+        None => (TokenStream2::new(), vec![TokenStream2::new(); table_names.len()]),
+    };
+
     quote! {
+        #[automatically_derived]
+        impl #ruststep::tables::NameTable for #ident {
+            #names_impl
+        }
+
+        #[automatically_derived]
+        impl #ruststep::tables::ReferenceTable for #ident {
+            #references_impl
+        }
+
         #[automatically_derived]
         impl #ruststep::tables::TableInit for #ident {
-            fn append_data_section(
+            fn append_entity_instance(
                 &mut self,
-                data_sec: &#ruststep::ast::DataSection
+                entity: &#ruststep::ast::EntityInstance
             ) -> #ruststep::error::Result<()> {
-                use #ruststep::{error::Error, tables::insert_record, ast::EntityInstance};
-                for entity in &data_sec.entities {
-                    match entity {
-                        EntityInstance::Simple { id, record } => match record.name.as_str() {
+                use #ruststep::{error::Error, ast::EntityInstance};
+                let (EntityInstance::Simple { id, .. } | EntityInstance::Complex { id, .. }) = entity;
+                // An instance stored in several tables is defined once
+                #(
+                if self.#table_names.contains_key(id) {
+                    return Err(Error::DuplicatedEntity(*id));
+                }
+                )*
+                match entity {
+                    EntityInstance::Simple { id, record } => {
+                        use #ruststep::tables::{check_instantiable, insert_simple_record};
+                        let result = match record.name.as_str() {
                             #(
-                            #entity_names => insert_record(&mut self.#table_names, *id, record)?,
+                            #entity_names => check_instantiable::<#holder_types>(&[#entity_names])
+                                .and_then(|_| insert_simple_record(&mut self.#table_names, *id, record))
+                                .map(|_| { #index_references }),
                             )*
-                            _ => {
-                                return Err(Error::UnknownEntityName {
-                                    entity_name: record.name.clone(),
-                                    schema: "".to_string(),
-                                });
+                            _ => Err(Error::UnknownEntityName {
+                                entity_name: record.name.clone(),
+                                schema: #schema.to_string(),
+                            }),
+                        };
+                        result.map_err(|e| e.at_entity_instance(*id))?;
+                    }
+                    EntityInstance::Complex { id, subsuper } => {
+                        use #ruststep::tables::{check_instantiable, complex_entity_tables, insert_subsuper_record, Holder};
+                        let mut insert = || -> #ruststep::error::Result<()> {
+                            let names: Vec<&str> = subsuper.0.iter().map(|record| record.name.as_str()).collect();
+                            for record in &subsuper.0 {
+                                match record.name.as_str() {
+                                    #(
+                                    #entity_names => check_instantiable::<#holder_types>(&names)?,
+                                    )*
+                                    _ => {
+                                        return Err(Error::UnknownEntityName {
+                                            entity_name: record.name.clone(),
+                                            schema: #schema.to_string(),
+                                        });
+                                    }
+                                }
                             }
-                        },
-                        EntityInstance::Complex { .. } => {
-                            unimplemented!("Complex entity is not supported")
-                        }
+                            let holders = [
+                                #(
+                                (#entity_names, <#holder_types as Holder>::partial_names(), <#holder_types as Holder>::components()),
+                                )*
+                            ];
+                            let tables = complex_entity_tables(&names, &holders)?;
+                            #(
+                            if tables.contains(&#entity_names) {
+                                insert_subsuper_record(&mut self.#table_names, *id, subsuper)?;
+                                #index_references
+                            }
+                            )*
+                            Ok(())
+                        };
+                        insert().map_err(|e| e.at_entity_instance(*id))?;
                     }
                 }
                 Ok(())
             }
         }
 
+        #[automatically_derived]
+        impl #ruststep::tables::TableDump for #ident {
+            fn to_data_section(&self) -> #ruststep::error::Result<#ruststep::ast::DataSection> {
+                use #ruststep::{ast::{ser::DataSectionSerializer, Record, SubSuperRecord}, tables::{external_mapping, Holder}};
+                let mut max_id = 0;
+                #( max_id = self.#table_names.keys().cloned().fold(max_id, u64::max); )*
+                // Entity instances defined in `REFERENCE` section are not in the tables
+                if let Some(names) = #ruststep::tables::NameTable::names(self) {
+                    max_id = max_id.max(names.max_entity_id());
+                }
+                let mut ser = DataSectionSerializer::new(max_id + 1);
+                // Partial records of the instances written in external mapping
+                let mut subsupers = ::std::collections::BTreeMap::<u64, Vec<Record>>::new();
+                // Complex entities are also stored in the tables of their components, which are not written
+                #(
+                if !<#holder_types as Holder>::components().is_empty() {
+                    let names = <#holder_types as Holder>::partial_names();
+                    for (id, holder) in &self.#table_names {
+                        let partials = subsupers.entry(*id).or_default();
+                        external_mapping(ser.record(holder)?, &names, partials);
+                    }
+                }
+                )*
+                let complex_ids: ::std::collections::HashSet<u64> = subsupers.keys().cloned().collect();
+                // Instances of subtypes combined by the default `ANDOR` are stored in the table of each subtype
+                let mut counts = ::std::collections::HashMap::<u64, usize>::new();
+                #(
+                if <#holder_types as Holder>::components().is_empty() {
+                    for id in self.#table_names.keys() {
+                        *counts.entry(*id).or_default() += 1;
+                    }
+                }
+                )*
+                #(
+                if <#holder_types as Holder>::components().is_empty() {
+                    let names = <#holder_types as Holder>::partial_names();
+                    for (id, holder) in &self.#table_names {
+                        if complex_ids.contains(id) {
+                            continue;
+                        }
+                        if counts[id] > 1 {
+                            let partials = subsupers.entry(*id).or_default();
+                            external_mapping(ser.record(holder)?, &names, partials);
+                        } else {
+                            ser.insert(*id, holder)?;
+                        }
+                    }
+                }
+                )*
+                for (id, mut partials) in subsupers {
+                    partials.sort_by(|a, b| a.name.cmp(&b.name));
+                    partials.dedup_by(|a, b| a.name == b.name);
+                    ser.insert_subsuper(id, SubSuperRecord(partials));
+                }
+                Ok(ser.finish())
+            }
+        }
+
         #[automatically_derived]
         impl ::std::str::FromStr for #ident {
             type Err = #ruststep::error::Error;
             fn from_str(input: &str) -> #ruststep::error::Result<Self> {
                 use #ruststep::{tables::TableInit, ast::DataSection};
                 let data_sec = DataSection::from_str(input)?;
-                Ok(Self::from_data_section(&data_sec)?)
+                Self::from_data_section(&data_sec).map_err(|e| e.locate(input))
+            }
+        }
+    }
+}
+
+/// Check the field is `Names` or `ReferenceIndex`, which are not entity tables
+fn is_type_of(ty: &syn::Type, name: &str) -> bool {
+    matches!(ty, syn::Type::Path(syn::TypePath { path, .. })
+        if path.segments.last().map_or(false, |seg| seg.ident == name))
+}
+
+/// Get `V` from the table type `HashMap<u64, V>`
+fn table_value_type(ty: &syn::Type) -> syn::Type {
+    if let syn::Type::Path(syn::TypePath { path, .. }) = ty {
+        if let Some(syn::PathSegment {
+            arguments: syn::PathArguments::AngleBracketed(args),
+            ..
+        }) = path.segments.last()
+        {
+            if let Some(syn::GenericArgument::Type(value)) = args.args.last() {
+                return value.clone();
             }
         }
     }
+    abort_call_site!("Table must be a HashMap<u64, Holder>")
 }
 
-fn tuple_impl_table_init(ident: &syn::Ident, st: &syn::DataStruct) -> TokenStream2 {
+fn tuple_impl_table_init(ident: &syn::Ident, schema: &str, st: &syn::DataStruct) -> TokenStream2 {
     let mut table_names = Vec::new();
     let mut entity_names = Vec::new();
     for field in &st.fields {
@@ -88,27 +281,29 @@ fn tuple_impl_table_init(ident: &syn::Ident, st: &syn::DataStruct) -> TokenStrea
     quote! {
         #[automatically_derived]
         impl #ruststep::tables::TableInit for #ident {
-            fn append_data_section(
+            fn append_entity_instance(
                 &mut self,
-                data_sec: &#ruststep::ast::DataSection
+                entity: &#ruststep::ast::EntityInstance
             ) -> #ruststep::error::Result<()> {
                 use #ruststep::{error::Error, tables::insert_record, ast::EntityInstance};
-                for entity in &data_sec.entities {
-                    match entity {
-                        EntityInstance::Simple { id, record } => match record.name.as_str() {
+                match entity {
+                    EntityInstance::Simple { id, record } => {
+                        let result = match record.name.as_str() {
                             #(
-                            #entity_names => insert_record(&mut self.#table_names, *id, record)?,
+                            #entity_names => insert_record(&mut self.#table_names, *id, record),
                             )*
-                            _ => {
-                                return Err(Error::UnknownEntityName {
-                                    entity_name: record.name.clone(),
-                                    schema: "".to_string(),
-                                });
-                            }
-                        },
-                        EntityInstance::Complex { .. } => {
-                            unimplemented!("Complex entity is not supported")
-                        }
+                            _ => Err(Error::UnknownEntityName {
+                                entity_name: record.name.clone(),
+                                schema: #schema.to_string(),
+                            }),
+                        };
+                        result.map_err(|e| e.at_entity_instance(*id))?;
+                    }
+                    EntityInstance::Complex { id, .. } => {
+                        return Err(Error::DeserializeFailed(
+                            "Complex entity instance cannot be stored in a tuple struct table".to_string(),
+                        )
+                        .at_entity_instance(*id));
                     }
                 }
                 Ok(())
@@ -121,7 +316,7 @@ fn tuple_impl_table_init(ident: &syn::Ident, st: &syn::DataStruct) -> TokenStrea
             fn from_str(input: &str) -> #ruststep::error::Result<Self> {
                 use #ruststep::{tables::TableInit, ast::DataSection};
                 let data_sec = DataSection::from_str(input)?;
-                Ok(Self::from_data_section(&data_sec)?)
+                Self::from_data_section(&data_sec).map_err(|e| e.locate(input))
             }
         }
     }
@@ -11,6 +11,8 @@ pub fn derive_holder(ident: &syn::Ident, st: &syn::DataStruct, attr: &HolderAttr
     let def_holder_tt = def_holder(ident, st);
     let impl_holder_tt = impl_holder(ident, attr, st);
     let impl_entity_table_tt = impl_entity_table(ident, attr);
+    let impl_serialize_tt = impl_serialize(ident, &name, st);
+    let impl_holder_serialize_tt = impl_serialize(&holder_ident, &name, st);
     if attr.generate_deserialize {
         let def_visitor_tt = def_visitor(&holder_ident, &name, st);
         let impl_deserialize_tt = impl_deserialize(&holder_ident, &name, st);
@@ -19,6 +21,8 @@ pub fn derive_holder(ident: &syn::Ident, st: &syn::DataStruct, attr: &HolderAttr
             #def_holder_tt
             #impl_holder_tt
             #impl_entity_table_tt
+            #impl_serialize_tt
+            #impl_holder_serialize_tt
             #def_visitor_tt
             #impl_deserialize_tt
             #impl_with_visitor_tt
@@ -28,6 +32,8 @@ pub fn derive_holder(ident: &syn::Ident, st: &syn::DataStruct, attr: &HolderAttr
             #def_holder_tt
             #impl_holder_tt
             #impl_entity_table_tt
+            #impl_serialize_tt
+            #impl_holder_serialize_tt
         }
     }
 }
@@ -48,10 +54,13 @@ pub fn impl_holder(ident: &syn::Ident, table: &HolderAttr, st: &syn::DataStruct)
     let FieldEntries {
         holder_types,
         into_owned,
+        resolve,
+        ..
     } = FieldEntries::parse(st);
     let HolderAttr { table, .. } = table;
     let tuple_len = holder_types.len();
     let table_arg = table_arg();
+    let resolver_arg = entity::resolver_arg();
     let ruststep = ruststep_crate();
 
     quote! {
@@ -71,6 +80,12 @@ pub fn impl_holder(ident: &syn::Ident, table: &HolderAttr, st: &syn::DataStruct)
             fn attr_len() -> usize {
                 #tuple_len
             }
+            fn resolve(
+                self,
+                #resolver_arg: &mut #ruststep::tables::Resolver<Self::Table>
+            ) -> #ruststep::error::Result<Self> {
+                Ok(#holder_ident ( #(#resolve),* ))
+            }
         }
     } // quote!
 }
@@ -83,6 +98,9 @@ pub fn impl_entity_table(ident: &syn::Ident, table: &HolderAttr) -> TokenStream2
     quote! {
         #[automatically_derived]
         impl #ruststep::tables::EntityTable<#holder_ident> for #table {
+            fn get_holder(&self, entity_id: u64) -> #ruststep::error::Result<#holder_ident> {
+                #ruststep::tables::get_holder(&self.#field, entity_id)
+            }
             fn get_owned(&self, entity_id: u64) -> #ruststep::error::Result<#ident> {
                 #ruststep::tables::get_owned(self, &self.#field, entity_id)
             }
@@ -97,12 +115,46 @@ pub fn impl_entity_table(ident: &syn::Ident, table: &HolderAttr) -> TokenStream2
 // because this will be used for both Entity struct and its `*Holder` struct.
 fn def_visitor(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> TokenStream2 {
     let visitor_ident = as_visitor_ident(ident);
-    let FieldEntries { holder_types, .. } = FieldEntries::parse(st);
+    let FieldEntries {
+        holder_types,
+        bound_checks,
+        ..
+    } = FieldEntries::parse(st);
     let attr_len = holder_types.len();
     let attributes = (0..attr_len)
         .map(|i| format_ident!("a_{}", i))
         .collect::<Vec<_>>();
     let serde = serde_crate();
+    // Defined type is written without its name in an entity instance, e.g. `'KORE'` in `E('KORE')`
+    let visit_primitive = if attr_len == 1 {
+        quote! {
+            fn visit_i64<E>(self, v: i64) -> ::std::result::Result<Self::Value, E>
+            where
+                E: #serde::de::Error,
+            {
+                use #serde::de::IntoDeserializer;
+                Ok(#ident(#serde::Deserialize::deserialize(v.into_deserializer())?))
+            }
+
+            fn visit_f64<E>(self, v: f64) -> ::std::result::Result<Self::Value, E>
+            where
+                E: #serde::de::Error,
+            {
+                use #serde::de::IntoDeserializer;
+                Ok(#ident(#serde::Deserialize::deserialize(v.into_deserializer())?))
+            }
+
+            fn visit_str<E>(self, v: &str) -> ::std::result::Result<Self::Value, E>
+            where
+                E: #serde::de::Error,
+            {
+                use #serde::de::IntoDeserializer;
+                Ok(#ident(#serde::Deserialize::deserialize(v.into_deserializer())?))
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
     quote! {
         #[doc(hidden)]
         pub struct #visitor_ident;
@@ -125,9 +177,13 @@ fn def_visitor(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> TokenStr
                     }
                 }
                 #( let #attributes = seq.next_element()?.unwrap(); )*
-                Ok(#ident ( #(#attributes),* ))
+                let value = #ident ( #(#attributes),* );
+                #(#bound_checks)*
+                Ok(value)
             }
 
+            #visit_primitive
+
             // Entry point for Record or Parameter::Typed
             fn visit_map<A>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error>
             where
@@ -167,6 +223,36 @@ fn impl_deserialize(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> Tok
     } // quote!
 }
 
+// `name` may be different from `ident`
+// because this will be used for both Entity struct and its `*Holder` struct.
+fn impl_serialize(ident: &syn::Ident, name: &str, st: &syn::DataStruct) -> TokenStream2 {
+    let FieldEntries { holder_types, .. } = FieldEntries::parse(st);
+    let serde = serde_crate();
+    let body = if holder_types.len() == 1 {
+        quote! { serializer.serialize_newtype_struct(#name, &self.0) }
+    } else {
+        let attr_len = holder_types.len();
+        let indices = (0..attr_len).map(syn::Index::from);
+        quote! {
+            use #serde::ser::SerializeTupleStruct;
+            let mut state = serializer.serialize_tuple_struct(#name, #attr_len)?;
+            #( state.serialize_field(&self.#indices)?; )*
+            state.end()
+        }
+    };
+    quote! {
+        #[automatically_derived]
+        impl #serde::ser::Serialize for #ident {
+            fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: #serde::ser::Serializer,
+            {
+                #body
+            }
+        }
+    } // quote!
+}
+
 fn impl_with_visitor(ident: &syn::Ident) -> TokenStream2 {
     let ruststep = ruststep_crate();
 
@@ -187,20 +273,33 @@ fn impl_with_visitor(ident: &syn::Ident) -> TokenStream2 {
 struct FieldEntries {
     holder_types: Vec<syn::Type>,
     into_owned: Vec<TokenStream2>,
+    resolve: Vec<TokenStream2>,
+    /// Checks of the number of elements in aggregate fields
+    bound_checks: Vec<TokenStream2>,
 }
 
 impl FieldEntries {
     fn parse(st: &syn::DataStruct) -> Self {
         let table_arg = table_arg();
+        let resolver_arg = entity::resolver_arg();
 
         let mut holder_types = Vec::new();
         let mut into_owned = Vec::new();
+        let mut resolve = Vec::new();
+        let mut bound_checks = Vec::new();
 
         for (i, field) in st.fields.iter().enumerate() {
             let ft: FieldType = field.ty.clone().try_into().unwrap();
             let index = syn::Index::from(i);
 
-            let HolderAttr { place_holder, .. } = HolderAttr::parse(&field.attrs);
+            let HolderAttr {
+                place_holder,
+                bound,
+                ..
+            } = HolderAttr::parse(&field.attrs);
+            if let Some(bound) = bound {
+                bound_checks.push(ft.check_bound(quote! { value.#index }, &bound));
+            }
             if place_holder {
                 match &ft {
                     FieldType::Path(_) => {
@@ -215,17 +314,25 @@ impl FieldEntries {
                             .map(|v| v.into_owned(#table_arg))
                             .collect::<::std::result::Result<Vec<_>, _>>()?
                     }),
+                    FieldType::Array(..) => {
+                        into_owned.push(quote! { self.#index.into_owned(#table_arg)? });
+                    }
                     FieldType::Boxed(_) => abort_call_site!("Unexpected Box<T>"),
                 }
+                let expr = ft.resolve_expr(quote! { self.#index }, &resolver_arg);
+                resolve.push(quote! { #expr? });
                 holder_types.push(ft.as_holder().as_place_holder().into());
             } else {
                 into_owned.push(quote! { self.#index });
+                resolve.push(quote! { self.#index });
                 holder_types.push(ft.into());
             }
         }
         FieldEntries {
             holder_types,
             into_owned,
+            resolve,
+            bound_checks,
         }
     }
 }
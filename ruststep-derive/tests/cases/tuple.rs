@@ -1,13 +1,14 @@
-use ruststep_derive::{as_holder, Holder};
+use ruststep_derive::{as_holder, Holder, TableInit};
 use std::collections::HashMap;
 
+#[derive(Default, TableInit)]
 pub struct Table {
     e: HashMap<u64, as_holder!(E)>,
     a: HashMap<u64, as_holder!(A)>,
     b: HashMap<u64, as_holder!(B)>,
 }
 
-#[derive(Debug, Clone, PartialEq, ::serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
 pub struct Simple(pub f64);
 
 #[derive(Debug, Clone, PartialEq, Holder)]
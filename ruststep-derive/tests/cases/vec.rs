@@ -1,6 +1,7 @@
-use ruststep_derive::{as_holder, Holder};
+use ruststep_derive::{as_holder, Holder, TableInit};
 use std::collections::HashMap;
 
+#[derive(Default, TableInit)]
 pub struct Table {
     a: HashMap<u64, as_holder!(A)>,
     b: HashMap<u64, as_holder!(B)>,
@@ -1,5 +1,5 @@
 use super::*;
-use crate::ast::*;
+use crate::{ast::*, primitive::BINARY_TOKEN};
 use inflector::Inflector;
 use serde::{
     de::{self, IntoDeserializer},
@@ -20,7 +20,8 @@ impl<'de, 'param> de::Deserializer<'de> for &'param Parameter {
             Parameter::Integer(val) => visitor.visit_i64(*val),
             Parameter::Real(val) => visitor.visit_f64(*val),
             Parameter::String(val) => visitor.visit_str(val),
-            Parameter::List(params) => visitor.visit_seq(SeqDeserializer::new(params)),
+            Parameter::Binary(val) => visitor.visit_str(&val.to_string()),
+            Parameter::List(params) => SeqDeserializer::new(params).deserialize_any(visitor),
             Parameter::Ref(name) => visitor.visit_enum(name),
             Parameter::NotProvided | Parameter::Omitted => visitor.visit_none(),
             Parameter::Enumeration(variant) => {
@@ -57,9 +58,26 @@ impl<'de, 'param> de::Deserializer<'de> for &'param Parameter {
         }
     }
 
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // A string literal is not a binary, e.g. `'0123'` for `"0123"`
+        if name == BINARY_TOKEN {
+            if let Parameter::String(val) = self {
+                return Err(de::Error::invalid_type(de::Unexpected::Str(val), &"binary"));
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
     forward_to_deserialize_any! {
         i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        bytes byte_buf unit unit_struct seq tuple
         struct tuple_struct map enum identifier ignored_any
     }
 }
@@ -77,16 +95,29 @@ impl<'p> SeqDeserializer<'p> {
             parameters,
         }
     }
+
+    /// Check all elements are consumed, e.g. by a visitor for `[T; N]`
+    fn end(&self) -> Result<(), crate::error::Error> {
+        if self.cursor < self.parameters.len() {
+            return Err(de::Error::invalid_length(
+                self.parameters.len(),
+                &format!("{} elements", self.cursor).as_str(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl<'de, 'p> de::Deserializer<'de> for SeqDeserializer<'p> {
     type Error = crate::error::Error;
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_seq(self)
+        let value = visitor.visit_seq(&mut self)?;
+        self.end()?;
+        Ok(value)
     }
 
     forward_to_deserialize_any! {
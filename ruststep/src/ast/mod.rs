@@ -37,7 +37,7 @@
 pub mod de;
 pub mod ser;
 
-use crate::parser;
+use crate::{parser, primitive::Binary};
 use std::str::FromStr;
 
 /// AST portion
@@ -73,7 +73,7 @@ macro_rules! derive_ast_from_str {
 /// Name of an entity instance or a value
 ///
 /// Corresponding to [parser::token::rhs_occurrence_name] and [parser::token::lhs_occurrence_name]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Name {
     /// Like `#11`, corresponds to [parser::token::entity_instance_name]
     Entity(u64),
@@ -86,6 +86,18 @@ pub enum Name {
 }
 derive_ast_from_str!(Name, parser::token::rhs_occurrence_name);
 
+impl std::fmt::Display for Name {
+    /// Same form as exchange structure, e.g. `#11` or `@CONST_VALUE`
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Name::Entity(id) => write!(f, "#{}", id),
+            Name::Value(id) => write!(f, "@{}", id),
+            Name::ConstantEntity(name) => write!(f, "#{}", name),
+            Name::ConstantValue(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
 /// A struct typed in EXPRESS schema, e.g. `A(1.0, 2.0)`
 ///
 /// FromStr
@@ -456,6 +468,7 @@ derive_ast_from_str!(DataSection, parser::exchange::data_section);
 /// | Integer     | i64              |
 /// | Real        | f64              |
 /// | String      | string           |
+/// | Binary      | string (hexadecimal digits with the leading unused-bit digit) |
 /// | List        | seq              |
 /// | NotProvided | option (always none)|
 /// | Omitted     | option (always none)|
@@ -603,6 +616,32 @@ pub enum Parameter {
     #[from]
     String(String),
 
+    /// Binary literal, like `"0123"`
+    ///
+    /// FromStr
+    /// --------
+    /// ```
+    /// use std::str::FromStr;
+    /// use ruststep::{ast::Parameter, primitive::Binary};
+    ///
+    /// let p = Parameter::from_str(r#""0123""#).unwrap();
+    /// assert_eq!(p, Parameter::Binary(Binary::from_str("0123").unwrap()));
+    /// ```
+    ///
+    /// Deserialize
+    /// ------------
+    /// ```
+    /// use std::str::FromStr;
+    /// use ruststep::{ast::Parameter, primitive::Binary};
+    /// use serde::Deserialize;
+    ///
+    /// let p = Parameter::from_str(r#""3A""#).unwrap();
+    /// let b = Binary::deserialize(&p).unwrap();
+    /// assert_eq!(b.iter().collect::<Vec<_>>(), [false]);
+    /// ```
+    #[from]
+    Binary(Binary),
+
     /// Enumeration defined in EXPRESS schema, like `.TRUE.`
     ///
     /// FromStr
@@ -802,6 +841,7 @@ pub enum AnchorItem {
     Real(f64),
     String(String),
     Enumeration(String),
+    Binary(Binary),
     /// The special token dollar sign (`$`) is used to represent an object whose value is not provided in the exchange structure.
     NotProvided,
     /// A reference to entity or value
@@ -810,3 +850,18 @@ pub enum AnchorItem {
     List(Vec<AnchorItem>),
 }
 derive_ast_from_str!(AnchorItem, parser::exchange::anchor_item);
+
+impl From<AnchorItem> for Parameter {
+    fn from(item: AnchorItem) -> Self {
+        match item {
+            AnchorItem::Integer(val) => Parameter::Integer(val),
+            AnchorItem::Real(val) => Parameter::Real(val),
+            AnchorItem::String(val) => Parameter::String(val),
+            AnchorItem::Enumeration(val) => Parameter::Enumeration(val),
+            AnchorItem::Binary(val) => Parameter::Binary(val),
+            AnchorItem::NotProvided => Parameter::NotProvided,
+            AnchorItem::Name(name) => Parameter::Ref(name),
+            AnchorItem::List(items) => Parameter::List(items.into_iter().map(Into::into).collect()),
+        }
+    }
+}
@@ -1,24 +1,339 @@
-use crate::{ast::*, error::*};
+//! Serialize Rust structs into STEP [Record] and [DataSection]
+//!
+//! [serde::Serializer] is implemented to map the serde data model into [Parameter]
+//! as the inverse of [super::de]:
+//!
+//! | serde data model | Parameter        |
+//! |:-----------------|:-----------------|
+//! | bool             | `.T.` or `.F.` as [Parameter::Enumeration] |
+//! | i64, u64, ...    | [Parameter::Integer] |
+//! | f64, f32         | [Parameter::Real] |
+//! | string, char     | [Parameter::String] |
+//! | bytes            | [Parameter::Binary] |
+//! | none, unit       | [Parameter::NotProvided] |
+//! | some, newtype_struct | transparent |
+//! | seq, tuple       | [Parameter::List] |
+//! | unit_variant     | [Parameter::Enumeration] in SCREAMING_SNAKE_CASE |
+//! | newtype_variant  | [Parameter::Typed] using variant name as keyword |
+//! | struct           | [Parameter::Typed], or a reference to a new instance in [DataSectionSerializer] |
+//!
+//! [Name] is serialized into [Parameter::Ref],
+//! and [Binary](crate::primitive::Binary) is serialized into [Parameter::Binary].
+//! A struct wrapped by [Inline] is always serialized into [Parameter::Typed].
+
+use crate::{
+    ast::*,
+    error::*,
+    primitive::{Binary, BINARY_TOKEN},
+};
+use inflector::Inflector;
 use serde::ser;
 use std::convert::TryFrom;
 
+/// Name of [Name] in serde data model
+const NAME_TOKEN: &str = "ruststep::ast::Name";
+
+/// Name of [Inline] in serde data model
+const INLINE_TOKEN: &str = "ruststep::ast::ser::Inline";
+
+/// Serialize a struct in place even in [DataSectionSerializer]
+///
+/// This is used for the supertype part of an entity in internal mapping,
+/// e.g. `PET(('Tama'))` in `CAT(PET(('Tama')), 9)`,
+/// which is not an entity instance by itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Inline<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: ?Sized + ser::Serialize> ser::Serialize for Inline<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(INLINE_TOKEN, self.0)
+    }
+}
+
+impl ser::Serialize for Name {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self {
+            Name::Entity(id) => serializer.serialize_newtype_variant(NAME_TOKEN, 0, "Entity", id),
+            Name::Value(id) => serializer.serialize_newtype_variant(NAME_TOKEN, 1, "Value", id),
+            Name::ConstantEntity(name) => {
+                serializer.serialize_newtype_variant(NAME_TOKEN, 2, "ConstantEntity", name)
+            }
+            Name::ConstantValue(name) => {
+                serializer.serialize_newtype_variant(NAME_TOKEN, 3, "ConstantValue", name)
+            }
+        }
+    }
+}
+
 /// Serialize struct into STEP [Record]
+///
+/// Nested structs are serialized as inline [Parameter::Typed]:
+///
+/// ```
+/// use ruststep::ast::{*, ser::to_record};
+/// use serde::Serialize;
+/// use std::str::FromStr;
+///
+/// #[derive(Serialize)]
+/// #[serde(rename = "A")]
+/// struct A {
+///     x: f64,
+///     y: Vec<i64>,
+/// }
+///
+/// #[derive(Serialize)]
+/// #[serde(rename = "B")]
+/// struct B {
+///     a: A,
+///     name: Option<String>,
+/// }
+///
+/// let b = B { a: A { x: 1.0, y: vec![2, 3] }, name: None };
+/// assert_eq!(to_record(&b).unwrap(), Record::from_str("B(A((1.0, (2, 3))), $)").unwrap());
+/// ```
 pub fn to_record(obj: &impl ser::Serialize) -> Result<Record> {
     let mut ser = RecordSerializer::default();
     obj.serialize(&mut ser)?;
-    assert!(ser.stack.is_empty()); // should panic because this must be bug, not a valid input
-    Ok(Record {
-        name: ser.name,
-        parameter: ser.parameters.iter().collect(),
-    })
+    Ok(ser.finish())
+}
+
+/// Serialize Rust values into [DataSection] with entity instance numbering
+///
+/// Different from [to_record], a nested struct is regarded as an entity,
+/// and is written as a new entity instance with fresh id except it is wrapped by [Inline].
+/// It is referred by its id from the parent record:
+///
+/// ```
+/// use ruststep::ast::{*, ser::DataSectionSerializer};
+/// use serde::Serialize;
+/// use std::str::FromStr;
+///
+/// #[derive(Serialize)]
+/// #[serde(rename = "A")]
+/// struct A {
+///     x: f64,
+/// }
+///
+/// #[derive(Serialize)]
+/// #[serde(rename = "B")]
+/// struct B {
+///     a: A,
+///     r: Name,
+/// }
+///
+/// let mut ser = DataSectionSerializer::new(10);
+/// ser.insert(1, &B { a: A { x: 1.0 }, r: Name::Entity(2) }).unwrap();
+/// let id = ser.push(&A { x: 2.0 }).unwrap();
+/// assert_eq!(id, 11);
+///
+/// assert_eq!(
+///     ser.finish(),
+///     DataSection::from_str(r#"
+///     DATA;
+///       #1 = B(#10, #2);
+///       #10 = A(1.0);
+///       #11 = A(2.0);
+///     ENDSEC;
+///     "#).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataSectionSerializer {
+    next_id: u64,
+    entities: Vec<EntityInstance>,
+}
+
+impl DataSectionSerializer {
+    /// Fresh ids are numbered from `first_fresh_id`
+    ///
+    /// `first_fresh_id` should be larger than any id given to [Self::insert]
+    /// to avoid duplicated entity instance names.
+    pub fn new(first_fresh_id: u64) -> Self {
+        DataSectionSerializer {
+            next_id: first_fresh_id,
+            entities: Vec::new(),
+        }
+    }
+
+    /// Serialize `obj` as an entity instance `#id`
+    pub fn insert(&mut self, id: u64, obj: &impl ser::Serialize) -> Result<()> {
+        let record = self.record(obj)?;
+        self.entities.push(EntityInstance::Simple { id, record });
+        Ok(())
+    }
+
+    /// Serialize `obj` into a record in internal mapping
+    ///
+    /// Entities nested in `obj` are written as entity instances with fresh ids,
+    /// while the returned record is not written.
+    pub fn record(&mut self, obj: &impl ser::Serialize) -> Result<Record> {
+        let mut ser = RecordSerializer {
+            instances: Some(Instances {
+                next_id: self.next_id,
+                entities: Vec::new(),
+            }),
+            ..Default::default()
+        };
+        obj.serialize(&mut ser)?;
+        let instances = ser.instances.take().unwrap();
+        self.next_id = instances.next_id;
+        self.entities.extend(instances.entities);
+        Ok(ser.finish())
+    }
+
+    /// Write a complex entity instance `#id` in external mapping, e.g. `#1 = (A(1) B(2));`
+    pub fn insert_subsuper(&mut self, id: u64, subsuper: SubSuperRecord) {
+        self.entities.push(EntityInstance::Complex { id, subsuper });
+    }
+
+    /// Serialize `obj` as an entity instance with fresh id, and returns the id
+    pub fn push(&mut self, obj: &impl ser::Serialize) -> Result<u64> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert(id, obj)?;
+        Ok(id)
+    }
+
+    /// Get [DataSection] sorted by entity instance names
+    pub fn finish(self) -> DataSection {
+        let mut entities = self.entities;
+        entities.sort_by_key(|entity| match entity {
+            EntityInstance::Simple { id, .. } | EntityInstance::Complex { id, .. } => *id,
+        });
+        DataSection {
+            meta: Vec::new(),
+            entities,
+        }
+    }
+}
+
+/// Entity instances extracted from nested struct
+#[derive(Debug)]
+struct Instances {
+    next_id: u64,
+    entities: Vec<EntityInstance>,
+}
+
+/// Compound value in serialization, e.g. nested struct or list
+#[derive(Debug)]
+enum Frame {
+    /// Name of struct, and if it is written in place by [Inline]
+    Struct(String, bool),
+    Seq,
+    Variant(&'static str, &'static str),
 }
 
 #[derive(Default, Debug)]
 struct RecordSerializer {
-    name: String,
+    name: Option<String>,
     parameters: Vec<Parameter>,
-    // For supporting nested record e.g. `B(3.0, A((1.0, 2.0)))`
-    stack: Vec<(String, Vec<Parameter>)>,
+    // For supporting nested values e.g. `B(3.0, A((1.0, 2.0)))`
+    stack: Vec<(Frame, Vec<Parameter>)>,
+    // Nested structs are extracted into new instances if exists
+    instances: Option<Instances>,
+    // Next struct is wrapped by [Inline]
+    inline: bool,
+}
+
+impl RecordSerializer {
+    fn finish(self) -> Record {
+        assert!(self.stack.is_empty()); // should panic because this must be bug, not a valid input
+        Record {
+            name: self.name.unwrap_or_default(),
+            parameter: self.parameters.into_iter().collect(),
+        }
+    }
+
+    fn is_top_level(&self) -> bool {
+        self.name.is_none() && self.stack.is_empty() && self.parameters.is_empty()
+    }
+
+    /// Put current parameters onto the top of stack, and start serializing a compound value.
+    /// This stack will be popped in [Self::pop].
+    fn push(&mut self, frame: Frame) {
+        let current_params = std::mem::take(&mut self.parameters);
+        self.stack.push((frame, current_params));
+    }
+
+    /// Restore stacked state, and push the compound value into restored parameters
+    fn pop(&mut self) -> Result<()> {
+        let (frame, params) = self
+            .stack
+            .pop()
+            .expect("Serializer stack is empty. This must be a bug.");
+        let params = std::mem::replace(&mut self.parameters, params);
+        let value = match frame {
+            Frame::Seq => Parameter::List(params),
+            Frame::Struct(name, inline) => {
+                let record = Record {
+                    name,
+                    parameter: params.into_iter().collect(),
+                };
+                if let (Some(instances), false) = (&mut self.instances, inline) {
+                    let id = instances.next_id;
+                    instances.next_id += 1;
+                    instances
+                        .entities
+                        .push(EntityInstance::Simple { id, record });
+                    Parameter::Ref(Name::Entity(id))
+                } else {
+                    Parameter::Typed {
+                        keyword: record.name,
+                        parameter: Box::new(record.parameter),
+                    }
+                }
+            }
+            Frame::Variant(name, variant) => {
+                let value = single_parameter(params)?;
+                if name == NAME_TOKEN {
+                    Parameter::Ref(as_name(variant, value)?)
+                } else {
+                    match value {
+                        // Reference or entity instance are not wrapped by the keyword
+                        Parameter::Ref(_) => value,
+                        Parameter::Typed { ref keyword, .. } if keyword == variant => value,
+                        _ => Parameter::Typed {
+                            keyword: variant.to_string(),
+                            parameter: Box::new(value),
+                        },
+                    }
+                }
+            }
+        };
+        self.parameters.push(value);
+        Ok(())
+    }
+}
+
+fn single_parameter(mut params: Vec<Parameter>) -> Result<Parameter> {
+    if params.len() != 1 {
+        return Err(Error::SerializeFailed(format!(
+            "Newtype variant must have one parameter, but {} found",
+            params.len()
+        )));
+    }
+    Ok(params.pop().unwrap())
+}
+
+fn as_name(variant: &str, value: Parameter) -> Result<Name> {
+    Ok(match (variant, value) {
+        ("Entity", Parameter::Integer(id)) => Name::Entity(id as u64),
+        ("Value", Parameter::Integer(id)) => Name::Value(id as u64),
+        ("ConstantEntity", Parameter::String(name)) => Name::ConstantEntity(name),
+        ("ConstantValue", Parameter::String(name)) => Name::ConstantValue(name),
+        (variant, value) => {
+            return Err(Error::SerializeFailed(format!(
+                "Invalid Name: {}({:?})",
+                variant, value
+            )))
+        }
+    })
 }
 
 impl<'se> ser::Serializer for &'se mut RecordSerializer {
@@ -35,10 +350,10 @@ impl<'se> ser::Serializer for &'se mut RecordSerializer {
     fn serialize_bool(self, v: bool) -> Result<()> {
         if v {
             self.parameters
-                .push(Parameter::Enumeration("TRUE".to_string()));
+                .push(Parameter::Enumeration("T".to_string()));
         } else {
             self.parameters
-                .push(Parameter::Enumeration("FALSE".to_string()));
+                .push(Parameter::Enumeration("F".to_string()));
         }
         Ok(())
     }
@@ -85,8 +400,10 @@ impl<'se> ser::Serializer for &'se mut RecordSerializer {
         self.parameters.push(Parameter::String(v.to_string()));
         Ok(())
     }
-    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
-        unimplemented!("Bytes is not supported yet")
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.parameters
+            .push(Parameter::Binary(Binary::from_bytes(v)));
+        Ok(())
     }
 
     fn serialize_none(self) -> Result<()> {
@@ -111,34 +428,71 @@ impl<'se> ser::Serializer for &'se mut RecordSerializer {
 
     fn serialize_unit_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        self.serialize_str(variant)
+        let value = match (name, variant) {
+            // LOGICAL is encoded as `.T.`, `.F.`, or `.U.`
+            ("Logical", "True") => "T".to_string(),
+            ("Logical", "False") => "F".to_string(),
+            ("Logical", "Unknown") => "U".to_string(),
+            _ => variant.to_screaming_snake_case(),
+        };
+        self.parameters.push(Parameter::Enumeration(value));
+        Ok(())
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
+        if name == BINARY_TOKEN {
+            // Binary is serialized as a string of hexadecimal digits
+            value.serialize(&mut *self)?;
+            return match self.parameters.pop() {
+                Some(Parameter::String(hex)) => {
+                    let b = hex
+                        .parse()
+                        .map_err(|e| Error::SerializeFailed(format!("{}", e)))?;
+                    self.parameters.push(Parameter::Binary(b));
+                    Ok(())
+                }
+                _ => unreachable!("Binary must be serialized as a string"),
+            };
+        }
+        if name == INLINE_TOKEN {
+            // The flag is taken by the struct directly wrapped,
+            // and is dropped if the value is not a struct, e.g. a reference
+            self.inline = true;
+            value.serialize(&mut *self)?;
+            self.inline = false;
+            return Ok(());
+        }
+        if self.is_top_level() {
+            // Top-level defined type, e.g. `#1 = LENGTH_MEASURE(1.0);`
+            self.name = Some(name.to_string());
+        }
         value.serialize(self)
     }
 
     fn serialize_newtype_variant<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
-        _value: &T,
+        variant: &'static str,
+        value: &T,
     ) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
-        unimplemented!("Newtype variant is not suuported yet.")
+        self.push(Frame::Variant(name, variant));
+        value.serialize(&mut *self)?;
+        self.pop()
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.push(Frame::Seq);
         Ok(self)
     }
 
@@ -169,8 +523,8 @@ impl<'se> ser::Serializer for &'se mut RecordSerializer {
     }
 
     fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
-        if self.name.is_empty() {
-            self.name = name.to_string();
+        if self.is_top_level() {
+            self.name = Some(name.to_string());
         } else {
             // Entering sub struct e.g.
             //
@@ -183,9 +537,8 @@ impl<'se> ser::Serializer for &'se mut RecordSerializer {
             // and start serializing `A((1.0, 2.0))`.
             // This stack will be popped in SerializeStruct::end()
             //
-            let current_name = std::mem::replace(&mut self.name, name.to_string());
-            let current_params = std::mem::take(&mut self.parameters);
-            self.stack.push((current_name, current_params));
+            let inline = std::mem::take(&mut self.inline);
+            self.push(Frame::Struct(name.to_string(), inline));
         }
         Ok(self)
     }
@@ -211,7 +564,7 @@ impl<'se> ser::SerializeSeq for &'se mut RecordSerializer {
         value.serialize(&mut **self)
     }
     fn end(self) -> Result<()> {
-        Ok(())
+        self.pop()
     }
 }
 
@@ -226,7 +579,7 @@ impl<'se> ser::SerializeTuple for &'se mut RecordSerializer {
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.pop()
     }
 }
 
@@ -242,7 +595,7 @@ impl<'se> ser::SerializeTupleStruct for &'se mut RecordSerializer {
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.pop()
     }
 }
 
@@ -297,16 +650,12 @@ impl<'se> ser::SerializeStruct for &'se mut RecordSerializer {
     }
 
     fn end(self) -> Result<()> {
-        if let Some((name, params)) = self.stack.pop() {
-            // restore stacked state
-            let name = std::mem::replace(&mut self.name, name);
-            let params = std::mem::replace(&mut self.parameters, params);
-            self.parameters.push(Parameter::Typed {
-                keyword: name,
-                parameter: Box::new(params.into_iter().collect()),
-            });
+        if self.stack.is_empty() {
+            // End of top-level struct
+            Ok(())
+        } else {
+            self.pop()
         }
-        Ok(())
     }
 }
 
@@ -325,3 +674,36 @@ impl<'se> ser::SerializeStructVariant for &'se mut RecordSerializer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Logical;
+    use serde::Serialize;
+    use std::str::FromStr;
+
+    #[derive(Serialize)]
+    #[serde(rename = "A")]
+    struct A {
+        flag: bool,
+        logical: Logical,
+        list: Vec<Vec<f64>>,
+        r: Name,
+        binary: Binary,
+    }
+
+    #[test]
+    fn record() {
+        let a = A {
+            flag: true,
+            logical: Logical::Unknown,
+            list: vec![vec![1.0, 2.0], vec![]],
+            r: Name::ConstantEntity("CONST".to_string()),
+            binary: Binary::from_str("1F").unwrap(),
+        };
+        assert_eq!(
+            to_record(&a).unwrap(),
+            Record::from_str(r#"A(.T., .U., ((1.0, 2.0), ()), #CONST, "1F")"#).unwrap()
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{ast::Name, tables::Bound};
 use serde::{de, ser};
 use std::fmt;
 
@@ -14,14 +15,135 @@ pub enum Error {
     #[error("Error while deserialize STEP struct: {0}")]
     DeserializeFailed(String),
 
+    #[error("Error while serialize into STEP struct: {0}")]
+    SerializeFailed(String),
+
     #[error("Lookup failed for #{0}")]
     UnknownEntity(u64),
 
     #[error("Entity ID #{0} is duplicated")]
     DuplicatedEntity(u64),
 
+    #[error("Lookup failed for {0}")]
+    UnknownName(Name),
+
+    #[error("{0} is duplicated")]
+    DuplicatedName(String),
+
+    #[error("{0} cannot be registered into the table without names")]
+    NamesUnavailable(String),
+
+    #[error("External reference <{0}> cannot be resolved")]
+    ExternalReference(String),
+
+    #[error("Cyclic reference through {0}")]
+    CyclicReference(Name),
+
+    #[error("#{id} is referred {count} times through '{attribute}', out of {bound}")]
+    InverseCardinality {
+        id: u64,
+        attribute: String,
+        count: usize,
+        bound: Bound,
+    },
+
+    #[error("Complex entity instance lacks partial record of '{0}'")]
+    LackedPartialRecord(String),
+
+    #[error(
+        "Entity '{0}' is declared as ABSTRACT, and cannot be instantiated without its subtypes"
+    )]
+    AbstractEntity(String),
+
+    #[error("Subtypes {subtypes:?} of '{supertype}' cannot be instantiated together")]
+    NotInstantiable {
+        supertype: String,
+        subtypes: Vec<String>,
+    },
+
+    #[error("Attribute '{attribute}' of '{entity}' {}", derived_message(.derived))]
+    DerivedAttribute {
+        entity: String,
+        attribute: String,
+        /// Redeclared as `DERIVE` in a subtype of the instance, and thus must be omitted as `*`
+        derived: bool,
+    },
+
     #[error("Entity '{entity_name}' is not a member of the schema '{schema}'")]
     UnknownEntityName { entity_name: String, schema: String },
+
+    #[error("Non-finite real number {0} cannot be written into exchange structure")]
+    NonFiniteReal(f64),
+
+    #[error("Error in entity instance #{id}{}: {source}", at(.position))]
+    EntityInstanceFailed {
+        id: u64,
+        /// Position of `#id = ...;` in the input, if known
+        position: Option<Position>,
+        source: Box<Error>,
+    },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Attach the id of the entity instance where this error occurs
+    ///
+    /// Errors which already know the entity instance, e.g. [Error::DuplicatedEntity], are kept as is.
+    pub fn at_entity_instance(self, id: u64) -> Self {
+        match self {
+            Error::EntityInstanceFailed { .. } | Error::DuplicatedEntity(_) => self,
+            _ => Error::EntityInstanceFailed {
+                id,
+                position: None,
+                source: Box::new(self),
+            },
+        }
+    }
+
+    /// Attach the position of the entity instance where this error occurs
+    pub fn at_position(mut self, at: Position) -> Self {
+        if let Error::EntityInstanceFailed { position, .. } = &mut self {
+            position.get_or_insert(at);
+        }
+        self
+    }
+
+    /// Attach the position of the entity instance in `input` where this error occurs
+    ///
+    /// Errors while creating tables from [DataSection](crate::ast::DataSection) know
+    /// only the id of the entity instance, and this finds `#id = ...;` in the original input.
+    pub fn locate(self, input: &str) -> Self {
+        match &self {
+            Error::EntityInstanceFailed {
+                id, position: None, ..
+            } => match entity_instance_offset(input, *id) {
+                Some(offset) => self.at_position(Position::new(input, offset)),
+                None => self,
+            },
+            _ => self,
+        }
+    }
+
+    /// Position in the input where this error occurs, if known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Error::TokenizeFailed(e) => Some(e.position),
+            Error::EntityInstanceFailed { position, .. } => *position,
+            _ => None,
+        }
+    }
+
+    /// Id of the entity instance where this error occurs, if known
+    pub fn entity_id(&self) -> Option<u64> {
+        match self {
+            Error::TokenizeFailed(e) => e.entity_id,
+            Error::EntityInstanceFailed { id, .. } => Some(*id),
+            Error::DuplicatedEntity(id) => Some(*id),
+            _ => None,
+        }
+    }
 }
 
 impl de::Error for Error {
@@ -38,22 +160,71 @@ impl ser::Error for Error {
     where
         T: fmt::Display,
     {
-        Error::DeserializeFailed(msg.to_string())
+        Error::SerializeFailed(msg.to_string())
+    }
+}
+
+/// Position in the input string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset from the beginning of the input
+    pub offset: usize,
+    /// Line number starting from 1
+    pub line: usize,
+    /// Column number in characters starting from 1
+    pub column: usize,
+}
+
+impl Position {
+    /// Position of `offset` in `input`
+    pub fn new(input: &str, offset: usize) -> Self {
+        let head = &input[..offset];
+        let line_start = head.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+        Position {
+            offset,
+            line: head.matches('\n').count() + 1,
+            column: head[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Position of `relative`, which is relative to `self`, in the entire input
+    pub(crate) fn advanced(self, relative: Position) -> Self {
+        Position {
+            offset: self.offset + relative.offset,
+            line: self.line + relative.line - 1,
+            column: if relative.line == 1 {
+                self.column + relative.column - 1
+            } else {
+                relative.column
+            },
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        write!(f, "line {}, column {}", self.line, self.column)
     }
 }
 
 /// Error while tokenizing STEP input
 pub struct TokenizeFailed {
+    /// Position where tokenize failed
+    pub position: Position,
+    /// Id of the entity instance `#id = ...;` containing [TokenizeFailed::position]
+    pub entity_id: Option<u64>,
+    /// The line of the input containing [TokenizeFailed::position]
+    pub excerpt: String,
     rendered_error: String,
 }
 
 impl fmt::Debug for TokenizeFailed {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
-        write!(
-            f,
-            "Error while tokenizing STEP input\n{}",
-            self.rendered_error
-        )?;
+        write!(f, "Error while tokenizing STEP input at {}", self.position)?;
+        if let Some(id) = self.entity_id {
+            write!(f, " in entity instance #{}", id)?;
+        }
+        write!(f, "\n{}", self.rendered_error)?;
         Ok(())
     }
 }
@@ -69,8 +240,138 @@ impl std::error::Error for TokenizeFailed {}
 
 impl TokenizeFailed {
     pub fn new(input: &str, err: nom::error::VerboseError<&str>) -> Self {
+        // The first error is the innermost one, and its input is a suffix of `input`
+        let offset = err
+            .errors
+            .first()
+            .map(|(residual, _kind)| input.len() - residual.len())
+            .unwrap_or(0);
+        let position = Position::new(input, offset);
+        let excerpt = input
+            .lines()
+            .nth(position.line - 1)
+            .unwrap_or_default()
+            .to_string();
         TokenizeFailed {
+            position,
+            entity_id: enclosing_entity_instance(input, offset),
+            excerpt,
             rendered_error: nom::error::convert_error(input, err),
         }
     }
+
+    /// Make position absolute when `input` of [TokenizeFailed::new] starts at `base`
+    pub(crate) fn shifted(mut self, base: Position) -> Self {
+        self.position = base.advanced(self.position);
+        self
+    }
+}
+
+fn at(position: &Option<Position>) -> String {
+    match position {
+        Some(position) => format!(" at {}", position),
+        None => String::new(),
+    }
+}
+
+fn derived_message(derived: &bool) -> &'static str {
+    if *derived {
+        "is derived in a subtype of the instance, and must be omitted as `*`"
+    } else {
+        "is not derived in the instance, and must be given"
+    }
+}
+
+/// Id of the entity instance `#id = ...;` in `statement`,
+/// and the offset of `#id` after leading spaces and comments
+fn entity_instance_id(statement: &str) -> Option<(u64, usize)> {
+    use crate::parser::{combinator::*, token::entity_instance_name};
+    use nom::Parser;
+
+    let (rest, ()) = ignorable(statement).ok()?;
+    let (_residual, (id, _eq)) = tuple_((entity_instance_name, char_('=')))
+        .parse(rest)
+        .ok()?;
+    Some((id, statement.len() - rest.len()))
+}
+
+/// Find `#id =` of the entity instance containing `offset`
+///
+/// An entity instance starts after the `;` of the previous instance or `DATA;`,
+/// or at the beginning of `input` if it is a single statement.
+/// `;` in strings and comments are skipped in the same way as [Reader](crate::parser::reader::Reader).
+fn enclosing_entity_instance(input: &str, offset: usize) -> Option<u64> {
+    let start = crate::parser::reader::statement_starts(input)
+        .take_while(|start| *start <= offset)
+        .last()?;
+    entity_instance_id(&input[start..]).map(|(id, _)| id)
+}
+
+/// Offset of `#id` in `input`
+fn entity_instance_offset(input: &str, id: u64) -> Option<usize> {
+    crate::parser::reader::statement_starts(input).find_map(|start| {
+        match entity_instance_id(&input[start..]) {
+            Some((found, head)) if found == id => Some(start + head),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn position() {
+        let input = "DATA;\n#1 = A(1);\n#2 = 🦀B(2);";
+        let offset = input.find('B').unwrap();
+        assert_eq!(
+            Position::new(input, offset),
+            Position {
+                offset,
+                line: 3,
+                column: 7
+            }
+        );
+    }
+
+    #[test]
+    fn tokenize_failed() {
+        let input = "DATA;\n#1 = A(1);\n#2 = B(2, $$);\n#3 = C(3);\nENDSEC;";
+        let err = crate::ast::DataSection::from_str(input).unwrap_err();
+        let err = match err {
+            Error::TokenizeFailed(err) => err,
+            _ => panic!("Unexpected error: {:?}", err),
+        };
+        assert_eq!(err.position.line, 3);
+        assert_eq!(err.entity_id, Some(2));
+        assert_eq!(err.excerpt, "#2 = B(2, $$);");
+
+        // `;` in strings and comments do not terminate entity instances
+        let input = "DATA;\n#1 = A('a;b', /* ; */ $$);\nENDSEC;";
+        let err = match crate::ast::DataSection::from_str(input).unwrap_err() {
+            Error::TokenizeFailed(err) => err,
+            err => panic!("Unexpected error: {:?}", err),
+        };
+        assert_eq!(err.entity_id, Some(1));
+    }
+
+    #[test]
+    fn locate() {
+        let input = "DATA;\n#1 = A('#2 = B(;');\n  #2 = B(2);\nENDSEC;";
+        let err = Error::UnknownEntity(3).at_entity_instance(2).locate(input);
+        assert_eq!(
+            err.position(),
+            Some(Position {
+                offset: input.find("#2 = B(2").unwrap(),
+                line: 3,
+                column: 3
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            "Error in entity instance #2 at line 3, column 3: Lookup failed for #3"
+        );
+    }
 }
@@ -20,8 +20,11 @@
 //! we write these definitions manually to keep development process simple.
 //!
 
-use crate::{ast::*, error::Result};
-use serde::Deserialize;
+use crate::{
+    ast::{ser::to_record, *},
+    error::Result,
+};
+use serde::{Deserialize, Serialize};
 
 /// File description
 ///
@@ -34,7 +37,8 @@ use serde::Deserialize;
 ///   implementation_level : STRING (256) ;
 /// END_ENTITY;
 /// ```
-#[derive(Debug, Clone, PartialEq, ruststep_derive::Deserialize)]
+#[derive(Debug, Clone, PartialEq, ruststep_derive::Deserialize, Serialize)]
+#[serde(rename = "FILE_DESCRIPTION")]
 pub struct FileDescription {
     pub description: Vec<String>,
     pub implementation_level: String,
@@ -59,7 +63,8 @@ pub struct FileDescription {
 /// TYPE time_stamp_text = STRING(256);
 /// END_TYPE;
 /// ```
-#[derive(Debug, Clone, PartialEq, ruststep_derive::Deserialize)]
+#[derive(Debug, Clone, PartialEq, ruststep_derive::Deserialize, Serialize)]
+#[serde(rename = "FILE_NAME")]
 pub struct FileName {
     pub name: String,
     /// ISO-8601 formatted date and time specifying when the exchange structure was created.
@@ -84,7 +89,8 @@ pub struct FileName {
 /// TYPE schema_name = STRING(1024);
 /// END_TYPE;
 /// ```
-#[derive(Debug, Clone, PartialEq, ruststep_derive::Deserialize)]
+#[derive(Debug, Clone, PartialEq, ruststep_derive::Deserialize, Serialize)]
+#[serde(rename = "FILE_SCHEMA")]
 pub struct FileSchema {
     pub schema: Vec<String>,
 }
@@ -113,6 +119,15 @@ impl Header {
             file_schema,
         })
     }
+
+    /// Serialize into records of `HEADER` section
+    pub fn to_records(&self) -> Result<Vec<Record>> {
+        Ok(vec![
+            to_record(&self.file_description)?,
+            to_record(&self.file_name)?,
+            to_record(&self.file_schema)?,
+        ])
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +148,6 @@ mod tests {
             .finish()
             .unwrap();
         let header = super::Header::from_records(&records).unwrap();
-        dbg!(header);
+        assert_eq!(header.to_records().unwrap(), records);
     }
 }
@@ -97,6 +97,7 @@
 //! - Actual data, triangular geometry in this example, is stored in `DATA` section.
 //!
 //! See the module document of [parser] for detail.
+//! [writer] module writes them back into an exchange structure.
 //!
 //! XML interoperation
 //! -------------------
@@ -113,7 +114,9 @@ pub mod error;
 pub mod header;
 pub mod parser;
 pub mod primitive;
+pub mod runtime;
 pub mod tables;
+pub mod writer;
 
 // To work generated code by ruststep-derive only with ruststep
 pub use derive_more;
@@ -53,6 +53,19 @@ pub fn opt_<'a, O>(f: impl ExchangeParser<'a, O>) -> impl ExchangeParser<'a, Opt
     }
 }
 
+/// Commit to `f`, i.e. turn its recoverable errors into failures
+///
+/// Combinators like [opt_] or [many0_] backtrack on recoverable errors,
+/// and then the error is reported at the beginning of the token which cannot be parsed as a whole.
+/// Use this after the input is determined to be parsed by `f`
+/// to report the position where `f` actually fails.
+pub fn cut_<'a, O>(f: impl ExchangeParser<'a, O>) -> impl ExchangeParser<'a, O> {
+    move |input| {
+        let (input, c) = nom::combinator::cut(f.clone())(input)?;
+        Ok((input, c))
+    }
+}
+
 /// Comment
 ///
 /// A comment shall be encoded as a solidus asterisk `/*`
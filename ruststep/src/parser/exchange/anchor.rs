@@ -29,16 +29,16 @@ pub fn anchor(input: &str) -> ParseResult<Anchor> {
     .parse(input)
 }
 
-/// anchor_item = `$` | [integer] | [real] | [string] | [enumeration] | binary | [rhs_occurrence_name] | [resource] | [anchor_item_list] .
+/// anchor_item = `$` | [integer] | [real] | [string] | [enumeration] | [binary] | [rhs_occurrence_name] | [resource] | [anchor_item_list] .
 pub fn anchor_item(input: &str) -> ParseResult<AnchorItem> {
     alt((
         char_('$').map(|_| AnchorItem::NotProvided),
-        integer.map(AnchorItem::Integer),
         real.map(AnchorItem::Real),
+        integer.map(AnchorItem::Integer),
         string.map(AnchorItem::String),
         rhs_occurrence_name.map(AnchorItem::Name),
         enumeration.map(AnchorItem::Enumeration),
-        // FIXME binary
+        binary.map(AnchorItem::Binary),
         anchor_item_list,
     ))
     .parse(input)
@@ -57,3 +57,20 @@ pub fn anchor_tag(input: &str) -> ParseResult<(String, AnchorItem)> {
         .map(|(_open, name, _colon, item, _close)| (name, item))
         .parse(input)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::AnchorItem;
+    use nom::Finish;
+
+    #[test]
+    fn anchor_item() {
+        let (res, item) = super::anchor_item("2").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(item, AnchorItem::Integer(2));
+
+        let (res, item) = super::anchor_item("2.5").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(item, AnchorItem::Real(2.5));
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     ast::*,
     parser::{combinator::*, exchange::*, token::*},
 };
-use nom::{branch::alt, Parser};
+use nom::Parser;
 
 /// data_section = `DATA` \[ `(` [parameter_list] `)` \] `;` [entity_instance_list] `ENDSEC;` .
 pub fn data_section(input: &str) -> ParseResult<DataSection> {
@@ -28,8 +28,17 @@ pub fn entity_instance_list(input: &str) -> ParseResult<Vec<EntityInstance>> {
 }
 
 /// entity_instance = [simple_entity_instance] | [complex_entity_instance] .
+///
+/// Once `#id =` is parsed, the remaining part must be an entity instance,
+/// and errors in it are reported as failures at the position where the record is broken.
 pub fn entity_instance(input: &str) -> ParseResult<EntityInstance> {
-    alt((simple_entity_instance, complex_entity_instance)).parse(input)
+    let (_, (_id, _eq, open)) =
+        tuple_((entity_instance_name, char_('='), opt_(char_('(')))).parse(input)?;
+    if open.is_some() {
+        cut_(complex_entity_instance).parse(input)
+    } else {
+        cut_(simple_entity_instance).parse(input)
+    }
 }
 
 /// simple_entity_instance = [entity_instance_name] `=` [simple_record] `;` .
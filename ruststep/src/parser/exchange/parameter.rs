@@ -6,8 +6,8 @@ use nom::{branch::alt, combinator::value, Parser};
 
 /// list = `(` \[ [parameter] { `,` [parameter] } \] `)` .
 pub fn list(input: &str) -> ParseResult<Parameter> {
-    tuple_((char_('('), comma_separated(parameter), char_(')')))
-        .map(|(_open, params, _close)| Parameter::List(params))
+    tuple_((char_('('), opt_(comma_separated(parameter)), char_(')')))
+        .map(|(_open, params, _close)| Parameter::List(params.unwrap_or_default()))
         .parse(input)
 }
 
@@ -26,7 +26,7 @@ pub fn typed_parameter(input: &str) -> ParseResult<Parameter> {
         .parse(input)
 }
 
-/// untyped_parameter = `$` | [integer] | [real] | [string] | [rhs_occurrence_name] | [enumeration] | binary | [list] .
+/// untyped_parameter = `$` | [integer] | [real] | [string] | [rhs_occurrence_name] | [enumeration] | [binary] | [list] .
 pub fn untyped_parameter(input: &str) -> ParseResult<Parameter> {
     alt((
         char_('$').map(|_| Parameter::NotProvided),
@@ -35,7 +35,7 @@ pub fn untyped_parameter(input: &str) -> ParseResult<Parameter> {
         string.map(Parameter::String),
         rhs_occurrence_name.map(Parameter::Ref),
         enumeration.map(Parameter::Enumeration),
-        // FIXME binary
+        binary.map(Parameter::Binary),
         list,
     ))
     .parse(input)
@@ -66,4 +66,18 @@ mod tests {
         assert_eq!(res, "");
         assert_eq!(record, Parameter::real(2.0));
     }
+
+    #[test]
+    fn list() {
+        let (res, list) = super::list("()").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(list, Parameter::List(Vec::new()));
+
+        let (res, list) = super::list("(1, ())").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(
+            list,
+            Parameter::List(vec![Parameter::integer(1), Parameter::List(Vec::new())])
+        );
+    }
 }
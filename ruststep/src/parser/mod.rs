@@ -28,12 +28,17 @@
 //! // Parse STEP file into `Exchange` struct
 //! let ex = ruststep::parser::parse(&step_str).unwrap();
 //! ```
+//!
+//! Large exchange structures can be read statement by statement using [reader::Reader].
 
 pub mod basic;
 pub mod combinator;
 pub mod exchange;
+pub mod reader;
 pub mod token;
 
+mod iso8859;
+
 use crate::{
     ast,
     error::{Result, TokenizeFailed},
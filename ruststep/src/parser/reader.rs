@@ -0,0 +1,427 @@
+//! Pull-based reader of exchange structure
+//!
+//! [super::parse] requires entire exchange structure as a `&str`,
+//! and creates [Exchange](crate::ast::Exchange) containing all entity instances.
+//! [Reader] reads exchange structure from [BufRead] statement by statement,
+//! where a statement is a string terminated by `;` outside of strings and comments,
+//! and yields entity instances in `DATA` sections one by one.
+//! Only the current statement is kept in memory while reading `DATA` sections.
+//!
+//! `HEADER`, `ANCHOR`, and `REFERENCE` sections are small,
+//! and are parsed as a whole by [header_section], [anchor_section], and [reference_section].
+//! `SIGNATURE` sections after `END-ISO-10303-21;` are not read.
+//!
+//! Example
+//! --------
+//!
+//! ```
+//! use ruststep::{ast::EntityInstance, parser::reader::Reader};
+//!
+//! let step_str = r#"
+//! ISO-10303-21;
+//! HEADER;
+//!   FILE_DESCRIPTION(('ruststep'), '2;1');
+//!   FILE_NAME('', '', (''), (''), '', '', '');
+//!   FILE_SCHEMA(('EXAMPLE'));
+//! ENDSEC;
+//! DATA;
+//!   #1 = A(1.0, 2.0);
+//!   #2 = B(3.0, #1);
+//! ENDSEC;
+//! END-ISO-10303-21;
+//! "#;
+//!
+//! let mut reader = Reader::new(step_str.as_bytes()).unwrap();
+//! assert_eq!(reader.header().len(), 3);
+//!
+//! let ids: Vec<u64> = reader
+//!     .map(|instance| match instance.unwrap() {
+//!         EntityInstance::Simple { id, .. } => id,
+//!         EntityInstance::Complex { id, .. } => id,
+//!     })
+//!     .collect();
+//! assert_eq!(ids, [1, 2]);
+//! ```
+//!
+//! Entity instances can be inserted into tables without creating [DataSection](crate::ast::DataSection)
+//! by [TableInit::from_entity_instances](crate::tables::TableInit::from_entity_instances).
+
+use crate::{
+    ast::*,
+    error::{Error, Position, Result, TokenizeFailed},
+    parser::{combinator::*, exchange::*},
+};
+use nom::{error::VerboseError, Finish};
+use std::io::BufRead;
+
+/// Section currently read by [Reader]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    /// Between sections, i.e. after `HEADER` section or `ENDSEC;`
+    Body,
+    /// In a `DATA` section
+    Data,
+    /// After `END-ISO-10303-21;` or an error
+    End,
+}
+
+/// Pull-based reader of exchange structure
+///
+/// See the [module document](self) for detail.
+pub struct Reader<R> {
+    input: R,
+    /// Bytes read from `input` but not yet consumed as statements
+    buffer: Vec<u8>,
+    /// Position of the beginning of `buffer` in the exchange structure
+    position: Position,
+    /// Bytes in `buffer` which has been scanned and contain no statement terminator
+    scanned: usize,
+    terminator: Terminator,
+    eof: bool,
+    /// Position of the last entity instance yielded
+    instance_position: Option<Position>,
+
+    section: Section,
+    header: Vec<Record>,
+    anchor: Vec<Anchor>,
+    reference: Vec<ReferenceEntry>,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Read until the end of `HEADER` section
+    pub fn new(input: R) -> Result<Self> {
+        let mut reader = Reader {
+            input,
+            buffer: Vec::new(),
+            position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            scanned: 0,
+            terminator: Terminator::default(),
+            eof: false,
+            instance_position: None,
+            section: Section::Body,
+            header: Vec::new(),
+            anchor: Vec::new(),
+            reference: Vec::new(),
+        };
+        let (statement, base) = reader.expect_statement()?;
+        parse_statement(tag_("ISO-10303-21;"), &statement, base)?;
+        let (section, base) = reader.read_section()?;
+        reader.header = parse_statement(header_section, &section, base)?;
+        Ok(reader)
+    }
+
+    /// Records in `HEADER` section
+    pub fn header(&self) -> &[Record] {
+        &self.header
+    }
+
+    /// Entries in `ANCHOR` section, available after the section is read
+    pub fn anchor(&self) -> &[Anchor] {
+        &self.anchor
+    }
+
+    /// Entries in `REFERENCE` section, available after the section is read
+    pub fn reference(&self) -> &[ReferenceEntry] {
+        &self.reference
+    }
+
+    /// Position of the entity instance yielded last, used to locate errors in it
+    pub fn instance_position(&self) -> Option<Position> {
+        self.instance_position
+    }
+
+    /// Read next entity instance in `DATA` sections
+    fn next_instance(&mut self) -> Result<Option<EntityInstance>> {
+        loop {
+            let (statement, base) = match self.section {
+                Section::End => return Ok(None),
+                _ => self.expect_statement()?,
+            };
+            match (self.section, statement.as_str()) {
+                (Section::Data, "ENDSEC;") => self.section = Section::Body,
+                (Section::Data, _) => {
+                    self.instance_position = Some(base);
+                    return parse_statement(entity_instance, &statement, base).map(Some);
+                }
+                (_, "END-ISO-10303-21;") => self.section = Section::End,
+                (_, "ANCHOR;") => {
+                    let (section, base) = self.read_rest_of_section(statement, base)?;
+                    self.anchor = parse_statement(anchor_section, &section, base)?;
+                }
+                (_, "REFERENCE;") => {
+                    let (section, base) = self.read_rest_of_section(statement, base)?;
+                    self.reference = parse_statement(reference_section, &section, base)?;
+                }
+                _ => {
+                    // Parameters of `DATA` are not used, but check its syntax
+                    parse_statement(
+                        tuple_((
+                            tag_("DATA"),
+                            opt_(tuple_((char_('('), parameter_list, char_(')')))),
+                            char_(';'),
+                        )),
+                        &statement,
+                        base,
+                    )?;
+                    self.section = Section::Data;
+                }
+            }
+        }
+    }
+
+    /// Read a section from its first statement to `ENDSEC;`
+    fn read_section(&mut self) -> Result<(String, Position)> {
+        let (statement, base) = self.expect_statement()?;
+        self.read_rest_of_section(statement, base)
+    }
+
+    fn read_rest_of_section(
+        &mut self,
+        mut section: String,
+        base: Position,
+    ) -> Result<(String, Position)> {
+        loop {
+            let (statement, _) = self.next_statement()?.ok_or_else(|| self.eof_error())?;
+            // Keep spaces and comments between statements to report correct positions
+            section.push_str(&statement);
+            let (tail, ()) = ignorable(&statement).finish().unwrap_or((&statement, ()));
+            if tail == "ENDSEC;" {
+                return Ok((section, base));
+            }
+        }
+    }
+
+    /// Next statement without leading spaces and comments, and its position
+    fn expect_statement(&mut self) -> Result<(String, Position)> {
+        let (statement, position) = self.next_statement()?.ok_or_else(|| self.eof_error())?;
+        let (tail, ()) = ignorable(&statement).finish().unwrap_or((&statement, ()));
+        let head = &statement[..statement.len() - tail.len()];
+        Ok((tail.to_string(), advance(position, head)))
+    }
+
+    /// Next statement including leading spaces and comments, and its position
+    ///
+    /// The last statement without `;` is returned at the end of input.
+    /// `None` is returned only when there is no more non-space input.
+    fn next_statement(&mut self) -> Result<Option<(String, Position)>> {
+        loop {
+            if let Some(end) = self.scan() {
+                return Ok(Some(self.consume(end + 1)?));
+            }
+            if self.eof {
+                if self.buffer.iter().all(|b| b.is_ascii_whitespace()) {
+                    return Ok(None);
+                }
+                return Ok(Some(self.consume(self.buffer.len())?));
+            }
+            let chunk = self.input.fill_buf()?;
+            if chunk.is_empty() {
+                self.eof = true;
+            }
+            self.buffer.extend_from_slice(chunk);
+            let len = chunk.len();
+            self.input.consume(len);
+        }
+    }
+
+    /// Find `;` terminating a statement in the buffer
+    fn scan(&mut self) -> Option<usize> {
+        self.terminator
+            .find(&self.buffer, &mut self.scanned, self.eof)
+    }
+
+    /// Remove first `len` bytes from the buffer as a statement
+    fn consume(&mut self, len: usize) -> Result<(String, Position)> {
+        let rest = self.buffer.split_off(len);
+        let statement = std::mem::replace(&mut self.buffer, rest);
+        let statement = String::from_utf8(statement)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let position = self.position;
+        self.position = advance(position, &statement);
+        self.scanned = 0;
+        Ok((statement, position))
+    }
+
+    fn eof_error(&self) -> Error {
+        let err = VerboseError {
+            errors: vec![(
+                "",
+                nom::error::VerboseErrorKind::Context("unexpected end of exchange structure"),
+            )],
+        };
+        TokenizeFailed::new("", err).shifted(self.position).into()
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<EntityInstance>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_instance() {
+            Ok(instance) => instance.map(Ok),
+            Err(e) => {
+                self.section = Section::End;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Scanner of `;` terminating statements, which skips strings and comments
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Terminator {
+    in_string: bool,
+    in_comment: bool,
+}
+
+impl Terminator {
+    /// Find `;` in `bytes` from `scanned`, which is advanced to the end of scanned bytes
+    ///
+    /// If `eof` is false, a trailing `/` or `*` is kept unscanned to check `/*` or `*/`.
+    pub(crate) fn find(&mut self, bytes: &[u8], scanned: &mut usize, eof: bool) -> Option<usize> {
+        while *scanned < bytes.len() {
+            let i = *scanned;
+            let next = bytes.get(i + 1).cloned();
+            if next.is_none() && !eof && matches!(bytes[i], b'/' | b'*') {
+                // Wait for the next byte to check `/*` or `*/`
+                return None;
+            }
+            *scanned += 1;
+            match bytes[i] {
+                b'*' if self.in_comment && next == Some(b'/') => {
+                    self.in_comment = false;
+                    *scanned += 1;
+                }
+                _ if self.in_comment => {}
+                b'\'' => self.in_string = !self.in_string,
+                _ if self.in_string => {}
+                b'/' if next == Some(b'*') => {
+                    self.in_comment = true;
+                    *scanned += 1;
+                }
+                b';' => return Some(i),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Byte offsets where statements in `input` start,
+/// i.e. the beginning of `input` and the next of each `;` outside of strings and comments
+pub(crate) fn statement_starts(input: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut terminator = Terminator::default();
+    let mut scanned = 0;
+    std::iter::once(0).chain(std::iter::from_fn(move || {
+        terminator
+            .find(input.as_bytes(), &mut scanned, true)
+            .map(|end| end + 1)
+    }))
+}
+
+/// Position after `s` starting from `position`
+fn advance(position: Position, s: &str) -> Position {
+    position.advanced(Position::new(s, s.len()))
+}
+
+/// Parse entire `statement` at `base`
+fn parse_statement<'a, O>(
+    mut parser: impl ExchangeParser<'a, O>,
+    statement: &'a str,
+    base: Position,
+) -> Result<O> {
+    let (residual, out) = parser
+        .parse(statement)
+        .finish()
+        .map_err(|err| TokenizeFailed::new(statement, err).shifted(base))?;
+    let (residual, ()) = ignorable(residual).finish().unwrap_or((residual, ()));
+    if !residual.is_empty() {
+        return Err(Error::ExtraInputRemaining(residual.to_string()));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXCHANGE: &str = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('semicolon; in string'), '2;1');
+FILE_NAME('', '', (''), (''), '', '', '');
+FILE_SCHEMA(('EXAMPLE'));
+ENDSEC;
+DATA;
+#1 = A('it''s; /* not a comment */');
+/* comment; with semicolon */
+#2 = (B(1.0) C(#1));
+ENDSEC;
+DATA(('second'));
+#3 = D(.T.);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+
+    /// [BufRead] returning one byte at once to check statements across chunks
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl std::io::Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.fill_buf()?.len().min(buf.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.consume(len);
+            Ok(len)
+        }
+    }
+
+    impl BufRead for ByteReader<'_> {
+        fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+            Ok(&self.0[..self.0.len().min(1)])
+        }
+        fn consume(&mut self, amt: usize) {
+            self.0 = &self.0[amt..];
+        }
+    }
+
+    #[test]
+    fn same_as_parse() {
+        let ex = crate::parser::parse(EXCHANGE).unwrap();
+        let entities: Vec<_> = ex.data.into_iter().flat_map(|data| data.entities).collect();
+
+        let reader = Reader::new(EXCHANGE.as_bytes()).unwrap();
+        assert_eq!(reader.header(), ex.header.as_slice());
+        assert_eq!(reader.collect::<Result<Vec<_>>>().unwrap(), entities);
+
+        let reader = Reader::new(ByteReader(EXCHANGE.as_bytes())).unwrap();
+        assert_eq!(reader.header(), ex.header.as_slice());
+        assert_eq!(reader.collect::<Result<Vec<_>>>().unwrap(), entities);
+    }
+
+    #[test]
+    fn error_position() {
+        let input = EXCHANGE.replace("#2 = (B(1.0) C(#1));", "#2 = (B(1.0) C($$));");
+        let mut reader = Reader::new(input.as_bytes()).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        let err = match reader.next().unwrap().unwrap_err() {
+            Error::TokenizeFailed(err) => err,
+            e => panic!("Unexpected error: {:?}", e),
+        };
+        assert_eq!(err.position.line, 10);
+        assert_eq!(err.position.column, 14);
+        assert_eq!(err.entity_id, Some(2));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let input = &EXCHANGE[..EXCHANGE.find("#3").unwrap()];
+        let reader = Reader::new(input.as_bytes()).unwrap();
+        let results: Vec<_> = reader.collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[2], Err(Error::TokenizeFailed(_))));
+    }
+}
@@ -3,15 +3,19 @@
 use crate::{
     ast::*,
     parser::{basic::*, combinator::*},
+    primitive::Binary,
 };
 use nom::{
     branch::alt,
+    bytes::complete::tag,
     character::complete::{char, digit0, digit1, multispace0, none_of, satisfy},
-    combinator::opt,
+    combinator::{opt, recognize},
+    error::{VerboseError, VerboseErrorKind},
     multi::{many0, many1},
     sequence::tuple,
     Parser,
 };
+use std::str::FromStr;
 
 /// sign = `+` | `-` .
 pub fn sign(input: &str) -> ParseResult<char> {
@@ -67,10 +71,103 @@ pub fn real(input: &str) -> ParseResult<f64> {
 }
 
 /// string = `'` { [special] | [digit] | [space] | [lower] | [upper] | high_codepoint | [apostrophe] [apostrophe] | [reverse_solidus] [reverse_solidus] | control_directive } `'` .
+///
+/// Escaped apostrophe `''` and reverse solidus `\\` are decoded into `'` and `\`,
+/// and control directives are decoded by [decode_string].
 pub fn string(input: &str) -> ParseResult<String> {
-    tuple((char('\''), many0(none_of("'")), char('\'')))
-        .map(|(_start, s, _end)| s.iter().collect())
-        .parse(input)
+    let escaped = alt((tag("''"), recognize(none_of("'"))));
+    let (residual, (_start, raw, _end)) =
+        tuple((char('\''), recognize(many0(escaped)), char('\''))).parse(input)?;
+    match decode_string(raw) {
+        Some(s) => Ok((residual, s)),
+        None => Err(nom::Err::Error(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context("control directive"))],
+        })),
+    }
+}
+
+/// Decode the content of [string] into Unicode
+///
+/// Following control directives are decoded:
+///
+/// - `\S\` followed by a character `c` is `c + 0x80` in the current ISO 8859 code page.
+/// - `\P?\` switches the code page to ISO 8859-1 (`?` = `A`) to ISO 8859-9 (`?` = `I`).
+///   The initial code page is ISO 8859-1.
+/// - `\X\HH` is the character of ISO 8859-1 in two hexadecimal digits.
+/// - `\X2\HHHH...\X0\` is UTF-16 in groups of four hexadecimal digits.
+/// - `\X4\HHHHHHHH...\X0\` is UCS-4 in groups of eight hexadecimal digits.
+///
+/// A reverse solidus which does not start a control directive is kept as it is.
+/// `None` is returned for broken control directives.
+pub fn decode_string(raw: &str) -> Option<String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut page = 1;
+    let mut rest = raw;
+    while let Some(c) = rest.chars().next() {
+        if let Some(tail) = rest.strip_prefix("''") {
+            out.push('\'');
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("\\\\") {
+            out.push('\\');
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("\\S\\") {
+            let c = tail.chars().next().filter(|c| matches!(c, ' '..='~'))?;
+            out.push(super::iso8859::upper_half(page, c as u8 + 0x80)?);
+            rest = &tail[1..];
+        } else if let [b'\\', b'P', part @ b'A'..=b'I', b'\\', ..] = rest.as_bytes() {
+            page = part - b'A' + 1;
+            rest = &rest[4..];
+        } else if let Some(tail) = rest.strip_prefix("\\X\\") {
+            let code = u8::from_str_radix(tail.get(..2)?, 16).ok()?;
+            out.push(code as char);
+            rest = &tail[2..];
+        } else if let Some(tail) = rest.strip_prefix("\\X2\\") {
+            let (hex, tail) = tail.split_once("\\X0\\")?;
+            let units = hex_groups(hex, 4)?
+                .into_iter()
+                .map(|unit| unit as u16)
+                .collect::<Vec<_>>();
+            for c in char::decode_utf16(units) {
+                out.push(c.ok()?);
+            }
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("\\X4\\") {
+            let (hex, tail) = tail.split_once("\\X0\\")?;
+            for code in hex_groups(hex, 8)? {
+                out.push(char::from_u32(code)?);
+            }
+            rest = tail;
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(out)
+}
+
+/// Split hexadecimal digits into groups of `width` digits
+fn hex_groups(hex: &str, width: usize) -> Option<Vec<u32>> {
+    if hex.len() % width != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(width)
+        .map(|i| u32::from_str_radix(&hex[i..i + width], 16).ok())
+        .collect()
+}
+
+/// binary = `"` ( `0` | `1` | `2` | `3` ) { [hex] } `"` .
+///
+/// The first digit is the number of unused bits at the head of the first [hex].
+pub fn binary(input: &str) -> ParseResult<Binary> {
+    let digits = recognize(tuple((satisfy(|c| matches!(c, '0'..='3')), many0(hex))));
+    let (residual, (_start, digits, _end)) = tuple((char('"'), digits, char('"'))).parse(input)?;
+    match Binary::from_str(digits) {
+        Ok(b) => Ok((residual, b)),
+        Err(_) => Err(nom::Err::Error(VerboseError {
+            errors: vec![(input, VerboseErrorKind::Context("binary"))],
+        })),
+    }
 }
 
 /// resource = `<` UNIVERSAL_RESOURCE_IDENTIFIER `>` .
@@ -257,6 +354,55 @@ mod tests {
         let (res, s) = super::string("'vim'").finish().unwrap();
         assert_eq!(res, "");
         assert_eq!(s, "vim");
+
+        let (res, s) = super::string(r"'It''s C:\\step'").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(s, r"It's C:\step");
+
+        let (res, s) = super::string("''").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(s, "");
+    }
+
+    #[test]
+    fn string_control_directive() {
+        let (res, s) = super::string(r"'\X2\30D130FC30C8\X0\'").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(s, "パート");
+
+        let (res, s) = super::string(r"'\X4\0001F980\X0\ M\X\FCller'")
+            .finish()
+            .unwrap();
+        assert_eq!(res, "");
+        assert_eq!(s, "🦀 Müller");
+
+        // `\PB\` switches to ISO 8859-2, and `\S\9` is 0xB9
+        let (res, s) = super::string(r"'\S\9\PB\\S\9'").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(s, "¹š");
+
+        // Reverse solidus not starting a control directive
+        let (res, s) = super::string(r"'C:\Program Files'").finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(s, r"C:\Program Files");
+
+        assert!(super::string(r"'\X2\30D\X0\'").finish().is_err());
+        assert!(super::string(r"'\X2\30D1'").finish().is_err());
+    }
+
+    #[test]
+    fn binary() {
+        let (res, b) = super::binary(r#""0123""#).finish().unwrap();
+        assert_eq!(res, "");
+        assert_eq!(b.len(), 12);
+
+        let (res, b) = super::binary(r#""0""#).finish().unwrap();
+        assert_eq!(res, "");
+        assert!(b.is_empty());
+
+        assert!(super::binary(r#""3""#).finish().is_err());
+        assert!(super::binary(r#""4A""#).finish().is_err());
+        assert!(super::binary(r#""0a""#).finish().is_err());
     }
 
     #[test]
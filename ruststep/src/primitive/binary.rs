@@ -0,0 +1,212 @@
+use serde::{de, ser, Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Name of [Binary] in serde data model
+pub(crate) const BINARY_TOKEN: &str = "ruststep::primitive::Binary";
+
+/// `BINARY` type, a sequence of bits
+///
+/// Bits are stored from the most significant bit of the first byte.
+///
+/// Exchange structure
+/// -------------------
+/// A binary is encoded in exchange structure as hexadecimal digits
+/// following a digit `0`-`3` which is the number of unused bits in the first hexadecimal digit,
+/// e.g. `"0123"` is `0001_0010_0011`, and `"3A"` is `0`.
+/// Unused bits are placed at the head of the first hexadecimal digit:
+///
+/// ```
+/// use ruststep::primitive::Binary;
+/// use std::str::FromStr;
+///
+/// let b = Binary::from_str("0123").unwrap();
+/// assert_eq!(b.len(), 12);
+/// assert_eq!(b.iter().collect::<Vec<_>>(), [
+///     false, false, false, true,
+///     false, false, true, false,
+///     false, false, true, true,
+/// ]);
+/// assert_eq!(b.to_string(), "0123");
+///
+/// let b = Binary::from_str("3A").unwrap();
+/// assert_eq!(b.iter().collect::<Vec<_>>(), [false]);
+/// assert_eq!(b.to_string(), "30");
+/// ```
+///
+/// Bytes
+/// ------
+/// ```
+/// use ruststep::primitive::Binary;
+///
+/// let mut b = Binary::from_bytes(&[0xAB]);
+/// assert_eq!(b.len(), 8);
+/// b.push(true);
+/// assert_eq!(b.as_bytes(), &[0xAB, 0x80]);
+/// assert_eq!(b.to_string(), "3157");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Binary {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Binary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create from bytes, each of them has 8 bits
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Binary {
+            bytes: bytes.to_vec(),
+            len: bytes.len() * 8,
+        }
+    }
+
+    /// Bytes where unused bits in the last byte are zero
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Number of bits
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index < self.len {
+            Some(self.bytes[index / 8] & (0x80 >> (index % 8)) != 0)
+        } else {
+            None
+        }
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len % 8 == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.get(i).unwrap())
+    }
+}
+
+impl std::iter::FromIterator<bool> for Binary {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut b = Binary::new();
+        for bit in iter {
+            b.push(bit);
+        }
+        b
+    }
+}
+
+/// Error for parsing [Binary] from a string which is not in the form of exchange structure
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Invalid binary literal: {0}")]
+pub struct ParseBinaryError(String);
+
+impl FromStr for Binary {
+    type Err = ParseBinaryError;
+
+    /// Parse from hexadecimal digits with the leading unused-bit digit, without double quotes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseBinaryError(s.to_string());
+        let mut digits = s.chars();
+        let unused = match digits.next() {
+            Some(c @ '0'..='3') => c as usize - '0' as usize,
+            _ => return Err(err()),
+        };
+        if unused > 0 && s.len() == 1 {
+            return Err(err());
+        }
+        let mut b = Binary::new();
+        for (i, c) in digits.enumerate() {
+            let hex = c
+                .to_digit(16)
+                .filter(|_| !c.is_ascii_lowercase())
+                .ok_or_else(err)?;
+            let skip = if i == 0 { unused } else { 0 };
+            for shift in (0..4 - skip).rev() {
+                b.push(hex & (1 << shift) != 0);
+            }
+        }
+        Ok(b)
+    }
+}
+
+impl fmt::Display for Binary {
+    /// Hexadecimal digits with the leading unused-bit digit, without double quotes
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unused = (4 - self.len % 4) % 4;
+        write!(f, "{}", unused)?;
+        // Pad unused bits at the head, and output every 4 bits
+        let mut hex = 0;
+        for (i, bit) in std::iter::repeat(false)
+            .take(unused)
+            .chain(self.iter())
+            .enumerate()
+        {
+            hex = (hex << 1) | bit as u32;
+            if i % 4 == 3 {
+                write!(f, "{:X}", hex)?;
+                hex = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Binary {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(BINARY_TOKEN, &self.to_string())
+    }
+}
+
+struct Visitor;
+
+impl<'de> de::Visitor<'de> for Visitor {
+    type Value = Binary;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "binary")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Binary::from_str(v).map_err(E::custom)
+    }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Binary::from_bytes(v))
+    }
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Binary {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(BINARY_TOKEN, Visitor)
+    }
+}
@@ -0,0 +1,1042 @@
+//! Runtime support for the code generated from EXPRESS expressions
+//!
+//! espr translates EXPRESS expressions, e.g. derived attributes in `DERIVE` clause,
+//! into Rust code evaluating them on dynamically typed [Value]:
+//!
+//! ```text
+//! ENTITY line;
+//!   start: point;
+//!   stop: point;
+//! DERIVE
+//!   dim: INTEGER := SIZEOF(start.coordinates);
+//! END_ENTITY;
+//! ```
+//!
+//! `dim` becomes a getter method of `Line` struct,
+//! which converts the attribute `start` into [Value] by [ToValue],
+//! looks up the attribute `coordinates` through [EntityValue],
+//! and then evaluates the built-in function [sizeof].
+//!
+//! Indeterminate
+//! --------------
+//! The indeterminate symbol `?` in EXPRESS is [Value::Indeterminate].
+//! Absent `OPTIONAL` attributes are also converted into it.
+//! Most operations return [Value::Indeterminate] if some operand is indeterminate,
+//! and logical operations treat it as [Logical::Unknown].
+//!
+//! ```
+//! use ruststep::{primitive::Logical, runtime::*};
+//!
+//! assert_eq!(add(Value::Integer(1), Value::Real(0.5)), Value::Real(1.5));
+//! assert_eq!(add(Value::Integer(1), Value::Indeterminate), Value::Indeterminate);
+//! assert_eq!(
+//!     and(Value::Logical(Logical::False), Value::Indeterminate),
+//!     Value::Logical(Logical::False)
+//! );
+//! assert_eq!(nvl(Value::Indeterminate, Value::Integer(2)), Value::Integer(2));
+//! ```
+
+use crate::{ast::Name, primitive::*, tables::PlaceHolder};
+use std::{any::Any, cmp::Ordering, fmt, rc::Rc};
+
+mod builtin;
+mod validate;
+
+pub use builtin::*;
+pub use validate::*;
+
+/// Dynamically typed value of EXPRESS
+///
+/// `BOOLEAN` is also represented by [Value::Logical] without [Logical::Unknown].
+#[derive(Debug, Clone)]
+pub enum Value {
+    /// `?`
+    Indeterminate,
+    Integer(i64),
+    Real(f64),
+    Logical(Logical),
+    String(String),
+    Binary(Binary),
+    /// Item of an enumeration in lower case, e.g. `.UP.` is `"up"`
+    Enumeration(String),
+    /// `ARRAY`, `BAG`, `LIST`, and `SET`
+    Aggregate(Vec<Value>),
+    Entity(Rc<dyn EntityValue>),
+}
+
+impl Value {
+    pub fn entity<E: EntityValue + 'static>(entity: E) -> Self {
+        Value::Entity(Rc::new(entity))
+    }
+
+    pub fn is_indeterminate(&self) -> bool {
+        matches!(self, Value::Indeterminate)
+    }
+
+    /// `true` iff the value is [Logical::True], used for conditions
+    pub fn is_true(&self) -> bool {
+        self.to_logical() == Logical::True
+    }
+
+    /// Logical value, [Logical::Unknown] for non-logical values
+    pub fn to_logical(&self) -> Logical {
+        match self {
+            Value::Logical(logical) => *logical,
+            _ => Logical::Unknown,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            Value::Real(r) if r.fract() == 0.0 => Some(*r as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_real(&self) -> Option<f64> {
+        match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Real(r) => Some(*r),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_aggregate(&self) -> Option<&[Value]> {
+        match self {
+            Value::Aggregate(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    pub fn as_entity(&self) -> Option<&dyn EntityValue> {
+        match self {
+            Value::Entity(entity) => Some(entity.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Value equality, entity instances are compared by [EntityValue::value_eq]
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        use Value::*;
+        match (self, other) {
+            (Indeterminate, Indeterminate) => true,
+            (Integer(a), Integer(b)) => a == b,
+            // Integer literals are not distinguished from real literals in EXPRESS sources
+            (Integer(_) | Real(_), Integer(_) | Real(_)) => self.as_real() == other.as_real(),
+            (Logical(a), Logical(b)) => a == b,
+            (String(a), String(b)) => a == b,
+            (Binary(a), Binary(b)) => a == b,
+            (Enumeration(a), Enumeration(b)) => a == b,
+            (Aggregate(a), Aggregate(b)) => a == b,
+            (Entity(a), Entity(b)) => a.value_eq(b.as_ref()),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Indeterminate => write!(f, "?"),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Real(r) => write!(f, "{:?}", r),
+            Value::Logical(l) => write!(f, "{}", l),
+            Value::String(s) => write!(f, "'{}'", s),
+            Value::Binary(b) => write!(f, "\"{}\"", b),
+            Value::Enumeration(e) => write!(f, ".{}.", e.to_uppercase()),
+            Value::Aggregate(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Value::Entity(entity) => match entity.type_names().first() {
+                Some(name) => write!(f, "{}(..)", name),
+                None => write!(f, "(..)"),
+            },
+        }
+    }
+}
+
+/// Entity instance seen from EXPRESS expressions
+///
+/// This is implemented by the structs generated by espr and their holders.
+pub trait EntityValue: fmt::Debug {
+    /// Names of the entity and its supertypes in upper case with schema name, e.g. `"AP.POINT"`,
+    /// which are returned by `TYPEOF`
+    fn type_names(&self) -> Vec<String>;
+
+    /// Value of an explicit attribute,
+    /// [Value::Indeterminate] if this entity does not have the attribute
+    fn attribute(&self, name: &str) -> Value;
+
+    /// Value of a derived attribute of the entity instance `#id` evaluated on `population`,
+    /// [Value::Indeterminate] if this entity does not have the attribute
+    fn derived_attribute(
+        &self,
+        _name: &str,
+        _id: Option<u64>,
+        _population: &dyn Population,
+    ) -> Value {
+        Value::Indeterminate
+    }
+
+    fn as_any(&self) -> &dyn Any;
+
+    /// Value equality `=` between entity instances
+    fn value_eq(&self, other: &dyn EntityValue) -> bool;
+
+    /// Id of the entity instance in tables, `None` if unknown
+    fn id(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Entity instance created by entity constructor in EXPRESS, e.g. `point(0.0, 0.0)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstructedEntity {
+    pub type_names: Vec<String>,
+    pub attributes: Vec<(String, Value)>,
+}
+
+impl EntityValue for ConstructedEntity {
+    fn type_names(&self) -> Vec<String> {
+        self.type_names.clone()
+    }
+
+    fn attribute(&self, name: &str) -> Value {
+        self.attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.clone())
+            .unwrap_or(Value::Indeterminate)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn value_eq(&self, other: &dyn EntityValue) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+}
+
+/// Convert Rust values into [Value]
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl ToValue for i64 {
+    fn to_value(&self) -> Value {
+        Value::Integer(*self)
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Value {
+        Value::Real(*self)
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Logical((*self).into())
+    }
+}
+
+impl ToValue for Logical {
+    fn to_value(&self) -> Value {
+        Value::Logical(*self)
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl ToValue for Binary {
+    fn to_value(&self) -> Value {
+        Value::Binary(self.clone())
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => value.to_value(),
+            None => Value::Indeterminate,
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> Value {
+        Value::Aggregate(self.iter().map(ToValue::to_value).collect())
+    }
+}
+
+impl<T: ToValue, const N: usize> ToValue for [T; N] {
+    fn to_value(&self) -> Value {
+        Value::Aggregate(self.iter().map(ToValue::to_value).collect())
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for Box<T> {
+    fn to_value(&self) -> Value {
+        self.as_ref().to_value()
+    }
+}
+
+/// A reference resolved by [crate::tables::Resolver] becomes the shared instance with its id,
+/// and an unresolved reference is indeterminate
+impl<T: ToValue> ToValue for PlaceHolder<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            PlaceHolder::Ref(_) => Value::Indeterminate,
+            PlaceHolder::Owned(holder) => holder.to_value(),
+            PlaceHolder::Shared(Name::Entity(id), holder) => identified(*id, holder.to_value()),
+            PlaceHolder::Shared(_, holder) => holder.to_value(),
+        }
+    }
+}
+
+/// Supertypes and components in holders are seen through the resolved holders
+impl<T: EntityValue + 'static> EntityValue for PlaceHolder<T> {
+    fn type_names(&self) -> Vec<String> {
+        self.resolved()
+            .map(EntityValue::type_names)
+            .unwrap_or_default()
+    }
+
+    fn attribute(&self, name: &str) -> Value {
+        match self.resolved() {
+            Some(holder) => holder.attribute(name),
+            None => Value::Indeterminate,
+        }
+    }
+
+    fn derived_attribute(&self, name: &str, id: Option<u64>, population: &dyn Population) -> Value {
+        match self.resolved() {
+            Some(holder) => holder.derived_attribute(name, id, population),
+            None => Value::Indeterminate,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        match self.resolved() {
+            Some(holder) => holder.as_any(),
+            None => self,
+        }
+    }
+
+    fn value_eq(&self, other: &dyn EntityValue) -> bool {
+        match self.resolved() {
+            Some(holder) => holder.value_eq(other),
+            None => false,
+        }
+    }
+}
+
+/// Convert [Value] into Rust values, `None` if the value is indeterminate or of another type
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: Value) -> Option<Self> {
+        value.as_integer()
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Option<Self> {
+        value.as_real()
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Option<Self> {
+        value.to_logical().into()
+    }
+}
+
+impl FromValue for Logical {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Logical(logical) => Some(logical),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Binary {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Binary(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Aggregate(elements) => elements.into_iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue, const N: usize> FromValue for [T; N] {
+    fn from_value(value: Value) -> Option<Self> {
+        Vec::<T>::from_value(value)?.try_into().ok()
+    }
+}
+
+fn arithmetic(
+    a: Value,
+    b: Value,
+    integer: impl Fn(i64, i64) -> Option<i64>,
+    real: impl Fn(f64, f64) -> f64,
+) -> Value {
+    match (&a, &b) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            integer(*a, *b).map_or(Value::Indeterminate, Value::Integer)
+        }
+        _ => match (a.as_real(), b.as_real()) {
+            (Some(a), Some(b)) => Value::Real(real(a, b)),
+            _ => Value::Indeterminate,
+        },
+    }
+}
+
+/// `+`, also concatenation of strings and union of aggregates
+pub fn add(a: Value, b: Value) -> Value {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Value::String(a + &b),
+        (Value::Aggregate(mut a), Value::Aggregate(b)) => {
+            a.extend(b);
+            Value::Aggregate(a)
+        }
+        (Value::Aggregate(mut a), b) if !b.is_indeterminate() => {
+            a.push(b);
+            Value::Aggregate(a)
+        }
+        (a, Value::Aggregate(mut b)) if !a.is_indeterminate() => {
+            b.insert(0, a);
+            Value::Aggregate(b)
+        }
+        (a, b) => arithmetic(a, b, i64::checked_add, |a, b| a + b),
+    }
+}
+
+/// `-`, also difference of aggregates
+pub fn sub(a: Value, b: Value) -> Value {
+    match (a, b) {
+        (Value::Aggregate(a), Value::Aggregate(b)) => {
+            Value::Aggregate(a.into_iter().filter(|e| !b.contains(e)).collect())
+        }
+        (Value::Aggregate(a), b) if !b.is_indeterminate() => {
+            Value::Aggregate(a.into_iter().filter(|e| *e != b).collect())
+        }
+        (a, b) => arithmetic(a, b, i64::checked_sub, |a, b| a - b),
+    }
+}
+
+/// `*`, also intersection of aggregates
+pub fn mul(a: Value, b: Value) -> Value {
+    match (a, b) {
+        (Value::Aggregate(a), Value::Aggregate(b)) => {
+            Value::Aggregate(a.into_iter().filter(|e| b.contains(e)).collect())
+        }
+        (a, b) => arithmetic(a, b, i64::checked_mul, |a, b| a * b),
+    }
+}
+
+/// `/`, always real
+pub fn real_div(a: Value, b: Value) -> Value {
+    match (a.as_real(), b.as_real()) {
+        (Some(a), Some(b)) if b != 0.0 => Value::Real(a / b),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `DIV`
+pub fn integer_div(a: Value, b: Value) -> Value {
+    match (a.as_integer(), b.as_integer()) {
+        (Some(a), Some(b)) => a
+            .checked_div(b)
+            .map_or(Value::Indeterminate, Value::Integer),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `MOD`
+pub fn modulo(a: Value, b: Value) -> Value {
+    match (a.as_integer(), b.as_integer()) {
+        (Some(a), Some(b)) => a
+            .checked_rem_euclid(b)
+            .map_or(Value::Indeterminate, Value::Integer),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `**`
+pub fn power(a: Value, b: Value) -> Value {
+    match (&a, &b) {
+        (Value::Integer(a), Value::Integer(b)) if *b >= 0 => u32::try_from(*b)
+            .ok()
+            .and_then(|b| a.checked_pow(b))
+            .map_or(Value::Indeterminate, Value::Integer),
+        _ => match (a.as_real(), b.as_real()) {
+            (Some(a), Some(b)) => Value::Real(a.powf(b)),
+            _ => Value::Indeterminate,
+        },
+    }
+}
+
+/// Unary `-`
+pub fn negate(a: Value) -> Value {
+    match a {
+        Value::Integer(i) => Value::Integer(-i),
+        Value::Real(r) => Value::Real(-r),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `NOT`
+pub fn not(a: Value) -> Value {
+    Value::Logical(!a.to_logical())
+}
+
+/// `AND`
+pub fn and(a: Value, b: Value) -> Value {
+    Value::Logical(a.to_logical() & b.to_logical())
+}
+
+/// `OR`
+pub fn or(a: Value, b: Value) -> Value {
+    Value::Logical(a.to_logical() | b.to_logical())
+}
+
+/// `XOR`
+pub fn xor(a: Value, b: Value) -> Value {
+    Value::Logical(a.to_logical() ^ b.to_logical())
+}
+
+/// Order of comparable values, `None` if incomparable or indeterminate
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Logical(a), Value::Logical(b)) => Some(a.cmp(b)),
+        _ => a.as_real()?.partial_cmp(&b.as_real()?),
+    }
+}
+
+fn relation(a: &Value, b: &Value, f: impl Fn(Ordering) -> bool) -> Value {
+    match compare(a, b) {
+        Some(ord) => Value::Logical(f(ord).into()),
+        None => Value::Logical(Logical::Unknown),
+    }
+}
+
+/// `=`
+pub fn eq(a: Value, b: Value) -> Value {
+    if a.is_indeterminate() || b.is_indeterminate() {
+        return Value::Logical(Logical::Unknown);
+    }
+    match compare(&a, &b) {
+        Some(ord) => Value::Logical((ord == Ordering::Equal).into()),
+        None => Value::Logical((a == b).into()),
+    }
+}
+
+/// `<>`
+pub fn ne(a: Value, b: Value) -> Value {
+    not(eq(a, b))
+}
+
+/// Entity instances are the same if they have the same id in tables,
+/// or are the same object if they are not stored in tables
+fn same_instance(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Entity(a), Value::Entity(b)) => match (a.id(), b.id()) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => std::ptr::eq(Rc::as_ptr(a) as *const u8, Rc::as_ptr(b) as *const u8),
+            _ => false,
+        },
+        (Value::Aggregate(a), Value::Aggregate(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_instance(a, b))
+        }
+        _ => matches!(eq(a.clone(), b.clone()), Value::Logical(Logical::True)),
+    }
+}
+
+/// `:=:`, entity instances are compared by their identity and other values by `=`
+pub fn instance_eq(a: Value, b: Value) -> Value {
+    if a.is_indeterminate() || b.is_indeterminate() {
+        return Value::Logical(Logical::Unknown);
+    }
+    Value::Logical(same_instance(&a, &b).into())
+}
+
+/// `:<>:`
+pub fn instance_ne(a: Value, b: Value) -> Value {
+    not(instance_eq(a, b))
+}
+
+/// `<`
+pub fn lt(a: Value, b: Value) -> Value {
+    relation(&a, &b, |ord| ord == Ordering::Less)
+}
+
+/// `>`
+pub fn gt(a: Value, b: Value) -> Value {
+    relation(&a, &b, |ord| ord == Ordering::Greater)
+}
+
+/// `<=`
+pub fn le(a: Value, b: Value) -> Value {
+    relation(&a, &b, |ord| ord != Ordering::Greater)
+}
+
+/// `>=`
+pub fn ge(a: Value, b: Value) -> Value {
+    relation(&a, &b, |ord| ord != Ordering::Less)
+}
+
+/// `IN`
+pub fn in_(element: Value, aggregate: Value) -> Value {
+    match aggregate {
+        Value::Aggregate(elements) if !element.is_indeterminate() => {
+            Value::Logical(elements.contains(&element).into())
+        }
+        _ => Value::Logical(Logical::Unknown),
+    }
+}
+
+/// `LIKE`, string matching with the patterns defined in ISO 10303-11 12.2.5
+///
+/// ```
+/// use ruststep::{primitive::Logical, runtime::*};
+///
+/// let s = |s: &str| Value::String(s.to_string());
+/// assert_eq!(like(s("The quick"), s("^#* qu*")), Value::Logical(Logical::False));
+/// assert_eq!(like(s("The quick"), s("^@@ qu*")), Value::Logical(Logical::True));
+/// assert_eq!(like(s("A1"), s("@#")), Value::Logical(Logical::True));
+/// assert_eq!(like(s("a*"), s("?\\*")), Value::Logical(Logical::True));
+/// ```
+pub fn like(target: Value, pattern: Value) -> Value {
+    match (target.as_str(), pattern.as_str()) {
+        (Some(target), Some(pattern)) => {
+            let target: Vec<char> = target.chars().collect();
+            let pattern: Vec<char> = pattern.chars().collect();
+            Value::Logical(like_match(&target, &pattern).into())
+        }
+        _ => Value::Logical(Logical::Unknown),
+    }
+}
+
+fn like_match(target: &[char], pattern: &[char]) -> bool {
+    let (p, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => return target.is_empty(),
+    };
+    let single = |f: fn(&char) -> bool| match target.split_first() {
+        Some((c, target)) => f(c) && like_match(target, rest),
+        None => false,
+    };
+    match p {
+        '@' => single(|c| c.is_alphabetic()),
+        '^' => single(|c| c.is_uppercase()),
+        '!' => single(|c| c.is_lowercase()),
+        '?' => single(|_| true),
+        '#' => single(|c| c.is_ascii_digit()),
+        '&' => true,
+        '*' => (0..=target.len()).any(|n| like_match(&target[n..], rest)),
+        '$' => (1..=target.len()).any(|n| {
+            let (word, tail) = target.split_at(n);
+            !word.contains(&' ') && (tail.is_empty() || tail[0] == ' ') && like_match(tail, rest)
+        }),
+        '\\' => match (rest.split_first(), target.split_first()) {
+            (Some((escaped, rest)), Some((c, target))) => escaped == c && like_match(target, rest),
+            _ => false,
+        },
+        p => match target.split_first() {
+            Some((c, target)) => c == p && like_match(target, rest),
+            None => false,
+        },
+    }
+}
+
+/// Interval expression `{ low < item <= high }`, `strict` means `<` instead of `<=`
+pub fn interval(
+    low: Value,
+    low_strict: bool,
+    item: Value,
+    high: Value,
+    high_strict: bool,
+) -> Value {
+    let low = if low_strict {
+        lt(low, item.clone())
+    } else {
+        le(low, item.clone())
+    };
+    let high = if high_strict {
+        lt(item, high)
+    } else {
+        le(item, high)
+    };
+    and(low, high)
+}
+
+/// Aggregate initializer, e.g. `[1, 2, 0:3]` where `0:3` means `0` repeated three times
+pub fn aggregate(elements: Vec<(Value, Option<Value>)>) -> Value {
+    let mut aggregate = Vec::new();
+    for (element, repetition) in elements {
+        match repetition {
+            Some(repetition) => match repetition.as_integer() {
+                Some(n) => {
+                    for _ in 0..n {
+                        aggregate.push(element.clone());
+                    }
+                }
+                None => return Value::Indeterminate,
+            },
+            None => aggregate.push(element),
+        }
+    }
+    Value::Aggregate(aggregate)
+}
+
+/// Attribute reference `value.name` of an explicit attribute
+///
+/// Derived attributes are evaluated on the population by [attribute_in].
+pub fn attribute(value: Value, name: &str) -> Value {
+    match value {
+        Value::Entity(entity) => entity.attribute(name),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// Attribute reference `value.name` of an explicit or derived attribute
+///
+/// Derived attributes are indeterminate without `population`.
+pub fn attribute_in(population: Option<&dyn Population>, value: Value, name: &str) -> Value {
+    match (value, population) {
+        (Value::Entity(entity), Some(population)) => {
+            attribute_of(entity.as_ref(), entity.id(), population, name)
+        }
+        (value, _) => attribute(value, name),
+    }
+}
+
+/// Explicit or derived attribute `name` of `entity`, the entity instance `#id`
+pub fn attribute_of(
+    entity: &dyn EntityValue,
+    id: Option<u64>,
+    population: &dyn Population,
+    name: &str,
+) -> Value {
+    match entity.attribute(name) {
+        Value::Indeterminate => entity.derived_attribute(name, id, population),
+        value => value,
+    }
+}
+
+/// Index `value[index]` starting from `1`, for aggregates, strings and binaries
+pub fn index(value: Value, index: Value) -> Value {
+    let i = match index.as_integer() {
+        Some(i) if i >= 1 => (i - 1) as usize,
+        _ => return Value::Indeterminate,
+    };
+    match value {
+        Value::Aggregate(elements) => elements.get(i).cloned().unwrap_or(Value::Indeterminate),
+        Value::String(s) => s
+            .chars()
+            .nth(i)
+            .map_or(Value::Indeterminate, |c| Value::String(c.to_string())),
+        Value::Binary(b) => b.get(i).map_or(Value::Indeterminate, |bit| {
+            let mut binary = Binary::new();
+            binary.push(bit);
+            Value::Binary(binary)
+        }),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// Assignment `target[index] := value` to an element of aggregate starting from `1`
+///
+/// Nothing happens if `target` is not an aggregate or `index` is out of range.
+pub fn set_index(target: &mut Value, index: Value, value: Value) {
+    if let (Value::Aggregate(elements), Some(i)) = (target, index.as_integer()) {
+        if i >= 1 {
+            if let Some(element) = elements.get_mut((i - 1) as usize) {
+                *element = value;
+            }
+        }
+    }
+}
+
+/// Sub-string or sub-binary `value[begin:end]` starting from `1`
+pub fn range(value: Value, begin: Value, end: Value) -> Value {
+    let (begin, end) = match (begin.as_integer(), end.as_integer()) {
+        (Some(begin), Some(end)) if 1 <= begin && begin <= end => {
+            ((begin - 1) as usize, end as usize)
+        }
+        _ => return Value::Indeterminate,
+    };
+    match value {
+        Value::String(s) if end <= s.chars().count() => {
+            Value::String(s.chars().skip(begin).take(end - begin).collect())
+        }
+        Value::Binary(b) if end <= b.len() => {
+            let mut binary = Binary::new();
+            for bit in b.iter().skip(begin).take(end - begin) {
+                binary.push(bit);
+            }
+            Value::Binary(binary)
+        }
+        _ => Value::Indeterminate,
+    }
+}
+
+/// Complex entity instance construction operator `||`
+pub fn complex(a: Value, b: Value) -> Value {
+    match (a.as_entity(), b.as_entity()) {
+        (Some(a), Some(b)) => {
+            let parts = [a, b];
+            let mut type_names = Vec::new();
+            let mut attributes = Vec::new();
+            for part in parts {
+                match part.as_any().downcast_ref::<ConstructedEntity>() {
+                    Some(constructed) => {
+                        type_names.extend(constructed.type_names.iter().cloned());
+                        attributes.extend(constructed.attributes.iter().cloned());
+                    }
+                    None => return Value::Indeterminate,
+                }
+            }
+            Value::entity(ConstructedEntity {
+                type_names,
+                attributes,
+            })
+        }
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `QUERY(variable <* source | condition)`
+pub fn query(source: Value, condition: impl Fn(Value) -> Value) -> Value {
+    match source {
+        Value::Aggregate(elements) => Value::Aggregate(
+            elements
+                .into_iter()
+                .filter(|element| condition(element.clone()).is_true())
+                .collect(),
+        ),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// Check if the increment control variable of `REPEAT` is in `begin TO end BY step`
+///
+/// The loop is not executed if any of them is indeterminate or `step` is zero.
+pub fn in_repeat_range(variable: &Value, end: &Value, step: &Value) -> bool {
+    let zero = Value::Integer(0);
+    if gt(step.clone(), zero.clone()).is_true() {
+        le(variable.clone(), end.clone()).is_true()
+    } else if lt(step.clone(), zero).is_true() {
+        ge(variable.clone(), end.clone()).is_true()
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(add(Value::Integer(1), Value::Integer(2)), Value::Integer(3));
+        assert_eq!(sub(Value::Real(1.0), Value::Integer(2)), Value::Real(-1.0));
+        assert_eq!(
+            real_div(Value::Integer(1), Value::Integer(2)),
+            Value::Real(0.5)
+        );
+        assert_eq!(
+            real_div(Value::Integer(1), Value::Integer(0)),
+            Value::Indeterminate
+        );
+        assert_eq!(
+            integer_div(Value::Integer(7), Value::Integer(2)),
+            Value::Integer(3)
+        );
+        assert_eq!(
+            modulo(Value::Integer(-7), Value::Integer(2)),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            power(Value::Integer(2), Value::Integer(10)),
+            Value::Integer(1024)
+        );
+        assert_eq!(add(s("ab"), s("c")), s("abc"));
+    }
+
+    #[test]
+    fn procedures() {
+        let i = Value::Integer;
+        let mut list = Value::Aggregate(vec![i(1), i(2)]);
+        insert(&mut list, i(0), i(0));
+        insert(&mut list, i(3), i(3));
+        assert_eq!(list, Value::Aggregate(vec![i(0), i(1), i(2), i(3)]));
+        remove(&mut list, i(2));
+        set_index(&mut list, i(1), i(5));
+        assert_eq!(list, Value::Aggregate(vec![i(5), i(2), i(3)]));
+        assert!(in_repeat_range(&i(3), &i(3), &i(1)));
+        assert!(!in_repeat_range(&i(4), &i(3), &i(1)));
+        assert!(in_repeat_range(&i(3), &i(1), &i(-2)));
+        assert!(!in_repeat_range(&i(1), &i(3), &i(0)));
+        // out of range
+        remove(&mut list, i(4));
+        set_index(&mut list, i(0), i(5));
+        assert_eq!(list, Value::Aggregate(vec![i(5), i(2), i(3)]));
+    }
+
+    #[test]
+    fn aggregates() {
+        let agg = aggregate(vec![
+            (Value::Integer(1), None),
+            (Value::Integer(0), Some(Value::Integer(2))),
+        ]);
+        assert_eq!(
+            agg,
+            Value::Aggregate(vec![
+                Value::Integer(1),
+                Value::Integer(0),
+                Value::Integer(0)
+            ])
+        );
+        assert_eq!(index(agg.clone(), Value::Integer(1)), Value::Integer(1));
+        assert_eq!(index(agg.clone(), Value::Integer(4)), Value::Indeterminate);
+        assert!(in_(Value::Integer(0), agg.clone()).is_true());
+        assert_eq!(
+            query(agg, |x| gt(x, Value::Integer(0))),
+            Value::Aggregate(vec![Value::Integer(1)])
+        );
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(index(s("abc"), Value::Integer(2)), s("b"));
+        assert_eq!(
+            range(s("abcd"), Value::Integer(2), Value::Integer(3)),
+            s("bc")
+        );
+        assert!(like(s("my word"), s("$ word")).is_true());
+        assert!(!like(s("my word"), s("$")).is_true());
+        assert!(like(s("Abc"), s("^&")).is_true());
+    }
+
+    #[test]
+    fn logical() {
+        assert_eq!(
+            eq(Value::Integer(1), Value::Real(1.0)),
+            Value::Logical(Logical::True)
+        );
+        assert_eq!(
+            lt(Value::Integer(1), Value::Indeterminate),
+            Value::Logical(Logical::Unknown)
+        );
+        assert!(interval(
+            Value::Integer(0),
+            true,
+            Value::Real(0.5),
+            Value::Integer(1),
+            false
+        )
+        .is_true());
+        assert_eq!(
+            eq(
+                Value::Enumeration("up".to_string()),
+                Value::Enumeration("up".to_string())
+            ),
+            Value::Logical(Logical::True)
+        );
+    }
+
+    #[test]
+    fn numeric_equality() {
+        assert_eq!(Value::Integer(0), Value::Real(0.0));
+        assert!(in_(
+            Value::Real(2.0),
+            Value::Aggregate(vec![Value::Integer(1), Value::Integer(2)])
+        )
+        .is_true());
+    }
+
+    #[test]
+    fn instance_equality() {
+        let point = |x: f64| {
+            Value::Entity(Rc::new(ConstructedEntity {
+                type_names: vec!["AP.POINT".to_string()],
+                attributes: vec![("x".to_string(), Value::Real(x))],
+            }))
+        };
+        // Same values but different instances
+        assert!(eq(point(0.0), point(0.0)).is_true());
+        assert!(!instance_eq(point(0.0), point(0.0)).is_true());
+        let p = point(0.0);
+        assert!(instance_eq(p.clone(), p).is_true());
+
+        assert!(instance_eq(identified(1, point(0.0)), identified(1, point(1.0))).is_true());
+        assert!(instance_ne(identified(1, point(0.0)), identified(2, point(0.0))).is_true());
+        assert!(instance_eq(
+            Value::Aggregate(vec![identified(1, point(0.0)), Value::Integer(1)]),
+            Value::Aggregate(vec![identified(1, point(0.0)), Value::Real(1.0)])
+        )
+        .is_true());
+    }
+}
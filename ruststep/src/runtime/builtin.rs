@@ -0,0 +1,253 @@
+//! Built-in functions and procedures of EXPRESS defined in ISO 10303-11 15 and 16
+
+use super::*;
+
+fn real(value: Value, f: impl Fn(f64) -> f64) -> Value {
+    match value.as_real() {
+        Some(x) => {
+            let y = f(x);
+            if y.is_finite() {
+                Value::Real(y)
+            } else {
+                Value::Indeterminate
+            }
+        }
+        None => Value::Indeterminate,
+    }
+}
+
+/// `ABS`
+pub fn abs(value: Value) -> Value {
+    match value {
+        Value::Integer(i) => Value::Integer(i.abs()),
+        value => real(value, f64::abs),
+    }
+}
+
+/// `ACOS`
+pub fn acos(value: Value) -> Value {
+    real(value, f64::acos)
+}
+
+/// `ASIN`
+pub fn asin(value: Value) -> Value {
+    real(value, f64::asin)
+}
+
+/// `ATAN(v1, v2)`, the angle of `v1 / v2` in the range of `[-π, π]`
+pub fn atan(v1: Value, v2: Value) -> Value {
+    match (v1.as_real(), v2.as_real()) {
+        (Some(v1), Some(v2)) => Value::Real(v1.atan2(v2)),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `BLENGTH`, the number of bits in a binary
+pub fn blength(value: Value) -> Value {
+    match value {
+        Value::Binary(b) => Value::Integer(b.len() as i64),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `COS`
+pub fn cos(value: Value) -> Value {
+    real(value, f64::cos)
+}
+
+/// `EXISTS`, `BOOLEAN` whether the value is not indeterminate
+pub fn exists(value: Value) -> Value {
+    Value::Logical((!value.is_indeterminate()).into())
+}
+
+/// `EXP`
+pub fn exp(value: Value) -> Value {
+    real(value, f64::exp)
+}
+
+/// `FORMAT(n, f)`
+///
+/// Only the default format is supported, and `f` is ignored.
+pub fn format(value: Value, _format: Value) -> Value {
+    match value {
+        Value::Integer(i) => Value::String(i.to_string()),
+        Value::Real(r) => Value::String(format!("{:?}", r)),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `HIBOUND`
+///
+/// The declared bounds of aggregates are not kept in [Value], and this is always indeterminate.
+pub fn hibound(_value: Value) -> Value {
+    Value::Indeterminate
+}
+
+/// `HIINDEX`, the number of elements for aggregates starting from `1`
+pub fn hiindex(value: Value) -> Value {
+    sizeof(value)
+}
+
+/// `LENGTH`, the number of characters in a string
+pub fn length(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::Integer(s.chars().count() as i64),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `LOBOUND`
+///
+/// The declared bounds of aggregates are not kept in [Value], and this is always indeterminate.
+pub fn lobound(_value: Value) -> Value {
+    Value::Indeterminate
+}
+
+/// `LOINDEX`, always `1` for aggregates
+pub fn loindex(value: Value) -> Value {
+    match value {
+        Value::Aggregate(_) => Value::Integer(1),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `LOG`
+pub fn log(value: Value) -> Value {
+    real(value, f64::ln)
+}
+
+/// `LOG2`
+pub fn log2(value: Value) -> Value {
+    real(value, f64::log2)
+}
+
+/// `LOG10`
+pub fn log10(value: Value) -> Value {
+    real(value, f64::log10)
+}
+
+/// `NVL(v, substitute)`, `substitute` if `v` is indeterminate
+pub fn nvl(value: Value, substitute: Value) -> Value {
+    if value.is_indeterminate() {
+        substitute
+    } else {
+        value
+    }
+}
+
+/// `ODD`
+pub fn odd(value: Value) -> Value {
+    match value.as_integer() {
+        Some(i) => Value::Logical((i % 2 != 0).into()),
+        None => Value::Logical(Logical::Unknown),
+    }
+}
+
+/// `ROLESOF`
+///
+/// Entity instances in [Value] do not know which instances refer them,
+/// and this is always indeterminate.
+pub fn rolesof(_value: Value) -> Value {
+    Value::Indeterminate
+}
+
+/// `SIN`
+pub fn sin(value: Value) -> Value {
+    real(value, f64::sin)
+}
+
+/// `SIZEOF`, the number of elements in an aggregate
+pub fn sizeof(value: Value) -> Value {
+    match value {
+        Value::Aggregate(elements) => Value::Integer(elements.len() as i64),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `SQRT`
+pub fn sqrt(value: Value) -> Value {
+    real(value, f64::sqrt)
+}
+
+/// `TAN`
+pub fn tan(value: Value) -> Value {
+    real(value, f64::tan)
+}
+
+/// `TYPEOF`, the names of the type of the value and its generalized types
+pub fn type_of(value: Value) -> Value {
+    let names: Vec<String> = match value {
+        Value::Indeterminate => return Value::Indeterminate,
+        Value::Integer(_) => vec!["INTEGER".into(), "REAL".into(), "NUMBER".into()],
+        Value::Real(_) => vec!["REAL".into(), "NUMBER".into()],
+        Value::Logical(Logical::Unknown) => vec!["LOGICAL".into()],
+        Value::Logical(_) => vec!["BOOLEAN".into(), "LOGICAL".into()],
+        Value::String(_) => vec!["STRING".into()],
+        Value::Binary(_) => vec!["BINARY".into()],
+        Value::Enumeration(_) => Vec::new(),
+        Value::Aggregate(_) => vec!["AGGREGATE".into()],
+        Value::Entity(entity) => entity.type_names(),
+    };
+    Value::Aggregate(names.into_iter().map(Value::String).collect())
+}
+
+/// `VALUE`, number represented by a string
+pub fn value(value: Value) -> Value {
+    match value.as_str() {
+        Some(s) => {
+            let s = s.trim();
+            if let Ok(i) = s.parse::<i64>() {
+                Value::Integer(i)
+            } else if let Ok(r) = s.parse::<f64>() {
+                Value::Real(r)
+            } else {
+                Value::Indeterminate
+            }
+        }
+        None => Value::Indeterminate,
+    }
+}
+
+/// `VALUE_IN(aggregate, value)`
+pub fn value_in(aggregate: Value, value: Value) -> Value {
+    in_(value, aggregate)
+}
+
+/// `VALUE_UNIQUE`, `true` if all elements of an aggregate are different in value
+pub fn value_unique(value: Value) -> Value {
+    match value {
+        Value::Aggregate(elements) => {
+            if elements.iter().any(Value::is_indeterminate) {
+                return Value::Logical(Logical::Unknown);
+            }
+            let unique = elements
+                .iter()
+                .enumerate()
+                .all(|(i, a)| elements[i + 1..].iter().all(|b| a != b));
+            Value::Logical(unique.into())
+        }
+        _ => Value::Logical(Logical::Unknown),
+    }
+}
+
+/// Built-in procedure `INSERT(L, E, P)`, inserts `E` into the list `L` after the position `P`
+///
+/// `P = 0` inserts at the head. Nothing happens if `L` is not an aggregate or `P` is out of range.
+pub fn insert(list: &mut Value, item: Value, position: Value) {
+    if let (Value::Aggregate(elements), Some(p)) = (list, position.as_integer()) {
+        if 0 <= p && p as usize <= elements.len() {
+            elements.insert(p as usize, item);
+        }
+    }
+}
+
+/// Built-in procedure `REMOVE(L, P)`, removes the element at the position `P` starting from `1`
+///
+/// Nothing happens if `L` is not an aggregate or `P` is out of range.
+pub fn remove(list: &mut Value, position: Value) {
+    if let (Value::Aggregate(elements), Some(p)) = (list, position.as_integer()) {
+        if 1 <= p && p as usize <= elements.len() {
+            elements.remove(p as usize - 1);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! Validation of local `WHERE` rules
+//!
+//! espr compiles the domain rules in `WHERE` clause of entities and defined types
+//! into [Validate] implementations:
+//!
+//! ```text
+//! TYPE positive_length_measure = REAL;
+//! WHERE
+//!   wr1: SELF > 0;
+//! END_TYPE;
+//! ```
+//!
+//! A domain rule is violated only if it evaluates to `FALSE`,
+//! i.e. a rule evaluated to `UNKNOWN` or [Value::Indeterminate] is satisfied.
+//! A rule which espr cannot translate yet is reported
+//! as a [RuleViolation] not evaluated instead of being satisfied.
+//!
+//! Rules referring other instances which refer `SELF`,
+//! e.g. `INVERSE` attributes or `USEDIN(SELF, ...)`,
+//! are evaluated through [Population] when the id of the instance is known.
+//! `Tables::validate` generated by espr converts all entity instances into [Value] only once
+//! as a [Snapshot], which is the [Population] shared by all rules.
+//!
+//! Global rules
+//! -------------
+//! `RULE` declarations are checked over the whole [Population]
+//! by `Tables::check_global_rules` generated by espr:
+//!
+//! ```text
+//! RULE single_front_door FOR (door);
+//! WHERE
+//!   wr1: SIZEOF(QUERY(d <* door | d.name = 'front')) <= 1;
+//! END_RULE;
+//! ```
+//!
+//! where `door` is the set of all instances of `door` and its subtypes by [Population::instances_of].
+
+use super::*;
+use crate::tables::{PlaceHolder, ReferenceIndex, ReferenceTable};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+};
+
+/// A domain rule evaluated to `FALSE`, or not evaluated
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RuleViolation {
+    /// Id of the entity instance, `None` if the value is validated out of tables
+    pub id: Option<u64>,
+    /// Entity or type declaring the rule in upper case with schema name, e.g. `"AP.POSITIVE_LENGTH_MEASURE"`
+    pub declared_in: &'static str,
+    /// Label of the rule, or its position starting from `#1` for an unlabeled rule
+    pub label: &'static str,
+    /// `false` if the rule is not evaluated since espr cannot translate it yet,
+    /// e.g. it calls a function whose body is not supported
+    pub evaluated: bool,
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(id) = self.id {
+            write!(f, "#{}: ", id)?;
+        }
+        if self.evaluated {
+            write!(f, "{} of {} is violated", self.label, self.declared_in)
+        } else {
+            write!(f, "{} of {} is not evaluated", self.label, self.declared_in)
+        }
+    }
+}
+
+/// Check the local `WHERE` rules of a value
+///
+/// This is implemented by the structs generated by espr,
+/// where `T` is the tables of the schema, and the other instances are taken from [Snapshot] of the tables.
+/// The rules of the types of attributes are also checked,
+/// while referred entity instances are checked as other instances.
+pub trait Validate<T> {
+    /// Check the rules as the entity instance `#id`, or a value in it
+    fn validate_rules(&self, id: Option<u64>, population: &Snapshot<T>) -> Vec<RuleViolation>;
+
+    /// Check the rules of a value out of tables
+    ///
+    /// `INVERSE` attributes and `USEDIN(SELF, role)` are indeterminate
+    /// since the instance referred by other instances is unknown.
+    fn validate(&self, population: &Snapshot<T>) -> Vec<RuleViolation> {
+        self.validate_rules(None, population)
+    }
+}
+
+impl<T, V: Validate<T>> Validate<T> for Option<V> {
+    fn validate_rules(&self, id: Option<u64>, population: &Snapshot<T>) -> Vec<RuleViolation> {
+        match self {
+            Some(value) => value.validate_rules(id, population),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<T, V: Validate<T>> Validate<T> for Vec<V> {
+    fn validate_rules(&self, id: Option<u64>, population: &Snapshot<T>) -> Vec<RuleViolation> {
+        self.iter()
+            .flat_map(|value| value.validate_rules(id, population))
+            .collect()
+    }
+}
+
+impl<T, V: Validate<T>, const N: usize> Validate<T> for [V; N] {
+    fn validate_rules(&self, id: Option<u64>, population: &Snapshot<T>) -> Vec<RuleViolation> {
+        self.iter()
+            .flat_map(|value| value.validate_rules(id, population))
+            .collect()
+    }
+}
+
+impl<T, V: Validate<T> + ?Sized> Validate<T> for Box<V> {
+    fn validate_rules(&self, id: Option<u64>, population: &Snapshot<T>) -> Vec<RuleViolation> {
+        self.as_ref().validate_rules(id, population)
+    }
+}
+
+impl<T, V: Validate<T>> Validate<T> for PlaceHolder<V> {
+    fn validate_rules(&self, id: Option<u64>, population: &Snapshot<T>) -> Vec<RuleViolation> {
+        match self.resolved() {
+            Some(holder) => holder.validate_rules(id, population),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Entity instances in tables seen from EXPRESS expressions
+pub trait Population: ReferenceTable {
+    /// Entity instance `#id` with its id by [identified], [Value::Indeterminate] if not found
+    fn instance(&self, id: u64) -> Value;
+
+    /// Ids of all entity instances
+    fn ids(&self) -> Vec<u64>;
+
+    /// All instances of the entity and its subtypes, e.g. `"AP.DOOR"`
+    fn instances_of(&self, type_name: &str) -> Value {
+        let mut ids = self.ids();
+        ids.sort_unstable();
+        Value::Aggregate(
+            ids.into_iter()
+                .map(|id| self.instance(id))
+                .filter(|instance| is_instance_of(instance, type_name))
+                .collect(),
+        )
+    }
+}
+
+/// Entity instances of tables converted into [Value] once for a validation pass
+///
+/// This is created by `Tables::population` generated by espr,
+/// which resolves the references in the tables through [crate::tables::Resolver],
+/// and shared by all rules checked in the pass.
+pub struct Snapshot<'tables, T> {
+    tables: &'tables T,
+    instances: BTreeMap<u64, Value>,
+    /// Cache of [Population::instances_of] keyed by type name
+    instances_of: RefCell<HashMap<String, Value>>,
+}
+
+impl<'tables, T> Snapshot<'tables, T> {
+    /// `instances` are entity instances keyed by their ids, which are [identified] by the ids
+    pub fn new(tables: &'tables T, instances: BTreeMap<u64, Value>) -> Self {
+        Snapshot {
+            tables,
+            instances,
+            instances_of: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn tables(&self) -> &'tables T {
+        self.tables
+    }
+
+    /// Entity instance `#id` as the holder `E`, or a part of [ComplexInstance] as `E`,
+    /// `None` if not found or the instance is not `E`
+    pub fn instance_of<E: 'static>(&self, id: u64) -> Option<&E> {
+        let entity = self.instances.get(&id)?.as_entity()?.as_any();
+        match entity.downcast_ref::<ComplexInstance>() {
+            Some(complex) => complex
+                .parts
+                .iter()
+                .find_map(|part| part.as_entity()?.as_any().downcast_ref()),
+            None => entity.downcast_ref(),
+        }
+    }
+
+    /// Entity instances in the order of their ids
+    pub fn instances(&self) -> impl Iterator<Item = (u64, &Value)> {
+        self.instances.iter().map(|(id, instance)| (*id, instance))
+    }
+}
+
+impl<T: ReferenceTable> ReferenceTable for Snapshot<'_, T> {
+    fn references(&self) -> Option<&ReferenceIndex> {
+        self.tables.references()
+    }
+}
+
+impl<T: ReferenceTable> Population for Snapshot<'_, T> {
+    fn instance(&self, id: u64) -> Value {
+        self.instances
+            .get(&id)
+            .cloned()
+            .unwrap_or(Value::Indeterminate)
+    }
+
+    fn ids(&self) -> Vec<u64> {
+        self.instances.keys().cloned().collect()
+    }
+
+    fn instances_of(&self, type_name: &str) -> Value {
+        self.instances_of
+            .borrow_mut()
+            .entry(type_name.to_string())
+            .or_insert_with(|| {
+                Value::Aggregate(
+                    self.instances
+                        .values()
+                        .filter(|instance| is_instance_of(instance, type_name))
+                        .cloned()
+                        .collect(),
+                )
+            })
+            .clone()
+    }
+}
+
+/// Entity instance taken from [Population] with its id
+#[derive(Debug, Clone)]
+pub struct Identified {
+    pub id: u64,
+    pub entity: Rc<dyn EntityValue>,
+}
+
+impl EntityValue for Identified {
+    fn type_names(&self) -> Vec<String> {
+        self.entity.type_names()
+    }
+
+    fn attribute(&self, name: &str) -> Value {
+        self.entity.attribute(name)
+    }
+
+    fn derived_attribute(
+        &self,
+        name: &str,
+        _id: Option<u64>,
+        population: &dyn Population,
+    ) -> Value {
+        self.entity
+            .derived_attribute(name, Some(self.id), population)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self.entity.as_any()
+    }
+
+    fn value_eq(&self, other: &dyn EntityValue) -> bool {
+        self.entity.value_eq(other)
+    }
+
+    fn id(&self) -> Option<u64> {
+        Some(self.id)
+    }
+}
+
+/// Entity instance stored in the tables of several entities,
+/// e.g. `(CAT(9) HAMSTER(.T.) PET('Tama'))` without a struct generated for the combination
+#[derive(Debug, Clone)]
+pub struct ComplexInstance {
+    pub parts: Vec<Value>,
+}
+
+impl EntityValue for ComplexInstance {
+    fn type_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for part in &self.parts {
+            for name in part
+                .as_entity()
+                .map(EntityValue::type_names)
+                .unwrap_or_default()
+            {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    fn attribute(&self, name: &str) -> Value {
+        self.parts
+            .iter()
+            .map(|part| attribute(part.clone(), name))
+            .find(|value| !value.is_indeterminate())
+            .unwrap_or(Value::Indeterminate)
+    }
+
+    fn derived_attribute(&self, name: &str, id: Option<u64>, population: &dyn Population) -> Value {
+        self.parts
+            .iter()
+            .filter_map(|part| part.as_entity())
+            .map(|part| part.derived_attribute(name, id, population))
+            .find(|value| !value.is_indeterminate())
+            .unwrap_or(Value::Indeterminate)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn value_eq(&self, other: &dyn EntityValue) -> bool {
+        match other.as_any().downcast_ref::<Self>() {
+            Some(other) => {
+                self.parts.len() == other.parts.len()
+                    && self.parts.iter().zip(&other.parts).all(|(a, b)| a == b)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Join the parts of an entity instance stored in the tables of several entities into [ComplexInstance]
+pub fn complex_instance(a: Value, b: Value) -> Value {
+    let mut parts = Vec::new();
+    for value in [a, b] {
+        let complex = value
+            .as_entity()
+            .and_then(|entity| entity.as_any().downcast_ref::<ComplexInstance>())
+            .map(|complex| complex.parts.clone());
+        match complex {
+            Some(complex) => parts.extend(complex),
+            None => parts.push(value),
+        }
+    }
+    Value::entity(ComplexInstance { parts })
+}
+
+/// Attach the id to an entity instance, which enables `USEDIN` and derived attributes on it
+pub fn identified(id: u64, value: Value) -> Value {
+    match value {
+        Value::Entity(entity) => Value::Entity(Rc::new(Identified { id, entity })),
+        value => value,
+    }
+}
+
+/// `SELF` in a derived attribute of `entity`, the entity instance `#id`
+///
+/// The instance in `population` is preferred since `entity` may be a supertype part of it.
+pub fn self_value(entity: &impl ToValue, id: Option<u64>, population: &dyn Population) -> Value {
+    match id {
+        Some(id) => match population.instance(id) {
+            Value::Indeterminate => identified(id, entity.to_value()),
+            instance => instance,
+        },
+        None => entity.to_value(),
+    }
+}
+
+fn is_instance_of(value: &Value, type_name: &str) -> bool {
+    match value {
+        Value::Entity(entity) => entity.type_names().iter().any(|name| name == type_name),
+        _ => false,
+    }
+}
+
+/// Domain rule is violated only if it is `FALSE`
+pub fn violated(rule: Value) -> bool {
+    rule == Value::Logical(Logical::False)
+}
+
+/// `USEDIN(SELF, role)` of the entity instance `#id`
+///
+/// `role` is `'SCHEMA.ENTITY.ATTRIBUTE'`, or empty string for all references.
+/// This is indeterminate if the id is unknown.
+pub fn usedin_in(population: &dyn Population, id: Option<u64>, role: Value) -> Value {
+    let (id, role) = match (id, role) {
+        (Some(id), Value::String(role)) => (id, role.to_uppercase()),
+        _ => return Value::Indeterminate,
+    };
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for reference in population.references_to(id) {
+        let suffix = format!(".{}.{}", reference.entity, reference.attribute).to_uppercase();
+        if (role.is_empty() || role.ends_with(&suffix)) && seen.insert(reference.id) {
+            ids.push(reference.id);
+        }
+    }
+    Value::Aggregate(ids.into_iter().map(|id| population.instance(id)).collect())
+}
+
+/// `USEDIN(value, role)` of the entity instance taken from the population
+///
+/// This is indeterminate if the id of the instance is unknown,
+/// e.g. the instance is an attribute of another instance.
+pub fn usedin_of(population: &dyn Population, value: Value, role: Value) -> Value {
+    match value {
+        Value::Entity(entity) => usedin_in(population, entity.id(), role),
+        _ => Value::Indeterminate,
+    }
+}
+
+/// `INVERSE` attribute `dest FOR attribute` of the entity instance `#id`
+///
+/// `dest` is the type name, e.g. `"AP.DOOR"`.
+/// The instances are aggregated if the attribute is declared as `SET` or `BAG`,
+/// and otherwise this is indeterminate unless exactly one instance refers `#id`.
+pub fn inverse_in(
+    population: &dyn Population,
+    id: Option<u64>,
+    attribute: &str,
+    dest: &str,
+    aggregated: bool,
+) -> Value {
+    let id = match id {
+        Some(id) => id,
+        None => return Value::Indeterminate,
+    };
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for reference in population.references_to(id) {
+        if reference.attribute == attribute && seen.insert(reference.id) {
+            ids.push(reference.id);
+        }
+    }
+    let mut instances: Vec<Value> = ids
+        .into_iter()
+        .map(|id| population.instance(id))
+        .filter(|instance| is_instance_of(instance, dest))
+        .collect();
+    if aggregated {
+        Value::Aggregate(instances)
+    } else if instances.len() == 1 {
+        instances.remove(0)
+    } else {
+        Value::Indeterminate
+    }
+}
@@ -100,13 +100,87 @@
 //! `AHolder` will also be introduced to keep consistency.
 //! These are automated by [ruststep_derive::Holder] proc-macro.
 //!
+//! Complex entity instance
+//! ------------------------
+//! An entity instance in external mapping, e.g. `#7 = (A(1, 2) C(3));` where `c` is a subtype of `a`,
+//! is composed into internal mapping `C(A((1, 2)), 3)` by [Holder::internal_mapping],
+//! and stored in the table of the leaf entity `c`.
+//! When the instance has several leaves, e.g. `employee` and `student` of `person`,
+//! it is stored in the tables of all leaves with the same id.
+//! If the schema declares the combination of leaves as instantiable,
+//! it is also stored in the table of the complex entity, e.g. `employee_and_student`,
+//! whose [Holder::components] are the leaves.
+//! Complex entity tables are not dumped by [TableDump] since the leaf tables keep the same instances.
+//!
+//! Value instances and constants
+//! -------------------------------
+//! A reference may also be a value instance `@11` or a constant `#CONST` and `@CONST`.
+//! Tables keep them in [Names] together with entity tables:
+//!
+//! - Constants are the anchors in `ANCHOR` section, e.g. `#ORIGIN` and `@ORIGIN` refer `<ORIGIN> = #1;`.
+//! - Value instances and entity instances may be defined in `REFERENCE` section, e.g. `@12 = <#ORIGIN>;`,
+//!   where an anchor in the same exchange structure `<#ORIGIN>` is resolved,
+//!   and other URIs are reported as [Error::ExternalReference].
+//! - Value instances may also be registered by [TableInit::append_value_instance].
+//!   They are written in place of `@id` by [TableDump::to_exchange].
+//!
+//! [PlaceHolder::into_owned] follows these names until it reaches an entity instance `#1` or a value,
+//! and a value is deserialized as if it were written in place of the reference.
+//!
+//! Inverse references
+//! -------------------
+//! [TableInit] also registers references between entity instances into [ReferenceIndex],
+//! e.g. `#3 = B(5, #1);` is registered as a reference to `#1` from the attribute `w` of `#3`.
+//! [ReferenceTable::referencing] answers entity instances of a type which refer an entity instance,
+//! which is the basis of `INVERSE` attributes in EXPRESS.
+//! References in value instances and constants are not registered.
+//! espr generates a method for each `INVERSE` attribute on top of [ReferenceTable::inverse],
+//! which also checks the declared cardinality [Bound].
+//!
+//! Shared resolution
+//! ------------------
+//! [IntoOwned::into_owned] copies referred entities into the owned struct,
+//! i.e. an entity referred from thousands of entities are copied thousands of times,
+//! and it never ends for cyclic references.
+//! [Resolver] is an alternative which resolves each entity instance once into a holder,
+//! and replaces references by [PlaceHolder::Shared] pointing the resolved holder through [std::rc::Rc].
+//! Cyclic references are reported as [Error::CyclicReference].
+//!
+//! Dumping table into exchange structure
+//! --------------------------------------
+//! [TableDump] is the inverse of [TableInit].
+//! Each holder in the tables is written as an entity instance keeping its id,
+//! and [PlaceHolder::Ref] is written as a reference e.g. `#1`.
+//! Inline struct e.g. `A((9, 10))` in `#6` is written as a new entity instance with fresh id,
+//! which is larger than any id in the tables:
+//!
+//! ```text
+//! DATA;
+//!   #1 = A(1, 2);
+//!   #2 = A(3, 4);
+//!   #3 = B(5, #1);
+//!   #4 = B(6, #1);
+//!   #5 = B(7, #2);
+//!   #6 = B(8, #7);
+//!   #7 = A(9, 10);
+//! ENDSEC;
+//! ```
+//!
 
-use crate::{ast::*, error::*};
+use crate::{ast::*, error::*, header::Header, parser::reader::Reader};
 use serde::{
     de::{self, IntoDeserializer, VariantAccess},
-    Deserialize,
+    ser, Deserialize,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    marker::PhantomData,
+    rc::Rc,
 };
-use std::{collections::HashMap, fmt, marker::PhantomData};
+
+mod resolver;
+pub use resolver::*;
 
 /// Trait for resolving a reference through entity id
 pub trait IntoOwned: Clone + 'static {
@@ -123,10 +197,94 @@ impl<T: IntoOwned> IntoOwned for Vec<T> {
     }
 }
 
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+    type Table = T::Table;
+    fn into_owned(self, table: &Self::Table) -> Result<Self::Owned> {
+        self.map(|x| x.into_owned(table)).transpose()
+    }
+}
+
+impl<T: IntoOwned, const N: usize> IntoOwned for [T; N] {
+    type Owned = [T::Owned; N];
+    type Table = T::Table;
+    fn into_owned(self, table: &Self::Table) -> Result<Self::Owned> {
+        let owned = self
+            .into_iter()
+            .map(|x| x.into_owned(table))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(owned.try_into().ok().expect("Length of array is kept"))
+    }
+}
+
 /// Trait for a field of tables
 pub trait Holder: IntoOwned {
     fn name() -> &'static str;
     fn attr_len() -> usize;
+
+    /// Names of this entity and all its supertypes, starting from this entity
+    fn entity_names() -> Vec<&'static str> {
+        vec![Self::name()]
+    }
+
+    /// Compose a record in internal mapping from partial records in external mapping,
+    /// e.g. `(BASE(1.0) SUB(2.0))` into `SUB(BASE((1.0)), 2.0)`
+    fn internal_mapping(subsuper: &SubSuperRecord) -> Result<Record> {
+        internal_mapping(Self::name(), subsuper, Vec::new())
+    }
+
+    /// Names of leaf entities if this is a complex entity, e.g. `["EMPLOYEE", "STUDENT"]`.
+    /// Empty for usual entities.
+    fn components() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Sorted names of the partial records of an instance of this entity,
+    /// e.g. `["EMPLOYEE", "PERSON", "STUDENT"]` for the complex entity of employee and student
+    fn partial_names() -> Vec<&'static str> {
+        let mut names = Self::entity_names();
+        if !Self::components().is_empty() {
+            // A complex entity does not appear as a partial record
+            names.retain(|name| *name != Self::name());
+        }
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Declared as `ABSTRACT`, and thus cannot be instantiated without its subtypes
+    fn is_abstract() -> bool {
+        false
+    }
+
+    /// Combinations of subtypes which can be instantiated together,
+    /// e.g. `[["EMPLOYEE"], ["STUDENT"], ["EMPLOYEE", "STUDENT"]]` for `employee ANDOR student`.
+    /// Empty if this entity has no subtypes.
+    fn instantiables() -> Vec<Vec<&'static str>> {
+        Vec::new()
+    }
+
+    /// Subtypes combined by the default `ANDOR` constraint, e.g. `["EMPLOYEE", "STUDENT"]`
+    /// for `person` without subtype constraints.
+    /// They can be instantiated together with each other and with one of [Holder::instantiables].
+    fn andor_subtypes() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Replace references in this holder by shared holders resolved by `resolver`
+    fn resolve(self, resolver: &mut Resolver<Self::Table>) -> Result<Self>;
+
+    /// References to entity instances in this holder as the entity instance `#id`,
+    /// paired with the referred entity ids
+    fn references(&self, _id: u64) -> Vec<(u64, Reference)> {
+        Vec::new()
+    }
+
+    /// Check that the attributes redeclared as `DERIVE` in one of the subtypes `names`
+    /// of this instance are omitted as `*`, and the other attributes are given
+    fn check_derived(&self, _names: &[&str]) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub trait WithVisitor {
@@ -136,6 +294,9 @@ pub trait WithVisitor {
 
 /// Trait for tables which pulls an entity (`T`) from an entity id (`u64`)
 pub trait EntityTable<T: Holder<Table = Self>> {
+    /// Get holder from table without resolving references in it
+    fn get_holder(&self, entity_id: u64) -> Result<T>;
+
     /// Get owned entity from table
     fn get_owned(&self, entity_id: u64) -> Result<T::Owned>;
 
@@ -143,9 +304,408 @@ pub trait EntityTable<T: Holder<Table = Self>> {
     fn owned_iter<'table>(&'table self) -> Box<dyn Iterator<Item = Result<T::Owned>> + 'table>;
 }
 
+/// Value instances and constants in `ANCHOR` section, which are referred by names other than `#11`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Names {
+    values: HashMap<u64, Parameter>,
+    anchors: HashMap<String, Anchor>,
+    /// Entries in `REFERENCE` section, e.g. `@12 = <#ORIGIN>;`
+    references: HashMap<Name, URI>,
+}
+
+impl Names {
+    /// Register a value instance `@id`
+    pub fn insert_value(&mut self, id: u64, value: Parameter) -> Result<()> {
+        let name = Name::Value(id);
+        if self.references.contains_key(&name) || self.values.insert(id, value).is_some() {
+            Err(Error::DuplicatedName(name.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Register an entry of `REFERENCE` section, which defines an entity instance or a value instance by URI
+    pub fn insert_reference(&mut self, entry: ReferenceEntry) -> Result<()> {
+        let defined = match &entry.name {
+            Name::Value(id) => self.values.contains_key(id),
+            _ => false,
+        };
+        let name = entry.name.to_string();
+        if defined || self.references.insert(entry.name, entry.resource).is_some() {
+            Err(Error::DuplicatedName(name))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Register an anchor, which will be referred as `#NAME` or `@NAME`
+    pub fn insert_anchor(&mut self, anchor: Anchor) -> Result<()> {
+        let name = format!("<{}>", anchor.name);
+        if self.anchors.insert(anchor.name.clone(), anchor).is_some() {
+            Err(Error::DuplicatedName(name))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn value(&self, id: u64) -> Option<&Parameter> {
+        self.values.get(&id)
+    }
+
+    pub fn anchor(&self, name: &str) -> Option<&Anchor> {
+        self.anchors.get(name)
+    }
+
+    /// Anchors sorted by their names
+    pub fn anchors(&self) -> Vec<Anchor> {
+        let mut anchors: Vec<Anchor> = self.anchors.values().cloned().collect();
+        anchors.sort_by(|a, b| a.name.cmp(&b.name));
+        anchors
+    }
+
+    pub fn reference(&self, name: &Name) -> Option<&URI> {
+        self.references.get(name)
+    }
+
+    /// Entries of `REFERENCE` section sorted by their names
+    pub fn references(&self) -> Vec<ReferenceEntry> {
+        let mut references: Vec<ReferenceEntry> = self
+            .references
+            .iter()
+            .map(|(name, resource)| ReferenceEntry {
+                name: name.clone(),
+                resource: resource.clone(),
+            })
+            .collect();
+        references.sort_by(|a, b| a.name.cmp(&b.name));
+        references
+    }
+
+    /// The largest id of entity instances defined in `REFERENCE` section
+    pub fn max_entity_id(&self) -> u64 {
+        self.references
+            .keys()
+            .filter_map(|name| match name {
+                Name::Entity(id) => Some(*id),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Replace references to value instances registered by [Names::insert_value] with their values
+    ///
+    /// Exchange structure has no syntax defining a value instance by a value,
+    /// and they are written in place of `@id` when dumping tables.
+    pub fn inline_values(&self, section: &mut DataSection) -> Result<()> {
+        for instance in &mut section.entities {
+            match instance {
+                EntityInstance::Simple { record, .. } => {
+                    self.inline_parameter(&mut record.parameter, &mut Vec::new())?;
+                }
+                EntityInstance::Complex { subsuper, .. } => {
+                    for record in &mut subsuper.0 {
+                        self.inline_parameter(&mut record.parameter, &mut Vec::new())?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn inline_parameter(&self, parameter: &mut Parameter, visiting: &mut Vec<u64>) -> Result<()> {
+        match parameter {
+            Parameter::Ref(Name::Value(id)) if self.values.contains_key(id) => {
+                let id = *id;
+                if visiting.contains(&id) {
+                    return Err(Error::CyclicReference(Name::Value(id)));
+                }
+                *parameter = self.resolve(&Name::Value(id))?;
+                visiting.push(id);
+                self.inline_parameter(parameter, visiting)?;
+                visiting.pop();
+            }
+            Parameter::Typed { parameter, .. } => self.inline_parameter(parameter, visiting)?,
+            Parameter::List(items) => {
+                for item in items {
+                    self.inline_parameter(item, visiting)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Follow value instances and constants until reaching an entity instance or a value
+    ///
+    /// ```
+    /// use ruststep::{ast::*, tables::Names};
+    /// use std::str::FromStr;
+    ///
+    /// let mut names = Names::default();
+    /// names.insert_anchor(Anchor::from_str("<ORIGIN> = @1;").unwrap()).unwrap();
+    /// names.insert_value(1, Parameter::Ref(Name::Entity(3))).unwrap();
+    /// names.insert_anchor(Anchor::from_str("<SCALE> = 2.0;").unwrap()).unwrap();
+    ///
+    /// let origin = names.resolve(&Name::ConstantEntity("ORIGIN".to_string())).unwrap();
+    /// assert_eq!(origin, Parameter::Ref(Name::Entity(3)));
+    /// let scale = names.resolve(&Name::ConstantValue("SCALE".to_string())).unwrap();
+    /// assert_eq!(scale, Parameter::Real(2.0));
+    /// ```
+    ///
+    /// Errors
+    /// -------
+    /// - [Error::UnknownName] if a name is not registered
+    /// - [Error::CyclicReference] if names refer each other without reaching an entity instance or a value
+    /// - [Error::ExternalReference] if a name is defined by URI other than anchors in `REFERENCE` section
+    ///
+    pub fn resolve(&self, name: &Name) -> Result<Parameter> {
+        let mut visited = Vec::new();
+        let mut current = name.clone();
+        loop {
+            if visited.contains(&current) {
+                return Err(Error::CyclicReference(name.clone()));
+            }
+            let next = match (&current, self.references.get(&current)) {
+                (_, Some(resource)) => Some(local_reference(&current, resource)?),
+                (Name::Entity(_), None) => return Ok(Parameter::Ref(current)),
+                (Name::Value(id), None) => self.values.get(id).cloned(),
+                (Name::ConstantEntity(constant) | Name::ConstantValue(constant), None) => self
+                    .anchors
+                    .get(constant)
+                    .map(|anchor| anchor.item.clone().into()),
+            }
+            .ok_or_else(|| Error::UnknownName(current.clone()))?;
+            visited.push(current);
+            match next {
+                Parameter::Ref(name) => current = name,
+                value => return Ok(value),
+            }
+        }
+    }
+}
+
+/// Anchor `<#NAME>` in the same exchange structure referred from `REFERENCE` section as `#NAME` or `@NAME`
+fn local_reference(name: &Name, resource: &URI) -> Result<Parameter> {
+    match resource.0.strip_prefix('#') {
+        Some(anchor) => Ok(Parameter::Ref(match name {
+            Name::Entity(_) | Name::ConstantEntity(_) => Name::ConstantEntity(anchor.to_string()),
+            Name::Value(_) | Name::ConstantValue(_) => Name::ConstantValue(anchor.to_string()),
+        })),
+        None => Err(Error::ExternalReference(resource.0.clone())),
+    }
+}
+
+/// Trait for tables which keep [Names]
+///
+/// Tables without a field of [Names] use the default implementations,
+/// and only entity instances `#11` can be referred in them.
+pub trait NameTable {
+    fn names(&self) -> Option<&Names> {
+        None
+    }
+
+    fn names_mut(&mut self) -> Option<&mut Names> {
+        None
+    }
+
+    /// Resolve a name through [Names::resolve]
+    fn resolve_name(&self, name: &Name) -> Result<Parameter> {
+        match (self.names(), name) {
+            (Some(names), _) => names.resolve(name),
+            (None, Name::Entity(_)) => Ok(Parameter::Ref(name.clone())),
+            (None, _) => Err(Error::UnknownName(name.clone())),
+        }
+    }
+}
+
+/// A reference from an attribute of an entity instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// Id of the referring entity instance
+    pub id: u64,
+    /// Name of the entity which declares the attribute, e.g. `"REPRESENTATION"`
+    pub entity: &'static str,
+    /// Name of the attribute, e.g. `"context_of_items"`
+    pub attribute: &'static str,
+}
+
+/// Index from an entity id into the references to it
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReferenceIndex {
+    inverse: HashMap<u64, Vec<Reference>>,
+    /// Pairs of a target and a reference in `inverse` to ignore duplicated ones
+    registered: HashSet<(u64, Reference)>,
+}
+
+impl ReferenceIndex {
+    /// Register a reference to `#target`. Duplicated reference is ignored.
+    pub fn insert(&mut self, target: u64, reference: Reference) {
+        if self.registered.insert((target, reference)) {
+            self.inverse.entry(target).or_default().push(reference);
+        }
+    }
+
+    /// References to `#target` in registered order
+    pub fn referencing(&self, target: u64) -> &[Reference] {
+        self.inverse
+            .get(&target)
+            .map(|references| references.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Cardinality `[lower:upper]` of an aggregate or an `INVERSE` attribute, `upper` is `None` for `?`
+///
+/// ```text
+/// points: LIST [2:?] OF point;              -- [2:?]
+/// INVERSE
+///   opens: door FOR handle;                 -- [1:1]
+///   hinged: SET [0:?] OF hinge FOR door;    -- [0:?]
+/// ```
+///
+/// The number of elements of an aggregate is checked in deserialization by `#[holder(bound = 2..)]`,
+/// and the number of referencing instances by [ReferenceTable::inverse].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bound {
+    pub lower: usize,
+    pub upper: Option<usize>,
+}
+
+impl Bound {
+    pub fn contains(&self, count: usize) -> bool {
+        self.lower <= count && self.upper.map_or(true, |upper| count <= upper)
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.upper {
+            Some(upper) => write!(f, "[{}:{}]", self.lower, upper),
+            None => write!(f, "[{}:?]", self.lower),
+        }
+    }
+}
+
+/// Trait for tables which keep [ReferenceIndex]
+///
+/// Tables without a field of [ReferenceIndex] use the default implementations,
+/// and no entity instance is found referring another.
+pub trait ReferenceTable {
+    fn references(&self) -> Option<&ReferenceIndex> {
+        None
+    }
+
+    fn references_mut(&mut self) -> Option<&mut ReferenceIndex> {
+        None
+    }
+
+    /// References to `#target` registered in [ReferenceIndex]
+    fn references_to(&self, target: u64) -> &[Reference] {
+        self.references()
+            .map(|index| index.referencing(target))
+            .unwrap_or_default()
+    }
+
+    /// Entity instances of `T` which refer `#target`, e.g. all `ProductDefinitionShape`s referring a `ProductDefinition`
+    fn referencing<T>(&self, target: u64) -> Result<Vec<T::Owned>>
+    where
+        T: Holder<Table = Self>,
+        Self: EntityTable<T> + Sized,
+    {
+        self.referencing_ids::<T>(target, None)
+            .into_iter()
+            .map(|id| self.get_owned(id))
+            .collect()
+    }
+
+    /// Ids of entity instances of `T` which refer `#target`,
+    /// through the attribute of the name if `attribute` is specified
+    fn referencing_ids<T>(&self, target: u64, attribute: Option<&str>) -> Vec<u64>
+    where
+        T: Holder<Table = Self>,
+        Self: EntityTable<T> + Sized,
+    {
+        let entity_names = T::entity_names();
+        let mut ids = Vec::new();
+        let mut seen = HashSet::new();
+        for reference in self.references_to(target) {
+            if attribute.map_or(false, |attr| {
+                attr != reference.attribute || !entity_names.contains(&reference.entity)
+            }) {
+                continue;
+            }
+            if seen.insert(reference.id) && self.get_holder(reference.id).is_ok() {
+                ids.push(reference.id);
+            }
+        }
+        ids
+    }
+
+    /// Entity instances of `T` which refer `#target` through `attribute`,
+    /// i.e. the value of an `INVERSE` attribute `T FOR attribute` of `#target`
+    ///
+    /// Errors
+    /// -------
+    /// - [Error::InverseCardinality] if the number of instances is out of `bound`
+    ///
+    fn inverse<T>(&self, target: u64, attribute: &str, bound: Bound) -> Result<Vec<T::Owned>>
+    where
+        T: Holder<Table = Self>,
+        Self: EntityTable<T> + Sized,
+    {
+        let ids = self.referencing_ids::<T>(target, Some(attribute));
+        if !bound.contains(ids.len()) {
+            return Err(Error::InverseCardinality {
+                id: target,
+                attribute: attribute.to_string(),
+                count: ids.len(),
+                bound,
+            });
+        }
+        ids.into_iter().map(|id| self.get_owned(id)).collect()
+    }
+}
+
 /// Create Table from [DataSection]
-pub trait TableInit: Default {
-    fn append_data_section(&mut self, section: &DataSection) -> Result<()>;
+pub trait TableInit: Default + NameTable + ReferenceTable {
+    fn append_entity_instance(&mut self, instance: &EntityInstance) -> Result<()>;
+
+    /// Register a value instance `@id`
+    fn append_value_instance(&mut self, id: u64, value: &Parameter) -> Result<()> {
+        let name = Name::Value(id);
+        self.names_mut()
+            .ok_or_else(|| Error::NamesUnavailable(name.to_string()))?
+            .insert_value(id, value.clone())
+    }
+
+    fn append_anchor_section(&mut self, anchors: &[Anchor]) -> Result<()> {
+        for anchor in anchors {
+            let name = format!("<{}>", anchor.name);
+            self.names_mut()
+                .ok_or(Error::NamesUnavailable(name))?
+                .insert_anchor(anchor.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Register entries of `REFERENCE` section, see [Names::insert_reference]
+    fn append_reference_section(&mut self, references: &[ReferenceEntry]) -> Result<()> {
+        for entry in references {
+            let name = entry.name.to_string();
+            self.names_mut()
+                .ok_or(Error::NamesUnavailable(name))?
+                .insert_reference(entry.clone())?;
+        }
+        Ok(())
+    }
+
+    fn append_data_section(&mut self, section: &DataSection) -> Result<()> {
+        for instance in &section.entities {
+            self.append_entity_instance(instance)?;
+        }
+        Ok(())
+    }
 
     fn from_data_section(section: &DataSection) -> Result<Self> {
         let mut table = Self::default();
@@ -160,6 +720,75 @@ pub trait TableInit: Default {
         }
         Ok(table)
     }
+
+    /// Create table from `ANCHOR`, `REFERENCE`, and `DATA` sections
+    fn from_exchange(exchange: &Exchange) -> Result<Self> {
+        let mut table = Self::default();
+        table.append_anchor_section(&exchange.anchor)?;
+        table.append_reference_section(&exchange.reference)?;
+        for section in &exchange.data {
+            table.append_data_section(section)?;
+        }
+        Ok(table)
+    }
+
+    /// Create table from entity instances, e.g. yielded by [crate::parser::reader::Reader]
+    fn from_entity_instances<I>(instances: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = Result<EntityInstance>>,
+    {
+        let mut table = Self::default();
+        for instance in instances {
+            table.append_entity_instance(&instance?)?;
+        }
+        Ok(table)
+    }
+
+    /// Create table from [Reader], where errors in entity instances are reported with their positions
+    fn from_reader<R: std::io::BufRead>(mut reader: Reader<R>) -> Result<Self> {
+        let mut table = Self::default();
+        while let Some(instance) = reader.next() {
+            table.append_entity_instance(&instance?).map_err(|e| {
+                match reader.instance_position() {
+                    Some(position) => e.at_position(position),
+                    None => e,
+                }
+            })?;
+        }
+        table.append_anchor_section(reader.anchor())?;
+        table.append_reference_section(reader.reference())?;
+        Ok(table)
+    }
+}
+
+/// Dump Table into [DataSection], the inverse of [TableInit]
+pub trait TableDump: NameTable {
+    /// Serialize all holders in the table keeping their ids.
+    /// Owned entities in holders are numbered from the next of the largest id in the table.
+    /// An instance stored in several tables, e.g. a complex entity and its components,
+    /// is written once in external mapping.
+    fn to_data_section(&self) -> Result<DataSection>;
+
+    /// Create [Exchange] with a `HEADER` section, `ANCHOR` and `REFERENCE` sections if exist, and a `DATA` section
+    ///
+    /// Value instances registered by [TableInit::append_value_instance] are written in place, see [Names::inline_values].
+    fn to_exchange(&self, header: &Header) -> Result<Exchange> {
+        let mut data = self.to_data_section()?;
+        let (anchor, reference) = match self.names() {
+            Some(names) => {
+                names.inline_values(&mut data)?;
+                (names.anchors(), names.references())
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        Ok(Exchange {
+            header: header.to_records()?,
+            anchor,
+            reference,
+            data: vec![data],
+            signature: Vec::new(),
+        })
+    }
 }
 
 pub fn get_owned<T, Table>(table: &Table, map: &HashMap<u64, T>, entity_id: u64) -> Result<T::Owned>
@@ -173,6 +802,21 @@ where
     }
 }
 
+pub fn get_holder<T: Holder>(map: &HashMap<u64, T>, entity_id: u64) -> Result<T> {
+    map.get(&entity_id)
+        .cloned()
+        .ok_or(Error::UnknownEntity(entity_id))
+}
+
+/// Helper function to register references in the holder `#id` into [ReferenceIndex]
+pub fn index_references<T: Holder>(index: &mut ReferenceIndex, map: &HashMap<u64, T>, id: u64) {
+    if let Some(holder) = map.get(&id) {
+        for (target, reference) in holder.references(id) {
+            index.insert(target, reference);
+        }
+    }
+}
+
 pub fn owned_iter<'table, T, Table>(
     table: &'table Table,
     map: &'table HashMap<u64, T>,
@@ -201,32 +845,311 @@ pub fn insert_record<'de, T: de::Deserialize<'de>>(
     }
 }
 
+/// Helper function to implement [Holder::internal_mapping]
+///
+/// `supertypes` are records of supertypes in internal mapping,
+/// and placed before the parameters of the partial record `name`.
+pub fn internal_mapping(
+    name: &str,
+    subsuper: &SubSuperRecord,
+    supertypes: Vec<Record>,
+) -> Result<Record> {
+    let partial = subsuper
+        .0
+        .iter()
+        .find(|record| record.name == name)
+        .ok_or_else(|| Error::LackedPartialRecord(name.to_string()))?;
+    let mut parameters: Vec<Parameter> = supertypes
+        .into_iter()
+        .map(|record| Parameter::Typed {
+            keyword: record.name,
+            parameter: Box::new(record.parameter),
+        })
+        .collect();
+    match &partial.parameter {
+        Parameter::List(own) => parameters.extend(own.iter().cloned()),
+        _ => unreachable!("Parameter of record must be a list"),
+    }
+    Ok(Record {
+        name: name.to_string(),
+        parameter: Parameter::List(parameters),
+    })
+}
+
+/// Helper function to implement [TableDump] for complex entity instances as the inverse of [internal_mapping]
+///
+/// Decompose `record` in internal mapping into partial records, e.g. `SUB(BASE((1.0)), 2.0)`
+/// into `BASE(1.0)` and `SUB(2.0)`, and append them to `partials`.
+/// `names` are [Holder::partial_names] of the holder serialized into `record`.
+pub fn external_mapping(record: Record, names: &[&str], partials: &mut Vec<Record>) {
+    let parameters = match record.parameter {
+        Parameter::List(parameters) => parameters,
+        _ => unreachable!("Parameter of record must be a list"),
+    };
+    let mut own = Vec::new();
+    for parameter in parameters {
+        match parameter {
+            // Supertypes, or components of a complex entity
+            Parameter::Typed { keyword, parameter }
+                if own.is_empty()
+                    && names.contains(&keyword.as_str())
+                    && matches!(parameter.as_ref(), Parameter::List(_)) =>
+            {
+                let supertype = Record {
+                    name: keyword,
+                    parameter: *parameter,
+                };
+                external_mapping(supertype, names, partials);
+            }
+            _ => own.push(parameter),
+        }
+    }
+    // A complex entity does not appear as a partial record
+    if names.contains(&record.name.as_str()) {
+        partials.push(Record {
+            name: record.name,
+            parameter: Parameter::List(own),
+        });
+    }
+}
+
+/// Helper function to implement TableInit trait for complex entity instances
+///
+/// `names` are the names of partial records, and `holders` are the table names with
+/// [Holder::partial_names] and [Holder::components] of their holders.
+/// Returns the tables into which the instance is inserted:
+///
+/// - the holder whose partial records are exactly `names`, e.g. `SUB` for `(BASE(1.0) SUB(2.0))`,
+///   or the complex entity generated for an instantiable combination of subtypes,
+/// - without such a holder, e.g. for subtypes combined by the default `ANDOR`,
+///   every holder made of a maximal part of the partial records,
+/// - and the components of the complex entities selected above.
+pub fn complex_entity_tables(
+    names: &[&str],
+    holders: &[(&'static str, Vec<&'static str>, Vec<&'static str>)],
+) -> Result<Vec<&'static str>> {
+    let mut names = names.to_vec();
+    names.sort_unstable();
+    names.dedup();
+    // Every partial record must come with the records of its supertypes
+    for (name, partial_names, components) in holders {
+        if components.is_empty() && names.contains(name) {
+            if let Some(lacked) = partial_names.iter().find(|n| !names.contains(n)) {
+                return Err(Error::LackedPartialRecord(lacked.to_string()));
+            }
+        }
+    }
+    let is_part = |partial_names: &[&str]| partial_names.iter().all(|n| names.contains(n));
+    let mut tables: Vec<&'static str> = match holders.iter().find(|(_, p, _)| *p == names) {
+        Some((name, _, _)) => vec![*name],
+        None => holders
+            .iter()
+            .filter(|(_, p, _)| is_part(p))
+            .filter(|(_, p, _)| {
+                !holders.iter().any(|(_, q, _)| {
+                    q.len() > p.len() && is_part(q) && p.iter().all(|n| q.contains(n))
+                })
+            })
+            .map(|(name, _, _)| *name)
+            .collect(),
+    };
+    for (name, _, components) in holders {
+        if tables.contains(name) {
+            tables.extend(components.iter().copied());
+        }
+    }
+    tables.sort_unstable();
+    tables.dedup();
+    Ok(tables)
+}
+
+/// Helper function to implement [Holder::internal_mapping] for complex entities
+///
+/// `components` are records of leaf entities in internal mapping,
+/// e.g. `EMPLOYEE_AND_STUDENT(EMPLOYEE(PERSON(('A')), 10), STUDENT(PERSON(('A')), 'B'))`.
+pub fn complex_mapping(name: &str, components: Vec<Record>) -> Result<Record> {
+    Ok(Record {
+        name: name.to_string(),
+        parameter: Parameter::List(
+            components
+                .into_iter()
+                .map(|record| Parameter::Typed {
+                    keyword: record.name,
+                    parameter: Box::new(record.parameter),
+                })
+                .collect(),
+        ),
+    })
+}
+
+/// Helper function to implement TableInit trait checking the `ABSTRACT` declaration
+/// and subtype constraints, e.g. `ONEOF`, of `T`
+///
+/// `names` are the names of all partial records in an entity instance,
+/// e.g. `["EMPLOYEE", "PERSON", "STUDENT"]`, or the record name of a simple entity instance.
+pub fn check_instantiable<T: Holder>(names: &[&str]) -> Result<()> {
+    let instantiables = T::instantiables();
+    let andor_subtypes = T::andor_subtypes();
+    let mut subtypes: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| {
+            andor_subtypes.contains(name) || instantiables.iter().any(|c| c.contains(name))
+        })
+        .collect();
+    if subtypes.is_empty() {
+        if T::is_abstract() {
+            return Err(Error::AbstractEntity(T::name().to_string()));
+        }
+        return Ok(());
+    }
+    subtypes.sort_unstable();
+    // Subtypes in the default `ANDOR` constraint can be combined freely
+    let constrained: Vec<&str> = subtypes
+        .iter()
+        .copied()
+        .filter(|name| !andor_subtypes.contains(name))
+        .collect();
+    let instantiable = constrained.is_empty()
+        || instantiables.into_iter().any(|mut c| {
+            c.sort_unstable();
+            c == constrained
+        });
+    if instantiable {
+        Ok(())
+    } else {
+        Err(Error::NotInstantiable {
+            supertype: T::name().to_string(),
+            subtypes: subtypes.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+}
+
+/// Helper function to implement TableInit trait for simple entity instances
+///
+/// Different from [insert_record], this checks the attributes omitted as `*`
+/// by [Holder::check_derived].
+pub fn insert_simple_record<'de, T: Holder + de::Deserialize<'de>>(
+    table: &mut HashMap<u64, T>,
+    id: u64,
+    record: &Record,
+) -> crate::error::Result<()> {
+    // Attributes are also derived in the instances of subtypes of the entity redeclaring them
+    insert_holder(table, id, record, &T::entity_names())
+}
+
+/// Helper function to implement TableInit trait for complex entity instances
+pub fn insert_subsuper_record<'de, T: Holder + de::Deserialize<'de>>(
+    table: &mut HashMap<u64, T>,
+    id: u64,
+    subsuper: &SubSuperRecord,
+) -> crate::error::Result<()> {
+    let names: Vec<&str> = subsuper
+        .0
+        .iter()
+        .map(|record| record.name.as_str())
+        .collect();
+    let record = T::internal_mapping(subsuper)?;
+    insert_holder(table, id, &record, &names)
+}
+
+fn insert_holder<'de, T: Holder + de::Deserialize<'de>>(
+    table: &mut HashMap<u64, T>,
+    id: u64,
+    record: &Record,
+    names: &[&str],
+) -> crate::error::Result<()> {
+    let holder: T = de::Deserialize::deserialize(record)?;
+    holder.check_derived(names)?;
+    if table.insert(id, holder).is_some() {
+        Err(Error::DuplicatedEntity(id))
+    } else {
+        Ok(())
+    }
+}
+
 /// Owned value or reference through entity/value id
 #[derive(Debug, Clone, PartialEq)]
 pub enum PlaceHolder<T> {
     Ref(Name),
     Owned(T),
+    /// Reference resolved by [Resolver]
+    Shared(Name, Rc<T>),
+}
+
+impl<T> PlaceHolder<T> {
+    /// Id of the entity instance if this is a reference to it
+    pub fn entity_id(&self) -> Option<u64> {
+        match self {
+            PlaceHolder::Ref(Name::Entity(id)) | PlaceHolder::Shared(Name::Entity(id), _) => {
+                Some(*id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Holder if it is owned or resolved
+    pub fn resolved(&self) -> Option<&T> {
+        match self {
+            PlaceHolder::Ref(_) => None,
+            PlaceHolder::Owned(holder) => Some(holder),
+            PlaceHolder::Shared(_, holder) => Some(holder),
+        }
+    }
+
+    /// Replace a reference by [PlaceHolder::Shared], and resolve references in an owned holder
+    ///
+    /// A reference to a value instance or a constant is replaced by an owned holder
+    /// if it is resolved into a value instead of an entity instance.
+    pub fn resolve(self, resolver: &mut Resolver<T::Table>) -> Result<Self>
+    where
+        T: Holder + WithVisitor + for<'de> Deserialize<'de>,
+        T::Table: EntityTable<T> + NameTable,
+    {
+        match self {
+            PlaceHolder::Ref(name) => match resolver.table().resolve_name(&name)? {
+                Parameter::Ref(Name::Entity(id)) => {
+                    Ok(PlaceHolder::Shared(name, resolver.get_shared(id)?))
+                }
+                value => match PlaceHolder::<T>::deserialize(&value)? {
+                    PlaceHolder::Owned(a) => Ok(PlaceHolder::Owned(a.resolve(resolver)?)),
+                    _ => unreachable!("Names::resolve never returns a reference except entity"),
+                },
+            },
+            PlaceHolder::Owned(a) => Ok(PlaceHolder::Owned(a.resolve(resolver)?)),
+            PlaceHolder::Shared(..) => Ok(self),
+        }
+    }
 }
 
-impl<T: Holder> IntoOwned for PlaceHolder<T>
+impl<T> IntoOwned for PlaceHolder<T>
 where
-    T::Table: EntityTable<T>,
+    T: Holder + WithVisitor + for<'de> Deserialize<'de>,
+    T::Table: EntityTable<T> + NameTable,
 {
     type Owned = T::Owned;
     type Table = T::Table;
     /// Get owned value, or look up entity table and clone it for a reference.
     ///
+    /// Value instances and constants are resolved through [Names],
+    /// and a value reached from them is deserialized into `T`.
+    ///
     /// Errors
     /// -------
     /// - if table lookup failed, i.e. unknown entity id not registered in the table
+    /// - if name resolution failed, see [Names::resolve]
     ///
     fn into_owned(self, table: &Self::Table) -> Result<T::Owned> {
         match self {
-            PlaceHolder::Ref(id) => match id {
-                Name::Entity(id) => table.get_owned(id),
-                _ => unimplemented!("ENTITY is only supported now"),
+            PlaceHolder::Ref(name) => match table.resolve_name(&name)? {
+                Parameter::Ref(Name::Entity(id)) => table.get_owned(id),
+                value => match PlaceHolder::<T>::deserialize(&value)? {
+                    PlaceHolder::Owned(a) => a.into_owned(table),
+                    _ => unreachable!("Names::resolve never returns a reference except entity"),
+                },
             },
             PlaceHolder::Owned(a) => a.into_owned(table),
+            PlaceHolder::Shared(_, a) => a.as_ref().clone().into_owned(table),
         }
     }
 }
@@ -243,6 +1166,18 @@ impl<T> From<Name> for PlaceHolder<T> {
     }
 }
 
+impl<T: ser::Serialize> ser::Serialize for PlaceHolder<T> {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self {
+            PlaceHolder::Ref(name) | PlaceHolder::Shared(name, _) => name.serialize(serializer),
+            PlaceHolder::Owned(owned) => owned.serialize(serializer),
+        }
+    }
+}
+
 impl<'de, T: Holder + WithVisitor + Deserialize<'de>> Deserialize<'de> for PlaceHolder<T> {
     fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
     where
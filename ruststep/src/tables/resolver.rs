@@ -0,0 +1,72 @@
+use super::*;
+use std::{any::Any, any::TypeId, rc::Rc};
+
+/// Resolve references in holders into [PlaceHolder::Shared]
+///
+/// [IntoOwned::into_owned] clones the referred entity for every reference,
+/// and does not terminate for cyclic references.
+/// A resolver instead keeps holders which have already been resolved,
+/// and shares them through [Rc] for all references to the same entity instance:
+///
+/// ```text
+/// #1 = POINT(0.0, 0.0);
+/// #2 = POINT(1.0, 1.0);
+/// #3 = LINE(#1, #2);
+/// #4 = LINE(#2, #1);
+/// ```
+///
+/// When `#3` and `#4` are resolved by [Resolver::get_shared] of the same resolver,
+/// `#1` and `#2` are resolved only once,
+/// and the references to them become [PlaceHolder::Shared] pointing the same `PointHolder`s.
+pub struct Resolver<'table, Table> {
+    table: &'table Table,
+    /// Resolved holders keyed by the type of holder and entity id
+    shared: HashMap<(TypeId, u64), Rc<dyn Any>>,
+    /// Entity ids being resolved now
+    resolving: Vec<u64>,
+}
+
+impl<'table, Table> Resolver<'table, Table> {
+    pub fn new(table: &'table Table) -> Self {
+        Resolver {
+            table,
+            shared: HashMap::new(),
+            resolving: Vec::new(),
+        }
+    }
+
+    pub fn table(&self) -> &'table Table {
+        self.table
+    }
+
+    /// Get the holder of entity instance `#id` whose references are resolved
+    ///
+    /// Errors
+    /// -------
+    /// - [Error::UnknownEntity] if the id is not registered in the table for `T`
+    /// - [Error::CyclicReference] if the entity instance refers itself through references
+    ///
+    pub fn get_shared<T>(&mut self, id: u64) -> Result<Rc<T>>
+    where
+        T: Holder<Table = Table>,
+        Table: EntityTable<T>,
+    {
+        let key = (TypeId::of::<T>(), id);
+        if let Some(shared) = self.shared.get(&key) {
+            return Ok(shared
+                .clone()
+                .downcast()
+                .expect("Resolved holder is stored with its TypeId"));
+        }
+        if self.resolving.contains(&id) {
+            return Err(Error::CyclicReference(Name::Entity(id)));
+        }
+        let holder = self.table.get_holder(id)?;
+        self.resolving.push(id);
+        let resolved = holder.resolve(self);
+        self.resolving.pop();
+        let shared = Rc::new(resolved?);
+        self.shared.insert(key, shared.clone());
+        Ok(shared)
+    }
+}
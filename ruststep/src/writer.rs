@@ -0,0 +1,437 @@
+//! Write [crate::ast] back into exchange structure string
+//!
+//! This module is the inverse of [crate::parser].
+//! Each function corresponds to the WSN definition of the same name in [crate::parser::exchange] or [crate::parser::token],
+//! and writes the AST component into [std::io::Write].
+//!
+//! Layout
+//! -------
+//!
+//! The output uses a fixed layout:
+//!
+//! - Each section keyword, header entity, and entity instance is written in its own line.
+//! - Parameters are separated by `, `.
+//! - Comments are not written since they are dropped while parsing.
+//!
+//! ```text
+//! ISO-10303-21;
+//! HEADER;
+//! FILE_DESCRIPTION(('ruststep'), '2;1');
+//! ...
+//! ENDSEC;
+//! DATA;
+//! #1 = A(1.0, 2.0);
+//! #2 = B(3.0, A((4.0, 5.0)));
+//! ENDSEC;
+//! END-ISO-10303-21;
+//! ```
+//!
+//! Round-trip
+//! -----------
+//!
+//! The output of [write] is parsed by [crate::parser::parse] into the same [Exchange]:
+//!
+//! ```
+//! use ruststep::{parser, writer};
+//!
+//! let step_str = r#"
+//! ISO-10303-21;
+//! HEADER;
+//!   FILE_DESCRIPTION(('It''s a sample'), '2;1');
+//!   FILE_NAME('sample.step', '2022-06-14T00:00:00', ('ruststep'), (''), '', '', '');
+//!   FILE_SCHEMA(('EXAMPLE_GEOMETRY'));
+//! ENDSEC;
+//! DATA;
+//!   #1 = CPT(0.0, 1.0E-7, -2.5);
+//!   #2 = (VX(#1) NAMED(*, $, .T.));
+//! ENDSEC;
+//! END-ISO-10303-21;
+//! "#.trim();
+//!
+//! let ex = parser::parse(step_str).unwrap();
+//! let output = writer::to_string(&ex).unwrap();
+//! assert_eq!(parser::parse(&output).unwrap(), ex);
+//! ```
+
+use crate::{
+    ast::*,
+    error::{Error, Result},
+    primitive::Binary,
+};
+use std::io::Write;
+
+/// Write entire exchange structure into `out`
+pub fn write<W: Write>(mut out: W, ex: &Exchange) -> Result<()> {
+    exchange_file(&mut out, ex)
+}
+
+/// Write entire exchange structure as a string
+pub fn to_string(ex: &Exchange) -> Result<String> {
+    let mut buf = Vec::new();
+    exchange_file(&mut buf, ex)?;
+    Ok(String::from_utf8(buf).expect("Writer must emit valid UTF-8"))
+}
+
+/// exchange_file = `ISO-10303-21;`
+///                 [header_section]
+///              \[ [anchor_section] \]
+///              \[ [reference_section] \]
+///               { [data_section] }
+///                 `END-ISO-10303-21;`
+///               { [signature_section] } .
+pub fn exchange_file<W: Write>(out: &mut W, ex: &Exchange) -> Result<()> {
+    writeln!(out, "ISO-10303-21;")?;
+    header_section(out, &ex.header)?;
+    if !ex.anchor.is_empty() {
+        anchor_section(out, &ex.anchor)?;
+    }
+    if !ex.reference.is_empty() {
+        reference_section(out, &ex.reference)?;
+    }
+    for data in &ex.data {
+        data_section(out, data)?;
+    }
+    writeln!(out, "END-ISO-10303-21;")?;
+    for signature in &ex.signature {
+        signature_section(out, signature)?;
+    }
+    Ok(())
+}
+
+/// header_section = `HEADER;` header_entity header_entity header_entity \[ header_entity_list \] `ENDSEC;` .
+pub fn header_section<W: Write>(out: &mut W, header: &[Record]) -> Result<()> {
+    writeln!(out, "HEADER;")?;
+    for record in header {
+        simple_record(out, record)?;
+        writeln!(out, ";")?;
+    }
+    writeln!(out, "ENDSEC;")?;
+    Ok(())
+}
+
+/// anchor_section = `ANCHOR;` anchor_list `ENDSEC;` .
+pub fn anchor_section<W: Write>(out: &mut W, anchors: &[Anchor]) -> Result<()> {
+    writeln!(out, "ANCHOR;")?;
+    for a in anchors {
+        anchor(out, a)?;
+        writeln!(out)?;
+    }
+    writeln!(out, "ENDSEC;")?;
+    Ok(())
+}
+
+/// anchor = anchor_name `=` [anchor_item] { anchor_tag } `;` .
+pub fn anchor<W: Write>(out: &mut W, anchor: &Anchor) -> Result<()> {
+    write!(out, "<{}> = ", anchor.name)?;
+    anchor_item(out, &anchor.item)?;
+    for (name, item) in &anchor.tags {
+        write!(out, " {{{}: ", name)?;
+        anchor_item(out, item)?;
+        write!(out, "}}")?;
+    }
+    write!(out, ";")?;
+    Ok(())
+}
+
+/// anchor_item = `$` | [integer] | [real] | [string] | [enumeration] | [binary] | [rhs_occurrence_name] | resource | anchor_item_list .
+pub fn anchor_item<W: Write>(out: &mut W, item: &AnchorItem) -> Result<()> {
+    match item {
+        AnchorItem::NotProvided => write!(out, "$")?,
+        AnchorItem::Integer(i) => integer(out, *i)?,
+        AnchorItem::Real(x) => real(out, *x)?,
+        AnchorItem::String(s) => string(out, s)?,
+        AnchorItem::Enumeration(e) => enumeration(out, e)?,
+        AnchorItem::Binary(b) => binary(out, b)?,
+        AnchorItem::Name(name) => rhs_occurrence_name(out, name)?,
+        AnchorItem::List(items) => {
+            write!(out, "(")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                anchor_item(out, item)?;
+            }
+            write!(out, ")")?;
+        }
+    }
+    Ok(())
+}
+
+/// reference_section = `REFERENCE;` reference_list `ENDSEC;` .
+pub fn reference_section<W: Write>(out: &mut W, references: &[ReferenceEntry]) -> Result<()> {
+    writeln!(out, "REFERENCE;")?;
+    for r in references {
+        reference(out, r)?;
+        writeln!(out)?;
+    }
+    writeln!(out, "ENDSEC;")?;
+    Ok(())
+}
+
+/// reference = [rhs_occurrence_name] `=` resource `;` .
+pub fn reference<W: Write>(out: &mut W, reference: &ReferenceEntry) -> Result<()> {
+    rhs_occurrence_name(out, &reference.name)?;
+    write!(out, " = <{}>;", reference.resource.0)?;
+    Ok(())
+}
+
+/// data_section = `DATA` \[ `(` [parameter_list] `)` \] `;` entity_instance_list `ENDSEC;` .
+pub fn data_section<W: Write>(out: &mut W, data: &DataSection) -> Result<()> {
+    write!(out, "DATA")?;
+    if !data.meta.is_empty() {
+        write!(out, "(")?;
+        parameter_list(out, &data.meta)?;
+        write!(out, ")")?;
+    }
+    writeln!(out, ";")?;
+    for instance in &data.entities {
+        entity_instance(out, instance)?;
+        writeln!(out)?;
+    }
+    writeln!(out, "ENDSEC;")?;
+    Ok(())
+}
+
+/// entity_instance = simple_entity_instance | complex_entity_instance .
+pub fn entity_instance<W: Write>(out: &mut W, instance: &EntityInstance) -> Result<()> {
+    match instance {
+        EntityInstance::Simple { id, record } => {
+            write!(out, "#{} = ", id)?;
+            simple_record(out, record)?;
+        }
+        EntityInstance::Complex { id, subsuper } => {
+            write!(out, "#{} = ", id)?;
+            subsuper_record(out, subsuper)?;
+        }
+    }
+    write!(out, ";")?;
+    Ok(())
+}
+
+/// simple_record = keyword `(` \[ [parameter_list] \] `)` .
+///
+/// The parameter of [Record] is usually a [Parameter::List] created by the parser,
+/// and its components are written as a parameter list.
+/// Other parameter is written as a single parameter.
+pub fn simple_record<W: Write>(out: &mut W, record: &Record) -> Result<()> {
+    write!(out, "{}(", record.name)?;
+    match &record.parameter {
+        Parameter::List(params) => parameter_list(out, params)?,
+        p => parameter(out, p)?,
+    }
+    write!(out, ")")?;
+    Ok(())
+}
+
+/// subsuper_record = `(` simple_record_list `)` .
+pub fn subsuper_record<W: Write>(out: &mut W, subsuper: &SubSuperRecord) -> Result<()> {
+    write!(out, "(")?;
+    for (i, record) in subsuper.0.iter().enumerate() {
+        if i > 0 {
+            write!(out, " ")?;
+        }
+        simple_record(out, record)?;
+    }
+    write!(out, ")")?;
+    Ok(())
+}
+
+/// parameter_list = [parameter] { `,` [parameter] } .
+pub fn parameter_list<W: Write>(out: &mut W, params: &[Parameter]) -> Result<()> {
+    for (i, p) in params.iter().enumerate() {
+        if i > 0 {
+            write!(out, ", ")?;
+        }
+        parameter(out, p)?;
+    }
+    Ok(())
+}
+
+/// parameter = typed_parameter | untyped_parameter | omitted_parameter .
+pub fn parameter<W: Write>(out: &mut W, p: &Parameter) -> Result<()> {
+    match p {
+        Parameter::Typed {
+            keyword,
+            parameter: inner,
+        } => {
+            write!(out, "{}(", keyword)?;
+            parameter(out, inner)?;
+            write!(out, ")")?;
+        }
+        Parameter::Integer(i) => integer(out, *i)?,
+        Parameter::Real(x) => real(out, *x)?,
+        Parameter::String(s) => string(out, s)?,
+        Parameter::Enumeration(e) => enumeration(out, e)?,
+        Parameter::Binary(b) => binary(out, b)?,
+        Parameter::List(params) => {
+            write!(out, "(")?;
+            parameter_list(out, params)?;
+            write!(out, ")")?;
+        }
+        Parameter::Ref(name) => rhs_occurrence_name(out, name)?,
+        Parameter::NotProvided => write!(out, "$")?,
+        Parameter::Omitted => write!(out, "*")?,
+    }
+    Ok(())
+}
+
+/// signature_section = `SIGNATURE` signature_content `ENDSEC;` .
+pub fn signature_section<W: Write>(out: &mut W, signature: &str) -> Result<()> {
+    writeln!(out, "SIGNATURE")?;
+    writeln!(out, "{}", signature)?;
+    writeln!(out, "ENDSEC;")?;
+    Ok(())
+}
+
+/// integer = \[ sign \] digit { digit } .
+pub fn integer<W: Write>(out: &mut W, i: i64) -> Result<()> {
+    write!(out, "{}", i)?;
+    Ok(())
+}
+
+/// real = \[ sign \] digit { digit } `.` { digit } \[ `E` \[ sign \] digit { digit } \] .
+///
+/// The shortest representation which is parsed into the same `f64` is written,
+/// e.g. `1.0`, `-0.25`, or `1.5E-7`.
+///
+/// Error
+/// ------
+/// - [Error::NonFiniteReal] if `x` is NaN or infinite, which cannot be represented in exchange structure.
+///
+pub fn real<W: Write>(out: &mut W, x: f64) -> Result<()> {
+    if !x.is_finite() {
+        return Err(Error::NonFiniteReal(x));
+    }
+    // `Debug` of f64 is the shortest representation for round-trip,
+    // and it always contains `.` when exponent is not used.
+    let s = format!("{:?}", x);
+    if let Some((mantissa, exp)) = s.split_once('e') {
+        if mantissa.contains('.') {
+            write!(out, "{}E{}", mantissa, exp)?;
+        } else {
+            write!(out, "{}.0E{}", mantissa, exp)?;
+        }
+    } else {
+        write!(out, "{}", s)?;
+    }
+    Ok(())
+}
+
+/// string = `'` { special | digit | space | lower | upper | high_codepoint | apostrophe apostrophe | reverse_solidus reverse_solidus | control_directive } `'` .
+///
+/// Apostrophe `'` and reverse solidus `\` are escaped as `''` and `\\`.
+/// Other characters than printable ASCII are encoded by control directives
+/// which [crate::parser::token::decode_string] decodes:
+///
+/// - `\X\HH` for ISO 8859-1 characters, e.g. `ü` as `\X\FC`
+/// - `\X2\HHHH...\X0\` for consecutive characters in the basic multilingual plane
+/// - `\X4\HHHHHHHH...\X0\` for consecutive characters out of the basic multilingual plane
+pub fn string<W: Write>(out: &mut W, s: &str) -> Result<()> {
+    write!(out, "'")?;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => write!(out, "''")?,
+            '\\' => write!(out, "\\\\")?,
+            ' '..='~' => write!(out, "{}", c)?,
+            '\0'..='\u{ff}' => write!(out, "\\X\\{:02X}", c as u32)?,
+            '\u{100}'..='\u{ffff}' => {
+                write!(out, "\\X2\\{:04X}", c as u32)?;
+                while let Some(c) = chars.next_if(|c| matches!(c, '\u{100}'..='\u{ffff}')) {
+                    write!(out, "{:04X}", c as u32)?;
+                }
+                write!(out, "\\X0\\")?;
+            }
+            _ => {
+                write!(out, "\\X4\\{:08X}", c as u32)?;
+                while let Some(c) = chars.next_if(|c| *c > '\u{ffff}') {
+                    write!(out, "{:08X}", c as u32)?;
+                }
+                write!(out, "\\X0\\")?;
+            }
+        }
+    }
+    write!(out, "'")?;
+    Ok(())
+}
+
+/// enumeration = `.` upper { upper | digit } `.` .
+pub fn enumeration<W: Write>(out: &mut W, e: &str) -> Result<()> {
+    write!(out, ".{}.", e)?;
+    Ok(())
+}
+
+/// binary = `"` ( `0` | `1` | `2` | `3` ) { hex } `"` .
+pub fn binary<W: Write>(out: &mut W, b: &Binary) -> Result<()> {
+    write!(out, "\"{}\"", b)?;
+    Ok(())
+}
+
+/// rhs_occurrence_name = ( entity_instance_name | value_instance_name | constant_entity_name | constant_value_name ) .
+pub fn rhs_occurrence_name<W: Write>(out: &mut W, name: &Name) -> Result<()> {
+    match name {
+        Name::Entity(id) => write!(out, "#{}", id)?,
+        Name::Value(id) => write!(out, "@{}", id)?,
+        Name::ConstantEntity(name) => write!(out, "#{}", name)?,
+        Name::ConstantValue(name) => write!(out, "@{}", name)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::*;
+    use std::str::FromStr;
+
+    fn real(x: f64) -> String {
+        let mut buf = Vec::new();
+        super::real(&mut buf, x).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn param(p: &Parameter) -> String {
+        let mut buf = Vec::new();
+        super::parameter(&mut buf, p).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn real_round_trip() {
+        assert_eq!(real(1.0), "1.0");
+        assert_eq!(real(-0.25), "-0.25");
+        assert_eq!(real(1e-7), "1.0E-7");
+        assert_eq!(real(1.5e300), "1.5E300");
+        for x in [0.1, 1.0 / 3.0, -2.0e-310, f64::MAX, f64::MIN_POSITIVE, -0.0] {
+            let p = Parameter::from_str(&real(x)).unwrap();
+            assert_eq!(p, Parameter::Real(x));
+        }
+        assert!(super::real(&mut Vec::new(), f64::NAN).is_err());
+        assert!(super::real(&mut Vec::new(), f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn string_escape() {
+        let p = Parameter::string(r"It's C:\step");
+        assert_eq!(param(&p), r"'It''s C:\\step'");
+        assert_eq!(Parameter::from_str(&param(&p)).unwrap(), p);
+    }
+
+    #[test]
+    fn string_control_directive() {
+        let p = Parameter::string("パート Müller 🦀🦀");
+        assert_eq!(
+            param(&p),
+            r"'\X2\30D130FC30C8\X0\ M\X\FCller \X4\0001F9800001F980\X0\'"
+        );
+        assert_eq!(Parameter::from_str(&param(&p)).unwrap(), p);
+    }
+
+    #[test]
+    fn parameter() {
+        let p = Parameter::from_str(r#"A((1, 2.0, 'a', .T., "3A", #1, @2, $, *, B(#C)))"#).unwrap();
+        assert_eq!(
+            param(&p),
+            r#"A((1, 2.0, 'a', .T., "30", #1, @2, $, *, B(#C)))"#
+        );
+    }
+}
@@ -41,3 +41,14 @@ fn abc_dataset() -> anyhow::Result<()> {
     dbg!(exchange);
     Ok(())
 }
+
+#[test]
+fn abc_dataset_reader() -> anyhow::Result<()> {
+    let step_str = format_example()?;
+    let exchange = parser::parse(&step_str)?;
+    let reader = parser::reader::Reader::new(step_str.as_bytes())?;
+    assert_eq!(reader.header(), exchange.header.as_slice());
+    let entities = reader.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(entities, exchange.data[0].entities);
+    Ok(())
+}
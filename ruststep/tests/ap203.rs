@@ -0,0 +1,70 @@
+#![cfg(feature = "ap203")]
+
+use ruststep::{ap203::config_control_design::*, runtime::Value, tables::*};
+use std::str::FromStr;
+
+#[test]
+fn complex_entity_instance() {
+    let table = Tables::from_str(
+        r#"
+        DATA;
+          #1 = (GEOMETRIC_REPRESENTATION_CONTEXT(3) GLOBAL_UNIT_ASSIGNED_CONTEXT((#2)) REPRESENTATION_CONTEXT('ID1', '3D'));
+          #2 = NAMED_UNIT(#3);
+          #3 = DIMENSIONAL_EXPONENTS(1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        ENDSEC;
+        "#,
+    )
+    .unwrap();
+
+    let context =
+        EntityTable::<GeometricRepresentationContextHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(context.coordinate_space_dimension, DimensionCount(3));
+    assert_eq!(
+        context.representation_context.context_identifier,
+        Identifier("ID1".to_string())
+    );
+
+    let context = EntityTable::<GlobalUnitAssignedContextHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(context.units.len(), 1);
+    assert_eq!(
+        context.representation_context.context_type,
+        Text("3D".to_string())
+    );
+}
+
+#[test]
+fn derived_dimension() {
+    let table = Tables::from_str(
+        r#"
+        DATA;
+          #1 = (GEOMETRIC_REPRESENTATION_CONTEXT(3) GLOBAL_UNIT_ASSIGNED_CONTEXT((#2)) REPRESENTATION_CONTEXT('ID1', '3D'));
+          #2 = NAMED_UNIT(#3);
+          #3 = DIMENSIONAL_EXPONENTS(1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+          #4 = GEOMETRIC_REPRESENTATION_CONTEXT(REPRESENTATION_CONTEXT(('ID2', '2D')), 2);
+          #11 = CARTESIAN_POINT(POINT((GEOMETRIC_REPRESENTATION_ITEM((REPRESENTATION_ITEM(('p1')))))), (0.0, 0.0, 1.0));
+          #12 = REPRESENTATION('solid', (#11), #1);
+          #21 = CARTESIAN_POINT(POINT((GEOMETRIC_REPRESENTATION_ITEM((REPRESENTATION_ITEM(('p2')))))), (1.0, 2.0));
+          #22 = REPRESENTATION('sketch', (#21), #4);
+        ENDSEC;
+        "#,
+    )
+    .unwrap();
+    let population = table.population().unwrap();
+
+    // `dim` is `dimension_of(SELF)`, the dimension of the context of the representation using the item
+    let p1 = EntityTable::<CartesianPointHolder>::get_owned(&table, 11).unwrap();
+    assert_eq!(
+        GeometricRepresentationItem::dim(&p1, Some(11), &population),
+        Value::Integer(3)
+    );
+    let p2 = EntityTable::<CartesianPointHolder>::get_owned(&table, 21).unwrap();
+    assert_eq!(
+        GeometricRepresentationItem::dim(&p2, Some(21), &population),
+        Value::Integer(2)
+    );
+    // The instance is unknown without its id
+    assert_eq!(
+        GeometricRepresentationItem::dim(&p2, None, &population),
+        Value::Indeterminate
+    );
+}
@@ -0,0 +1,87 @@
+use ruststep::{error::Error, primitive::Binary, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      TYPE pixels = BINARY;
+      END_TYPE;
+
+      ENTITY image;
+        checksum: BINARY (8) FIXED;
+        data: pixels;
+      END_ENTITY;
+
+      ENTITY signature;
+        digest: BINARY (16);
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = IMAGE("0A5", "310F");
+  #2 = IMAGE("0FF", PIXELS("0"));
+ENDSEC;
+"#;
+
+#[test]
+fn deserialize_binary() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let image = EntityTable::<ImageHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(image.checksum.as_bytes(), &[0xA5]);
+    assert_eq!(image.data.0.len(), 9);
+    assert_eq!(image.data.0.to_string(), "310F");
+
+    let image = EntityTable::<ImageHolder>::get_owned(&table, 2).unwrap();
+    assert_eq!(image.checksum, Binary::from_bytes(&[0xFF]));
+    assert!(image.data.0.is_empty());
+}
+
+#[test]
+fn dump_binary() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let data = table.to_data_section().unwrap();
+    assert_eq!(Tables::from_data_section(&data).unwrap(), table);
+}
+
+#[test]
+fn string_is_not_binary() {
+    let err = Tables::from_str("DATA; #1 = IMAGE('0A5', \"310F\"); ENDSEC;").unwrap_err();
+    assert_eq!(err.entity_id(), Some(1));
+    assert!(err.to_string().contains("expected binary"));
+}
+
+#[test]
+fn fixed_width() {
+    // `BINARY (8) FIXED` has exactly 8 bits
+    for checksum in ["\"0F\"", "\"00A5\""] {
+        let input = format!("DATA; #1 = IMAGE({}, \"310F\"); ENDSEC;", checksum);
+        let err = Tables::from_str(&input).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::EntityInstanceFailed { id: 1, source, .. }
+                if source.to_string().contains("expected 8 to 8 bits")
+        ));
+    }
+}
+
+#[test]
+fn maximum_width() {
+    // `BINARY (16)` has at most 16 bits
+    let table =
+        Tables::from_str("DATA; #1 = SIGNATURE(\"0ABCD\"); #2 = SIGNATURE(\"2C\"); ENDSEC;")
+            .unwrap();
+    let signature = EntityTable::<SignatureHolder>::get_owned(&table, 2).unwrap();
+    assert_eq!(signature.digest.len(), 2);
+
+    let err = Tables::from_str("DATA; #1 = SIGNATURE(\"0ABCDE\"); ENDSEC;").unwrap_err();
+    assert!(matches!(
+        err,
+        Error::EntityInstanceFailed { id: 1, source, .. }
+            if source.to_string().contains("expected 0 to 16 bits")
+    ));
+}
@@ -0,0 +1,76 @@
+use ruststep::tables::*;
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY point;
+        coordinates: ARRAY [1:3] OF REAL;
+      END_ENTITY;
+
+      ENTITY triangle;
+        vertices: ARRAY [1:3] OF point;
+        normal: OPTIONAL LIST [3:3] OF REAL;
+      END_ENTITY;
+
+      ENTITY polyline;
+        points: LIST [2:?] OF point;
+        weights: ARRAY [0:1] OF OPTIONAL REAL;
+      END_ENTITY;
+
+      ENTITY group;
+        members: BAG [1:2 * 1] OF point;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const POINTS: &str = r#"
+  #1 = POINT((0.0, 0.0, 0.0));
+  #2 = POINT((1.0, 0.0, 0.0));
+  #3 = POINT((0.0, 1.0, 0.0));
+"#;
+
+fn load(data: &str) -> ruststep::error::Result<Tables> {
+    Tables::from_str(&format!("DATA;\n{}\n{}\nENDSEC;\n", POINTS, data))
+}
+
+#[test]
+fn fixed_size_array() {
+    let table = load("#4 = TRIANGLE((#1, #2, #3), $);").unwrap();
+    let triangle = EntityTable::<TriangleHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(triangle.vertices[1].coordinates, [1.0, 0.0, 0.0]);
+    assert_eq!(triangle.normal, None);
+
+    let table = load("#4 = TRIANGLE((#1, #2, #3), (0.0, 0.0, 1.0));").unwrap();
+    let triangle = EntityTable::<TriangleHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(triangle.normal, Some(vec![0.0, 0.0, 1.0]));
+
+    assert!(load("#4 = TRIANGLE((#1, #2), $);").is_err());
+    assert!(load("#4 = TRIANGLE((#1, #2, #3, #1), $);").is_err());
+    assert!(load("#4 = POINT((0.0, 0.0));").is_err());
+    assert!(load("#4 = TRIANGLE((#1, #2, #3), (0.0, 1.0));").is_err());
+}
+
+#[test]
+fn optional_elements() {
+    let table = load("#4 = POLYLINE((#1, #2), (1.0, $));").unwrap();
+    let polyline = EntityTable::<PolylineHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(polyline.points.len(), 2);
+    assert_eq!(polyline.weights, vec![Some(1.0), None]);
+
+    assert!(load("#4 = POLYLINE((#1, #2), (1.0));").is_err());
+}
+
+#[test]
+fn cardinality() {
+    assert!(load("#4 = POLYLINE((#1, #2, #3), ($, $));").is_ok());
+    assert!(load("#4 = POLYLINE((#1), ($, $));").is_err());
+
+    assert!(load("#4 = GROUP((#1));").is_ok());
+    assert!(load("#4 = GROUP((#1, #2));").is_ok());
+    assert!(load("#4 = GROUP(());").is_err());
+    assert!(load("#4 = GROUP((#1, #2, #3));").is_err());
+}
@@ -0,0 +1,244 @@
+use ruststep::{error::Error, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY person SUPERTYPE OF (employee ANDOR student);
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY employee SUBTYPE OF (person);
+        pay: INTEGER;
+      END_ENTITY;
+
+      ENTITY student SUBTYPE OF (person);
+        school_name: STRING;
+      END_ENTITY;
+
+      ENTITY teaching_assistant SUBTYPE OF (employee, student);
+        course: STRING;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = (EMPLOYEE(10) PERSON('Hitori Goto'));
+  #2 = (PERSON('Ikuyo Kita') STUDENT('Shuka'));
+  #3 = (EMPLOYEE(20) PERSON('Nijika Ijichi') STUDENT('Shimokitazawa'));
+  #4 = (EMPLOYEE(30) PERSON('Ryo Yamada') STUDENT('Shimokitazawa') TEACHING_ASSISTANT('EXPRESS'));
+  #5 = EMPLOYEE(PERSON(('Seika Ijichi')), 40);
+ENDSEC;
+"#;
+
+#[test]
+fn single_leaf() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    let employee = EntityTable::<EmployeeHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(
+        employee,
+        Employee {
+            person: Person {
+                name: "Hitori Goto".to_string()
+            },
+            pay: 10
+        }
+    );
+
+    let student = EntityTable::<StudentHolder>::get_owned(&table, 2).unwrap();
+    assert_eq!(
+        student,
+        Student {
+            person: Person {
+                name: "Ikuyo Kita".to_string()
+            },
+            school_name: "Shuka".to_string()
+        }
+    );
+
+    // Only leaf entity is registered
+    assert!(EntityTable::<PersonHolder>::get_owned(&table, 1).is_err());
+    assert!(matches!(
+        EntityTable::<PersonAnyHolder>::get_owned(&table, 1).unwrap(),
+        PersonAny::Employee(_)
+    ));
+}
+
+#[test]
+fn multiple_leaves() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    // Both leaves `employee` and `student` are registered
+    let employee = EntityTable::<EmployeeHolder>::get_owned(&table, 3).unwrap();
+    assert_eq!(employee.pay, 20);
+    assert_eq!(employee.person.name, "Nijika Ijichi");
+    let student = EntityTable::<StudentHolder>::get_owned(&table, 3).unwrap();
+    assert_eq!(student.school_name, "Shimokitazawa");
+    assert_eq!(student.person.name, "Nijika Ijichi");
+}
+
+#[test]
+fn complex_entity() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    let es = EntityTable::<EmployeeAndStudentHolder>::get_owned(&table, 3).unwrap();
+    assert_eq!(AsRef::<Employee>::as_ref(&es).pay, 20);
+    assert_eq!(AsRef::<Student>::as_ref(&es).school_name, "Shimokitazawa");
+    assert!(EntityTable::<EmployeeAndStudentHolder>::get_owned(&table, 1).is_err());
+
+    let any = EntityTable::<PersonAnyHolder>::get_owned(&table, 3).unwrap();
+    assert!(matches!(any, PersonAny::EmployeeAndStudent(_)));
+    assert_eq!(AsRef::<Person>::as_ref(&any).name, "Nijika Ijichi");
+
+    // Complex entity is dumped once in external mapping, and its components are not
+    let data = table.to_data_section().unwrap();
+    assert_eq!(
+        data,
+        ruststep::ast::DataSection::from_str(
+            r#"
+            DATA;
+              #1 = EMPLOYEE(PERSON(('Hitori Goto')), 10);
+              #2 = STUDENT(PERSON(('Ikuyo Kita')), 'Shuka');
+              #3 = (EMPLOYEE(20) PERSON('Nijika Ijichi') STUDENT('Shimokitazawa'));
+              #4 = TEACHING_ASSISTANT(EMPLOYEE((PERSON(('Ryo Yamada')), 30)), STUDENT((PERSON(('Ryo Yamada')), 'Shimokitazawa')), 'EXPRESS');
+              #5 = EMPLOYEE(PERSON(('Seika Ijichi')), 40);
+            ENDSEC;
+            "#
+        )
+        .unwrap()
+    );
+    let reloaded = Tables::from_data_section(&data).unwrap();
+    assert_eq!(reloaded, table);
+}
+
+#[test]
+fn duplicated_entity() {
+    // `#1` would be stored in the tables of `employee` and `student`
+    let err = Tables::from_str(
+        "DATA; #1 = EMPLOYEE(PERSON(('A')), 10); #1 = STUDENT(PERSON(('A')), 'B'); ENDSEC;",
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::DuplicatedEntity(1)));
+}
+
+#[test]
+fn diamond() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    // `teaching_assistant` is the only leaf
+    assert!(EntityTable::<EmployeeHolder>::get_owned(&table, 4).is_err());
+    let ta = EntityTable::<TeachingAssistantHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(
+        ta,
+        TeachingAssistant {
+            employee: Employee {
+                person: Person {
+                    name: "Ryo Yamada".to_string()
+                },
+                pay: 30
+            },
+            student: Student {
+                person: Person {
+                    name: "Ryo Yamada".to_string()
+                },
+                school_name: "Shimokitazawa".to_string()
+            },
+            course: "EXPRESS".to_string()
+        }
+    );
+}
+
+#[test]
+fn internal_mapping_coexists() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let employee = EntityTable::<EmployeeHolder>::get_owned(&table, 5).unwrap();
+    assert_eq!(employee.pay, 40);
+}
+
+#[test]
+fn lacked_partial_record() {
+    let err = Tables::from_str("DATA; #1 = (EMPLOYEE(10)); ENDSEC;").unwrap_err();
+    assert_eq!(err.entity_id(), Some(1));
+    assert!(matches!(
+        err,
+        Error::EntityInstanceFailed { source, .. }
+            if matches!(source.as_ref(), Error::LackedPartialRecord(name) if name == "PERSON")
+    ));
+}
+
+#[test]
+fn unknown_partial_record() {
+    let err =
+        Tables::from_str("DATA; #1 = (EMPLOYEE(10) PERSON('A') ROBOT(1)); ENDSEC;").unwrap_err();
+    assert!(matches!(
+        &err,
+        Error::EntityInstanceFailed { id: 1, source, .. }
+            if matches!(source.as_ref(), Error::UnknownEntityName { entity_name, schema } if entity_name == "ROBOT" && schema == "test_schema")
+    ));
+    assert!(err
+        .to_string()
+        .contains("Entity 'ROBOT' is not a member of the schema 'test_schema'"));
+}
+
+mod club {
+    use ruststep::tables::*;
+    use std::str::FromStr;
+
+    espr_derive::inline_express!(
+        r#"
+        SCHEMA club_schema;
+          ENTITY club;
+            name: STRING;
+          INVERSE
+            members: SET [0:?] OF member FOR club;
+          END_ENTITY;
+
+          ENTITY person SUPERTYPE OF (member ANDOR officer);
+            name: STRING;
+          END_ENTITY;
+
+          ENTITY member SUBTYPE OF (person);
+            club: club;
+          END_ENTITY;
+
+          ENTITY officer SUBTYPE OF (person);
+            role: STRING;
+          END_ENTITY;
+        END_SCHEMA;
+        "#
+    );
+
+    use club_schema::*;
+
+    #[test]
+    fn complex_references() {
+        let table = Tables::from_str(
+            r#"
+            DATA;
+              #1 = CLUB('Kessoku Band');
+              #2 = (MEMBER(#1) OFFICER('leader') PERSON('Nijika Ijichi'));
+              #3 = MEMBER(PERSON(('Hitori Goto')), #1);
+            ENDSEC;
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            table.referencing_ids::<MemberAndOfficerHolder>(1, Some("club")),
+            vec![2]
+        );
+        let mut members: Vec<_> = table
+            .members_of(1)
+            .unwrap()
+            .into_iter()
+            .map(|member| member.person.name)
+            .collect();
+        members.sort();
+        assert_eq!(members, vec!["Hitori Goto", "Nijika Ijichi"]);
+    }
+}
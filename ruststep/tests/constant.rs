@@ -0,0 +1,80 @@
+use ruststep::runtime::*;
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      CONSTANT
+        max_items : INTEGER := 3;
+        tolerance : REAL := -1.0E-6;
+        dummy_gri : geometric_representation_item := representation_item('') ||
+                                                     geometric_representation_item();
+        primary_colors : SET OF STRING := ['red', 'green', 'blue'];
+        max_primary_colors : INTEGER := SIZEOF(primary_colors);
+      END_CONSTANT;
+
+      ENTITY representation_item;
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY geometric_representation_item SUBTYPE OF (representation_item);
+      END_ENTITY;
+
+      ENTITY palette;
+        colors: LIST [1:?] OF STRING;
+      WHERE
+        wr1: SIZEOF(colors) <= max_items;
+        wr2: SIZEOF(QUERY(c <* colors | NOT (c IN primary_colors))) = 0;
+      END_ENTITY;
+
+      FUNCTION is_dummy(item: representation_item): BOOLEAN;
+        RETURN (item :=: dummy_gri);
+      END_FUNCTION;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+#[test]
+fn constants() {
+    assert_eq!(MAX_ITEMS, 3);
+    assert_eq!(TOLERANCE, -1.0e-6);
+    assert_eq!(MAX_PRIMARY_COLORS.with(Value::clone), Value::Integer(3));
+
+    let dummy = DUMMY_GRI.with(Value::clone);
+    let entity = dummy.as_entity().unwrap();
+    assert_eq!(
+        entity.type_names(),
+        vec![
+            "TEST_SCHEMA.REPRESENTATION_ITEM".to_string(),
+            "TEST_SCHEMA.GEOMETRIC_REPRESENTATION_ITEM".to_string(),
+        ]
+    );
+    assert_eq!(entity.attribute("name"), Value::String(String::new()));
+    assert_eq!(
+        is_dummy(None, dummy),
+        Value::Logical(ruststep::primitive::Logical::True)
+    );
+}
+
+#[test]
+fn rules_referring_constants() {
+    let table = Tables::from_str(
+        r#"
+        DATA;
+          #1 = PALETTE(('red', 'blue'));
+          #2 = PALETTE(('red', 'blue', 'green', 'red'));
+          #3 = PALETTE(('white'));
+        ENDSEC;
+        "#,
+    )
+    .unwrap();
+    let labels: Vec<_> = table
+        .validate()
+        .unwrap()
+        .into_iter()
+        .map(|violation| (violation.id, violation.label))
+        .collect();
+    assert_eq!(labels, vec![(Some(2), "wr1"), (Some(3), "wr2")]);
+}
@@ -0,0 +1,128 @@
+use ruststep::{primitive::Logical, runtime::*, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      TYPE label = STRING;
+      END_TYPE;
+
+      TYPE side = ENUMERATION OF (left, right);
+      END_TYPE;
+
+      ENTITY representation_item;
+        name: label;
+      DERIVE
+        named: BOOLEAN := EXISTS(name) AND (name <> '');
+      END_ENTITY;
+
+      ENTITY cartesian_point SUBTYPE OF (representation_item);
+        coordinates: LIST [1:3] OF REAL;
+      DERIVE
+        dim: INTEGER := HIINDEX(coordinates);
+        positive: LIST [0:?] OF REAL := QUERY(c <* coordinates | c > 0.0);
+        lines: INTEGER := SIZEOF(USEDIN(SELF, 'TEST_SCHEMA.POLYLINE.POINTS'));
+      END_ENTITY;
+
+      ENTITY polyline SUBTYPE OF (representation_item);
+        points: LIST [2:?] OF cartesian_point;
+        weight: OPTIONAL REAL;
+        orientation: side;
+      DERIVE
+        dim: INTEGER := points[1].dim;
+        n: INTEGER := SIZEOF(points);
+        w: REAL := NVL(weight, 1.0) * 2;
+        left: BOOLEAN := orientation = side.left;
+        types: SET [0:?] OF STRING := TYPEOF(SELF);
+        in_plane: LOGICAL := SIZEOF(QUERY(p <* points | p.dim <> 2)) = 0;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = CARTESIAN_POINT(REPRESENTATION_ITEM(('p1')), (1.0, -2.0));
+  #2 = CARTESIAN_POINT(REPRESENTATION_ITEM(('p2')), (3.0, 4.0));
+  #3 = POLYLINE(REPRESENTATION_ITEM(('')), (#1, #2), $, .LEFT.);
+  #4 = CARTESIAN_POINT(REPRESENTATION_ITEM(('p3')), (3.0, 4.0, 5.0));
+  #5 = POLYLINE(REPRESENTATION_ITEM(('l2')), (#1, #4), 0.5, .RIGHT.);
+ENDSEC;
+"#;
+
+#[test]
+fn derived_attributes() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let population = table.population().unwrap();
+
+    let p1 = EntityTable::<CartesianPointHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(p1.dim(Some(1), &population), Some(2));
+    assert_eq!(p1.positive(Some(1), &population), Some(vec![1.0]));
+    assert_eq!(p1.named(Some(1), &population), Some(true));
+    // `USEDIN` on the population
+    assert_eq!(p1.lines(Some(1), &population), Some(2));
+    let p3 = EntityTable::<CartesianPointHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(p3.lines(Some(4), &population), Some(1));
+
+    let l1 = EntityTable::<PolylineHolder>::get_owned(&table, 3).unwrap();
+    assert_eq!(l1.dim(Some(3), &population), Some(2));
+    assert_eq!(l1.n(Some(3), &population), Some(2));
+    assert_eq!(l1.w(Some(3), &population), Some(2.0));
+    assert_eq!(l1.left(Some(3), &population), Some(true));
+    assert_eq!(l1.in_plane(Some(3), &population), Some(Logical::True));
+    // Derived attribute of the supertype
+    assert_eq!(l1.named(Some(3), &population), Some(false));
+    assert_eq!(
+        l1.types(Some(3), &population),
+        Some(vec![
+            "TEST_SCHEMA.POLYLINE".to_string(),
+            "TEST_SCHEMA.REPRESENTATION_ITEM".to_string()
+        ])
+    );
+
+    let l2 = EntityTable::<PolylineHolder>::get_owned(&table, 5).unwrap();
+    assert_eq!(l2.w(Some(5), &population), Some(1.0));
+    assert_eq!(l2.left(Some(5), &population), Some(false));
+    assert_eq!(l2.in_plane(Some(5), &population), Some(Logical::False));
+}
+
+#[test]
+fn entity_value() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let population = table.population().unwrap();
+    let l1 = EntityTable::<PolylineHolder>::get_owned(&table, 3).unwrap();
+    let value = l1.to_value();
+    // derived attributes are evaluated on the population
+    assert_eq!(attribute(value.clone(), "n"), Value::Indeterminate);
+    assert_eq!(
+        attribute_in(Some(&population), value.clone(), "n"),
+        Value::Integer(2)
+    );
+    assert_eq!(attribute(value.clone(), "weight"), Value::Indeterminate);
+    assert_eq!(
+        attribute(value.clone(), "orientation"),
+        Value::Enumeration("left".to_string())
+    );
+    // explicit attribute of supertype
+    assert_eq!(attribute(value, "name"), Value::String("".to_string()));
+}
+
+#[test]
+fn shared_population() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let population = table.population().unwrap();
+    // Instances refer the instances in the population by their ids without copying them
+    let l1 = population.instance_of::<PolylineHolder>(3).unwrap();
+    let p1 = population.instance_of::<CartesianPointHolder>(1).unwrap();
+    match &l1.points[0] {
+        PlaceHolder::Shared(_, point) => assert!(std::ptr::eq(point.as_ref(), p1)),
+        _ => panic!("Reference is not resolved"),
+    }
+    let points = attribute(population.instance(3), "points");
+    assert_eq!(
+        index(points, Value::Integer(2)).as_entity().unwrap().id(),
+        Some(2)
+    );
+}
@@ -0,0 +1,197 @@
+use ruststep::{
+    ast::{ser::DataSectionSerializer, *},
+    header::*,
+    tables::*,
+};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      TYPE label = STRING;
+      END_TYPE;
+
+      TYPE direction = ENUMERATION OF (ahead, behind);
+      END_TYPE;
+
+      TYPE value_select = SELECT (label, point);
+      END_TYPE;
+
+      ENTITY point;
+        x: REAL;
+        y: REAL;
+      END_ENTITY;
+
+      ENTITY line;
+        name: label;
+        start: point;
+        stop: point;
+        dir: direction;
+        closed: BOOLEAN;
+        tag: OPTIONAL value_select;
+      END_ENTITY;
+
+      ENTITY polyline;
+        points: LIST [1:?] OF point;
+      END_ENTITY;
+
+      ENTITY base SUPERTYPE OF (sub);
+        x: REAL;
+      END_ENTITY;
+
+      ENTITY sub SUBTYPE OF (base);
+        y: REAL;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = POINT(0.0, 1.0);
+  #2 = POINT(2.0, 3.0);
+  #3 = LINE('l1', #1, #2, .AHEAD., .T., $);
+  #4 = LINE('l2', #2, POINT((4.0, 5.0)), .BEHIND., .F., LABEL('v'));
+  #5 = POLYLINE((#1, #2, POINT((6.0, 7.0))));
+  #6 = BASE(1.0);
+  #7 = SUB(#6, 2.0);
+  #8 = SUB(BASE((3.0)), 4.0);
+ENDSEC;
+"#;
+
+/// Check all entities are recovered from dumped data section
+fn check_owned(table: &Tables, dumped: &Tables) {
+    for id in 1..=2 {
+        assert_eq!(
+            EntityTable::<PointHolder>::get_owned(table, id).unwrap(),
+            EntityTable::<PointHolder>::get_owned(dumped, id).unwrap(),
+        );
+    }
+    for id in 3..=4 {
+        assert_eq!(
+            EntityTable::<LineHolder>::get_owned(table, id).unwrap(),
+            EntityTable::<LineHolder>::get_owned(dumped, id).unwrap(),
+        );
+    }
+    assert_eq!(
+        EntityTable::<PolylineHolder>::get_owned(table, 5).unwrap(),
+        EntityTable::<PolylineHolder>::get_owned(dumped, 5).unwrap(),
+    );
+    for id in 7..=8 {
+        assert_eq!(
+            EntityTable::<SubHolder>::get_owned(table, id).unwrap(),
+            EntityTable::<SubHolder>::get_owned(dumped, id).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn dump_data_section() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let data = table.to_data_section().unwrap();
+
+    // Existing ids are kept, and owned entities are numbered after them.
+    // The supertype `BASE((3.0))` of `#8` is not an entity instance.
+    let ids: Vec<u64> = data
+        .entities
+        .iter()
+        .map(|entity| match entity {
+            EntityInstance::Simple { id, .. } => *id,
+            EntityInstance::Complex { id, .. } => *id,
+        })
+        .collect();
+    assert_eq!(ids, (1..=10).collect::<Vec<u64>>());
+
+    let dumped = Tables::from_data_section(&data).unwrap();
+    check_owned(&table, &dumped);
+}
+
+#[test]
+fn dump_record() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let data = table.to_data_section().unwrap();
+    let line = data
+        .entities
+        .iter()
+        .find_map(|entity| match entity {
+            EntityInstance::Simple { id: 3, record } => Some(record),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        line,
+        &Record::from_str("LINE('l1', #1, #2, .AHEAD., .T., $)").unwrap()
+    );
+
+    let sub = data
+        .entities
+        .iter()
+        .find_map(|entity| match entity {
+            EntityInstance::Simple { id: 8, record } => Some(record),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(sub, &Record::from_str("SUB(BASE((3.0)), 4.0)").unwrap());
+}
+
+#[test]
+fn dump_exchange() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let header = Header {
+        file_description: FileDescription {
+            description: vec!["dump test".to_string()],
+            implementation_level: "2;1".to_string(),
+        },
+        file_name: FileName {
+            name: "dump.step".to_string(),
+            time_stamp: "2021-01-01T00:00:00".to_string(),
+            author: vec!["ruststep".to_string()],
+            organization: vec![String::new()],
+            preprocessor_version: String::new(),
+            originating_system: String::new(),
+            authorization: String::new(),
+        },
+        file_schema: FileSchema {
+            schema: vec!["TEST_SCHEMA".to_string()],
+        },
+    };
+    let exchange = table.to_exchange(&header).unwrap();
+    let output = ruststep::writer::to_string(&exchange).unwrap();
+
+    let exchange = Exchange::from_str(&output).unwrap();
+    assert_eq!(Header::from_records(&exchange.header).unwrap(), header);
+    let dumped = Tables::from_data_sections(&exchange.data).unwrap();
+    check_owned(&table, &dumped);
+}
+
+#[test]
+fn serialize_owned() {
+    let line = Line {
+        name: Label("l".to_string()),
+        start: Point { x: 0.0, y: 1.0 },
+        stop: Point { x: 2.0, y: 3.0 },
+        dir: Direction::Behind,
+        closed: false,
+        tag: Some(ValueSelect::Point(Box::new(Point { x: 4.0, y: 5.0 }))),
+    };
+
+    let mut ser = DataSectionSerializer::new(1);
+    let id = ser.push(&line).unwrap();
+    assert_eq!(id, 1);
+    assert_eq!(
+        ser.finish(),
+        DataSection::from_str(
+            r#"
+            DATA;
+              #1 = LINE('l', #2, #3, .BEHIND., .F., #4);
+              #2 = POINT(0.0, 1.0);
+              #3 = POINT(2.0, 3.0);
+              #4 = POINT(4.0, 5.0);
+            ENDSEC;
+            "#
+        )
+        .unwrap()
+    );
+}
@@ -156,3 +156,33 @@ fn get_owned_b3() {
         }
     );
 }
+
+#[test]
+fn from_entity_instances() {
+    let step_str = format!(
+        "ISO-10303-21;\nHEADER;\nFILE_DESCRIPTION((''), '2;1');\nFILE_NAME('', '', (''), (''), '', '', '');\nFILE_SCHEMA(('TEST_SCHEMA'));\nENDSEC;\n{}END-ISO-10303-21;",
+        EXAMPLE.trim_start()
+    );
+    let reader = ruststep::parser::reader::Reader::new(step_str.as_bytes()).unwrap();
+    let table = Tables::from_entity_instances(reader).unwrap();
+    assert_eq!(table, Tables::from_str(EXAMPLE).unwrap());
+}
+
+#[test]
+fn deserialize_error_position() {
+    let input = "DATA;\n  #1 = A(1.0, 2.0);\n  #2 = B('not; real', #1);\nENDSEC;\n";
+    let err = Tables::from_str(input).unwrap_err();
+    assert_eq!(err.entity_id(), Some(2));
+    let position = err.position().unwrap();
+    assert_eq!((position.line, position.column), (3, 3));
+
+    let step_str = format!(
+        "ISO-10303-21;\nHEADER;\nFILE_DESCRIPTION((''), '2;1');\nFILE_NAME('', '', (''), (''), '', '', '');\nFILE_SCHEMA(('TEST_SCHEMA'));\nENDSEC;\n{}END-ISO-10303-21;",
+        input
+    );
+    let reader = ruststep::parser::reader::Reader::new(step_str.as_bytes()).unwrap();
+    let err = Tables::from_reader(reader).unwrap_err();
+    assert_eq!(err.entity_id(), Some(2));
+    let position = err.position().unwrap();
+    assert_eq!((position.line, position.column), (9, 3));
+}
@@ -0,0 +1,273 @@
+use ruststep::{primitive::Logical, runtime::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY polyline;
+        name: STRING;
+        points: LIST [2:?] OF REAL;
+      DERIVE
+        total: REAL := sum(points);
+      WHERE
+        wr1: is_increasing(points);
+      END_ENTITY;
+
+      ENTITY tag;
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY gauge;
+        reading: REAL;
+      WHERE
+        wr1: is_positive(reading);
+      END_ENTITY;
+
+      RULE unique_tag_name FOR (tag);
+      LOCAL
+        names: LIST OF STRING := [];
+        duplicated: BOOLEAN := FALSE;
+      END_LOCAL;
+        REPEAT i := 1 TO SIZEOF(tag);
+          IF tag[i].name IN names THEN
+            duplicated := TRUE;
+            ESCAPE;
+          END_IF;
+          INSERT(names, tag[i].name, 0);
+        END_REPEAT;
+      WHERE
+        wr1: NOT duplicated;
+      END_RULE;
+
+      FUNCTION sum(values: LIST OF REAL): REAL;
+      LOCAL
+        total: REAL := 0.0;
+      END_LOCAL;
+        REPEAT i := 1 TO SIZEOF(values);
+          total := total + values[i];
+        END_REPEAT;
+        RETURN (total);
+      END_FUNCTION;
+
+      FUNCTION is_increasing(values: LIST OF REAL): LOGICAL;
+        REPEAT i := 2 TO SIZEOF(values);
+          IF values[i - 1] >= values[i] THEN
+            RETURN (FALSE);
+          END_IF;
+        END_REPEAT;
+        RETURN (TRUE);
+      END_FUNCTION;
+
+      FUNCTION sign_name(x: REAL): STRING;
+        CASE sign(x) OF
+          -1: RETURN ('negative');
+          1: RETURN ('positive');
+          OTHERWISE: RETURN ('zero');
+        END_CASE;
+      END_FUNCTION;
+
+      FUNCTION sign(x: REAL): INTEGER;
+        IF x > 0.0 THEN
+          RETURN (1);
+        ELSE
+          IF x < 0.0 THEN
+            RETURN (-1);
+          END_IF;
+        END_IF;
+        RETURN (0);
+      END_FUNCTION;
+
+      FUNCTION collatz_steps(n: INTEGER): INTEGER;
+      LOCAL
+        steps: INTEGER := 0;
+      END_LOCAL;
+        REPEAT UNTIL n = 1;
+          IF ODD(n) THEN
+            n := 3 * n + 1;
+          ELSE
+            n := n DIV 2;
+          END_IF;
+          steps := steps + 1;
+        END_REPEAT;
+        RETURN (steps);
+      END_FUNCTION;
+
+      FUNCTION odd_values(values: LIST OF INTEGER): LIST OF INTEGER;
+      LOCAL
+        result: LIST OF INTEGER := [];
+      END_LOCAL;
+        REPEAT i := SIZEOF(values) TO 1 BY -1;
+          IF NOT ODD(values[i]) THEN
+            SKIP;
+          END_IF;
+          INSERT(result, values[i], 0);
+        END_REPEAT;
+        RETURN (result);
+      END_FUNCTION;
+
+      FUNCTION first_name(p: polyline): STRING;
+        ALIAS n FOR p.name;
+          RETURN (n);
+        END_ALIAS;
+      END_FUNCTION;
+
+      FUNCTION unknown_size(values: LIST OF INTEGER): INTEGER;
+        RETURN (SIZEOF(values) + ?);
+      END_FUNCTION;
+
+      FUNCTION triangular(n: INTEGER): INTEGER;
+        FUNCTION partial_sum(n: INTEGER; total: INTEGER): INTEGER;
+          IF n < 1 THEN
+            RETURN (total);
+          END_IF;
+          RETURN (partial_sum(n - 1, total + n));
+        END_FUNCTION;
+        RETURN (partial_sum(n, 0));
+      END_FUNCTION;
+
+      FUNCTION is_positive(x: REAL): BOOLEAN;
+        TYPE local_measure = REAL;
+        END_TYPE;
+        RETURN (x > 0.0);
+      END_FUNCTION;
+
+      PROCEDURE swap(VAR values: LIST OF INTEGER);
+      LOCAL
+        head: INTEGER;
+      END_LOCAL;
+        head := values[1];
+        values[1] := values[2];
+        values[2] := head;
+      END_PROCEDURE;
+
+      FUNCTION swapped(values: LIST OF INTEGER): LIST OF INTEGER;
+        swap(values);
+        RETURN (values);
+      END_FUNCTION;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+fn i(value: i64) -> Value {
+    Value::Integer(value)
+}
+
+fn r(value: f64) -> Value {
+    Value::Real(value)
+}
+
+fn list(values: &[Value]) -> Value {
+    Value::Aggregate(values.to_vec())
+}
+
+#[test]
+fn control_statements() {
+    assert_eq!(sum(None, list(&[r(1.0), r(2.5)])), r(3.5));
+    assert_eq!(
+        is_increasing(None, list(&[r(1.0), r(2.5)])),
+        Value::Logical(Logical::True)
+    );
+    assert_eq!(
+        is_increasing(None, list(&[r(1.0), r(0.5)])),
+        Value::Logical(Logical::False)
+    );
+    assert_eq!(
+        sign_name(None, r(-2.0)),
+        Value::String("negative".to_string())
+    );
+    assert_eq!(sign_name(None, r(0.0)), Value::String("zero".to_string()));
+    assert_eq!(collatz_steps(None, i(6)), i(8));
+    assert_eq!(
+        odd_values(None, list(&[i(1), i(2), i(3), i(4), i(5)])),
+        list(&[i(1), i(3), i(5)])
+    );
+}
+
+#[test]
+fn nested_function() {
+    assert_eq!(triangular(None, i(4)), i(10));
+}
+
+#[test]
+fn indeterminate() {
+    // Operations on indeterminate value result in indeterminate
+    assert_eq!(unknown_size(None, list(&[i(1)])), Value::Indeterminate);
+    // `REPEAT` is not executed if its bound is indeterminate
+    assert_eq!(sum(None, Value::Indeterminate), i(0));
+}
+
+#[test]
+fn procedure() {
+    let mut values = list(&[i(1), i(2), i(3)]);
+    swap(None, &mut values);
+    assert_eq!(values, list(&[i(2), i(1), i(3)]));
+    // Arguments of functions are passed by value
+    let values = list(&[i(1), i(2)]);
+    assert_eq!(swapped(None, values.clone()), list(&[i(2), i(1)]));
+    assert_eq!(values, list(&[i(1), i(2)]));
+}
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = POLYLINE('p1', (1.0, 2.0, 4.0));
+  #2 = POLYLINE('p2', (1.0, 0.5));
+  #3 = TAG('a');
+  #4 = TAG('b');
+ENDSEC;
+"#;
+
+#[test]
+fn derived_and_rules() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let p1 = ruststep::tables::EntityTable::<PolylineHolder>::get_owned(&table, 1).unwrap();
+    let population = table.population().unwrap();
+    assert_eq!(p1.total(Some(1), &population), Some(7.0));
+    assert_eq!(
+        first_name(Some(&population), ToValue::to_value(&p1)),
+        Value::String("p1".to_string())
+    );
+    assert_eq!(
+        table.validate().unwrap(),
+        vec![RuleViolation {
+            id: Some(2),
+            declared_in: "TEST_SCHEMA.POLYLINE",
+            label: "wr1",
+            evaluated: true,
+        }]
+    );
+    assert!(table.check_global_rules().unwrap().is_empty());
+
+    let duplicated = EXAMPLE.replace("TAG('b')", "TAG('a')");
+    let table = Tables::from_str(&duplicated).unwrap();
+    assert_eq!(
+        table.check_global_rules().unwrap(),
+        vec![RuleViolation {
+            id: None,
+            declared_in: "TEST_SCHEMA.UNIQUE_TAG_NAME",
+            label: "wr1",
+            evaluated: true,
+        }]
+    );
+}
+
+#[test]
+fn not_evaluated() {
+    // `is_positive` is not generated since its nested `TYPE` is not supported
+    let table = Tables::from_str("DATA; #1 = GAUGE(-1.0); ENDSEC;").unwrap();
+    let violations = table.validate().unwrap();
+    assert_eq!(
+        violations,
+        vec![RuleViolation {
+            id: Some(1),
+            declared_in: "TEST_SCHEMA.GAUGE",
+            label: "wr1",
+            evaluated: false,
+        }]
+    );
+    assert_eq!(
+        violations[0].to_string(),
+        "#1: wr1 of TEST_SCHEMA.GAUGE is not evaluated"
+    );
+}
@@ -0,0 +1,123 @@
+use ruststep::runtime::*;
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY product;
+        id: STRING;
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY product_category;
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY product_related_product_category SUBTYPE OF (product_category);
+        products: SET [1:?] OF product;
+      END_ENTITY;
+
+      RULE restrict_product_category_value FOR (product_related_product_category);
+      WHERE
+        wr1: SIZEOF(QUERY(prpc <* product_related_product_category |
+               NOT (prpc.name IN ['assembly', 'detail', 'part']))) = 0;
+      END_RULE;
+
+      RULE subtype_mandatory_product_category FOR (product_category);
+      WHERE
+        wr1: SIZEOF(QUERY(pc <* product_category |
+               NOT ('TEST_SCHEMA.PRODUCT_RELATED_PRODUCT_CATEGORY' IN TYPEOF(pc)))) = 0;
+      END_RULE;
+
+      RULE dependent_instantiable_product FOR (product);
+      WHERE
+        wr1: SIZEOF(QUERY(p <* product | SIZEOF(USEDIN(p, '')) = 0)) = 0;
+      END_RULE;
+
+      RULE unique_product_id FOR (product);
+      WHERE
+        wr1: SIZEOF(QUERY(p1 <* product |
+               SIZEOF(QUERY(p2 <* product | p1.id = p2.id)) > 1)) = 0;
+      END_RULE;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const CONFORMING: &str = r#"
+DATA;
+  #1 = PRODUCT('p1', 'bolt');
+  #2 = PRODUCT('p2', 'nut');
+  #3 = PRODUCT_RELATED_PRODUCT_CATEGORY(PRODUCT_CATEGORY(('part')), (#1, #2));
+ENDSEC;
+"#;
+
+const VIOLATING: &str = r#"
+DATA;
+  #1 = PRODUCT('p1', 'bolt');
+  #2 = PRODUCT('p2', 'nut');
+  #3 = PRODUCT('p1', 'washer');
+  #4 = PRODUCT_RELATED_PRODUCT_CATEGORY(PRODUCT_CATEGORY(('part')), (#1, #2));
+  #5 = PRODUCT_RELATED_PRODUCT_CATEGORY(PRODUCT_CATEGORY(('tool')), (#1));
+  #6 = PRODUCT_CATEGORY('misc');
+ENDSEC;
+"#;
+
+fn violation(declared_in: &'static str) -> RuleViolation {
+    RuleViolation {
+        id: None,
+        declared_in,
+        label: "wr1",
+        evaluated: true,
+    }
+}
+
+#[test]
+fn conforming() {
+    let table = Tables::from_str(CONFORMING).unwrap();
+    assert!(table.check_global_rules().unwrap().is_empty());
+}
+
+#[test]
+fn violating() {
+    let table = Tables::from_str(VIOLATING).unwrap();
+    assert_eq!(
+        table.check_global_rules().unwrap(),
+        vec![
+            // category 'tool'
+            violation("TEST_SCHEMA.RESTRICT_PRODUCT_CATEGORY_VALUE"),
+            // #6 is not a product_related_product_category
+            violation("TEST_SCHEMA.SUBTYPE_MANDATORY_PRODUCT_CATEGORY"),
+            // #3 is not referred
+            violation("TEST_SCHEMA.DEPENDENT_INSTANTIABLE_PRODUCT"),
+            // #1 and #3 have the same id
+            violation("TEST_SCHEMA.UNIQUE_PRODUCT_ID"),
+        ]
+    );
+}
+
+#[test]
+fn instances_of() {
+    let table = Tables::from_str(VIOLATING).unwrap();
+    let population = table.population().unwrap();
+    let categories = population.instances_of("TEST_SCHEMA.PRODUCT_CATEGORY");
+    let names: Vec<_> = categories
+        .as_aggregate()
+        .unwrap()
+        .iter()
+        .map(|category| attribute(category.clone(), "name"))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            Value::String("part".to_string()),
+            Value::String("tool".to_string()),
+            Value::String("misc".to_string()),
+        ]
+    );
+    // Instances taken from the population know their ids
+    let products = population.instances_of("TEST_SCHEMA.PRODUCT");
+    let p3 = &products.as_aggregate().unwrap()[2];
+    assert_eq!(p3.as_entity().unwrap().id(), Some(3));
+}
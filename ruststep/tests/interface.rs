@@ -0,0 +1,68 @@
+use ruststep::{runtime::RuleViolation, tables::EntityTable};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA geometry_schema;
+      ENTITY point;
+        x, y: REAL;
+      END_ENTITY;
+
+      ENTITY direction;
+        dx, dy: REAL;
+      END_ENTITY;
+
+      ENTITY edge;
+        weight: REAL;
+      END_ENTITY;
+    END_SCHEMA;
+
+    SCHEMA topology_schema;
+      USE FROM geometry_schema (point AS vertex_point);
+      REFERENCE FROM geometry_schema (direction);
+
+      ENTITY edge;
+        head: vertex_point;
+        tail: vertex_point;
+        dir: OPTIONAL direction;
+      WHERE
+        wr1: 'GEOMETRY_SCHEMA.POINT' IN TYPEOF(head);
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = POINT(0.0, 0.0);
+  #2 = POINT(1.0, 2.0);
+  #3 = EDGE(#1, #2, #4);
+  #4 = DIRECTION(1.0, 2.0);
+ENDSEC;
+"#;
+
+#[test]
+fn use_from() {
+    let table = topology_schema::Tables::from_str(EXAMPLE).unwrap();
+    let edge = EntityTable::<topology_schema::EdgeHolder>::get_owned(&table, 3).unwrap();
+    // Entities declared in the interfaced schema are also visible through its own module.
+    // `geometry_schema.edge` is not interfaced, and does not conflict with `topology_schema.edge`.
+    assert_eq!(edge.tail, geometry_schema::Point::new(1.0, 2.0));
+    assert!(table.validate().unwrap().is_empty());
+}
+
+#[test]
+fn reference_from() {
+    // `direction` is instantiated only as an attribute value of `edge`
+    let input = EXAMPLE.replace("EDGE(#1, #2, #4)", "EDGE(#1, #2, $)");
+    let table = topology_schema::Tables::from_str(&input).unwrap();
+    assert_eq!(
+        table.validate().unwrap(),
+        vec![RuleViolation {
+            id: Some(4),
+            declared_in: "GEOMETRY_SCHEMA.DIRECTION",
+            label: "REFERENCE FROM",
+            evaluated: true,
+        }]
+    );
+}
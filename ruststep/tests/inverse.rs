@@ -0,0 +1,178 @@
+use ruststep::{error::Error, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY product_definition;
+        id: STRING;
+      INVERSE
+        shapes: SET [0:?] OF product_definition_shape FOR definition;
+        assemblies: SET [0:1] OF assembly FOR relating;
+      END_ENTITY;
+
+      TYPE characterized_definition = SELECT (product_definition, shape_aspect);
+      END_TYPE;
+
+      ENTITY property_definition SUPERTYPE OF (product_definition_shape);
+        name: STRING;
+        definition: characterized_definition;
+      END_ENTITY;
+
+      ENTITY product_definition_shape SUBTYPE OF (property_definition);
+      INVERSE
+        aspect: shape_aspect FOR of_shape;
+      END_ENTITY;
+
+      ENTITY shape_aspect;
+        name: STRING;
+        of_shape: product_definition_shape;
+      END_ENTITY;
+
+      ENTITY assembly;
+        relating: product_definition;
+        related: LIST [0:?] OF product_definition;
+        note: OPTIONAL product_definition;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = PRODUCT_DEFINITION('top');
+  #2 = PRODUCT_DEFINITION('part');
+  #3 = PRODUCT_DEFINITION_SHAPE(PROPERTY_DEFINITION(('top shape', #1)));
+  #4 = PROPERTY_DEFINITION('top property', #1);
+  #5 = SHAPE_ASPECT('face', #3);
+  #6 = PROPERTY_DEFINITION('aspect property', #5);
+  #7 = ASSEMBLY(#1, (#2, #2), $);
+  #8 = ASSEMBLY(#2, (), #1);
+ENDSEC;
+"#;
+
+#[test]
+fn referencing() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let index = table.references().unwrap();
+    assert_eq!(
+        index.referencing(1),
+        &[
+            Reference {
+                id: 3,
+                entity: "PROPERTY_DEFINITION",
+                attribute: "definition"
+            },
+            Reference {
+                id: 4,
+                entity: "PROPERTY_DEFINITION",
+                attribute: "definition"
+            },
+            Reference {
+                id: 7,
+                entity: "ASSEMBLY",
+                attribute: "relating"
+            },
+            Reference {
+                id: 8,
+                entity: "ASSEMBLY",
+                attribute: "note"
+            },
+        ]
+    );
+    // Duplicated references in a list are registered once
+    assert_eq!(
+        index.referencing(2),
+        &[
+            Reference {
+                id: 7,
+                entity: "ASSEMBLY",
+                attribute: "related"
+            },
+            Reference {
+                id: 8,
+                entity: "ASSEMBLY",
+                attribute: "relating"
+            },
+        ]
+    );
+    assert!(index.referencing(8).is_empty());
+}
+
+#[test]
+fn typed_query() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    let shapes = table
+        .referencing::<ProductDefinitionShapeHolder>(1)
+        .unwrap();
+    assert_eq!(shapes.len(), 1);
+    assert_eq!(shapes[0].property_definition.name, "top shape");
+
+    let properties = table.referencing::<PropertyDefinitionAnyHolder>(1).unwrap();
+    assert_eq!(properties.len(), 2);
+
+    let aspects = table.referencing::<ShapeAspectHolder>(3).unwrap();
+    assert_eq!(aspects.len(), 1);
+    assert_eq!(aspects[0].name, "face");
+
+    assert_eq!(
+        table.referencing_ids::<AssemblyHolder>(2, Some("related")),
+        vec![7]
+    );
+    assert_eq!(
+        table.referencing_ids::<AssemblyHolder>(2, Some("relating")),
+        vec![8]
+    );
+    assert!(table
+        .referencing_ids::<AssemblyHolder>(2, Some("note"))
+        .is_empty());
+}
+
+#[test]
+fn inverse_attributes() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    // inherited attribute `definition` of `property_definition`
+    let shapes = table.shapes_of(1).unwrap();
+    assert_eq!(shapes.len(), 1);
+    assert_eq!(shapes[0].property_definition.name, "top shape");
+    assert!(table.shapes_of(2).unwrap().is_empty());
+
+    assert_eq!(table.assemblies_of(2).unwrap().len(), 1);
+    assert_eq!(table.aspect_of(3).unwrap().name, "face");
+}
+
+#[test]
+fn inverse_cardinality() {
+    let table = Tables::from_str(
+        r#"
+        DATA;
+          #1 = PRODUCT_DEFINITION('top');
+          #2 = PRODUCT_DEFINITION_SHAPE(PROPERTY_DEFINITION(('shape', #1)));
+          #3 = ASSEMBLY(#1, (), $);
+          #4 = ASSEMBLY(#1, (), $);
+        ENDSEC;
+        "#,
+    )
+    .unwrap();
+
+    // `aspect` must be referred exactly once
+    let err = table.aspect_of(2).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::InverseCardinality {
+            id: 2,
+            count: 0,
+            ..
+        }
+    ));
+
+    let err = table.assemblies_of(1).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "#1 is referred 2 times through 'relating', out of [0:1]"
+    );
+}
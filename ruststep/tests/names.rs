@@ -0,0 +1,156 @@
+use ruststep::{ast::*, error::Error, header::*, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      TYPE label = STRING;
+      END_TYPE;
+
+      ENTITY point;
+        x: REAL;
+        y: REAL;
+      END_ENTITY;
+
+      ENTITY line;
+        name: label;
+        start: point;
+        stop: point;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+ISO-10303-21;
+HEADER;
+  FILE_DESCRIPTION(('names test'), '2;1');
+  FILE_NAME('names.step', '2021-01-01T00:00:00', ('ruststep'), (''), '', '', '');
+  FILE_SCHEMA(('TEST_SCHEMA'));
+ENDSEC;
+ANCHOR;
+  <ORIGIN> = #1;
+  <NAME> = 'l1';
+  <START> = @ORIGIN;
+ENDSEC;
+REFERENCE;
+  @6 = <#ORIGIN>;
+  #7 = <part.stp#END>;
+ENDSEC;
+DATA;
+  #1 = POINT(0.0, 0.0);
+  #2 = POINT(1.0, 2.0);
+  #3 = LINE(@NAME, #ORIGIN, #2);
+  #4 = LINE('l2', #START, @5);
+  #5 = LINE('l3', @6, #7);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+
+fn tables() -> Tables {
+    let exchange = Exchange::from_str(EXAMPLE).unwrap();
+    let mut table = Tables::from_exchange(&exchange).unwrap();
+    table
+        .append_value_instance(5, &Parameter::from_str("POINT((3.0, 4.0))").unwrap())
+        .unwrap();
+    table
+}
+
+#[test]
+fn constants() {
+    let table = tables();
+    let line = EntityTable::<LineHolder>::get_owned(&table, 3).unwrap();
+    assert_eq!(
+        line,
+        Line {
+            name: Label("l1".to_string()),
+            start: Point { x: 0.0, y: 0.0 },
+            stop: Point { x: 1.0, y: 2.0 },
+        }
+    );
+}
+
+#[test]
+fn value_instance() {
+    let table = tables();
+    let line = EntityTable::<LineHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(line.start, Point { x: 0.0, y: 0.0 });
+    assert_eq!(line.stop, Point { x: 3.0, y: 4.0 });
+}
+
+#[test]
+fn unknown_name() {
+    let exchange = Exchange::from_str(EXAMPLE).unwrap();
+    let table = Tables::from_exchange(&exchange).unwrap();
+    let err = EntityTable::<LineHolder>::get_owned(&table, 4).unwrap_err();
+    assert!(matches!(err, Error::UnknownName(Name::Value(5))));
+}
+
+#[test]
+fn reference_section() {
+    let table = tables();
+    let names = table.names().unwrap();
+    assert_eq!(
+        names.resolve(&Name::Value(6)).unwrap(),
+        Parameter::Ref(Name::Entity(1))
+    );
+    let err = EntityTable::<LineHolder>::get_owned(&table, 5).unwrap_err();
+    assert!(matches!(err, Error::ExternalReference(uri) if uri == "part.stp#END"));
+
+    let line = EntityTable::<LineHolder>::get_holder(&table, 5).unwrap();
+    assert_eq!(
+        line.start.into_owned(&table).unwrap(),
+        Point { x: 0.0, y: 0.0 }
+    );
+}
+
+#[test]
+fn cyclic_names() {
+    let mut table = Tables::default();
+    table
+        .append_anchor_section(&[
+            Anchor::from_str("<A> = @B;").unwrap(),
+            Anchor::from_str("<B> = @A;").unwrap(),
+        ])
+        .unwrap();
+    table
+        .append_data_section(
+            &DataSection::from_str("DATA; #1 = LINE('l', #A, #A); ENDSEC;").unwrap(),
+        )
+        .unwrap();
+    let err = EntityTable::<LineHolder>::get_owned(&table, 1).unwrap_err();
+    assert!(matches!(err, Error::CyclicReference(Name::ConstantEntity(name)) if name == "A"));
+}
+
+#[test]
+fn duplicated_anchor() {
+    let exchange = Exchange::from_str(EXAMPLE).unwrap();
+    let mut table = Tables::from_exchange(&exchange).unwrap();
+    let err = table.append_anchor_section(&exchange.anchor).unwrap_err();
+    assert!(matches!(err, Error::DuplicatedName(name) if name == "<ORIGIN>"));
+}
+
+#[test]
+fn dump_anchor() {
+    let table = tables();
+    let header = Header::from_records(&Exchange::from_str(EXAMPLE).unwrap().header).unwrap();
+    let exchange = table.to_exchange(&header).unwrap();
+    let output = ruststep::writer::to_string(&exchange).unwrap();
+
+    let exchange = Exchange::from_str(&output).unwrap();
+    assert_eq!(exchange.anchor.len(), 3);
+    assert_eq!(exchange.reference.len(), 2);
+    let dumped = Tables::from_exchange(&exchange).unwrap();
+    let line = EntityTable::<LineHolder>::get_owned(&dumped, 3).unwrap();
+    assert_eq!(line.name, Label("l1".to_string()));
+
+    // The value instance `@5` registered by hand is written in place
+    let line = EntityTable::<LineHolder>::get_owned(&dumped, 4).unwrap();
+    assert_eq!(line.stop, Point { x: 3.0, y: 4.0 });
+    assert_eq!(
+        dumped.names().unwrap().reference(&Name::Entity(7)),
+        Some(&URI("part.stp#END".to_string()))
+    );
+}
@@ -0,0 +1,133 @@
+use ruststep::{error::Error, runtime::*, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY named_unit;
+        dimensions: REAL;
+      END_ENTITY;
+
+      ENTITY si_unit SUBTYPE OF (named_unit);
+        prefix: OPTIONAL STRING;
+      DERIVE
+        SELF\named_unit.dimensions: REAL := 1.0;
+      END_ENTITY;
+
+      ENTITY representation_item;
+      END_ENTITY;
+
+      ENTITY point SUBTYPE OF (representation_item);
+        x: REAL;
+      END_ENTITY;
+
+      ENTITY representation;
+        items: SET [0:?] OF representation_item;
+        description: OPTIONAL STRING;
+      END_ENTITY;
+
+      ENTITY shape_representation SUBTYPE OF (representation);
+        SELF\representation.items: SET [1:?] OF point;
+        SELF\representation.description RENAMED name: STRING;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = NAMED_UNIT(2.0);
+  #2 = SI_UNIT(NAMED_UNIT((*)), 'milli');
+  #3 = (NAMED_UNIT(*) SI_UNIT($));
+  #4 = POINT(REPRESENTATION_ITEM(()), 1.0);
+  #5 = SHAPE_REPRESENTATION(REPRESENTATION(((#4), 'shape')));
+  #6 = SHAPE_REPRESENTATION(REPRESENTATION(((#4), $)));
+ENDSEC;
+"#;
+
+#[test]
+fn derived_in_subtype() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    let unit = EntityTable::<NamedUnitHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(unit.dimensions, Some(2.0));
+
+    let si = EntityTable::<SiUnitHolder>::get_owned(&table, 2).unwrap();
+    assert_eq!(si.named_unit.dimensions, None);
+    let population = table.population().unwrap();
+    assert_eq!(si.dimensions(Some(2), &population), Some(1.0));
+    assert_eq!(si.prefix, Some("milli".to_string()));
+    assert_eq!(
+        attribute_in(Some(&population), si.to_value(), "dimensions"),
+        Value::Real(1.0)
+    );
+
+    // external mapping
+    let si = EntityTable::<SiUnitHolder>::get_owned(&table, 3).unwrap();
+    assert_eq!(si.named_unit.dimensions, None);
+    assert_eq!(si.prefix, None);
+}
+
+fn derived_error(input: &str) -> (String, String, bool) {
+    match Tables::from_str(input).unwrap_err() {
+        Error::EntityInstanceFailed { source, .. } => match *source {
+            Error::DerivedAttribute {
+                entity,
+                attribute,
+                derived,
+            } => (entity, attribute, derived),
+            e => panic!("Unexpected error: {}", e),
+        },
+        e => panic!("Unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn derived_in_subtype_checked() {
+    // `*` only in the instances of `si_unit`
+    assert_eq!(
+        derived_error("DATA;\n#1 = NAMED_UNIT(*);\nENDSEC;\n"),
+        ("NAMED_UNIT".to_string(), "dimensions".to_string(), false)
+    );
+    // explicit value is not allowed in the instances of `si_unit`
+    assert_eq!(
+        derived_error("DATA;\n#1 = SI_UNIT(NAMED_UNIT((2.0)), $);\nENDSEC;\n"),
+        ("NAMED_UNIT".to_string(), "dimensions".to_string(), true)
+    );
+    assert_eq!(
+        derived_error("DATA;\n#1 = (NAMED_UNIT(2.0) SI_UNIT($));\nENDSEC;\n"),
+        ("NAMED_UNIT".to_string(), "dimensions".to_string(), true)
+    );
+}
+
+#[test]
+fn redeclared_explicit() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+
+    // The value is stored in the supertype
+    let shape = EntityTable::<ShapeRepresentationHolder>::get_owned(&table, 5).unwrap();
+    assert_eq!(shape.representation.items.len(), 1);
+    assert_eq!(shape.representation.description, Some("shape".to_string()));
+    let value = shape.to_value();
+    assert_eq!(
+        attribute(value.clone(), "name"),
+        Value::String("shape".to_string())
+    );
+    assert_eq!(
+        attribute(value, "description"),
+        Value::String("shape".to_string())
+    );
+
+    // `description` is mandatory in `shape_representation`
+    assert_eq!(
+        table.validate().unwrap(),
+        vec![RuleViolation {
+            id: Some(6),
+            declared_in: "TEST_SCHEMA.SHAPE_REPRESENTATION",
+            label: "SELF\\representation.description",
+            evaluated: true,
+        }]
+    );
+}
@@ -0,0 +1,130 @@
+use ruststep::{ast::*, error::Error, tables::*};
+use std::{rc::Rc, str::FromStr};
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY context;
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY representation;
+        items: LIST [0:?] OF node;
+        context_of_items: context;
+      END_ENTITY;
+
+      ENTITY node;
+        name: STRING;
+        children: LIST [0:?] OF node;
+      END_ENTITY;
+
+      TYPE item_select = SELECT (context, node);
+      END_TYPE;
+
+      ENTITY tag;
+        item: item_select;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = CONTEXT('shared');
+  #2 = NODE('a', ());
+  #3 = NODE('b', (#2));
+  #4 = REPRESENTATION((#2, #3), #1);
+  #5 = REPRESENTATION((#3), #1);
+  #6 = TAG(#1);
+  #7 = NODE('x', (#2, #8));
+  #8 = NODE('y', (#7));
+  #9 = NODE('self', (#9));
+  #10 = REPRESENTATION((#7), #1);
+ENDSEC;
+"#;
+
+fn shared<T>(place_holder: &PlaceHolder<T>) -> &Rc<T> {
+    match place_holder {
+        PlaceHolder::Shared(_, shared) => shared,
+        _ => panic!("not resolved"),
+    }
+}
+
+#[test]
+fn shared_context() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let mut resolver = Resolver::new(&table);
+    let r4 = resolver.get_shared::<RepresentationHolder>(4).unwrap();
+    let r5 = resolver.get_shared::<RepresentationHolder>(5).unwrap();
+    assert!(Rc::ptr_eq(
+        shared(&r4.context_of_items),
+        shared(&r5.context_of_items)
+    ));
+    // `#3` in the list is also shared
+    assert!(Rc::ptr_eq(shared(&r4.items[1]), shared(&r5.items[0])));
+    // `#2` referred from `#4` directly and through `#3`
+    let n2 = shared(&r4.items[0]);
+    let n3 = shared(&r4.items[1]);
+    assert!(Rc::ptr_eq(n2, shared(&n3.children[0])));
+    assert_eq!(n2.name, "a");
+    assert!(n2.children.is_empty());
+}
+
+#[test]
+fn same_as_into_owned() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let mut resolver = Resolver::new(&table);
+    let r4 = resolver.get_shared::<RepresentationHolder>(4).unwrap();
+    assert_eq!(
+        r4.as_ref().clone().into_owned(&table).unwrap(),
+        EntityTable::<RepresentationHolder>::get_owned(&table, 4).unwrap()
+    );
+    // Shared holder is written as its reference
+    assert_eq!(
+        ser::to_record(r4.as_ref()).unwrap(),
+        Record::from_str("REPRESENTATION((#2, #3), #1)").unwrap()
+    );
+}
+
+#[test]
+fn select() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let mut resolver = Resolver::new(&table);
+    let tag = resolver.get_shared::<TagHolder>(6).unwrap();
+    match shared(&tag.item).as_ref() {
+        ItemSelectHolder::Context(context) => assert_eq!(context.name, "shared"),
+        _ => panic!("must be context"),
+    }
+}
+
+#[test]
+fn cyclic() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let mut resolver = Resolver::new(&table);
+    assert!(matches!(
+        resolver.get_shared::<NodeHolder>(7).unwrap_err(),
+        Error::CyclicReference(Name::Entity(7))
+    ));
+    assert!(matches!(
+        resolver.get_shared::<NodeHolder>(9).unwrap_err(),
+        Error::CyclicReference(Name::Entity(9))
+    ));
+    assert!(matches!(
+        resolver.get_shared::<RepresentationHolder>(10).unwrap_err(),
+        Error::CyclicReference(Name::Entity(7))
+    ));
+    // Resolver is still usable after errors
+    assert!(resolver.get_shared::<RepresentationHolder>(4).is_ok());
+}
+
+#[test]
+fn unknown_entity() {
+    let table = Tables::from_str("DATA; #1 = NODE('a', (#2)); ENDSEC;").unwrap();
+    let mut resolver = Resolver::new(&table);
+    assert!(matches!(
+        resolver.get_shared::<NodeHolder>(1).unwrap_err(),
+        Error::UnknownEntity(2)
+    ));
+}
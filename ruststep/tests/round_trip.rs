@@ -0,0 +1,76 @@
+//! Parse -> write -> parse round-trip test for STEP files in `tests/steps`
+
+use ruststep::{parser, writer};
+use std::{fs, path::*};
+
+#[test]
+fn round_trip() -> anyhow::Result<()> {
+    let steps = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/steps");
+    for entry in fs::read_dir(steps)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |ext| ext != "step") {
+            continue;
+        }
+        let step_str = fs::read_to_string(&path)?;
+        let ex = parser::parse(&step_str)?;
+        let output = writer::to_string(&ex)?;
+        let reparsed = parser::parse(&output)?;
+        assert_eq!(ex, reparsed, "Round-trip failed for {}", path.display());
+    }
+    Ok(())
+}
+
+#[test]
+fn round_trip_optional_sections() -> anyhow::Result<()> {
+    let step_str = r#"
+    ISO-10303-21;
+    HEADER;
+      FILE_DESCRIPTION(('ruststep'), '3;1');
+      FILE_NAME('', '', (''), (''), '', '', '');
+      FILE_SCHEMA(('EXAMPLE'));
+    ENDSEC;
+    ANCHOR;
+      <first> = #1 {label: 'x'} {values: (1, 2.5, .A., $)};
+    ENDSEC;
+    REFERENCE;
+      #2 = <http://example.com/other.step#a>;
+    ENDSEC;
+    DATA(('first'), 1);
+      #1 = A(1.0, B((#2, @3)), 'It''s');
+    ENDSEC;
+    DATA;
+      #3 = (C(*) D(#CONST, @CONST));
+    ENDSEC;
+    END-ISO-10303-21;
+    "#
+    .trim();
+    let ex = parser::parse(step_str)?;
+    let output = writer::to_string(&ex)?;
+    assert_eq!(parser::parse(&output)?, ex);
+    Ok(())
+}
+
+#[test]
+fn round_trip_non_ascii_header() -> anyhow::Result<()> {
+    let step_str = r#"
+    ISO-10303-21;
+    HEADER;
+      FILE_DESCRIPTION(('ruststep'), '2;1');
+      FILE_NAME('\X2\30D130FC30C8\X0\.step', '', ('J\X\FCrgen M\X\FCller', '\X2\5C71753030CE\X0\'), (''), '', '', '');
+      FILE_SCHEMA(('EXAMPLE'));
+    ENDSEC;
+    DATA;
+    ENDSEC;
+    END-ISO-10303-21;
+    "#
+    .trim();
+    let ex = parser::parse(step_str)?;
+    let header = ruststep::header::Header::from_records(&ex.header)?;
+    assert_eq!(header.file_name.name, "パート.step");
+    assert_eq!(header.file_name.author, ["Jürgen Müller", "山田ノ"]);
+
+    let output = writer::to_string(&ex)?;
+    assert!(output.is_ascii());
+    assert_eq!(parser::parse(&output)?, ex);
+    Ok(())
+}
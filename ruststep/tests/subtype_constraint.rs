@@ -0,0 +1,148 @@
+use ruststep::{error::Error, primitive::Logical, runtime::*, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      ENTITY pet ABSTRACT SUPERTYPE OF (ONEOF (cat, dog));
+        name: STRING;
+      END_ENTITY;
+
+      ENTITY cat SUBTYPE OF (pet);
+        lives: INTEGER;
+      END_ENTITY;
+
+      ENTITY dog SUBTYPE OF (pet);
+        breed: STRING;
+      END_ENTITY;
+
+      ENTITY hamster SUBTYPE OF (pet);
+        wheel: BOOLEAN;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+fn load(data: &str) -> ruststep::error::Result<Tables> {
+    Tables::from_str(&format!("DATA;\n{}\nENDSEC;\n", data))
+}
+
+#[test]
+fn instantiate_subtype() {
+    let table = load(
+        r#"
+        #1 = CAT(PET(('Tama')), 9);
+        #2 = (DOG('Shiba') PET('Hachi'));
+        "#,
+    )
+    .unwrap();
+
+    let any = EntityTable::<PetAnyHolder>::get_owned(&table, 1).unwrap();
+    assert!(matches!(any, PetAny::Cat(_)));
+    assert_eq!(AsRef::<Pet>::as_ref(&any).name, "Tama");
+
+    let dog = EntityTable::<DogHolder>::get_owned(&table, 2).unwrap();
+    assert_eq!(dog.breed, "Shiba");
+    assert_eq!(dog.pet.name, "Hachi");
+}
+
+#[test]
+fn abstract_entity() {
+    for data in ["#1 = PET('Tama');", "#1 = (PET('Tama'));"] {
+        let err = load(data).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::EntityInstanceFailed { id: 1, source, .. }
+                if matches!(source.as_ref(), Error::AbstractEntity(name) if name == "PET")
+        ));
+    }
+}
+
+#[test]
+fn oneof() {
+    let err = load("#1 = (CAT(9) DOG('Shiba') PET('Tama'));").unwrap_err();
+    assert!(matches!(
+        err,
+        Error::EntityInstanceFailed { id: 1, source, .. }
+            if matches!(
+                source.as_ref(),
+                Error::NotInstantiable { supertype, subtypes }
+                    if supertype == "PET" && subtypes == &["CAT", "DOG"]
+            )
+    ));
+}
+
+#[test]
+fn default_andor() {
+    // `hamster` is combined with `ONEOF (cat, dog)` by the default `ANDOR` constraint
+    let table = load("#1 = (CAT(9) HAMSTER(.T.) PET('Tama'));").unwrap();
+    let cat = EntityTable::<CatHolder>::get_owned(&table, 1).unwrap();
+    assert_eq!(cat.lives, 9);
+    let hamster = EntityTable::<HamsterHolder>::get_owned(&table, 1).unwrap();
+    assert!(hamster.wheel);
+    // Seen as one instance of both from EXPRESS
+    let population = table.population().unwrap();
+    let instance = Population::instance(&population, 1);
+    assert_eq!(
+        instance.as_entity().unwrap().type_names(),
+        ["TEST_SCHEMA.CAT", "TEST_SCHEMA.PET", "TEST_SCHEMA.HAMSTER"]
+    );
+    assert_eq!(attribute(instance.clone(), "lives"), Value::Integer(9));
+    assert_eq!(attribute(instance, "wheel"), Value::Logical(Logical::True));
+    // Written once from the tables of `cat` and `hamster`
+    assert_eq!(
+        table.to_data_section().unwrap(),
+        ruststep::ast::DataSection::from_str(
+            "DATA; #1 = (CAT(9) HAMSTER(.T.) PET('Tama')); ENDSEC;"
+        )
+        .unwrap()
+    );
+
+    let err = load("#1 = (CAT(9) DOG('Shiba') HAMSTER(.T.) PET('Tama'));").unwrap_err();
+    assert!(matches!(
+        err,
+        Error::EntityInstanceFailed { id: 1, source, .. }
+            if matches!(
+                source.as_ref(),
+                Error::NotInstantiable { supertype, subtypes }
+                    if supertype == "PET" && subtypes == &["CAT", "DOG", "HAMSTER"]
+            )
+    ));
+}
+
+#[test]
+fn dump_and_load() {
+    let table = load(
+        r#"
+        #1 = CAT(PET(('Tama')), 9);
+        #2 = (DOG('Shiba') PET('Hachi'));
+        "#,
+    )
+    .unwrap();
+    let data = table.to_data_section().unwrap();
+    assert_eq!(
+        data,
+        ruststep::ast::DataSection::from_str(
+            r#"
+            DATA;
+              #1 = CAT(PET(('Tama')), 9);
+              #2 = DOG(PET(('Hachi')), 'Shiba');
+            ENDSEC;
+            "#
+        )
+        .unwrap()
+    );
+
+    // The abstract supertype `PET` is not written as an entity instance
+    let reloaded = Tables::from_data_section(&data).unwrap();
+    assert_eq!(
+        EntityTable::<CatHolder>::get_owned(&reloaded, 1).unwrap(),
+        EntityTable::<CatHolder>::get_owned(&table, 1).unwrap()
+    );
+    assert_eq!(
+        EntityTable::<DogHolder>::get_owned(&reloaded, 2).unwrap(),
+        EntityTable::<DogHolder>::get_owned(&table, 2).unwrap()
+    );
+}
@@ -0,0 +1,136 @@
+use ruststep::{runtime::*, tables::*};
+use std::str::FromStr;
+
+espr_derive::inline_express!(
+    r#"
+    SCHEMA test_schema;
+      TYPE label = STRING;
+      END_TYPE;
+
+      TYPE positive_length_measure = REAL;
+      WHERE
+        wr1: SELF > 0.0;
+      END_TYPE;
+
+      ENTITY named_item;
+        name: label;
+      WHERE
+        wr1: LENGTH(name) > 0;
+      END_ENTITY;
+
+      ENTITY circle SUBTYPE OF (named_item);
+        radius: positive_length_measure;
+        tolerance: OPTIONAL positive_length_measure;
+      WHERE
+        radius < 100.0;
+        wr2: NOT EXISTS(tolerance) OR (tolerance < radius);
+      END_ENTITY;
+
+      ENTITY door;
+        name: label;
+        handle: knob;
+      END_ENTITY;
+
+      ENTITY knob;
+      INVERSE
+        opens: door FOR handle;
+      WHERE
+        wr1: EXISTS(opens);
+        wr2: SIZEOF(USEDIN(SELF, 'TEST_SCHEMA.DOOR.HANDLE')) = 1;
+      END_ENTITY;
+
+      ENTITY node;
+        next: LIST [0:?] OF node;
+      END_ENTITY;
+    END_SCHEMA;
+    "#
+);
+
+use test_schema::*;
+
+const EXAMPLE: &str = r#"
+DATA;
+  #1 = CIRCLE(NAMED_ITEM(('c1')), 1.0, $);
+  #2 = CIRCLE(NAMED_ITEM(('')), -1.0, 2.0);
+  #3 = CIRCLE(NAMED_ITEM(('c3')), 120.0, 0.5);
+  #4 = KNOB();
+  #5 = DOOR('front', #4);
+  #6 = KNOB();
+  #7 = KNOB();
+  #8 = DOOR('back', #7);
+  #9 = DOOR('side', #7);
+ENDSEC;
+"#;
+
+fn violation(id: u64, declared_in: &'static str, label: &'static str) -> RuleViolation {
+    RuleViolation {
+        id: Some(id),
+        declared_in,
+        label,
+        evaluated: true,
+    }
+}
+
+#[test]
+fn validate_tables() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    assert_eq!(
+        table.validate().unwrap(),
+        vec![
+            // `name` is empty, `radius` is negative, and `tolerance` is larger than `radius`
+            violation(2, "TEST_SCHEMA.CIRCLE", "wr2"),
+            violation(2, "TEST_SCHEMA.NAMED_ITEM", "wr1"),
+            violation(2, "TEST_SCHEMA.POSITIVE_LENGTH_MEASURE", "wr1"),
+            // unlabeled rule
+            violation(3, "TEST_SCHEMA.CIRCLE", "#1"),
+            // no door refers #6
+            violation(6, "TEST_SCHEMA.KNOB", "wr1"),
+            violation(6, "TEST_SCHEMA.KNOB", "wr2"),
+            // two doors refer #7
+            violation(7, "TEST_SCHEMA.KNOB", "wr1"),
+            violation(7, "TEST_SCHEMA.KNOB", "wr2"),
+        ]
+    );
+}
+
+#[test]
+fn validate_value() {
+    let table = Tables::from_str(EXAMPLE).unwrap();
+    let population = table.population().unwrap();
+
+    let c1 = EntityTable::<CircleHolder>::get_owned(&table, 1).unwrap();
+    assert!(c1.validate(&population).is_empty());
+
+    let measure = PositiveLengthMeasure(-1.0);
+    assert_eq!(
+        measure.validate(&population),
+        vec![RuleViolation {
+            id: None,
+            declared_in: "TEST_SCHEMA.POSITIVE_LENGTH_MEASURE",
+            label: "wr1",
+            evaluated: true,
+        }]
+    );
+
+    // `INVERSE` attributes are indeterminate without id, while `USEDIN` is unknown
+    let knob = EntityTable::<KnobHolder>::get_owned(&table, 4).unwrap();
+    assert_eq!(
+        knob.validate(&population),
+        vec![RuleViolation {
+            id: None,
+            declared_in: "TEST_SCHEMA.KNOB",
+            label: "wr1",
+            evaluated: true,
+        }]
+    );
+    assert!(knob.validate_rules(Some(4), &population).is_empty());
+}
+
+#[test]
+fn cyclic_reference() {
+    let table = Tables::from_str("DATA; #1 = NODE((#2)); #2 = NODE((#1)); ENDSEC;").unwrap();
+    assert!(matches!(
+        table.validate(),
+        Err(ruststep::error::Error::CyclicReference(_))
+    ));
+}